@@ -0,0 +1,244 @@
+use bevy::prelude::*;
+use bevy::transform::TransformSystem;
+use serde::{Deserialize, Serialize};
+
+use crate::feedback::HitFlash;
+use crate::mods::EnemyVariant;
+use crate::profile::Profile;
+use crate::{BonusTank, Bullet, Enemy, EnemyKind, Faction, Player, PlayerUpgradeLevel, Size};
+
+// Accessibility: colour palettes that keep the player and the enemies apart
+// for colour-blind players, and optional outlines around every tank and shell
+// (light for the player's side, dark for the enemy's) so the two can be told
+// apart without relying on colour at all. Gameplay code doesn't pick team
+// colours itself: the tint systems here run after Update and colour tanks and
+// fresh shells from the palette in the profile, so a change applies at once.
+//
+// Reduced flashes swaps every blink and flash for something steady: bonus
+// tanks wear a badge instead of blinking, the shield ring shrinks instead of
+// blinking before it drops, frozen enemies and a fortified base hold a dimmed
+// or in-between colour, the base alarm stays red, hits don't flash the tank and
+// explosions are drawn faint. Each of those checks `reduced_flashes` itself.
+
+const BONUS_COLOR: Color = Color::srgb(1.0, 0.15, 0.15);
+const TANK_OUTLINE: f32 = 3.0;
+const SHELL_OUTLINE: f32 = 1.5;
+const PLAYER_OUTLINE: Color = Color::srgb(0.95, 0.95, 0.95);
+const ENEMY_OUTLINE: Color = Color::srgb(0.05, 0.05, 0.05);
+// Share of the tank's width
+const BADGE_SIZE: f32 = 0.4;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Palette {
+    #[default]
+    Classic,
+    // Safe for red-green colour blindness
+    BlueOrange,
+    // Safe for blue-yellow colour blindness
+    RedTeal,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 3] = [Palette::Classic, Palette::BlueOrange, Palette::RedTeal];
+
+    // Key into the language files
+    pub fn label(self) -> &'static str {
+        match self {
+            Palette::Classic => "palette.classic",
+            Palette::BlueOrange => "palette.blue_orange",
+            Palette::RedTeal => "palette.red_teal",
+        }
+    }
+
+    // One colour per weapon upgrade level, getting lighter as it goes up
+    pub fn player(self, upgrade: u8) -> Color {
+        let levels = match self {
+            Palette::Classic => [(0.2, 0.9, 0.2), (0.2, 0.8, 1.0), (1.0, 0.9, 0.2), (1.0, 0.4, 0.9)],
+            Palette::BlueOrange => [(0.15, 0.45, 1.0), (0.3, 0.7, 1.0), (0.55, 0.88, 1.0), (0.85, 0.95, 1.0)],
+            Palette::RedTeal => [(0.0, 0.65, 0.6), (0.15, 0.82, 0.78), (0.45, 0.95, 0.9), (0.8, 1.0, 1.0)],
+        };
+        let (r, g, b) = levels[(upgrade as usize).min(levels.len() - 1)];
+        Color::srgb(r, g, b)
+    }
+
+    pub fn enemy(self, kind: EnemyKind) -> Color {
+        let (r, g, b) = match self {
+            Palette::Classic => return kind.color(),
+            Palette::BlueOrange => match kind {
+                EnemyKind::Basic => (1.0, 0.55, 0.1),
+                EnemyKind::Fast => (1.0, 0.78, 0.35),
+                EnemyKind::Power => (0.8, 0.35, 0.0),
+                EnemyKind::Armor => (0.5, 0.22, 0.0),
+                EnemyKind::Saboteur => (0.7, 0.6, 0.4),
+                EnemyKind::Boss => (0.95, 0.3, 0.1),
+            },
+            Palette::RedTeal => match kind {
+                EnemyKind::Basic => (0.9, 0.15, 0.2),
+                EnemyKind::Fast => (1.0, 0.45, 0.5),
+                EnemyKind::Power => (0.7, 0.1, 0.35),
+                EnemyKind::Armor => (0.45, 0.05, 0.1),
+                EnemyKind::Saboteur => (0.65, 0.45, 0.45),
+                EnemyKind::Boss => (0.85, 0.2, 0.55),
+            },
+        };
+        Color::srgb(r, g, b)
+    }
+
+    // The lit half of a bonus tank's blink
+    pub fn bonus(self) -> Color {
+        match self {
+            Palette::Classic => BONUS_COLOR,
+            Palette::BlueOrange => Color::srgb(1.0, 1.0, 0.4),
+            Palette::RedTeal => Color::srgb(1.0, 0.65, 0.75),
+        }
+    }
+
+    // None keeps the colour the shell was fired with
+    fn shell(self, faction: Faction) -> Option<Color> {
+        match (self, faction) {
+            (Palette::Classic, _) => None,
+            (Palette::BlueOrange, Faction::Player) => Some(Color::srgb(0.45, 0.8, 1.0)),
+            (Palette::BlueOrange, Faction::Enemy) => Some(Color::srgb(1.0, 0.6, 0.1)),
+            (Palette::RedTeal, Faction::Player) => Some(Color::srgb(0.4, 1.0, 0.95)),
+            (Palette::RedTeal, Faction::Enemy) => Some(Color::srgb(1.0, 0.3, 0.35)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    pub palette: Palette,
+    pub outlines: bool,
+    pub reduced_flashes: bool,
+    // Shots bend towards an enemy close to the line of fire (see handle_fire)
+    pub aim_assist: bool,
+}
+
+// On a tank or shell that has an outline, pointing at the outline child
+#[derive(Component)]
+struct Outlined(Entity);
+
+#[derive(Component)]
+struct Outline;
+
+// Marks a bonus tank in place of the blink, as a diamond over its middle
+#[derive(Component)]
+struct BonusBadge;
+
+pub struct AccessibilityPlugin;
+
+impl Plugin for AccessibilityPlugin {
+    fn build(&self, app: &mut App) {
+        // Ahead of transform propagation, so a new outline is in place the frame it's added
+        app.add_systems(
+            PostUpdate,
+            (tint_tanks, tint_shells, add_outlines, remove_outlines, fit_outlines, badge_bonus_tanks)
+                .chain()
+                .before(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+// Every frame, so upgrades, bonus blinks and a palette change all show; a
+// tank mid hit flash is left to the flash. Alpha is kept for the blink effects.
+fn tint_tanks(
+    profile: Res<Profile>,
+    upgrade: Res<PlayerUpgradeLevel>,
+    mut q_player: Query<&mut Sprite, (With<Player>, Without<HitFlash>)>,
+    mut q_enemies: Query<
+        (&EnemyKind, Option<&EnemyVariant>, Option<&BonusTank>, &mut Sprite),
+        (With<Enemy>, Without<Player>, Without<HitFlash>),
+    >,
+) {
+    let palette = profile.accessibility.palette;
+    let steady = profile.accessibility.reduced_flashes;
+    let tint = |sprite: &mut Sprite, color: Color| {
+        let alpha = sprite.color.alpha();
+        if sprite.color.with_alpha(1.0) != color { sprite.color = color.with_alpha(alpha); }
+    };
+    for mut sprite in &mut q_player {
+        tint(&mut sprite, palette.player(upgrade.0));
+    }
+    for (kind, variant, bonus, mut sprite) in &mut q_enemies {
+        let lit = !steady && bonus.is_some_and(|b| b.lit);
+        // A mod's colour is for the classic palette; the others keep their contrast
+        let own = variant.and_then(|v| v.color).filter(|_| palette == Palette::Classic);
+        tint(&mut sprite, if lit { palette.bonus() } else { own.unwrap_or_else(|| palette.enemy(*kind)) });
+    }
+}
+
+// Pooled shells get Bullet again each time they're fired, so this sees every shot
+fn tint_shells(profile: Res<Profile>, mut q: Query<(&Faction, &mut Sprite), Added<Bullet>>) {
+    for (faction, mut sprite) in &mut q {
+        if let Some(color) = profile.accessibility.palette.shell(*faction) { sprite.color = color; }
+    }
+}
+
+fn add_outlines(
+    mut commands: Commands,
+    profile: Res<Profile>,
+    q: Query<Entity, (Or<(With<Player>, With<EnemyKind>, With<Bullet>)>, Without<Outlined>)>,
+) {
+    if !profile.accessibility.outlines { return; }
+    for e in &q {
+        // Just under its tank or shell, sized and coloured by fit_outlines
+        let outline = commands
+            .spawn((Sprite::default(), Transform::from_xyz(0.0, 0.0, -0.05), Outline, ChildOf(e)))
+            .id();
+        commands.entity(e).insert(Outlined(outline));
+    }
+}
+
+fn remove_outlines(mut commands: Commands, profile: Res<Profile>, q: Query<(Entity, &Outlined)>) {
+    if profile.accessibility.outlines { return; }
+    for (e, outlined) in &q {
+        commands.entity(outlined.0).despawn();
+        commands.entity(e).remove::<Outlined>();
+    }
+}
+
+// A spent shell loses its Size and is skipped until it's fired again, maybe by the other side
+fn fit_outlines(
+    q_owners: Query<(&Outlined, &Size, &Sprite, Option<&Faction>, Has<Bullet>, Has<Player>)>,
+    mut q_outlines: Query<&mut Sprite, (With<Outline>, Without<Outlined>)>,
+) {
+    for (outlined, size, sprite, faction, is_shell, is_player) in &q_owners {
+        let Ok(mut outline) = q_outlines.get_mut(outlined.0) else { continue; };
+        let players = is_player || faction == Some(&Faction::Player);
+        let width = if is_shell { SHELL_OUTLINE } else { TANK_OUTLINE };
+        let target_size = Some(size.0 + Vec2::splat(width * 2.0));
+        let color = if players { PLAYER_OUTLINE } else { ENEMY_OUTLINE }.with_alpha(sprite.color.alpha());
+        if outline.custom_size != target_size { outline.custom_size = target_size; }
+        if outline.color != color { outline.color = color; }
+    }
+}
+
+// Badges come and go with the setting; one on a destroyed tank goes with it
+fn badge_bonus_tanks(
+    mut commands: Commands,
+    profile: Res<Profile>,
+    q_tanks: Query<(Entity, &Size, Option<&Children>), With<BonusTank>>,
+    mut q_badges: Query<&mut Sprite, With<BonusBadge>>,
+) {
+    let palette = profile.accessibility.palette;
+    for (e, size, children) in &q_tanks {
+        let kids = children.map_or(&[][..], |c| &c[..]);
+        let badges: Vec<Entity> = kids.iter().copied().filter(|&c| q_badges.contains(c)).collect();
+        if !profile.accessibility.reduced_flashes {
+            for badge in badges { commands.entity(badge).despawn(); }
+        } else if !badges.is_empty() {
+            // Kept in step with a palette change
+            for badge in badges {
+                if let Ok(mut sprite) = q_badges.get_mut(badge) { sprite.color = palette.bonus(); }
+            }
+        } else {
+            commands.spawn((
+                Sprite { color: palette.bonus(), custom_size: Some(Vec2::splat(size.0.x * BADGE_SIZE)), ..default() },
+                Transform::from_xyz(0.0, 0.0, 0.1).with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_4)),
+                BonusBadge,
+                ChildOf(e),
+            ));
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::attract::Demo;
+use crate::feedback::HitEvent;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::{Locale, Tr};
+use crate::persistence;
+use crate::recap::StageCleared;
+use crate::scores::confirm_pressed;
+use crate::stats::{PlayerStats, ShotFired};
+use crate::toasts::Notify;
+use crate::{AppState, EnemyKilled, EnemyKind, LevelBounds};
+
+// Achievements: one-off goals for the player, worked out from the events the
+// game already sends, unlocked for good in achievements.ron and announced
+// with a toast. They have their own screen from the loadout menu. Like stats,
+// a demo run doesn't count.
+
+const ACHIEVEMENTS_FILE: &str = "achievements.ron";
+const UNLOCK_COLOR: Color = Color::srgb(1.0, 0.85, 0.3);
+const LOCKED_COLOR: Color = Color::srgb(0.55, 0.55, 0.55);
+
+const VETERAN_KILLS: u32 = 100;
+const MARKSMAN_SHOTS: u32 = 20;
+const SURVIVOR_SECS: f32 = 300.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Achievement {
+    // Tanks destroyed, over every run
+    Veteran,
+    // A stage cleared on few shells
+    Marksman,
+    // A stage cleared without being hit
+    Untouchable,
+    // One run lasting long enough
+    Survivor,
+    GiantSlayer,
+}
+
+impl Achievement {
+    const ALL: [Achievement; 5] = [
+        Achievement::Veteran,
+        Achievement::Marksman,
+        Achievement::Untouchable,
+        Achievement::Survivor,
+        Achievement::GiantSlayer,
+    ];
+
+    // Also the key in the save file, so keep these stable
+    fn id(self) -> &'static str {
+        match self {
+            Achievement::Veteran => "veteran",
+            Achievement::Marksman => "marksman",
+            Achievement::Untouchable => "untouchable",
+            Achievement::Survivor => "survivor",
+            Achievement::GiantSlayer => "giant_slayer",
+        }
+    }
+
+    fn title(self, locale: &Locale) -> &str {
+        locale.t(match self {
+            Achievement::Veteran => "achievement.veteran",
+            Achievement::Marksman => "achievement.marksman",
+            Achievement::Untouchable => "achievement.untouchable",
+            Achievement::Survivor => "achievement.survivor",
+            Achievement::GiantSlayer => "achievement.giant_slayer",
+        })
+    }
+
+    fn describe(self, locale: &Locale) -> String {
+        match self {
+            Achievement::Veteran => locale.f("achievement.veteran.describe", &[("kills", &VETERAN_KILLS)]),
+            Achievement::Marksman => locale.f("achievement.marksman.describe", &[("shots", &MARKSMAN_SHOTS)]),
+            Achievement::Untouchable => locale.t("achievement.untouchable.describe").to_string(),
+            Achievement::Survivor => {
+                locale.f("achievement.survivor.describe", &[("minutes", &(SURVIVOR_SECS as u32 / 60))])
+            }
+            Achievement::GiantSlayer => locale.t("achievement.giant_slayer.describe").to_string(),
+        }
+    }
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Achievements {
+    pub unlocked: BTreeSet<String>,
+}
+
+impl Achievements {
+    pub fn load() -> Self {
+        persistence::load(ACHIEVEMENTS_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(ACHIEVEMENTS_FILE, self);
+    }
+
+    fn has(&self, achievement: Achievement) -> bool {
+        self.unlocked.contains(achievement.id())
+    }
+}
+
+// What the current stage and run have come to so far
+#[derive(Resource, Default)]
+struct Progress {
+    stage_shots: u32,
+    stage_hits: u32,
+    run_secs: f32,
+}
+
+#[derive(Component)]
+struct AchievementsScreen;
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Achievements::load())
+            .init_resource::<Progress>()
+            .add_systems(OnEnter(AppState::Playing), reset_run)
+            .add_systems(OnEnter(AppState::Achievements), spawn_achievements_screen)
+            .add_systems(OnExit(AppState::Achievements), despawn_achievements_screen)
+            .add_systems(
+                Update,
+                (reset_stage.run_if(resource_exists_and_changed::<LevelBounds>), track_progress)
+                    .chain()
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<Demo>)),
+            )
+            .add_systems(Update, achievements_screen_input.run_if(in_state(AppState::Achievements)));
+    }
+}
+
+fn reset_run(mut progress: ResMut<Progress>) {
+    *progress = Progress::default();
+}
+
+fn reset_stage(mut progress: ResMut<Progress>) {
+    progress.stage_shots = 0;
+    progress.stage_hits = 0;
+}
+
+fn track_progress(
+    time: Res<Time>,
+    stats: Res<PlayerStats>,
+    locale: Res<Locale>,
+    mut progress: ResMut<Progress>,
+    mut achievements: ResMut<Achievements>,
+    mut notify: EventWriter<Notify>,
+    mut fired: EventReader<ShotFired>,
+    mut hits: EventReader<HitEvent>,
+    mut kills: EventReader<EnemyKilled>,
+    mut cleared: EventReader<StageCleared>,
+) {
+    progress.run_secs += time.delta_secs();
+    progress.stage_shots += fired.read().count() as u32;
+    progress.stage_hits += hits.read().filter(|h| h.player).count() as u32;
+    let boss_down = kills.read().any(|k| k.for_player() && k.kind == EnemyKind::Boss);
+    let stage_clear = cleared.read().count() > 0;
+
+    let mut earned = Vec::new();
+    if stats.kills.values().sum::<u32>() >= VETERAN_KILLS { earned.push(Achievement::Veteran); }
+    if stage_clear && progress.stage_shots <= MARKSMAN_SHOTS { earned.push(Achievement::Marksman); }
+    if stage_clear && progress.stage_hits == 0 { earned.push(Achievement::Untouchable); }
+    if progress.run_secs >= SURVIVOR_SECS { earned.push(Achievement::Survivor); }
+    if boss_down { earned.push(Achievement::GiantSlayer); }
+
+    let mut changed = false;
+    for achievement in earned {
+        if achievements.has(achievement) { continue; }
+        achievements.unlocked.insert(achievement.id().to_string());
+        let text = locale.f("achievement.unlocked", &[("title", &achievement.title(&locale))]);
+        notify.write(Notify::new(text).with_color(UNLOCK_COLOR));
+        changed = true;
+    }
+    if changed { achievements.save(); }
+}
+
+fn spawn_achievements_screen(
+    mut commands: Commands,
+    achievements: Res<Achievements>,
+    stats: Res<PlayerStats>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    let kills: u32 = stats.kills.values().sum();
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            AchievementsScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("achievements.title")));
+            root.spawn(Node { flex_direction: FlexDirection::Column, row_gap: Val::Px(4.0), ..default() })
+                .with_children(|list| {
+                    for achievement in Achievement::ALL {
+                        let done = achievements.has(achievement);
+                        // Only the lifetime count has progress worth showing
+                        let progress = match achievement {
+                            Achievement::Veteran if !done => format!("  ({kills}/{VETERAN_KILLS})"),
+                            _ => String::new(),
+                        };
+                        let line = format!(
+                            "{} {:<13} {}{progress}",
+                            if done { "*" } else { " " },
+                            achievement.title(&locale),
+                            achievement.describe(&locale),
+                        );
+                        let color = if done { UNLOCK_COLOR } else { LOCKED_COLOR };
+                        list.spawn((Text::new(line), font(18.0), TextColor(color)));
+                    }
+                });
+            root.spawn((
+                Text::new(format!("{}: {}", glyph(Action::Confirm, *device), locale.t("hint.back"))),
+                font(16.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn despawn_achievements_screen(mut commands: Commands, q: Query<Entity, With<AchievementsScreen>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn achievements_screen_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if confirm_pressed(&keys, &pads) || keys.just_pressed(KeyCode::Escape) {
+        next.set(AppState::Loadout);
+    }
+}
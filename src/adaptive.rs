@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::profile::Profile;
+use crate::recap::StageCleared;
+use crate::{AppState, GameSet, Lives, start_run};
+
+// Adaptive difficulty: with it on in the settings, the enemies keep pace with
+// how a run is going. Every stage cleared without losing a tank makes them a
+// step quicker, both driving and firing; every couple of tanks lost makes
+// them a step slower again, down to a few steps under the difficulty's own.
+// Only stage ends move it, so it never shifts mid-fight, and tanks already on
+// the field keep the guns they rolled out with. It scales whatever the
+// difficulty has made of config.ron, and every run starts back at the
+// difficulty as picked.
+
+// How much one step changes enemy speed and fire rate
+const STEP: f32 = 0.08;
+// Steps either way at most
+const MAX_STEPS: i32 = 3;
+// Tanks lost, counted across stages, before the enemies ease off
+const EASE_AFTER: u32 = 2;
+
+#[derive(Resource, Default)]
+pub struct Pacing {
+    step: i32,
+    // Lives as last seen, to count the ones lost
+    lives: u8,
+    lost_this_stage: u32,
+    lost_since_ease: u32,
+}
+
+impl Pacing {
+    fn factor(&self) -> f32 {
+        1.0 + self.step as f32 * STEP
+    }
+
+    // Onto a config freshly scaled for the difficulty
+    pub fn apply(&self, cfg: &mut GameConfig) {
+        cfg.enemy.speed *= self.factor();
+        cfg.enemy.fire_secs /= self.factor();
+    }
+
+    fn set_step(&mut self, step: i32, cfg: &mut GameConfig) {
+        let step = step.clamp(-MAX_STEPS, MAX_STEPS);
+        if step == self.step { return; }
+        cfg.enemy.speed /= self.factor();
+        cfg.enemy.fire_secs *= self.factor();
+        self.step = step;
+        self.apply(cfg);
+    }
+}
+
+pub struct AdaptivePlugin;
+
+impl Plugin for AdaptivePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Pacing>()
+            .add_systems(OnEnter(AppState::Playing), reset_pacing.after(start_run))
+            .add_systems(
+                Update,
+                (
+                    count_losses,
+                    pace_stage.run_if(on_event::<StageCleared>),
+                    follow_setting.run_if(resource_changed::<Profile>),
+                )
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn reset_pacing(mut pacing: ResMut<Pacing>, mut cfg: ResMut<GameConfig>, lives: Res<Lives>) {
+    pacing.set_step(0, &mut cfg);
+    *pacing = Pacing { lives: lives.0, ..default() };
+}
+
+// Extra lives picked up aren't taken off the count
+fn count_losses(mut pacing: ResMut<Pacing>, lives: Res<Lives>) {
+    if !lives.is_changed() { return; }
+    let lost = pacing.lives.saturating_sub(lives.0) as u32;
+    pacing.lost_this_stage += lost;
+    pacing.lost_since_ease += lost;
+    pacing.lives = lives.0;
+}
+
+fn pace_stage(
+    mut cleared: EventReader<StageCleared>,
+    profile: Res<Profile>,
+    mut pacing: ResMut<Pacing>,
+    mut cfg: ResMut<GameConfig>,
+) {
+    cleared.clear();
+    let lost = std::mem::take(&mut pacing.lost_this_stage);
+    if !profile.adaptive_difficulty { return; }
+    let mut step = pacing.step;
+    if lost == 0 {
+        step += 1;
+        pacing.lost_since_ease = 0;
+    } else if pacing.lost_since_ease >= EASE_AFTER {
+        step -= 1;
+        pacing.lost_since_ease = 0;
+    }
+    let step = step.clamp(-MAX_STEPS, MAX_STEPS);
+    if step != pacing.step { info!("adaptive difficulty: step {} -> {step}", pacing.step); }
+    pacing.set_step(step, &mut cfg);
+}
+
+// Turned off mid-run, the enemies go straight back to the difficulty's own
+fn follow_setting(profile: Res<Profile>, mut pacing: ResMut<Pacing>, mut cfg: ResMut<GameConfig>) {
+    if !profile.adaptive_difficulty { pacing.set_step(0, &mut cfg); }
+}
@@ -0,0 +1,209 @@
+use bevy::prelude::*;
+
+use crate::config::Difficulty;
+use crate::level_format;
+use crate::patrols::PatrolRoute;
+use crate::script::{self, ScriptEvent};
+use crate::themes::LevelTheme;
+use crate::triggers::ZoneSpec;
+use crate::waves::WaveSchedule;
+
+// Launch options, so a tester or speedrunner can set up a run without editing
+// config.ron or the profile. They only last for the session. Anything that
+// doesn't parse prints the usage and quits rather than starting a run that
+// isn't the one asked for.
+
+const USAGE: &str = "\
+usage: BudgetBattleCity [options]
+  --level <path>       play the maze in a text file (same glyphs as the built-in one),
+                       with an optional event script after it, or in a .ron level
+  --convert-level <text file> <ron file>
+                       write a text level out as a .ron level, and quit
+  --seed <n>           start the gameplay rolls from a fixed seed
+  --headless           run without a window or renderer
+  --fullscreen         start fullscreen, whatever the profile says
+  --difficulty <name>  easy, normal or hard, in place of the one in the settings
+  --mute               silence all audio
+  --stress             stress test: a packed demo run that logs frame timings
+  --event-log          write each run's spawns, shots, hits, deaths and pickups
+                       to a JSON-lines file in the data directory";
+
+// A maze read from a level file, from --level or a level pack; the parts
+// after the script only come from .ron levels (see level_format.rs)
+#[derive(Clone, Debug)]
+pub struct LevelFile {
+    // The file name, for messages
+    pub name: String,
+    pub rows: Vec<String>,
+    // Named trigger zones (see triggers.rs) and patrolling barriers' routes (see patrols.rs)
+    pub zones: Vec<ZoneSpec>,
+    pub patrols: Vec<PatrolRoute>,
+    // Events after the maze (see script.rs)
+    pub script: Vec<ScriptEvent>,
+    pub theme: Option<LevelTheme>,
+    // Seconds between shots for turrets that don't use config.ron's, by cell
+    pub turrets: Vec<(UVec2, f32)>,
+    pub waves: Option<WaveSchedule>,
+    // The Rhai script beside the file, for builds with scripting (see scripting.rs)
+    pub code: Option<String>,
+}
+
+impl LevelFile {
+    // `name.rhai` beside `name.txt` or `name.ron`
+    pub fn code_name(file: &str) -> String {
+        let stem = file.rsplit_once('.').map_or(file, |(stem, _)| stem);
+        format!("{stem}.rhai")
+    }
+
+    pub fn turret_fire_secs(&self, cell: UVec2) -> Option<f32> {
+        self.turrets.iter().find(|(c, _)| *c == cell).map(|&(_, secs)| secs)
+    }
+}
+
+#[derive(Resource, Clone, Default)]
+pub struct LaunchArgs {
+    pub level: Option<LevelFile>,
+    // Where --level read it from, to watch for edits (see level_reload.rs)
+    pub level_path: Option<String>,
+    pub seed: Option<u64>,
+    pub headless: bool,
+    pub fullscreen: bool,
+    pub difficulty: Option<Difficulty>,
+    pub mute: bool,
+    pub stress: bool,
+    pub event_log: bool,
+}
+
+impl LaunchArgs {
+    pub fn parse() -> Self {
+        match Self::from_args(std::env::args().skip(1)) {
+            Ok(args) => args,
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                std::process::exit(2);
+            }
+        }
+    }
+
+    fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        while let Some(flag) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{flag} needs a value"));
+            match flag.as_str() {
+                "--level" => {
+                    let path = value()?;
+                    let text = std::fs::read_to_string(&path).map_err(|e| format!("can't read {path}: {e}"))?;
+                    let mut level = level_format::load(&path, &text)?;
+                    level.code = std::fs::read_to_string(LevelFile::code_name(&path)).ok();
+                    parsed.level = Some(level);
+                    parsed.level_path = Some(path);
+                }
+                "--convert-level" => {
+                    let (from, to) = (value()?, value()?);
+                    let text = std::fs::read_to_string(&from).map_err(|e| format!("can't read {from}: {e}"))?;
+                    let converted = level_format::convert(&from, &text)?;
+                    std::fs::write(&to, converted).map_err(|e| format!("can't write {to}: {e}"))?;
+                    println!("wrote {to}");
+                    std::process::exit(0);
+                }
+                "--seed" => {
+                    let n = value()?;
+                    parsed.seed = Some(n.parse().map_err(|_| format!("--seed wants a number, not {n}"))?);
+                }
+                "--difficulty" => {
+                    let name = value()?;
+                    let difficulty = Difficulty::from_name(&name).ok_or_else(|| format!("no difficulty {name}"))?;
+                    parsed.difficulty = Some(difficulty);
+                }
+                "--headless" => parsed.headless = true,
+                "--fullscreen" => parsed.fullscreen = true,
+                "--mute" => parsed.mute = true,
+                "--stress" => parsed.stress = true,
+                "--event-log" => parsed.event_log = true,
+                "--help" | "-h" => {
+                    println!("{USAGE}");
+                    std::process::exit(0);
+                }
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+// One maze row per line; blank lines ahead of it are ignored, and the first
+// blank line after it ends the maze. Anything past that is the level's script.
+// `path` names the file in the messages. Whether the maze can be played is
+// for level_check.rs, once it's known what it'll be played as.
+pub fn parse_level(path: &str, text: &str) -> Result<LevelFile, String> {
+    let Some((lines, first, end)) = split_level(text) else {
+        return Err(format!("{path} has no maze in it"));
+    };
+    let rows = lines[first..end].to_vec();
+
+    if let Some(i) = rows.iter().position(|r| !r.is_ascii()) {
+        return Err(format!("{path}: row {} has a character that isn't a maze glyph", first + i + 1));
+    }
+    let section = script::parse(&lines[end..], end + 1, &rows).map_err(|e| format!("{path}: {e}"))?;
+    Ok(LevelFile {
+        name: path.to_string(),
+        rows,
+        zones: section.zones,
+        patrols: section.patrols,
+        script: section.events,
+        theme: None,
+        turrets: Vec::new(),
+        waves: None,
+        code: None,
+    })
+}
+
+// The file's lines, with the maze's first row and the one past its last;
+// none when there's no maze
+pub fn split_level(text: &str) -> Option<(Vec<String>, usize, usize)> {
+    let lines: Vec<String> = text.lines().map(|l| l.trim_end_matches('\r').to_string()).collect();
+    let first = lines.iter().position(|r| !r.trim().is_empty())?;
+    let end = lines[first..].iter().position(|r| r.trim().is_empty()).map_or(lines.len(), |n| first + n);
+    Some((lines, first, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<LaunchArgs, String> {
+        LaunchArgs::from_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn reads_flags_and_values() {
+        let args = parse(&["--seed", "42", "--difficulty", "hard", "--headless", "--stress"])
+            .unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(args.seed, Some(42));
+        assert_eq!(args.difficulty, Some(Difficulty::Hard));
+        assert!(args.headless && args.stress);
+        assert!(!args.fullscreen && !args.mute && !args.event_log);
+    }
+
+    #[test]
+    fn rejects_unknown_options() {
+        assert_eq!(parse(&["--turbo"]).err().as_deref(), Some("unknown option --turbo"));
+        assert_eq!(parse(&["level.txt"]).err().as_deref(), Some("unknown option level.txt"));
+    }
+
+    #[test]
+    fn rejects_missing_values() {
+        assert_eq!(parse(&["--seed"]).err().as_deref(), Some("--seed needs a value"));
+        assert_eq!(parse(&["--headless", "--difficulty"]).err().as_deref(), Some("--difficulty needs a value"));
+        assert_eq!(parse(&["--level"]).err().as_deref(), Some("--level needs a value"));
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        assert_eq!(parse(&["--seed", "soon"]).err().as_deref(), Some("--seed wants a number, not soon"));
+        assert_eq!(parse(&["--seed", "-1"]).err().as_deref(), Some("--seed wants a number, not -1"));
+        assert_eq!(parse(&["--difficulty", "brutal"]).err().as_deref(), Some("no difficulty brutal"));
+        let missing = parse(&["--level", "no/such/level.txt"]).err().unwrap();
+        assert!(missing.starts_with("can't read no/such/level.txt: "), "{missing}");
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::controller::{PlayerController, SimpleBot};
+use crate::locale::Tr;
+use crate::recap::StageRecap;
+use crate::settings::settings_closed;
+
+// Attract mode: left idle on the loadout screen, the game starts a demo run
+// with the built-in bot as the player's controller, like an arcade cabinet
+// between customers. Any key or button, losing, or clearing the stage goes
+// back to the menu. A demo run keeps nothing: no stats, high score or saved
+// run.
+
+const IDLE_SECS: f32 = 20.0;
+
+// Present while the current run is a demo
+#[derive(Resource)]
+pub struct Demo;
+
+#[derive(Resource)]
+struct IdleTimer(Timer);
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(IDLE_SECS, TimerMode::Once))
+    }
+}
+
+#[derive(Component)]
+struct DemoBanner;
+
+pub struct AttractPlugin;
+
+impl Plugin for AttractPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<IdleTimer>()
+            .add_systems(OnEnter(AppState::Loadout), leave_demo)
+            .add_systems(OnEnter(AppState::Playing), start_demo.run_if(resource_exists::<Demo>))
+            .add_systems(OnExit(AppState::Playing), despawn_banner)
+            // A lost demo skips the game-over screen
+            .add_systems(OnEnter(AppState::GameOver), back_to_menu.run_if(resource_exists::<Demo>))
+            .add_systems(
+                Update,
+                (
+                    idle_on_menu.run_if(in_state(AppState::Loadout)).run_if(settings_closed),
+                    (end_demo_on_input, back_to_menu.run_if(resource_exists::<StageRecap>))
+                        .run_if(in_state(AppState::Playing))
+                        .run_if(resource_exists::<Demo>),
+                ),
+            );
+    }
+}
+
+fn any_input(keys: &ButtonInput<KeyCode>, pads: &Query<&Gamepad>) -> bool {
+    keys.get_just_pressed().next().is_some() || pads.iter().any(|p| p.digital().get_just_pressed().next().is_some())
+}
+
+fn idle_on_menu(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut idle: ResMut<IdleTimer>,
+    mut commands: Commands,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let busy = keys.get_pressed().next().is_some()
+        || pads.iter().any(|p| p.digital().get_pressed().next().is_some() || p.left_stick().length() > 0.5);
+    if busy {
+        idle.0.reset();
+        return;
+    }
+    if idle.0.tick(time.delta()).just_finished() {
+        commands.insert_resource(Demo);
+        next.set(AppState::Playing);
+    }
+}
+
+fn leave_demo(mut commands: Commands, mut idle: ResMut<IdleTimer>) {
+    commands.remove_resource::<Demo>();
+    commands.remove_resource::<PlayerController>();
+    idle.0.reset();
+}
+
+fn start_demo(mut commands: Commands) {
+    commands.insert_resource(PlayerController(Box::new(SimpleBot::default())));
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(Color::srgb(1.0, 0.9, 0.3)),
+        Node { position_type: PositionType::Absolute, bottom: Val::Px(12.0), left: Val::Px(12.0), ..default() },
+        Tr("attract.banner"),
+        DemoBanner,
+    ));
+}
+
+fn despawn_banner(mut commands: Commands, q: Query<Entity, With<DemoBanner>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn end_demo_on_input(keys: Res<ButtonInput<KeyCode>>, pads: Query<&Gamepad>, mut next: ResMut<NextState<AppState>>) {
+    if any_input(&keys, &pads) { next.set(AppState::Loadout); }
+}
+
+fn back_to_menu(mut next: ResMut<NextState<AppState>>) {
+    next.set(AppState::Loadout);
+}
@@ -0,0 +1,167 @@
+use bevy::audio::{AddAudioSource, Decodable, Source};
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::config::GameConfig;
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::recap::StageRecap;
+use crate::settings;
+use crate::toasts::Notify;
+use crate::{AppState, Base, Bullet, Enemy, Faction, GameSet, TILE};
+
+// Base-under-attack warning: an enemy tank or enemy shell coming within
+// alarm.radius_tiles of the base makes it flash red, sounds a siren and puts
+// up a toast. After that the alarm stays quiet for alarm.cooldown_secs, so a
+// tank parked next to the base doesn't keep it going.
+
+const ALARM_COLOR: Color = Color::srgb(1.0, 0.2, 0.15);
+const BLINK_SECS: f32 = 0.15;
+
+// The siren is made up here rather than loaded, as the game ships no sound
+// files: a square wave stepping between two tones
+const SAMPLE_RATE: u32 = 44_100;
+const SIREN_SECS: f32 = 0.8;
+const SIREN_STEP_SECS: f32 = 0.2;
+const SIREN_TONES: [f32; 2] = [880.0, 660.0];
+const SIREN_LEVEL: f32 = 0.2;
+
+#[derive(Asset, TypePath)]
+struct Siren;
+
+struct SirenDecoder {
+    sample: u32,
+}
+
+impl Iterator for SirenDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let t = self.sample as f32 / SAMPLE_RATE as f32;
+        if t >= SIREN_SECS { return None; }
+        self.sample += 1;
+        let tone = SIREN_TONES[(t / SIREN_STEP_SECS) as usize % SIREN_TONES.len()];
+        Some(if (t * tone).fract() < 0.5 { SIREN_LEVEL } else { -SIREN_LEVEL })
+    }
+}
+
+impl Source for SirenDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(SIREN_SECS))
+    }
+}
+
+impl Decodable for Siren {
+    type DecoderItem = f32;
+    type Decoder = SirenDecoder;
+
+    fn decoder(&self) -> SirenDecoder {
+        SirenDecoder { sample: 0 }
+    }
+}
+
+#[derive(Resource)]
+struct SirenSound(Handle<Siren>);
+
+#[derive(Resource)]
+struct BaseAlarm {
+    cooldown: Timer,
+}
+
+#[derive(Component)]
+struct BaseFlash {
+    timer: Timer,
+    base: Color,
+}
+
+pub struct BaseAlarmPlugin;
+
+impl Plugin for BaseAlarmPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<Siren>()
+            .add_systems(Startup, make_siren)
+            .add_systems(OnEnter(AppState::Playing), reset_alarm)
+            .add_systems(
+                Update,
+                (watch_base, flash_base)
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<StageRecap>)),
+            );
+    }
+}
+
+fn make_siren(mut commands: Commands, mut sirens: ResMut<Assets<Siren>>) {
+    commands.insert_resource(SirenSound(sirens.add(Siren)));
+}
+
+// Ready to go off from the start of a run
+fn reset_alarm(mut commands: Commands, cfg: Res<GameConfig>) {
+    let mut cooldown = Timer::from_seconds(cfg.alarm.cooldown_secs, TimerMode::Once);
+    cooldown.tick(cooldown.duration());
+    commands.insert_resource(BaseAlarm { cooldown });
+}
+
+fn watch_base(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    siren: Res<SirenSound>,
+    profile: Res<Profile>,
+    locale: Res<Locale>,
+    mut alarm: ResMut<BaseAlarm>,
+    mut notify: EventWriter<Notify>,
+    q_base: Query<(Entity, &Transform, &Sprite, Option<&BaseFlash>), With<Base>>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    q_shells: Query<(&Transform, &Faction), With<Bullet>>,
+) {
+    if !alarm.cooldown.tick(time.delta()).finished() { return; }
+    let Ok((base_e, base_t, sprite, flash)) = q_base.single() else { return; };
+    let base = base_t.translation.truncate();
+    let reach = cfg.alarm.radius_tiles * TILE;
+    let near = |t: &Transform| t.translation.truncate().distance_squared(base) <= reach * reach;
+    let threatened =
+        q_enemies.iter().any(near) || q_shells.iter().any(|(t, faction)| *faction == Faction::Enemy && near(t));
+    if !threatened { return; }
+
+    alarm.cooldown.reset();
+    notify.write(Notify::new(locale.t("alarm.base_under_attack")).with_color(ALARM_COLOR));
+    commands.spawn((AudioPlayer(siren.0.clone()), settings::sfx(&profile)));
+    // Keep the colour from before the flash if the last one hasn't finished
+    let base_color = flash.map_or(sprite.color, |f| f.base);
+    commands
+        .entity(base_e)
+        .insert(BaseFlash { timer: Timer::from_seconds(cfg.alarm.flash_secs, TimerMode::Once), base: base_color });
+}
+
+// Held red the whole time with reduced flashes
+fn flash_base(
+    time: Res<Time>,
+    mut commands: Commands,
+    profile: Res<Profile>,
+    mut q: Query<(Entity, &mut BaseFlash, &mut Sprite)>,
+) {
+    for (e, mut flash, mut sprite) in &mut q {
+        if flash.timer.tick(time.delta()).finished() {
+            sprite.color = flash.base;
+            commands.entity(e).remove::<BaseFlash>();
+        } else {
+            let blink_on = ((flash.timer.elapsed_secs() / BLINK_SECS) as u32).is_multiple_of(2);
+            let lit = profile.accessibility.reduced_flashes || blink_on;
+            sprite.color = if lit { ALARM_COLOR } else { flash.base };
+        }
+    }
+}
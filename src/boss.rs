@@ -0,0 +1,265 @@
+use bevy::prelude::*;
+use rand::Rng;
+use std::f32::consts::TAU;
+
+use crate::bullets::{BulletKind, BulletPool, Shot};
+use crate::config::GameConfig;
+use crate::death::{DeathEvent, DeathReason};
+use crate::feedback::CameraShake;
+use crate::floating_text::spawn_floating_text;
+use crate::locale::{Locale, Tr};
+use crate::recap::StageRecap;
+use crate::status::{StatusEffects, StatusKind};
+use crate::waves::LevelEnemyQueue;
+use crate::{
+    AppState, Bullet, EnemyKilled, EnemyKind, Faction, FreezeEnemies, GameRng, GameSet, Health, Player, Size,
+    SpawnPoints, Velocity, quantize_to_cardinal, random_cardinal, spawn_enemy,
+};
+
+// Boss fights on every Nth level: one big tank takes the place of the wave.
+// It gets nastier as it loses health (aimed spread, wider spread, then rings
+// of shells), has a health bar at the top of the screen, and the level clears
+// the normal way once it is destroyed.
+
+const THINK_SECS: f32 = 0.8;
+// Chance a direction change heads for the player rather than anywhere
+const HUNT_CHANCE: f64 = 0.65;
+const RING_SHELLS: usize = 12;
+const BAR_WIDTH: f32 = 240.0;
+const BAR_FILL: Color = Color::srgb(0.85, 0.15, 0.25);
+
+#[derive(Component)]
+pub struct Boss {
+    max_hp: u8,
+    phase: usize,
+    gun: Timer,
+    think: Timer,
+    dir: Vec2,
+}
+
+impl Boss {
+    fn new(cfg: &GameConfig) -> Self {
+        Self {
+            max_hp: EnemyKind::Boss.hit_points(cfg),
+            phase: 0,
+            gun: Timer::from_seconds(fire_secs(0, cfg), TimerMode::Repeating),
+            think: Timer::from_seconds(THINK_SECS, TimerMode::Repeating),
+            dir: Vec2::NEG_Y,
+        }
+    }
+}
+
+// Each phase fires a little faster and drives a little quicker
+fn fire_secs(phase: usize, cfg: &GameConfig) -> f32 {
+    cfg.boss.fire_secs * (1.0 - 0.2 * phase as f32)
+}
+
+fn phase_for(hp: u8, max_hp: u8, cfg: &GameConfig) -> usize {
+    let frac = hp as f32 / max_hp as f32;
+    cfg.boss.phase_thresholds.iter().filter(|&&t| frac <= t).count()
+}
+
+#[derive(Component)]
+struct BossBar;
+
+#[derive(Component)]
+struct BossBarFill;
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), despawn_boss_bar)
+            .add_systems(
+                Update,
+                (spawn_boss, boss_phase, (boss_ai, boss_fire).run_if(not(resource_exists::<FreezeEnemies>)))
+                    .chain()
+                    .in_set(GameSet::Ai)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<StageRecap>)),
+            )
+            // The killing blow usually starts the stage recap in the same frame
+            .add_systems(
+                Update,
+                (boss_defeated, spawn_boss_bar, update_boss_bar, remove_boss_bar)
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+// The level's queue asks for a boss; it rolls out of the first spawn point
+fn spawn_boss(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut queue: ResMut<LevelEnemyQueue>,
+    spawns: Option<Res<SpawnPoints>>,
+    locale: Res<Locale>,
+    mut rng: ResMut<GameRng>,
+) {
+    if !queue.boss { return; }
+    let Some(pos) = spawns.and_then(|s| s.points.first().copied()) else { return; };
+    queue.boss = false;
+
+    let boss = spawn_enemy(&mut commands, &mut rng, pos, EnemyKind::Boss, &cfg);
+    commands.entity(boss).insert(Boss::new(&cfg));
+    spawn_floating_text(&mut commands, pos, locale.t("boss.arrives"), Color::WHITE, 28.0, 1.5, 40.0);
+}
+
+fn boss_phase(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut shake: ResMut<CameraShake>,
+    locale: Res<Locale>,
+    mut q_boss: Query<(&Transform, &Health, &mut Boss)>,
+) {
+    for (t, hp, mut boss) in &mut q_boss {
+        let phase = phase_for(hp.0, boss.max_hp, &cfg);
+        if phase <= boss.phase { continue; }
+        boss.phase = phase;
+        boss.gun = Timer::from_seconds(fire_secs(phase, &cfg), TimerMode::Repeating);
+        shake.add(0.4);
+        let label = locale.f("boss.phase", &[("phase", &(phase + 1))]);
+        spawn_floating_text(&mut commands, t.translation.truncate(), label, Color::srgb(1.0, 0.5, 0.3), 22.0, 1.2, 36.0);
+    }
+}
+
+fn boss_ai(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut q_boss: Query<(&Transform, &mut Velocity, &mut Boss)>,
+    q_player: Query<&Transform, (With<Player>, Without<Boss>)>,
+    mut rng: ResMut<GameRng>,
+) {
+    let player = q_player.single().ok().map(|t| t.translation.truncate());
+    for (t, mut v, mut boss) in &mut q_boss {
+        // Re-picking often also gets it unstuck from walls
+        if boss.think.tick(time.delta()).just_finished() {
+            boss.dir = match player {
+                Some(p) if rng.gen_bool(HUNT_CHANCE) => quantize_to_cardinal(p - t.translation.truncate()),
+                _ => random_cardinal(&mut rng),
+            };
+        }
+        let speed = EnemyKind::Boss.speed(&cfg) * (1.0 + 0.25 * boss.phase as f32);
+        **v = boss.dir * speed;
+    }
+}
+
+fn boss_fire(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut commands: Commands,
+    mut pool: ResMut<BulletPool>,
+    mut q_boss: Query<(Entity, &Transform, &Size, &StatusEffects, &mut Boss)>,
+    q_player: Query<&Transform, (With<Player>, Without<Boss>)>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Ok(player_t) = q_player.single() else { return; };
+    let player_pos = player_t.translation.truncate();
+    let bullet_size = cfg.bullet.size();
+    let speed = EnemyKind::Boss.bullet_speed(&cfg);
+
+    for (boss_e, t, size, effects, mut boss) in &mut q_boss {
+        if !boss.gun.tick(time.delta()).just_finished() || effects.has(StatusKind::Stunned) { continue; }
+        let pos = t.translation.truncate();
+        let aim = (player_pos - pos).try_normalize().unwrap_or(Vec2::NEG_Y);
+
+        // Aimed spreads of 3 then 5 shells, then whole rings at a random twist
+        let dirs: Vec<Vec2> = if boss.phase < 2 {
+            let shells = 3 + 2 * boss.phase;
+            let step = cfg.boss.spread_deg.to_radians();
+            (0..shells)
+                .map(|i| Vec2::from_angle((i as f32 - (shells - 1) as f32 * 0.5) * step).rotate(aim))
+                .collect()
+        } else {
+            let twist = rng.gen_range(0.0..TAU);
+            (0..RING_SHELLS).map(|i| Vec2::from_angle(twist + TAU * i as f32 / RING_SHELLS as f32)).collect()
+        };
+
+        for dir in dirs {
+            pool.fire(
+                &mut commands,
+                Shot {
+                    pos: pos + dir * (size.0.x * 0.6),
+                    velocity: dir * speed,
+                    faction: Faction::Enemy,
+                    owner: boss_e,
+                    kind: BulletKind::Standard,
+                    size: bullet_size,
+                    color: Color::srgb(1.0, 0.5, 0.9),
+                },
+            );
+        }
+    }
+}
+
+// The level clears through level_completion as usual; this is the fanfare,
+// plus wiping the boss's shells still in the air.
+fn boss_defeated(
+    mut commands: Commands,
+    mut deaths: EventWriter<DeathEvent>,
+    mut kills: EventReader<EnemyKilled>,
+    mut shake: ResMut<CameraShake>,
+    locale: Res<Locale>,
+    q_bullets: Query<(Entity, &Faction), With<Bullet>>,
+) {
+    let Some(kill) = kills.read().find(|k| k.kind == EnemyKind::Boss) else { return; };
+    shake.add(1.0);
+    let text = locale.t("boss.defeated");
+    spawn_floating_text(&mut commands, kill.pos, text, Color::srgb(1.0, 0.9, 0.3), 30.0, 2.0, 48.0);
+    for (e, faction) in &q_bullets {
+        if *faction == Faction::Enemy { deaths.write(DeathEvent::new(e, DeathReason::Spent)); }
+    }
+}
+
+// === Health bar ===
+fn spawn_boss_bar(mut commands: Commands, q_new: Query<(), Added<Boss>>, q_bar: Query<(), With<BossBar>>) {
+    if q_new.is_empty() || !q_bar.is_empty() { return; }
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(28.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BossBar,
+        ))
+        .with_children(|root| {
+            root.spawn((
+                Text::new(""),
+                TextFont { font_size: 14.0, ..default() },
+                TextColor(Color::WHITE),
+                Tr("boss.bar"),
+            ));
+            root.spawn((
+                Node { width: Val::Px(BAR_WIDTH), height: Val::Px(10.0), ..default() },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    Node { width: Val::Percent(100.0), height: Val::Percent(100.0), ..default() },
+                    BackgroundColor(BAR_FILL),
+                    BossBarFill,
+                ));
+            });
+        });
+}
+
+fn update_boss_bar(q_boss: Query<(&Health, &Boss), Changed<Health>>, mut q_fill: Query<&mut Node, With<BossBarFill>>) {
+    let Ok((hp, boss)) = q_boss.single() else { return; };
+    for mut node in &mut q_fill {
+        node.width = Val::Percent(100.0 * hp.0 as f32 / boss.max_hp as f32);
+    }
+}
+
+fn remove_boss_bar(commands: Commands, q_boss: Query<(), With<Boss>>, q_bar: Query<Entity, With<BossBar>>) {
+    if q_boss.is_empty() { despawn_boss_bar(commands, q_bar); }
+}
+
+fn despawn_boss_bar(mut commands: Commands, q_bar: Query<Entity, With<BossBar>>) {
+    for e in &q_bar { commands.entity(e).despawn(); }
+}
@@ -0,0 +1,131 @@
+use bevy::prelude::*;
+
+use crate::config::BulletConfig;
+use crate::{Bullet, Faction, Owner, Size, Velocity};
+
+// Shell behaviours. The collision systems only work out what a shell touched
+// and then ask its kind how to respond, so a new kind is a new case here
+// rather than another branch through bullet_hits and bullet_wall_cull.
+//
+// Shells are also pooled: everything that fires goes through BulletPool, and
+// despawn_dead hands spent shells back to it. A spent shell keeps its entity,
+// hidden and without the Bullet, Velocity and Size that every bullet and
+// collision query needs, and the next shot fired overwrites it in place.
+
+#[derive(Component, Clone, Debug, PartialEq)]
+pub enum BulletKind {
+    Standard,
+    // Responds like a standard shell, it just flies faster
+    Fast,
+    // Flies on through tanks until `left` runs out
+    Piercing { left: u8, passed: Vec<Entity> },
+    // Ricochets off walls until `left` runs out
+    Bouncing { left: u8 },
+}
+
+// A shell about to leave the barrel
+pub struct Shot {
+    pub pos: Vec2,
+    pub velocity: Vec2,
+    pub faction: Faction,
+    pub owner: Entity,
+    pub kind: BulletKind,
+    pub size: Vec2,
+    pub color: Color,
+}
+
+#[derive(Resource, Default)]
+pub struct BulletPool {
+    free: Vec<Entity>,
+}
+
+impl BulletPool {
+    pub fn fire(&mut self, commands: &mut Commands, shot: Shot) -> Entity {
+        let shell = (
+            Sprite { color: shot.color, custom_size: Some(shot.size), ..default() },
+            Transform::from_xyz(shot.pos.x, shot.pos.y, 0.5)
+                .with_rotation(Quat::from_rotation_z(shot.velocity.to_angle())),
+            Visibility::Inherited,
+            Bullet,
+            shot.faction,
+            Owner(shot.owner),
+            Velocity(shot.velocity),
+            Size(shot.size),
+            shot.kind,
+        );
+        match self.free.pop() {
+            Some(e) => {
+                commands.entity(e).insert(shell);
+                e
+            }
+            None => commands.spawn(shell).id(),
+        }
+    }
+
+    // Takes a spent shell out of play, to be reused by a later shot
+    pub fn release(&mut self, commands: &mut Commands, e: Entity) {
+        commands.entity(e).remove::<(Bullet, Velocity, Size)>().insert(Visibility::Hidden);
+        self.free.push(e);
+    }
+}
+
+pub enum TankHit {
+    Stop,
+    PassThrough,
+}
+
+impl BulletKind {
+    pub fn piercing(cfg: &BulletConfig) -> Self {
+        BulletKind::Piercing { left: cfg.pierce_count, passed: Vec::new() }
+    }
+
+    pub fn bouncing(cfg: &BulletConfig) -> Self {
+        BulletKind::Bouncing { left: cfg.max_bounces }
+    }
+
+    pub fn speed_factor(&self, cfg: &BulletConfig) -> f32 {
+        match self {
+            BulletKind::Fast => cfg.fast_speed_factor,
+            _ => 1.0,
+        }
+    }
+
+    // Already went through this tank; it can't be hit again on the way out
+    pub fn has_passed(&self, tank: Entity) -> bool {
+        matches!(self, BulletKind::Piercing { passed, .. } if passed.contains(&tank))
+    }
+
+    // Nothing has been hit by this shell yet
+    pub fn first_hit(&self) -> bool {
+        !matches!(self, BulletKind::Piercing { passed, .. } if !passed.is_empty())
+    }
+
+    // The shell damaged a tank; does it carry on?
+    pub fn on_tank_hit(&mut self, tank: Entity) -> TankHit {
+        match self {
+            BulletKind::Piercing { left, passed } if *left > 0 => {
+                *left -= 1;
+                passed.push(tank);
+                TankHit::PassThrough
+            }
+            _ => TankHit::Stop,
+        }
+    }
+
+    // The shell is about to enter a wall, blocked along x and/or y. Returns its
+    // new velocity, or None when it is spent.
+    pub fn on_wall_hit(&mut self, velocity: Vec2, blocked_x: bool, blocked_y: bool) -> Option<Vec2> {
+        match self {
+            BulletKind::Bouncing { left } if *left > 0 => {
+                *left -= 1;
+                // Only the diagonal step hits: a corner, so straight back
+                let corner = !blocked_x && !blocked_y;
+                let mut v = velocity;
+                if blocked_x || corner { v.x = -v.x; }
+                if blocked_y || corner { v.y = -v.y; }
+                Some(v)
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use crate::args::LaunchArgs;
+use crate::locale::Locale;
+use crate::persistence;
+use crate::toasts::Notify;
+
+// Screen capture: F12 saves a screenshot, F10 the last few seconds as an
+// animated PNG. For the clip a small copy of the screen is taken ten times a
+// second into a ring buffer, and the clip is encoded off the main thread so
+// the game doesn't hitch. Both go to captures/ in the data directory.
+
+const CAPTURES_DIR: &str = "captures";
+const CLIP_SECS: f32 = 5.0;
+const CLIP_FPS: u16 = 10;
+const CLIP_FRAMES: usize = (CLIP_SECS * CLIP_FPS as f32) as usize;
+// Clip frames keep every other pixel each way, to keep the buffer small
+const CLIP_SCALE: usize = 2;
+
+#[derive(Resource, Default)]
+struct ClipFrames {
+    // Tightly packed RGBA, oldest first
+    frames: VecDeque<Vec<u8>>,
+    size: UVec2,
+}
+
+#[derive(Resource)]
+struct ClipTimer(Timer);
+
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        // A headless run has no window to capture, and a browser has nowhere to save to
+        if app.world().get_resource::<LaunchArgs>().is_some_and(|a| a.headless) { return; }
+        if cfg!(target_arch = "wasm32") { return; }
+        app.init_resource::<ClipFrames>()
+            .insert_resource(ClipTimer(Timer::from_seconds(1.0 / CLIP_FPS as f32, TimerMode::Repeating)))
+            .add_systems(Update, (grab_clip_frame, capture_keys));
+    }
+}
+
+// Real time, so slow motion and the game speed keys don't stretch the clip
+fn grab_clip_frame(mut commands: Commands, real: Res<Time<Real>>, mut timer: ResMut<ClipTimer>) {
+    if !timer.0.tick(real.delta()).just_finished() { return; }
+    commands.spawn(Screenshot::primary_window()).observe(store_clip_frame);
+}
+
+fn store_clip_frame(trigger: Trigger<ScreenshotCaptured>, mut clip: ResMut<ClipFrames>) {
+    let Ok(image) = trigger.event().0.clone().try_into_dynamic() else { return; };
+    let rgba = image.to_rgba8();
+    let (width, height) = (rgba.width() as usize, rgba.height() as usize);
+    let size = UVec2::new((width / CLIP_SCALE) as u32, (height / CLIP_SCALE) as u32);
+    let pixels = rgba.into_raw();
+    let mut frame = Vec::with_capacity(size.x as usize * size.y as usize * 4);
+    for y in 0..size.y as usize {
+        for x in 0..size.x as usize {
+            let i = (y * CLIP_SCALE * width + x * CLIP_SCALE) * 4;
+            // Opaque whatever the swapchain left in alpha
+            frame.extend_from_slice(&[pixels[i], pixels[i + 1], pixels[i + 2], 255]);
+        }
+    }
+    // A resized window starts the clip over, as the frames must all match
+    if clip.size != size {
+        clip.frames.clear();
+        clip.size = size;
+    }
+    clip.frames.push_back(frame);
+    while clip.frames.len() > CLIP_FRAMES { clip.frames.pop_front(); }
+}
+
+fn capture_keys(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    clip: Res<ClipFrames>,
+    locale: Res<Locale>,
+    mut notify: EventWriter<Notify>,
+) {
+    if keys.just_pressed(KeyCode::F12) {
+        let Some(path) = capture_path("screenshot") else { return; };
+        notify.write(Notify::new(locale.f("capture.screenshot", &[("path", &path.display())])));
+        commands.spawn(Screenshot::primary_window()).observe(save_to_disk(path));
+    }
+    if keys.just_pressed(KeyCode::F10) && !clip.frames.is_empty() {
+        let Some(path) = capture_path("clip") else { return; };
+        notify.write(Notify::new(locale.f("capture.clip", &[("path", &path.display())])));
+        let frames: Vec<Vec<u8>> = clip.frames.iter().cloned().collect();
+        let size = clip.size;
+        std::thread::spawn(move || {
+            if let Err(e) = write_apng(&path, &frames, size) {
+                warn!("failed to save {}: {e}", path.display());
+            }
+        });
+    }
+}
+
+// A fresh file in captures/, named after the time so captures never overwrite each other
+fn capture_path(prefix: &str) -> Option<PathBuf> {
+    let dir = persistence::data_dir().join(CAPTURES_DIR);
+    if let Err(e) = fs::create_dir_all(&dir) {
+        warn!("failed to create {}: {e}", dir.display());
+        return None;
+    }
+    Some(dir.join(format!("{prefix}-{}.png", persistence::unix_millis())))
+}
+
+// Looping, one frame every 1/CLIP_FPS seconds
+fn write_apng(path: &Path, frames: &[Vec<u8>], size: UVec2) -> Result<(), png::EncodingError> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, size.x, size.y);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(1, CLIP_FPS)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(frame)?;
+    }
+    writer.finish()
+}
@@ -0,0 +1,567 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::persistence;
+
+// Gameplay tuning loaded from `config.ron` in the data directory. A file with
+// the defaults is written on first launch; any key left out keeps its default.
+
+const CONFIG_FILE: &str = "config.ron";
+
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct GameConfig {
+    pub player: PlayerConfig,
+    pub bullet: BulletConfig,
+    pub enemy: EnemyConfig,
+    pub powerups: PowerUpConfig,
+    pub utility: UtilityConfig,
+    pub terrain: TerrainConfig,
+    pub handling: HandlingConfig,
+    pub vision: VisionConfig,
+    pub status: StatusConfig,
+    pub boss: BossConfig,
+    pub turret: TurretConfig,
+    pub drops: DropConfig,
+    pub damage: DamageConfig,
+    pub alarm: AlarmConfig,
+    pub horde: HordeConfig,
+}
+
+impl GameConfig {
+    pub fn load() -> Self {
+        if !persistence::exists(CONFIG_FILE) {
+            persistence::save(CONFIG_FILE, &GameConfig::default());
+        }
+        let mut config: GameConfig = persistence::load(CONFIG_FILE);
+
+        // gen_range panics on an empty range
+        let enemy = &mut config.enemy;
+        enemy.wander_change_max = enemy.wander_change_max.max(enemy.wander_change_min + 0.01);
+        config
+    }
+
+    // Scales the loaded values rather than replacing them, so a tweaked
+    // config.ron stays tweaked at every difficulty
+    pub fn apply_difficulty(&mut self, difficulty: Difficulty) {
+        let enemy = &mut self.enemy;
+        match difficulty {
+            Difficulty::Easy => {
+                self.player.lives = self.player.lives.saturating_add(2);
+                enemy.speed *= 0.85;
+                enemy.fire_secs *= 1.5;
+                enemy.spawn_secs *= 1.3;
+                enemy.detect_radius *= 0.8;
+            }
+            Difficulty::Normal => {}
+            Difficulty::Hard => {
+                self.player.lives = self.player.lives.saturating_sub(1).max(1);
+                enemy.speed *= 1.2;
+                enemy.fire_secs *= 0.7;
+                enemy.spawn_secs *= 0.75;
+                enemy.detect_radius *= 1.3;
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    // Key into the language files
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "difficulty.easy",
+            Difficulty::Normal => "difficulty.normal",
+            Difficulty::Hard => "difficulty.hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PlayerConfig {
+    pub speed: f32,
+    pub size: [f32; 2],
+    pub fire_cooldown: f32,
+    // Cooldown multiplier applied per weapon upgrade
+    pub upgrade_cooldown_factor: f32,
+    pub max_upgrade_stack: u8,
+    pub respawn_invuln_secs: f32,
+    // How long the wreck stays on screen before the respawn or game over
+    pub respawn_delay_secs: f32,
+    // Enemies hold fire on, and keep out of, this radius around the start tile after a respawn
+    pub spawn_protect_secs: f32,
+    pub spawn_protect_radius: f32,
+    pub lives: u8,
+    // With aim assist on, a shot bends towards the nearest enemy within this
+    // many degrees of the barrel and this distance
+    pub aim_assist_degrees: f32,
+    pub aim_assist_range: f32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            speed: 300.0,
+            size: [28.0, 28.0],
+            fire_cooldown: 0.16,
+            upgrade_cooldown_factor: 0.8,
+            max_upgrade_stack: 3,
+            respawn_invuln_secs: 2.0,
+            respawn_delay_secs: 1.5,
+            spawn_protect_secs: 1.0,
+            spawn_protect_radius: 100.0,
+            lives: 3,
+            aim_assist_degrees: 12.0,
+            aim_assist_range: 400.0,
+        }
+    }
+}
+
+impl PlayerConfig {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BulletConfig {
+    pub speed: f32,
+    pub size: [f32; 2],
+    // Speed multiplier for fast shells (upgraded player guns, power tanks)
+    pub fast_speed_factor: f32,
+    // Tanks a piercing shell goes through before it stops in the next one
+    pub pierce_count: u8,
+    pub max_bounces: u8,
+}
+
+impl Default for BulletConfig {
+    fn default() -> Self {
+        Self { speed: 600.0, size: [6.0, 12.0], fast_speed_factor: 1.5, pierce_count: 1, max_bounces: 2 }
+    }
+}
+
+impl BulletConfig {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EnemyConfig {
+    pub speed: f32,
+    pub size: [f32; 2],
+    pub spawn_secs: f32,
+    pub fire_secs: f32,
+    pub detect_radius: f32,
+    pub chase_reaction_secs: f32,
+    pub wander_change_min: f32,
+    pub wander_change_max: f32,
+    pub roam_speed_factor: f32,
+    pub spawn_telegraph_secs: f32,
+    // Spawn points this close to the player (in tiles) are passed over
+    pub spawn_min_player_tiles: f32,
+    // Pick among the usable spawn points at random instead of in turn
+    pub random_spawn_points: bool,
+    pub morale_radius: f32,
+    // Nearby deaths (after decay) that send a tank running
+    pub morale_break: f32,
+    // Time for one death's worth of shock to fade
+    pub morale_recover_secs: f32,
+    pub retreat_secs: f32,
+    // Enemies closer than this steer apart; weight is relative to their own heading
+    pub separation_radius: f32,
+    pub separation_weight: f32,
+    // Saboteurs drop a mine this often, while fewer than the cap are on the field
+    pub saboteur_mine_secs: f32,
+    pub max_enemy_mines: usize,
+    // Each time a roaming tank picks a new direction, the chance it makes for
+    // the base instead, and how long it keeps at it before roaming again
+    pub siege_chance: f32,
+    pub siege_secs: f32,
+    // How far off the player, in tiles, chasing tanks sent round to flank them
+    // take up their places; 0 sends them all straight on
+    pub flank_tiles: u32,
+    // A tank trying to drive this long without getting anywhere is turned aside
+    pub stuck_secs: f32,
+    // An armoured tank down to its last hit point falls back this long to
+    // repair; 0 has it fight on
+    pub repair_secs: f32,
+}
+
+impl Default for EnemyConfig {
+    fn default() -> Self {
+        Self {
+            speed: 180.0,
+            size: [24.0, 24.0],
+            spawn_secs: 1.25,
+            fire_secs: 1.1,
+            detect_radius: 240.0,
+            chase_reaction_secs: 0.45,
+            wander_change_min: 1.2,
+            wander_change_max: 2.2,
+            roam_speed_factor: 0.75,
+            spawn_telegraph_secs: 1.0,
+            spawn_min_player_tiles: 3.0,
+            random_spawn_points: false,
+            morale_radius: 160.0,
+            morale_break: 3.0,
+            morale_recover_secs: 4.0,
+            retreat_secs: 4.0,
+            separation_radius: 48.0,
+            separation_weight: 0.8,
+            saboteur_mine_secs: 5.0,
+            max_enemy_mines: 6,
+            siege_chance: 0.15,
+            siege_secs: 20.0,
+            flank_tiles: 3,
+            stuck_secs: 1.5,
+            repair_secs: 10.0,
+        }
+    }
+}
+
+impl EnemyConfig {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PowerUpConfig {
+    pub fortify_secs: f32,
+    pub freeze_secs: f32,
+    pub emp_radius: f32,
+    pub emp_stun_secs: f32,
+    // Mines granted by the mines power-up
+    pub mine_charges: u8,
+}
+
+impl Default for PowerUpConfig {
+    fn default() -> Self {
+        Self {
+            fortify_secs: 12.0,
+            freeze_secs: 8.0,
+            emp_radius: 200.0,
+            emp_stun_secs: 3.0,
+            mine_charges: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct UtilityConfig {
+    pub dash_secs: f32,
+    pub dash_speed_factor: f32,
+    pub smoke_secs: f32,
+    pub smoke_radius: f32,
+    pub mine_arm_secs: f32,
+    pub mine_size: [f32; 2],
+    pub mine_blast_radius: f32,
+    // Hits dealt to every tank in the blast
+    pub mine_damage: u8,
+}
+
+impl Default for UtilityConfig {
+    fn default() -> Self {
+        Self {
+            dash_secs: 0.18,
+            dash_speed_factor: 3.0,
+            smoke_secs: 4.0,
+            smoke_radius: 70.0,
+            mine_arm_secs: 0.5,
+            mine_size: [14.0, 14.0],
+            mine_blast_radius: 60.0,
+            mine_damage: 2,
+        }
+    }
+}
+
+impl UtilityConfig {
+    pub fn mine_size(&self) -> Vec2 {
+        Vec2::from(self.mine_size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TerrainConfig {
+    // Higher = momentum on ice catches up with input faster
+    pub ice_grip: f32,
+    // A tank driving into a wall whose edge is at most this far off to the
+    // side gets nudged around the corner instead of sticking
+    pub corner_slide_px: f32,
+    // How fast a conveyor belt carries tanks along, in pixels per second
+    pub conveyor_speed: f32,
+    // How long a teleporter pair rests after a jump
+    pub teleport_cooldown_secs: f32,
+}
+
+impl Default for TerrainConfig {
+    fn default() -> Self {
+        Self { ice_grip: 2.5, corner_slide_px: 12.0, conveyor_speed: 60.0, teleport_cooldown_secs: 1.0 }
+    }
+}
+
+// How tanks respond to their controls. The default arcade handling snaps to
+// the new speed and direction at once; heavy handling eases instead.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HandlingConfig {
+    pub heavy_tanks: bool,
+    // Roughly how long a heavy tank takes to reach full speed or stop
+    pub accel_secs: f32,
+    // How fast a heavy tank's hull swings round to a new heading
+    pub turn_rate_degs: f32,
+}
+
+impl Default for HandlingConfig {
+    fn default() -> Self {
+        Self { heavy_tanks: false, accel_secs: 0.2, turn_rate_degs: 540.0 }
+    }
+}
+
+// Fog of war: with it on, only tiles near the player and in plain sight of it
+// are shown; the rest of the level stays dark.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct VisionConfig {
+    pub fog_of_war: bool,
+    // How far the player sees, in pixels
+    pub radius: f32,
+}
+
+impl Default for VisionConfig {
+    fn default() -> Self {
+        Self { fog_of_war: false, radius: 200.0 }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StatusConfig {
+    pub burn_secs: f32,
+    pub burn_tick_secs: f32,
+    pub burn_max_stacks: u8,
+    pub barrel_blast_radius: f32,
+    pub tar_slow_factor: f32,
+    // How long the slow hangs on after leaving tar
+    pub tar_linger_secs: f32,
+}
+
+impl Default for StatusConfig {
+    fn default() -> Self {
+        Self {
+            burn_secs: 3.0,
+            burn_tick_secs: 1.0,
+            burn_max_stacks: 3,
+            barrel_blast_radius: 80.0,
+            tar_slow_factor: 0.5,
+            tar_linger_secs: 0.3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct BossConfig {
+    // Every Nth level is a boss fight instead of a wave; 0 turns bosses off
+    pub every_n_levels: usize,
+    pub hit_points: u8,
+    pub size: [f32; 2],
+    pub speed: f32,
+    pub fire_secs: f32,
+    // Angle between neighbouring shells of a spread
+    pub spread_deg: f32,
+    // Health fractions at which the boss moves to its second and third phase
+    pub phase_thresholds: [f32; 2],
+}
+
+impl Default for BossConfig {
+    fn default() -> Self {
+        Self {
+            every_n_levels: 5,
+            hit_points: 30,
+            size: [36.0, 36.0],
+            speed: 90.0,
+            fire_secs: 1.6,
+            spread_deg: 15.0,
+            phase_thresholds: [0.66, 0.33],
+        }
+    }
+}
+
+impl BossConfig {
+    pub fn size(&self) -> Vec2 {
+        Vec2::from(self.size)
+    }
+}
+
+// Fixed gun emplacements (maze glyph `T`)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct TurretConfig {
+    pub hit_points: u8,
+    // Only a player closer than this, in plain sight, is tracked
+    pub range: f32,
+    pub turn_rate_degs: f32,
+    pub fire_secs: f32,
+    // How far off the player the barrel may be and still fire
+    pub aim_tolerance_degs: f32,
+    pub bullet_speed: f32,
+}
+
+impl Default for TurretConfig {
+    fn default() -> Self {
+        Self {
+            hit_points: 4,
+            range: 320.0,
+            turn_rate_degs: 120.0,
+            fire_secs: 1.8,
+            aim_tolerance_degs: 5.0,
+            bullet_speed: 260.0,
+        }
+    }
+}
+
+// What a destroyed tank leaves behind, by kind. A power-up drops with
+// `powerup_chance` and is then picked by weight; coins are rolled separately.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DropTable {
+    pub powerup_chance: f32,
+    pub weapon_weight: f32,
+    pub shovel_weight: f32,
+    pub timer_weight: f32,
+    pub emp_weight: f32,
+    pub mines_weight: f32,
+    pub coin_chance: f32,
+    pub coins: u32,
+}
+
+impl Default for DropTable {
+    fn default() -> Self {
+        Self {
+            powerup_chance: 0.6,
+            weapon_weight: 0.55,
+            shovel_weight: 0.2,
+            timer_weight: 0.15,
+            emp_weight: 0.1,
+            mines_weight: 0.1,
+            coin_chance: 0.5,
+            coins: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DropConfig {
+    pub basic: DropTable,
+    pub fast: DropTable,
+    pub power: DropTable,
+    pub armor: DropTable,
+    pub saboteur: DropTable,
+    pub boss: DropTable,
+    // Pickups closer than this to the player drift toward it
+    pub magnet_radius: f32,
+    pub magnet_speed: f32,
+}
+
+impl Default for DropConfig {
+    fn default() -> Self {
+        Self {
+            basic: DropTable::default(),
+            fast: DropTable { powerup_chance: 0.75, coins: 2, ..default() },
+            power: DropTable { powerup_chance: 0.9, coin_chance: 0.75, coins: 2, ..default() },
+            armor: DropTable { powerup_chance: 1.0, coin_chance: 1.0, coins: 3, ..default() },
+            saboteur: DropTable { powerup_chance: 0.8, mines_weight: 0.4, coins: 2, ..default() },
+            boss: DropTable { powerup_chance: 1.0, weapon_weight: 1.0, coin_chance: 1.0, coins: 10, ..default() },
+            magnet_radius: 60.0,
+            magnet_speed: 220.0,
+        }
+    }
+}
+
+// Who shells can hurt, by the shooter's faction
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct DamageConfig {
+    // Player shells hurt other player tanks (co-op teammates)
+    pub friendly_fire: bool,
+    // Enemy shells hurt other enemy tanks
+    pub chaos: bool,
+    pub player_breaks_brick: bool,
+    pub enemy_breaks_brick: bool,
+    // A shell reaching the base destroys it and ends the run
+    pub player_hits_base: bool,
+    pub enemy_hits_base: bool,
+}
+
+impl Default for DamageConfig {
+    fn default() -> Self {
+        Self {
+            friendly_fire: false,
+            chaos: false,
+            player_breaks_brick: true,
+            enemy_breaks_brick: true,
+            player_hits_base: false,
+            enemy_hits_base: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AlarmConfig {
+    // Enemy tanks or shells this close to the base, in tiles, sound the alarm
+    pub radius_tiles: f32,
+    // Quiet time after each alarm, so a siege doesn't keep it going
+    pub cooldown_secs: f32,
+    pub flash_secs: f32,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> Self {
+        Self { radius_tiles: 3.0, cooldown_secs: 10.0, flash_secs: 1.5 }
+    }
+}
+
+// Horde mode: coins to build with before the first wave, paid for each wave
+// held off, and what each tile costs
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct HordeConfig {
+    pub start_coins: u32,
+    pub wave_coins: u32,
+    pub brick_cost: u32,
+    pub steel_cost: u32,
+}
+
+impl Default for HordeConfig {
+    fn default() -> Self {
+        Self { start_coins: 20, wave_coins: 15, brick_cost: 4, steel_cost: 12 }
+    }
+}
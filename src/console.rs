@@ -0,0 +1,153 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::game_time::GameTime;
+use crate::locale::Locale;
+
+// Debug console: ` (backquote) during a run opens a command line at the
+// bottom of the screen, Enter runs what's been typed and ` closes it again.
+// The tank ignores the keyboard while it's open. What a command did, or why
+// it couldn't, shows on the line above, in the player's language; the
+// commands themselves stay in English. They are:
+//
+//   speed [factor]  set the game speed (1 is normal), or say what it is
+//   pause           stop or restart the game clock
+
+const TOGGLE_KEY: KeyCode = KeyCode::Backquote;
+const MAX_LEN: usize = 40;
+const PROMPT_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+const REPLY_COLOR: Color = Color::srgb(0.6, 0.9, 0.6);
+
+// Present while the console is open
+#[derive(Resource, Default)]
+pub struct Console {
+    line: String,
+    reply: String,
+}
+
+#[derive(Component)]
+struct ConsolePanel;
+
+#[derive(Component)]
+struct ConsoleReply;
+
+#[derive(Component)]
+struct ConsolePrompt;
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), close_console).add_systems(
+            Update,
+            (toggle_console, (console_input, refresh_console).chain().run_if(resource_exists::<Console>))
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+// Gameplay keys reach the tank only while the console is closed
+pub fn console_closed(console: Option<Res<Console>>) -> bool {
+    console.is_none()
+}
+
+fn toggle_console(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    console: Option<Res<Console>>,
+    q_panel: Query<Entity, With<ConsolePanel>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) { return; }
+    if console.is_some() {
+        close_console(commands, q_panel);
+        return;
+    }
+    commands.insert_resource(Console::default());
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            // Over the HUD and menus, under the toasts
+            GlobalZIndex(8),
+            ConsolePanel,
+        ))
+        .with_children(|panel| {
+            let font = TextFont { font_size: 16.0, ..default() };
+            panel.spawn((Text::new(""), font.clone(), TextColor(REPLY_COLOR), ConsoleReply));
+            panel.spawn((Text::new("> _"), font, TextColor(PROMPT_COLOR), ConsolePrompt));
+        });
+}
+
+fn close_console(mut commands: Commands, q_panel: Query<Entity, With<ConsolePanel>>) {
+    commands.remove_resource::<Console>();
+    for e in &q_panel { commands.entity(e).despawn(); }
+}
+
+fn console_input(
+    mut typed: EventReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+    mut game_time: ResMut<GameTime>,
+    locale: Res<Locale>,
+) {
+    // The key that opens and closes the console types nothing
+    for ev in typed.read().filter(|ev| ev.state.is_pressed() && ev.key_code != TOGGLE_KEY) {
+        match &ev.logical_key {
+            Key::Backspace => { console.line.pop(); }
+            Key::Enter => {
+                let line = std::mem::take(&mut console.line);
+                console.reply = run_command(&line, &mut game_time, &locale);
+            }
+            Key::Character(s) => {
+                for c in s.chars().filter(|c| !c.is_control()) {
+                    if console.line.len() < MAX_LEN { console.line.push(c); }
+                }
+            }
+            Key::Space if console.line.len() < MAX_LEN => console.line.push(' '),
+            _ => {}
+        }
+    }
+}
+
+fn run_command(line: &str, game_time: &mut GameTime, locale: &Locale) -> String {
+    let mut words = line.split_whitespace();
+    match (words.next(), words.next(), words.next()) {
+        (None, ..) => String::new(),
+        (Some("speed"), None, _) => locale.f("console.speed", &[("speed", &game_time.speed)]),
+        (Some("speed"), Some(factor), None) => match factor.parse::<f32>() {
+            Ok(factor) if factor > 0.0 => {
+                game_time.set_speed(factor);
+                locale.f("console.speed", &[("speed", &game_time.speed)])
+            }
+            _ => locale.f("console.speed_wants", &[("factor", &factor)]),
+        },
+        (Some("pause"), None, _) => {
+            game_time.paused = !game_time.paused;
+            locale.t(if game_time.paused { "console.paused" } else { "console.resumed" }).to_string()
+        }
+        (Some(command @ ("speed" | "pause")), ..) => locale.f("console.too_many", &[("command", &command)]),
+        (Some(command), ..) => locale.f("console.unknown", &[("command", &command)]),
+    }
+}
+
+fn refresh_console(
+    console: Res<Console>,
+    mut q_reply: Query<&mut Text, (With<ConsoleReply>, Without<ConsolePrompt>)>,
+    mut q_prompt: Query<&mut Text, With<ConsolePrompt>>,
+) {
+    if !console.is_changed() { return; }
+    for mut text in &mut q_reply {
+        text.0.clone_from(&console.reply);
+    }
+    for mut text in &mut q_prompt {
+        text.0 = format!("> {}_", console.line);
+    }
+}
@@ -0,0 +1,160 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+use rand::{Rng, thread_rng};
+
+use crate::intent::PlayerIntent;
+use crate::{AppState, Bullet, Enemy, Faction, Player, Velocity};
+
+// Pluggable drivers for the player tank. A TankController sees a plain
+// snapshot of the field each frame and answers with a PlayerIntent, exactly
+// what the keyboard would produce, so anything driving through here plays by
+// the normal rules. Install one with the PlayerController resource; while it
+// exists the devices are ignored. SimpleBot is the built-in one the attract
+// mode uses.
+
+// What a controller gets to see
+pub struct WorldView {
+    // Game-clock seconds since the last decision
+    pub dt: f32,
+    pub player: Option<TankView>,
+    pub enemies: Vec<TankView>,
+    // Enemy shells in flight: position and velocity
+    pub enemy_shells: Vec<(Vec2, Vec2)>,
+}
+
+pub struct TankView {
+    pub pos: Vec2,
+    // Unit vector the gun points along
+    pub facing: Vec2,
+}
+
+pub trait TankController: Send + Sync + 'static {
+    fn decide(&mut self, view: &WorldView) -> PlayerIntent;
+}
+
+#[derive(Resource)]
+pub struct PlayerController(pub Box<dyn TankController>);
+
+pub struct ControllerPlugin;
+
+impl Plugin for ControllerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PreUpdate,
+            drive_player
+                .after(InputSystem)
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_exists::<PlayerController>),
+        );
+    }
+}
+
+fn tank_view(t: &Transform) -> TankView {
+    TankView { pos: t.translation.truncate(), facing: t.rotation.mul_vec3(Vec3::X).truncate() }
+}
+
+fn drive_player(
+    time: Res<Time>,
+    mut controller: ResMut<PlayerController>,
+    mut intent: ResMut<PlayerIntent>,
+    q_player: Query<&Transform, With<Player>>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    q_shells: Query<(&Transform, &Velocity, &Faction), With<Bullet>>,
+) {
+    let view = WorldView {
+        dt: time.delta_secs(),
+        player: q_player.single().ok().map(tank_view),
+        enemies: q_enemies.iter().map(tank_view).collect(),
+        enemy_shells: q_shells
+            .iter()
+            .filter(|(_, _, f)| **f == Faction::Enemy)
+            .map(|(t, v, _)| (t.translation.truncate(), **v))
+            .collect(),
+    };
+    *intent = controller.0.decide(&view);
+}
+
+// === Built-in bot ===
+// How often the bot picks a new heading, and how far it must have moved since
+// the last pick not to count as stuck
+const RETHINK_SECS: f32 = 0.6;
+const STUCK_PX: f32 = 6.0;
+// Within this many pixels of an enemy's row or column, the bot turns and fires
+const ALIGN_PX: f32 = 12.0;
+// Enemy shells closer than this and coming straight at the bot get sidestepped
+const DODGE_PX: f32 = 120.0;
+
+// Hunts the nearest enemy: closes in on its row or column, then faces it and
+// fires. A bot that makes no headway picks a random way out and shoots
+// whatever is in front of it, which clears bricks. When not lined up for a
+// shot, it steps out of the way of incoming shells.
+pub struct SimpleBot {
+    rethink: f32,
+    heading: Vec2,
+    last_pos: Vec2,
+    stuck: bool,
+}
+
+impl Default for SimpleBot {
+    fn default() -> Self {
+        Self { rethink: RETHINK_SECS, heading: Vec2::Y, last_pos: Vec2::ZERO, stuck: false }
+    }
+}
+
+impl TankController for SimpleBot {
+    fn decide(&mut self, view: &WorldView) -> PlayerIntent {
+        let mut intent = PlayerIntent::default();
+        let Some(me) = &view.player else { return intent; };
+        let pos = me.pos;
+        let target = view
+            .enemies
+            .iter()
+            .map(|e| e.pos)
+            .min_by(|a, b| a.distance_squared(pos).total_cmp(&b.distance_squared(pos)));
+
+        self.rethink -= view.dt;
+        if self.rethink <= 0.0 {
+            self.rethink += RETHINK_SECS;
+            self.stuck = self.last_pos.distance(pos) < STUCK_PX;
+            self.last_pos = pos;
+            self.heading = match target {
+                Some(target) if !self.stuck => {
+                    // Close the smaller gap first, to line up sooner
+                    let to = target - pos;
+                    if to.x.abs() < to.y.abs() { Vec2::new(to.x.signum(), 0.0) } else { Vec2::new(0.0, to.y.signum()) }
+                }
+                _ => [Vec2::X, Vec2::NEG_X, Vec2::Y, Vec2::NEG_Y][thread_rng().gen_range(0..4)],
+            };
+        }
+
+        let lined_up = target.and_then(|target| {
+            let to = target - pos;
+            if to.x.abs() < ALIGN_PX { Some(Vec2::new(0.0, to.y.signum())) }
+            else if to.y.abs() < ALIGN_PX { Some(Vec2::new(to.x.signum(), 0.0)) }
+            else { None }
+        });
+        match lined_up {
+            Some(dir) => {
+                intent.movement = dir;
+                intent.fire = me.facing.dot(dir) > 0.9;
+            }
+            None => {
+                intent.movement = incoming(view, pos).map_or(self.heading, |v| v.perp().normalize_or_zero());
+                intent.fire = self.stuck;
+            }
+        }
+        intent
+    }
+}
+
+// Velocity of the nearest enemy shell headed right at `pos`, if any is close
+fn incoming(view: &WorldView, pos: Vec2) -> Option<Vec2> {
+    view.enemy_shells
+        .iter()
+        .filter(|(p, v)| {
+            let to_me = pos - *p;
+            to_me.length() < DODGE_PX && to_me.normalize_or_zero().dot(v.normalize_or_zero()) > 0.95
+        })
+        .min_by(|a, b| a.0.distance_squared(pos).total_cmp(&b.0.distance_squared(pos)))
+        .map(|(_, v)| *v)
+}
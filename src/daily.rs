@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::attract::Demo;
+use crate::config::GameConfig;
+use crate::locale::Locale;
+use crate::persistence;
+use crate::scores::Score;
+use crate::toasts::Notify;
+use crate::waves::{GameMode, LevelOrder};
+use crate::{AppState, GameRng, seed_run, start_run};
+
+// Daily challenge: the campaign with its seed and level order taken from
+// today's date (UTC), so everyone playing on the same day gets the same run.
+// The levels leading up to each boss come in a shuffled order; the boss levels
+// stay where they are. The best score for each day is kept in daily.ron.
+
+const DAILY_FILE: &str = "daily.ron";
+// Stages whose order is shuffled; the run goes on as usual after them
+const DAILY_STAGES: usize = 20;
+const BEST_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+
+// Best score per day, keyed by the date as YYYY-MM-DD
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct DailyBests {
+    pub scores: BTreeMap<String, u32>,
+}
+
+impl DailyBests {
+    pub fn load() -> Self {
+        persistence::load(DAILY_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(DAILY_FILE, self);
+    }
+
+    // For the loadout screen: today's date, and the best so far if it's been played
+    pub fn describe_today(&self, locale: &Locale) -> String {
+        let date = date_key(today());
+        match self.scores.get(&date) {
+            Some(best) => locale.f("daily.today_best", &[("date", &date), ("best", best)]),
+            None => date,
+        }
+    }
+}
+
+// The day being played, fixed as the run starts so midnight doesn't move it
+#[derive(Resource)]
+struct DailyRun {
+    date: String,
+}
+
+pub struct DailyPlugin;
+
+impl Plugin for DailyPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(DailyBests::load())
+            .add_systems(OnEnter(AppState::Playing), set_level_order.after(seed_run).before(start_run))
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                record_best.run_if(resource_exists::<DailyRun>).run_if(not(resource_exists::<Demo>)),
+            );
+    }
+}
+
+// (year, month, day) in UTC, from the days since 1970 (Howard Hinnant's civil_from_days)
+fn today() -> (i32, u32, u32) {
+    let z = (persistence::unix_millis() / 86_400_000) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = (yoe + era * 400 + i64::from(month <= 2)) as i32;
+    (year, month, day)
+}
+
+fn date_key((year, month, day): (i32, u32, u32)) -> String {
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+// Spread out with splitmix64, so that neighbouring days' runs (and their
+// shuffles) don't start from neighbouring seeds
+fn date_seed((year, month, day): (i32, u32, u32)) -> u64 {
+    let mut z = (year as u64 * 10_000 + month as u64 * 100 + day as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+// Shuffled a boss stretch at a time, each boss level left last in its stretch
+fn shuffled_levels(seed: u64, boss_every: usize) -> Vec<usize> {
+    let mut shuffle = StdRng::seed_from_u64(seed);
+    let mut order: Vec<usize> = (0..DAILY_STAGES).collect();
+    let stretch = if boss_every == 0 { DAILY_STAGES } else { boss_every };
+    for levels in order.chunks_mut(stretch) {
+        let keep = usize::from(boss_every > 0 && levels.len() == stretch);
+        let n = levels.len() - keep;
+        levels[..n].shuffle(&mut shuffle);
+    }
+    order
+}
+
+// Ahead of start_run, which lines up the first stage from the order, and
+// after seed_run, so that the date's seed replaces the launch one: from here
+// every gameplay roll of the run comes from the date
+fn set_level_order(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mode: Res<GameMode>,
+    mut order: ResMut<LevelOrder>,
+    mut rng: ResMut<GameRng>,
+) {
+    commands.remove_resource::<DailyRun>();
+    if *mode != GameMode::Daily {
+        order.0.clear();
+        return;
+    }
+    let date = today();
+    let seed = date_seed(date);
+    *rng = GameRng::new(seed);
+    order.0 = shuffled_levels(seed, cfg.boss.every_n_levels);
+    commands.insert_resource(DailyRun { date: date_key(date) });
+}
+
+fn record_best(
+    run: Res<DailyRun>,
+    score: Res<Score>,
+    locale: Res<Locale>,
+    mut bests: ResMut<DailyBests>,
+    mut notify: EventWriter<Notify>,
+) {
+    let best = bests.scores.get(&run.date).copied().unwrap_or(0);
+    if score.0 > best {
+        bests.scores.insert(run.date.clone(), score.0);
+        bests.save();
+        let text = locale.f("daily.new_best", &[("date", &run.date)]);
+        notify.write(Notify::new(text).with_color(BEST_COLOR));
+    } else {
+        notify.write(Notify::new(locale.f("daily.best", &[("date", &run.date), ("best", &best)])));
+    }
+}
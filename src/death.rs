@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::bullets::BulletPool;
+use crate::{BonusTank, Bullet, Enemy, EnemyKilled, EnemyKind, Faction};
+
+// Everything that takes a tank, tile, shell or pickup out of play during a
+// level sends a DeathEvent rather than despawning it on the spot. One system
+// at the end of the frame despawns each entity once, however many events name
+// it, and announces enemy kills with EnemyKilled, so score, drops, recap and
+// stats count a tank once even when two shells finish it in the same frame.
+// Kills carry the credit of whatever shell or mine made them. Shells go back
+// to the BulletPool instead of being despawned.
+// Tearing a whole level down (restarts, leaving Playing) and short-lived
+// visuals still despawn directly.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeathReason {
+    // Destroyed by a shell
+    Shot,
+    // Caught in a mine blast
+    Blast,
+    Burned,
+    // Run over by a patrolling barrier
+    Crushed,
+    // A shell or mine that hit something and is used up
+    Spent,
+    // A pickup or coin the player drove over
+    Collected,
+}
+
+impl DeathReason {
+    pub fn is_kill(self) -> bool {
+        matches!(self, DeathReason::Shot | DeathReason::Blast | DeathReason::Burned | DeathReason::Crushed)
+    }
+}
+
+// Whose shell or mine it was. The faction is kept alongside because the
+// owner may be gone (blown up, despawned) by the time anyone asks; an owner
+// equal to the dead entity is a self-kill.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Credit {
+    pub owner: Entity,
+    pub faction: Faction,
+}
+
+#[derive(Event)]
+pub struct DeathEvent {
+    pub entity: Entity,
+    pub reason: DeathReason,
+    // None for hazards (burning) and for things that aren't kills
+    pub credit: Option<Credit>,
+}
+
+impl DeathEvent {
+    pub fn new(entity: Entity, reason: DeathReason) -> Self {
+        Self { entity, reason, credit: None }
+    }
+
+    pub fn credited(entity: Entity, reason: DeathReason, credit: Option<Credit>) -> Self {
+        Self { entity, reason, credit }
+    }
+}
+
+pub struct DeathPlugin;
+
+impl Plugin for DeathPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<DeathEvent>().add_systems(PostUpdate, despawn_dead);
+    }
+}
+
+pub fn despawn_dead(
+    mut commands: Commands,
+    mut deaths: EventReader<DeathEvent>,
+    mut killed: EventWriter<EnemyKilled>,
+    mut pool: ResMut<BulletPool>,
+    q_alive: Query<()>,
+    q_bullets: Query<(), With<Bullet>>,
+    q_enemies: Query<(&Transform, &EnemyKind, Has<BonusTank>), With<Enemy>>,
+) {
+    let mut done = HashSet::new();
+    for death in deaths.read() {
+        if !done.insert(death.entity) || q_alive.get(death.entity).is_err() { continue; }
+        let enemy = q_enemies.get(death.entity).ok().filter(|_| death.reason.is_kill());
+        if let Some((t, &kind, bonus)) = enemy {
+            // Loot, score and stats are all driven by this event
+            killed.write(EnemyKilled { pos: t.translation.truncate(), kind, bonus, credit: death.credit });
+        }
+        if q_bullets.contains(death.entity) {
+            pool.release(&mut commands, death.entity);
+        } else {
+            commands.entity(death.entity).despawn();
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::unstick::EnemyStuck;
+use crate::{
+    AppState, Blocks, Bullet, Enemy, EnemyAI, EnemyState, LevelBounds, Player, PlayerStart, Size, SpawnPoints, TILE,
+};
+
+// F3 debug overlay: every collision box, the tile grid, spawn points, and for
+// each enemy its AI state and where it is currently heading. Boxes are drawn
+// with gizmos, so they show exactly what the collision code sees this frame.
+// Where enemies got stuck this run is marked too, and logged the first time
+// it happens on each cell, overlay or not.
+
+const GRID: Color = Color::srgba(1.0, 1.0, 1.0, 0.08);
+const BOX_TANK: Color = Color::srgb(0.2, 1.0, 0.3);
+const BOX_ENEMY: Color = Color::srgb(1.0, 0.3, 0.3);
+const BOX_BULLET: Color = Color::srgb(1.0, 1.0, 0.3);
+const BOX_WALL: Color = Color::srgba(0.4, 0.6, 1.0, 0.6);
+const BOX_OTHER: Color = Color::srgba(0.8, 0.8, 0.8, 0.4);
+const SPAWN: Color = Color::srgb(1.0, 0.5, 0.1);
+const WAYPOINT: Color = Color::srgb(1.0, 0.4, 1.0);
+const STUCK: Color = Color::srgb(1.0, 0.1, 0.1);
+const LABEL_OFFSET: f32 = 22.0;
+// The newest are kept
+const MAX_STUCK_SPOTS: usize = 64;
+
+// Present while the overlay is shown
+#[derive(Resource)]
+struct DebugOverlay;
+
+// Floating AI state text that follows an enemy
+#[derive(Component)]
+struct DebugLabel(Entity);
+
+// Where enemies got stuck, and the cells already logged this run
+#[derive(Resource, Default)]
+struct StuckSpots {
+    spots: Vec<Vec2>,
+    logged: HashSet<UVec2>,
+}
+
+pub struct DebugPlugin;
+
+impl Plugin for DebugPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StuckSpots>()
+            .add_systems(Update, (toggle_overlay, note_stuck))
+            .add_systems(OnExit(AppState::Playing), (despawn_labels, clear_stuck))
+            .add_systems(
+                Update,
+                (draw_grid, draw_boxes, draw_spawns, draw_waypoints, draw_stuck, update_labels)
+                    .run_if(resource_exists::<DebugOverlay>)
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn toggle_overlay(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    overlay: Option<Res<DebugOverlay>>,
+    q_labels: Query<Entity, With<DebugLabel>>,
+) {
+    if !keys.just_pressed(KeyCode::F3) { return; }
+    if overlay.is_some() {
+        commands.remove_resource::<DebugOverlay>();
+        despawn_labels(commands, q_labels);
+    } else {
+        commands.insert_resource(DebugOverlay);
+    }
+}
+
+fn despawn_labels(mut commands: Commands, q_labels: Query<Entity, With<DebugLabel>>) {
+    for e in &q_labels { commands.entity(e).despawn(); }
+}
+
+// Cells are (column,row) from the top left, as in level scripts
+fn note_stuck(mut stuck: EventReader<EnemyStuck>, bounds: Option<Res<LevelBounds>>, mut spots: ResMut<StuckSpots>) {
+    for event in stuck.read() {
+        let cell = bounds.as_ref().and_then(|b| b.cell_at(event.pos));
+        match cell {
+            Some(c) if spots.logged.insert(c) => info!("enemy {} stuck at ({},{})", event.enemy, c.x, c.y),
+            Some(_) => {}
+            None => debug!("enemy {} stuck at {}", event.enemy, event.pos),
+        }
+        spots.spots.push(event.pos);
+    }
+    let over = spots.spots.len().saturating_sub(MAX_STUCK_SPOTS);
+    spots.spots.drain(..over);
+}
+
+fn clear_stuck(mut spots: ResMut<StuckSpots>) {
+    spots.spots.clear();
+    spots.logged.clear();
+}
+
+fn draw_stuck(spots: Res<StuckSpots>, mut gizmos: Gizmos) {
+    let arm = Vec2::splat(TILE * 0.2);
+    for &p in &spots.spots {
+        gizmos.line_2d(p - arm, p + arm, STUCK);
+        gizmos.line_2d(p + Vec2::new(-arm.x, arm.y), p + Vec2::new(arm.x, -arm.y), STUCK);
+    }
+}
+
+fn draw_grid(bounds: Option<Res<LevelBounds>>, mut gizmos: Gizmos) {
+    let Some(bounds) = bounds else { return; };
+    let top_left = bounds.origin + Vec2::new(-TILE, TILE) * 0.5;
+    let size = bounds.size();
+    for c in 0..=bounds.cols {
+        let x = top_left.x + c as f32 * TILE;
+        gizmos.line_2d(Vec2::new(x, top_left.y), Vec2::new(x, top_left.y - size.y), GRID);
+    }
+    for r in 0..=bounds.rows {
+        let y = top_left.y - r as f32 * TILE;
+        gizmos.line_2d(Vec2::new(top_left.x, y), Vec2::new(top_left.x + size.x, y), GRID);
+    }
+}
+
+fn draw_boxes(
+    q: Query<(&Transform, &Size, Has<Player>, Has<Enemy>, Has<Bullet>, Has<Blocks>)>,
+    mut gizmos: Gizmos,
+) {
+    for (t, size, is_player, is_enemy, is_bullet, blocks) in &q {
+        let color = if is_player {
+            BOX_TANK
+        } else if is_enemy {
+            BOX_ENEMY
+        } else if is_bullet {
+            BOX_BULLET
+        } else if blocks {
+            BOX_WALL
+        } else {
+            BOX_OTHER
+        };
+        gizmos.rect_2d(Isometry2d::from_translation(t.translation.truncate()), size.0, color);
+    }
+}
+
+fn draw_spawns(spawns: Option<Res<SpawnPoints>>, start: Option<Res<PlayerStart>>, mut gizmos: Gizmos) {
+    for &p in spawns.iter().flat_map(|s| &s.points) {
+        gizmos.circle_2d(Isometry2d::from_translation(p), TILE * 0.45, SPAWN);
+    }
+    if let Some(start) = start {
+        gizmos.circle_2d(Isometry2d::from_translation(start.0), TILE * 0.45, BOX_TANK);
+    }
+}
+
+// Where each enemy is steering: the player when chasing, its rally point when
+// retreating, one tile along its wander direction or its way to the base otherwise
+fn draw_waypoints(
+    q_enemies: Query<(&Transform, &EnemyAI)>,
+    q_player: Query<&Transform, With<Player>>,
+    mut gizmos: Gizmos,
+) {
+    let player = q_player.single().ok().map(|t| t.translation.truncate());
+    for (t, ai) in &q_enemies {
+        let pos = t.translation.truncate();
+        let target = match ai.state {
+            EnemyState::Chasing => player.unwrap_or(pos),
+            EnemyState::Retreating => ai.home,
+            EnemyState::Roaming | EnemyState::Besieging => pos + ai.roam_dir * TILE,
+        };
+        gizmos.line_2d(pos, target, WAYPOINT);
+        gizmos.circle_2d(Isometry2d::from_translation(target), 4.0, WAYPOINT);
+    }
+}
+
+fn update_labels(
+    mut commands: Commands,
+    q_enemies: Query<(Entity, &Transform, &EnemyAI), Without<DebugLabel>>,
+    mut q_labels: Query<(Entity, &DebugLabel, &mut Transform, &mut Text2d)>,
+) {
+    let mut labelled = HashSet::new();
+    for (label_e, label, mut t, mut text) in &mut q_labels {
+        let Ok((_, enemy_t, ai)) = q_enemies.get(label.0) else {
+            commands.entity(label_e).despawn();
+            continue;
+        };
+        labelled.insert(label.0);
+        t.translation = (enemy_t.translation.truncate() + Vec2::Y * LABEL_OFFSET).extend(20.0);
+        text.0 = label_text(ai);
+    }
+
+    for (e, t, ai) in &q_enemies {
+        if labelled.contains(&e) { continue; }
+        let pos = t.translation.truncate() + Vec2::Y * LABEL_OFFSET;
+        commands.spawn((
+            Text2d::new(label_text(ai)),
+            TextFont { font_size: 10.0, ..default() },
+            TextColor(Color::WHITE),
+            Transform::from_xyz(pos.x, pos.y, 20.0),
+            DebugLabel(e),
+        ));
+    }
+}
+
+fn label_text(ai: &EnemyAI) -> String {
+    let state = match ai.state {
+        EnemyState::Roaming => "roam",
+        EnemyState::Chasing => "chase",
+        EnemyState::Retreating => "retreat",
+        EnemyState::Besieging => "siege",
+    };
+    format!("{state} {:.0}%", ai.awareness * 100.0)
+}
@@ -0,0 +1,190 @@
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::render::view::RenderLayers;
+use bevy::window::{MonitorSelection, PresentMode, PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::locale::Locale;
+use crate::photo::photo_mode_off;
+use crate::profile::Profile;
+use crate::settings::settings_closed;
+use crate::toasts::Notify;
+use crate::{AppState, WINDOW_H, WINDOW_W};
+
+// The game always shows a WINDOW_W x WINDOW_H view (the camera projection is
+// fixed to it); a window of any other shape gets bars around a viewport
+// of that aspect ratio instead of a cropped or stretched picture. In a
+// browser the window is the page's canvas, kept to the size of whatever holds
+// it, so the window size in the profile only applies on the desktop.
+//
+// The world is drawn by one camera and the UI by another over it, so the
+// zoom (1x to 3x, in the settings or with +/- during a run) only brings the
+// field closer and the HUD stays as it is. With pixel-perfect scaling on, the
+// field is scaled by a whole number of pixels, leaving wider bars, so every
+// point of the view covers the same number of screen pixels; the HUD still
+// fills the usual 4:3 area.
+
+// Window sizes on offer in the settings, all the game's own 4:3
+pub const RESOLUTIONS: [(u32, u32); 4] = [(800, 600), (1024, 768), (1280, 960), (1600, 1200)];
+
+// The <canvas> web/index.html gives the game
+const WEB_CANVAS: &str = "#bevy";
+pub const MAX_ZOOM: u8 = 3;
+
+// The camera the field is drawn with, which follows the player
+#[derive(Component)]
+pub struct WorldCamera;
+
+#[derive(Component)]
+struct UiCamera;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct DisplaySettings {
+    pub fullscreen: bool,
+    pub vsync: bool,
+    // Window size when not fullscreen
+    pub resolution: (u32, u32),
+    // 1 to MAX_ZOOM
+    pub zoom: u8,
+    pub pixel_perfect: bool,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            fullscreen: false,
+            vsync: true,
+            resolution: (WINDOW_W as u32, WINDOW_H as u32),
+            zoom: 1,
+            pixel_perfect: false,
+        }
+    }
+}
+
+impl DisplaySettings {
+    // The world camera's projection scale; below 1 is closer in
+    pub fn camera_scale(&self) -> f32 {
+        1.0 / self.zoom.clamp(1, MAX_ZOOM) as f32
+    }
+}
+
+pub fn window_mode(fullscreen: bool) -> WindowMode {
+    if fullscreen { WindowMode::BorderlessFullscreen(MonitorSelection::Current) } else { WindowMode::Windowed }
+}
+
+// The canvas to draw into and whether to fit it to its parent, on the web
+pub fn canvas() -> (Option<String>, bool) {
+    let web = cfg!(target_arch = "wasm32");
+    (web.then(|| WEB_CANVAS.to_string()), web)
+}
+
+pub fn present_mode(vsync: bool) -> PresentMode {
+    if vsync { PresentMode::AutoVsync } else { PresentMode::AutoNoVsync }
+}
+
+// Alt+Enter belongs to the fullscreen toggle, not to menus confirming on Enter
+pub fn alt_held(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::AltLeft, KeyCode::AltRight])
+}
+
+pub struct DisplayPlugin;
+
+impl Plugin for DisplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_ui_camera).add_systems(
+            Update,
+            (
+                toggle_fullscreen,
+                zoom_keys
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(settings_closed)
+                    .run_if(photo_mode_off),
+                // Photo mode has a zoom of its own
+                apply_zoom.run_if(photo_mode_off),
+                fit_viewport,
+            )
+                .chain(),
+        );
+    }
+}
+
+// Drawn after the world camera and over it, without wiping what it drew, and
+// with no layer of sprites to draw
+fn spawn_ui_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera2d,
+        Camera { order: 1, clear_color: ClearColorConfig::None, ..default() },
+        RenderLayers::none(),
+        IsDefaultUiCamera,
+        UiCamera,
+    ));
+}
+
+fn zoom_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    locale: Res<Locale>,
+    mut profile: ResMut<Profile>,
+    mut notify: EventWriter<Notify>,
+) {
+    let zoom = profile.display.zoom;
+    let zoomed = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        (zoom + 1).min(MAX_ZOOM)
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        zoom.saturating_sub(1).max(1)
+    } else {
+        return;
+    };
+    if zoomed == zoom { return; }
+    profile.display.zoom = zoomed;
+    profile.save();
+    notify.write(Notify::new(locale.f("display.zoom", &[("zoom", &zoomed)])));
+}
+
+fn apply_zoom(profile: Res<Profile>, mut q_camera: Query<&mut Projection, With<WorldCamera>>) {
+    let scale = profile.display.camera_scale();
+    for mut projection in &mut q_camera {
+        let Projection::Orthographic(ortho) = &mut *projection else { continue; };
+        if ortho.scale != scale { ortho.scale = scale; }
+    }
+}
+
+fn toggle_fullscreen(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut profile: ResMut<Profile>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !(alt_held(&keys) && keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter])) { return; }
+    let Ok(mut window) = q_window.single_mut() else { return; };
+    // From what's on screen, which --fullscreen may have set without the profile
+    profile.display.fullscreen = window.mode == WindowMode::Windowed;
+    window.mode = window_mode(profile.display.fullscreen);
+    profile.save();
+}
+
+// A window too small for the field at 1x still gets it scaled down
+fn fit_viewport(
+    profile: Res<Profile>,
+    q_window: Query<Ref<Window>, With<PrimaryWindow>>,
+    mut q_camera: Query<(&mut Camera, Has<WorldCamera>), Or<(With<WorldCamera>, With<UiCamera>)>>,
+) {
+    let Ok(window) = q_window.single() else { return; };
+    if !(window.is_changed() || profile.is_changed()) { return; }
+    let physical = window.physical_size();
+    // Minimised
+    if physical.x == 0 || physical.y == 0 { return; }
+
+    let fit = (physical.x as f32 / WINDOW_W).min(physical.y as f32 / WINDOW_H);
+    let viewport = |scale: f32| {
+        let size = (Vec2::new(WINDOW_W, WINDOW_H) * scale).as_uvec2().clamp(UVec2::ONE, physical);
+        Viewport { physical_position: (physical - size) / 2, physical_size: size, ..default() }
+    };
+    let world_scale = if profile.display.pixel_perfect && fit >= 1.0 { fit.floor() } else { fit };
+    for (mut camera, world) in &mut q_camera {
+        let wanted = viewport(if world { world_scale } else { fit });
+        let same =
+            |v: &Viewport| v.physical_position == wanted.physical_position && v.physical_size == wanted.physical_size;
+        if camera.viewport.as_ref().is_some_and(same) { continue; }
+        camera.viewport = Some(wanted);
+    }
+}
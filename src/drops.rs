@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::config::{DropTable, GameConfig};
+use crate::death::{DeathEvent, DeathReason};
+use crate::floating_text::spawn_floating_text;
+use crate::mods::ContentRegistry;
+use crate::recap::StageRecap;
+use crate::{
+    AppState, EnemyKilled, EnemyKind, GameRng, GameSet, Pickup, Player, PowerUp, Size, aabb_overlap, powerup_color,
+};
+
+// Loot from destroyed tanks: a per-kind roll on every EnemyKilled (whatever did
+// the killing; bonus tanks skip the chance roll and always drop), coins, and the
+// magnet that pulls nearby pickups to the player.
+
+const POWERUP_SIZE: f32 = 16.0;
+const COIN_SIZE: f32 = 10.0;
+const COIN_COLOR: Color = Color::srgb(1.0, 0.82, 0.1);
+// Coins from one kill land in a small cluster instead of a single stack
+const COIN_SCATTER: f32 = 10.0;
+
+// Coins picked up this run
+#[derive(Resource, Default)]
+pub struct Coins(pub u32);
+
+#[derive(Component)]
+struct Coin;
+
+pub struct DropsPlugin;
+
+impl Plugin for DropsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Coins>()
+            // Kills are read even on the frame the stage recap starts
+            .add_systems(Update, roll_drops.in_set(GameSet::Cleanup).run_if(in_state(AppState::Playing)))
+            .add_systems(
+                Update,
+                (magnetize_pickups.in_set(GameSet::Physics), collect_coins.in_set(GameSet::Damage))
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<StageRecap>)),
+            );
+    }
+}
+
+fn drop_table(cfg: &GameConfig, kind: EnemyKind) -> &DropTable {
+    let drops = &cfg.drops;
+    match kind {
+        EnemyKind::Basic => &drops.basic,
+        EnemyKind::Fast => &drops.fast,
+        EnemyKind::Power => &drops.power,
+        EnemyKind::Armor => &drops.armor,
+        EnemyKind::Saboteur => &drops.saboteur,
+        EnemyKind::Boss => &drops.boss,
+    }
+}
+
+fn roll_powerup(rng: &mut GameRng, table: &DropTable) -> Option<PowerUp> {
+    if !rng.gen_bool(table.powerup_chance.clamp(0.0, 1.0) as f64) { return None; }
+    pick_powerup(rng, table)
+}
+
+// Which power-up, by the table's weights
+fn pick_powerup(rng: &mut GameRng, table: &DropTable) -> Option<PowerUp> {
+    let weights = [
+        (PowerUp::WeaponUpgrade, table.weapon_weight),
+        (PowerUp::Shovel, table.shovel_weight),
+        (PowerUp::Timer, table.timer_weight),
+        (PowerUp::Emp, table.emp_weight),
+        (PowerUp::Mines, table.mines_weight),
+    ];
+    let total: f32 = weights.iter().map(|(_, w)| w.max(0.0)).sum();
+    if total <= 0.0 { return None; }
+    let mut roll = rng.gen_range(0.0..total);
+    for (kind, weight) in weights {
+        if roll < weight.max(0.0) { return Some(kind); }
+        roll -= weight.max(0.0);
+    }
+    None
+}
+
+fn roll_drops(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    content: Res<ContentRegistry>,
+    mut kills: EventReader<EnemyKilled>,
+    mut rng: ResMut<GameRng>,
+) {
+    for kill in kills.read().filter(|k| k.for_player()) {
+        let table = drop_table(&cfg, kill.kind);
+        let powerup = if kill.bonus {
+            Some(pick_powerup(&mut rng, table).unwrap_or(PowerUp::WeaponUpgrade))
+        } else {
+            roll_powerup(&mut rng, table)
+        };
+        if let Some(kind) = powerup {
+            // Or one of the mods' pickups built on it (see mods.rs)
+            let variant = content.roll_pickup(&mut rng, kind);
+            let mut pickup = commands.spawn((
+                Sprite {
+                    color: variant.and_then(|v| v.color).unwrap_or(powerup_color(kind)),
+                    custom_size: Some(Vec2::splat(POWERUP_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(kill.pos.x, kill.pos.y, 0.6),
+                Pickup,
+                kind,
+                Size(Vec2::splat(POWERUP_SIZE)),
+            ));
+            if let Some(variant) = variant { pickup.insert(variant.clone()); }
+        }
+
+        if !rng.gen_bool(table.coin_chance.clamp(0.0, 1.0) as f64) { continue; }
+        for _ in 0..table.coins {
+            let pos = kill.pos + Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * COIN_SCATTER;
+            commands.spawn((
+                Sprite {
+                    color: COIN_COLOR,
+                    custom_size: Some(Vec2::splat(COIN_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(pos.x, pos.y, 0.6),
+                Pickup,
+                Coin,
+                Size(Vec2::splat(COIN_SIZE)),
+            ));
+        }
+    }
+}
+
+// Pull speed ramps up as a pickup gets closer
+fn magnetize_pickups(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    q_player: Query<&Transform, (With<Player>, Without<Pickup>)>,
+    mut q_pickups: Query<&mut Transform, With<Pickup>>,
+) {
+    let Ok(player_t) = q_player.single() else { return; };
+    let player_pos = player_t.translation.truncate();
+    let radius = cfg.drops.magnet_radius;
+    if radius <= 0.0 { return; }
+
+    for mut t in &mut q_pickups {
+        let to_player = player_pos - t.translation.truncate();
+        let dist = to_player.length();
+        if dist > radius || dist < f32::EPSILON { continue; }
+        let pull = cfg.drops.magnet_speed * (1.0 - dist / radius * 0.5) * time.delta_secs();
+        let step = to_player / dist * pull.min(dist);
+        t.translation.x += step.x;
+        t.translation.y += step.y;
+    }
+}
+
+fn collect_coins(
+    mut commands: Commands,
+    mut deaths: EventWriter<DeathEvent>,
+    mut coins: ResMut<Coins>,
+    q_player: Query<(&Transform, &Size), With<Player>>,
+    q_coins: Query<(Entity, &Transform, &Size), With<Coin>>,
+) {
+    let Ok((p_t, p_s)) = q_player.single() else { return; };
+    let p_pos = p_t.translation.truncate();
+    for (e, t, s) in &q_coins {
+        if !aabb_overlap(p_pos, p_s.0 * 0.5, t.translation.truncate(), s.0 * 0.5) { continue; }
+        coins.0 += 1;
+        deaths.write(DeathEvent::new(e, DeathReason::Collected));
+        spawn_floating_text(&mut commands, p_pos, "+1", COIN_COLOR, 12.0, 0.5, 16.0);
+    }
+}
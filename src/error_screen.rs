@@ -0,0 +1,157 @@
+use bevy::ecs::error::{BevyError, ErrorContext, GLOBAL_ERROR_HANDLER};
+use bevy::prelude::*;
+use std::sync::Mutex;
+
+use crate::AppState;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::Locale;
+use crate::persistence;
+use crate::scores::confirm_pressed;
+
+// Error screen: what goes wrong while the game runs ends up here rather than
+// taking the process down. A level that can't be built, and any system that
+// fails (one asking for a resource that isn't there, say), skip what they
+// were doing, log why and put up a screen with the message and a way back to
+// the menu; ECS errors in general are turned from panics into reports by
+// `install`. A real panic still ends the game, but the hook left for it first
+// writes the message to `crash.txt` in the data directory, and the next launch
+// opens on this screen with it.
+
+const CRASH_FILE: &str = "crash.txt";
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+const BUTTON_COLOR: Color = Color::srgb(0.25, 0.25, 0.3);
+const BUTTON_HOVER: Color = Color::srgb(0.35, 0.35, 0.45);
+
+// The first error since the screen was last shown, from wherever it came up
+static REPORTED: Mutex<Option<String>> = Mutex::new(None);
+
+// What the screen shows
+#[derive(Resource)]
+pub struct GameError {
+    pub message: String,
+    // From the session before, which crashed
+    pub last_session: bool,
+}
+
+#[derive(Component)]
+struct ErrorScreen;
+
+#[derive(Component)]
+struct BackButton;
+
+// Before the app is built, so that errors from the very first frame come here
+pub fn install() {
+    if GLOBAL_ERROR_HANDLER.set(handle_error).is_err() { warn!("an error handler was already installed"); }
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        persistence::save_text(CRASH_FILE, &info.to_string());
+        default_hook(info);
+    }));
+}
+
+// Once for each error, but only the first is kept for the screen
+fn handle_error(error: BevyError, ctx: ErrorContext) {
+    let message = format!("{} {}: {error}", ctx.kind(), ctx.name());
+    let mut reported = REPORTED.lock().unwrap_or_else(|e| e.into_inner());
+    if reported.is_some() { return; }
+    error!("{message}");
+    *reported = Some(message);
+}
+
+// For code that finds a problem itself rather than failing a system
+pub fn report(message: String) {
+    error!("{message}");
+    REPORTED.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert(message);
+}
+
+pub struct ErrorScreenPlugin;
+
+impl Plugin for ErrorScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, show_last_crash)
+            .add_systems(OnEnter(AppState::Error), spawn_error_screen)
+            .add_systems(OnExit(AppState::Error), despawn_error_screen)
+            .add_systems(Update, show_reported.run_if(not(in_state(AppState::Error))))
+            .add_systems(Update, error_screen_input.run_if(in_state(AppState::Error)));
+    }
+}
+
+fn show_last_crash(mut commands: Commands, mut next: ResMut<NextState<AppState>>) {
+    let Some(message) = persistence::load_text(CRASH_FILE) else { return; };
+    persistence::remove(CRASH_FILE);
+    commands.insert_resource(GameError { message, last_session: true });
+    next.set(AppState::Error);
+}
+
+fn show_reported(mut commands: Commands, mut next: ResMut<NextState<AppState>>) {
+    let Some(message) = REPORTED.lock().unwrap_or_else(|e| e.into_inner()).take() else { return; };
+    commands.insert_resource(GameError { message, last_session: false });
+    next.set(AppState::Error);
+}
+
+fn spawn_error_screen(
+    mut commands: Commands,
+    error: Option<Res<GameError>>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    let title = match &error {
+        Some(error) if error.last_session => locale.t("error.last_session"),
+        _ => locale.t("error.title"),
+    };
+    let message = error.map_or_else(String::new, |e| e.message.clone());
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            // Over whatever was left on the screen
+            GlobalZIndex(10),
+            ErrorScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(title), font(32.0), TextColor(TITLE_COLOR)));
+            root.spawn((Text::new(message), font(16.0), TextColor(Color::WHITE)));
+            root.spawn((
+                Button,
+                Node { padding: UiRect::axes(Val::Px(16.0), Val::Px(8.0)), ..default() },
+                BackgroundColor(BUTTON_COLOR),
+                BackButton,
+            ))
+            .with_children(|button| {
+                let label = format!("{}: {}", glyph(Action::Confirm, *device), locale.t("error.back"));
+                button.spawn((Text::new(label), font(18.0), TextColor(Color::WHITE)));
+            });
+        });
+}
+
+fn despawn_error_screen(mut commands: Commands, q: Query<Entity, With<ErrorScreen>>) {
+    commands.remove_resource::<GameError>();
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+// The loadout screen is the menu
+fn error_screen_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut q_button: Query<(&Interaction, &mut BackgroundColor), (With<BackButton>, Changed<Interaction>)>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let mut clicked = false;
+    for (interaction, mut color) in &mut q_button {
+        clicked |= *interaction == Interaction::Pressed;
+        color.0 = if *interaction == Interaction::None { BUTTON_COLOR } else { BUTTON_HOVER };
+    }
+    if clicked || confirm_pressed(&keys, &pads) || keys.just_pressed(KeyCode::Escape) {
+        next.set(AppState::Loadout);
+    }
+}
@@ -0,0 +1,246 @@
+use bevy::prelude::*;
+use std::fmt::Write as _;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::args::LaunchArgs;
+use crate::bullets::BulletKind;
+use crate::death::{DeathEvent, DeathReason, despawn_dead};
+use crate::feedback::HitEvent;
+use crate::packs::{self, LevelPacks};
+use crate::persistence;
+use crate::profile::Profile;
+use crate::recap::RunStats;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, Bullet, Enemy, EnemyKind, Faction, GameRng, LevelBounds, Player, PowerUp, start_run};
+
+// Event log: with --event-log, every run writes what happened in it to a file
+// of its own in `runs/` in the data directory, one JSON object a line, for
+// balancing and for heatmaps (see heatmap.rs). Each line has the run clock
+// in seconds (`t`), the stage and the level's name, what happened (`event`),
+// where, both in world units and as a maze cell, and what it was about:
+//
+//   run     once, first: mode, difficulty and seed
+//   level   each time a maze is built: its size
+//   spawn   a tank rolls in: `side` and, for enemies, `kind`
+//   shot    a shell leaves a barrel: `side` and `shell`
+//   hit     a shell, mine or barrier hit a tank: `side`, `fatal`
+//   death   a tank is destroyed: `side`, `kind`, `reason` and `by`, the side
+//           credited with it, if any
+//   pickup  the player picks up a power-up (`kind`) or a coin
+//
+// It's all logged at the end of the frame, before despawn_dead takes the
+// dead off the field, so they can still be found.
+
+pub const RUNS_DIR: &str = "runs";
+
+// Present for the length of a logged run
+#[derive(Resource)]
+struct EventLog {
+    out: BufWriter<File>,
+    path: PathBuf,
+    // The maze being played, by name
+    level: String,
+}
+
+impl EventLog {
+    // `fields` go on the end as they are, each with a leading comma
+    fn line(&mut self, t: f32, stage: usize, event: &str, pos: Option<(Vec2, &LevelBounds)>, fields: &str) {
+        let level = quote(&self.level);
+        let mut line = format!("{{\"t\":{t:.3},\"stage\":{stage},\"level\":{level},\"event\":\"{event}\"");
+        if let Some((pos, bounds)) = pos {
+            let _ = write!(line, ",\"x\":{:.1},\"y\":{:.1}", pos.x, pos.y);
+            if let Some(cell) = bounds.cell_at(pos) { let _ = write!(line, ",\"col\":{},\"row\":{}", cell.x, cell.y); }
+        }
+        line.push_str(fields);
+        line.push('}');
+        if let Err(err) = writeln!(self.out, "{line}") { warn!("can't write to {}: {err}", self.path.display()); }
+    }
+}
+
+// A JSON string
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            c if (c as u32) < 0x20 => { let _ = write!(quoted, "\\u{:04x}", c as u32); }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn side(faction: Faction) -> &'static str {
+    match faction {
+        Faction::Player => "player",
+        Faction::Enemy => "enemy",
+    }
+}
+
+fn shell(kind: &BulletKind) -> &'static str {
+    match kind {
+        BulletKind::Standard => "Standard",
+        BulletKind::Fast => "Fast",
+        BulletKind::Piercing { .. } => "Piercing",
+        BulletKind::Bouncing { .. } => "Bouncing",
+    }
+}
+
+// What the log calls the maze a stage plays: its file, or the game's own
+pub fn level_name(args: &LaunchArgs, packs: &LevelPacks, mode: GameMode, stage: usize) -> String {
+    match packs::custom_level(args, packs, stage) {
+        _ if mode == GameMode::Tutorial => "tutorial".to_string(),
+        Some(file) => file.name.clone(),
+        None => "built-in".to_string(),
+    }
+}
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), open_log.after(start_run))
+            .add_systems(OnExit(AppState::Playing), close_log)
+            .add_systems(
+                PostUpdate,
+                (log_level, log_spawns, log_shots, log_hits, log_deaths)
+                    .chain()
+                    .before(despawn_dead)
+                    .run_if(resource_exists::<EventLog>)
+                    .run_if(resource_exists::<LevelBounds>),
+            );
+    }
+}
+
+fn open_log(
+    mut commands: Commands,
+    args: Res<LaunchArgs>,
+    profile: Res<Profile>,
+    mode: Res<GameMode>,
+    rng: Res<GameRng>,
+) {
+    if !args.event_log { return; }
+    let dir = persistence::data_dir().join(RUNS_DIR);
+    let path = dir.join(format!("run-{}.jsonl", persistence::unix_millis()));
+    let file = fs::create_dir_all(&dir).and_then(|_| File::create(&path));
+    let file = match file {
+        Ok(file) => file,
+        Err(err) => {
+            warn!("can't start the event log at {}: {err}", path.display());
+            return;
+        }
+    };
+    info!("logging this run's events to {}", path.display());
+    let mut log = EventLog { out: BufWriter::new(file), path, level: String::new() };
+    let difficulty = args.difficulty.unwrap_or(profile.difficulty);
+    let (mode, seed) = (*mode, rng.seed);
+    log.line(0.0, 0, "run", None, &format!(",\"mode\":\"{mode:?}\",\"difficulty\":\"{difficulty:?}\",\"seed\":{seed}"));
+    commands.insert_resource(log);
+}
+
+fn close_log(mut commands: Commands, log: Option<ResMut<EventLog>>) {
+    let Some(mut log) = log else { return; };
+    if let Err(err) = log.out.flush() { warn!("can't write to {}: {err}", log.path.display()); }
+    commands.remove_resource::<EventLog>();
+}
+
+// Whenever a maze goes up: a new stage, a restart, a reload
+fn log_level(
+    mut log: ResMut<EventLog>,
+    bounds: Res<LevelBounds>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    mode: Res<GameMode>,
+    stage: Res<CurrentLevel>,
+    run: Res<RunStats>,
+) {
+    if !bounds.is_changed() { return; }
+    log.level = level_name(&args, &packs, *mode, stage.0);
+    log.line(run.secs, stage.0, "level", None, &format!(",\"cols\":{},\"rows\":{}", bounds.cols, bounds.rows));
+}
+
+fn log_spawns(
+    mut log: ResMut<EventLog>,
+    bounds: Res<LevelBounds>,
+    stage: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    q: Query<(&Transform, Option<&EnemyKind>), Or<(Added<Enemy>, Added<Player>)>>,
+) {
+    for (t, kind) in &q {
+        let fields = match kind {
+            Some(kind) => format!(",\"side\":\"enemy\",\"kind\":\"{kind:?}\""),
+            None => ",\"side\":\"player\"".to_string(),
+        };
+        log.line(run.secs, stage.0, "spawn", Some((t.translation.truncate(), &bounds)), &fields);
+    }
+}
+
+// A pooled shell gets its Bullet back each time it's fired
+fn log_shots(
+    mut log: ResMut<EventLog>,
+    bounds: Res<LevelBounds>,
+    stage: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    q: Query<(&Transform, &Faction, &BulletKind), Added<Bullet>>,
+) {
+    for (t, faction, kind) in &q {
+        let fields = format!(",\"side\":\"{}\",\"shell\":\"{}\"", side(*faction), shell(kind));
+        log.line(run.secs, stage.0, "shot", Some((t.translation.truncate(), &bounds)), &fields);
+    }
+}
+
+// Tanks only; walls and turrets are hit too
+fn log_hits(
+    mut log: ResMut<EventLog>,
+    mut hits: EventReader<HitEvent>,
+    bounds: Res<LevelBounds>,
+    stage: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    q_tanks: Query<(&Transform, Has<Player>), Or<(With<Enemy>, With<Player>)>>,
+) {
+    for hit in hits.read() {
+        let Ok((t, player)) = q_tanks.get(hit.target) else { continue; };
+        let fields = format!(",\"side\":\"{}\",\"fatal\":{}", if player { "player" } else { "enemy" }, hit.fatal);
+        log.line(run.secs, stage.0, "hit", Some((t.translation.truncate(), &bounds)), &fields);
+    }
+}
+
+fn log_deaths(
+    mut log: ResMut<EventLog>,
+    mut deaths: EventReader<DeathEvent>,
+    bounds: Res<LevelBounds>,
+    stage: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    q_tanks: Query<(&Transform, Option<&EnemyKind>), Or<(With<Enemy>, With<Player>)>>,
+    q_pickups: Query<(&Transform, Option<&PowerUp>)>,
+) {
+    let mut logged = Vec::new();
+    for death in deaths.read() {
+        // Two shells finishing the same tank still make one death
+        if logged.contains(&death.entity) { continue; }
+        if death.reason == DeathReason::Collected {
+            let Ok((t, powerup)) = q_pickups.get(death.entity) else { continue; };
+            let fields = match powerup {
+                Some(kind) => format!(",\"kind\":\"{kind:?}\""),
+                None => ",\"kind\":\"Coin\"".to_string(),
+            };
+            log.line(run.secs, stage.0, "pickup", Some((t.translation.truncate(), &bounds)), &fields);
+        } else if death.reason.is_kill() {
+            let Ok((t, kind)) = q_tanks.get(death.entity) else { continue; };
+            let mut fields = match kind {
+                Some(kind) => format!(",\"side\":\"enemy\",\"kind\":\"{kind:?}\""),
+                None => ",\"side\":\"player\"".to_string(),
+            };
+            let by = death.credit.map_or("null".to_string(), |c| format!("\"{}\"", side(c.faction)));
+            let _ = write!(fields, ",\"reason\":\"{:?}\",\"by\":{by}", death.reason);
+            log.line(run.secs, stage.0, "death", Some((t.translation.truncate(), &bounds)), &fields);
+        } else {
+            continue;
+        }
+        logged.push(death.entity);
+    }
+}
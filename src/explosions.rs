@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use rand::{Rng, thread_rng};
+use std::f32::consts::TAU;
+
+use crate::profile::Profile;
+
+// Tank explosions: a flash that swells and fades plus a handful of debris
+// flying outward. Purely visual; whatever destroyed the tank has already
+// applied the damage. With reduced flashes the flash is drawn faint.
+
+const FLASH_SECS: f32 = 0.6;
+const FLASH_COLOR: Color = Color::srgb(1.0, 0.75, 0.3);
+const REDUCED_FLASH_ALPHA: f32 = 0.3;
+const DEBRIS: usize = 10;
+const DEBRIS_SECS: f32 = 0.9;
+const DEBRIS_SPEED: f32 = 140.0;
+const DEBRIS_COLOR: Color = Color::srgb(0.9, 0.35, 0.1);
+
+#[derive(Component)]
+struct ExplosionFlash(Timer);
+
+#[derive(Component)]
+struct Debris {
+    timer: Timer,
+    velocity: Vec2,
+}
+
+pub fn spawn_explosion(commands: &mut Commands, pos: Vec2, size: Vec2) {
+    commands.spawn((
+        Sprite { color: FLASH_COLOR, custom_size: Some(size), ..default() },
+        Transform::from_xyz(pos.x, pos.y, 2.6),
+        ExplosionFlash(Timer::from_seconds(FLASH_SECS, TimerMode::Once)),
+    ));
+
+    let mut rng = thread_rng();
+    for i in 0..DEBRIS {
+        let angle = TAU * i as f32 / DEBRIS as f32 + rng.gen_range(-0.3..0.3);
+        let speed = DEBRIS_SPEED * rng.gen_range(0.5..1.0);
+        commands.spawn((
+            Sprite { color: DEBRIS_COLOR, custom_size: Some(Vec2::splat(rng.gen_range(3.0..6.0))), ..default() },
+            Transform::from_xyz(pos.x, pos.y, 2.6),
+            Debris { timer: Timer::from_seconds(DEBRIS_SECS, TimerMode::Once), velocity: Vec2::from_angle(angle) * speed },
+        ));
+    }
+}
+
+pub struct ExplosionsPlugin;
+
+impl Plugin for ExplosionsPlugin {
+    fn build(&self, app: &mut App) {
+        // Not tied to a state, like floating text, so the last blast of a run finishes
+        app.add_systems(Update, (animate_flashes, animate_debris));
+    }
+}
+
+fn animate_flashes(
+    time: Res<Time>,
+    mut commands: Commands,
+    profile: Res<Profile>,
+    mut q: Query<(Entity, &mut ExplosionFlash, &mut Transform, &mut Sprite)>,
+) {
+    for (e, mut flash, mut t, mut sprite) in &mut q {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        let f = flash.0.fraction();
+        t.scale = Vec3::splat(1.0 + 1.5 * f);
+        let peak = if profile.accessibility.reduced_flashes { REDUCED_FLASH_ALPHA } else { 1.0 };
+        sprite.color = FLASH_COLOR.with_alpha(peak * (1.0 - f));
+    }
+}
+
+fn animate_debris(time: Res<Time>, mut commands: Commands, mut q: Query<(Entity, &mut Debris, &mut Transform, &mut Sprite)>) {
+    let dt = time.delta_secs();
+    for (e, mut debris, mut t, mut sprite) in &mut q {
+        if debris.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        // Slows as it flies
+        let v = debris.velocity * debris.timer.fraction_remaining();
+        t.translation += (v * dt).extend(0.0);
+        sprite.color.set_alpha(debris.timer.fraction_remaining());
+    }
+}
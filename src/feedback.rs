@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use rand::{Rng, thread_rng};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::profile::Profile;
+
+// Camera shake and damage flashes. Gameplay only reports hits through
+// `HitEvent`; how loud the feedback is stays a player setting, and reduced
+// flashes (see accessibility.rs) leave the flashes out.
+
+const SHAKE_MAX_OFFSET: f32 = 12.0;
+const SHAKE_DECAY: f32 = 1.5; // trauma lost per second
+const PLAYER_HIT_TRAUMA: f32 = 0.6;
+const EXPLOSION_TRAUMA: f32 = 0.3;
+const FLASH_SECS: f32 = 0.1;
+const FLASH_COLOR: Color = Color::WHITE;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct FeedbackSettings {
+    pub screen_shake: bool,
+    // Scales shake distance; 1.0 is the default feel
+    pub shake_intensity: f32,
+}
+
+impl Default for FeedbackSettings {
+    fn default() -> Self {
+        Self { screen_shake: true, shake_intensity: 1.0 }
+    }
+}
+
+#[derive(Event)]
+pub struct HitEvent {
+    pub target: Entity,
+    // The target was destroyed by this hit
+    pub fatal: bool,
+    pub player: bool,
+}
+
+// trauma is 0.0 .. 1.0; the offset grows with its square so small bumps stay
+// subtle. The camera follow adds `offset` on top of its own position.
+#[derive(Resource, Default)]
+pub struct CameraShake {
+    pub trauma: f32,
+    pub offset: Vec2,
+}
+
+impl CameraShake {
+    pub fn add(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+}
+
+#[derive(Component)]
+pub struct HitFlash {
+    timer: Timer,
+    base: Color,
+}
+
+pub struct FeedbackPlugin;
+
+impl Plugin for FeedbackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraShake>()
+            .add_event::<HitEvent>()
+            .add_systems(OnExit(AppState::Playing), reset_shake)
+            .add_systems(Update, (react_to_hits, tick_flashes, apply_shake).chain());
+    }
+}
+
+fn react_to_hits(
+    mut commands: Commands,
+    mut hits: EventReader<HitEvent>,
+    mut shake: ResMut<CameraShake>,
+    profile: Res<Profile>,
+    q_sprites: Query<(&Sprite, Option<&HitFlash>)>,
+) {
+    for hit in hits.read() {
+        if hit.player {
+            shake.add(PLAYER_HIT_TRAUMA);
+        } else if hit.fatal {
+            shake.add(EXPLOSION_TRAUMA);
+        }
+        if hit.fatal || profile.accessibility.reduced_flashes { continue; }
+
+        let Ok((sprite, flash)) = q_sprites.get(hit.target) else { continue; };
+        // Keep the colour from before the first flash when hits overlap
+        let base = flash.map_or(sprite.color, |f| f.base);
+        commands.entity(hit.target).insert(HitFlash { timer: Timer::from_seconds(FLASH_SECS, TimerMode::Once), base });
+    }
+}
+
+fn tick_flashes(time: Res<Time>, mut commands: Commands, mut q: Query<(Entity, &mut HitFlash, &mut Sprite)>) {
+    for (e, mut flash, mut sprite) in &mut q {
+        // Keep the current alpha so blink effects (freeze) still read
+        let alpha = sprite.color.alpha();
+        if flash.timer.tick(time.delta()).finished() {
+            sprite.color = flash.base.with_alpha(alpha);
+            commands.entity(e).remove::<HitFlash>();
+        } else {
+            sprite.color = FLASH_COLOR.with_alpha(alpha);
+        }
+    }
+}
+
+pub fn apply_shake(time: Res<Time<Real>>, profile: Res<Profile>, mut shake: ResMut<CameraShake>) {
+    // The frame trauma runs out already wrote a zero offset
+    if shake.trauma <= 0.0 { return; }
+    shake.trauma = (shake.trauma - SHAKE_DECAY * time.delta_secs()).max(0.0);
+
+    let settings = profile.feedback;
+    let strength = if settings.screen_shake { shake.trauma * shake.trauma * settings.shake_intensity } else { 0.0 };
+    let mut rng = thread_rng();
+    shake.offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0)) * SHAKE_MAX_OFFSET * strength;
+}
+
+fn reset_shake(mut shake: ResMut<CameraShake>) {
+    *shake = CameraShake::default();
+}
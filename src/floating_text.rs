@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+// Short-lived world-space text that rises and fades out, then despawns itself.
+// Anything can spawn one through `spawn_floating_text`; score popups are the
+// first user.
+
+const Z: f32 = 20.0;
+
+#[derive(Component)]
+pub struct FloatingText {
+    timer: Timer,
+    start: Vec2,
+    // Total distance travelled upwards over the lifetime
+    rise: f32,
+    color: Color,
+}
+
+pub fn spawn_floating_text(
+    commands: &mut Commands,
+    pos: Vec2,
+    text: impl Into<String>,
+    color: Color,
+    font_size: f32,
+    secs: f32,
+    rise: f32,
+) {
+    commands.spawn((
+        Text2d::new(text),
+        TextFont { font_size, ..default() },
+        TextColor(color),
+        Transform::from_xyz(pos.x, pos.y, Z),
+        FloatingText { timer: Timer::from_seconds(secs, TimerMode::Once), start: pos, rise, color },
+    ));
+}
+
+pub struct FloatingTextPlugin;
+
+impl Plugin for FloatingTextPlugin {
+    fn build(&self, app: &mut App) {
+        // Not tied to a state, so popups from the last kill still finish on
+        // the game-over screen instead of freezing in place
+        app.add_systems(Update, tween_floating_text);
+    }
+}
+
+fn tween_floating_text(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut FloatingText, &mut Transform, &mut TextColor)>,
+) {
+    for (e, mut float, mut t, mut color) in &mut q {
+        if float.timer.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        let f = float.timer.fraction();
+        // Ease out: quick pop upwards, slowing as it fades
+        let eased = 1.0 - (1.0 - f) * (1.0 - f);
+        t.translation.x = float.start.x;
+        t.translation.y = float.start.y + float.rise * eased;
+        color.0 = float.color.with_alpha(1.0 - f);
+    }
+}
@@ -0,0 +1,136 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::themes::LevelTheme;
+use crate::{AppState, CollisionCache, GameSet, LevelBounds, LevelTile, Player, TILE, line_clear};
+
+// Fog of war (vision.fog_of_war, or a night level): a dark sprite over every
+// tile of the level, lifted from the tiles within the player's vision radius
+// that walls don't hide. Tiles seen before stay half-lit so the layout is
+// remembered, but tanks and shells in them are covered like anywhere else in
+// the fog. The minimap only shows what the player has seen, and enemies in
+// plain sight.
+
+const FOG_COLOR: Color = Color::srgb(0.02, 0.02, 0.05);
+const UNSEEN_ALPHA: f32 = 0.97;
+const REMEMBERED_ALPHA: f32 = 0.7;
+// How quickly a tile brightens or darkens, per second
+const FADE_RATE: f32 = 6.0;
+
+// Which tiles are in sight now and which ever have been, indexed by cell
+#[derive(Resource)]
+pub struct FogOfWar {
+    cols: usize,
+    visible: Vec<bool>,
+    seen: Vec<bool>,
+}
+
+impl FogOfWar {
+    fn index(&self, cell: UVec2) -> usize {
+        cell.y as usize * self.cols + cell.x as usize
+    }
+
+    pub fn visible(&self, cell: UVec2) -> bool {
+        self.visible.get(self.index(cell)).copied().unwrap_or(false)
+    }
+
+    pub fn seen(&self, cell: UVec2) -> bool {
+        self.seen.get(self.index(cell)).copied().unwrap_or(false)
+    }
+}
+
+#[derive(Component)]
+struct FogCell(UVec2);
+
+pub struct FogPlugin;
+
+impl Plugin for FogPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), remove_fog).add_systems(
+            Update,
+            (
+                (spawn_fog.run_if(resource_exists_and_changed::<LevelBounds>), update_vision, shade_fog)
+                    .chain()
+                    .run_if(fog_enabled),
+                // A daytime level after a night one sees everything again
+                remove_fog.run_if(resource_exists_and_changed::<LevelBounds>).run_if(not(fog_enabled)),
+            )
+                .in_set(GameSet::Cleanup)
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+fn fog_enabled(cfg: Res<GameConfig>, theme: Res<LevelTheme>) -> bool {
+    cfg.vision.fog_of_war || theme.night
+}
+
+// The old level's cells went with its other LevelTiles
+fn spawn_fog(mut commands: Commands, bounds: Res<LevelBounds>) {
+    for row in 0..bounds.rows as u32 {
+        for col in 0..bounds.cols as u32 {
+            let pos = bounds.origin + Vec2::new(col as f32, -(row as f32)) * TILE;
+            commands.spawn((
+                Sprite { color: FOG_COLOR.with_alpha(UNSEEN_ALPHA), custom_size: Some(Vec2::splat(TILE)), ..default() },
+                // Over everything in the level, forest included
+                Transform::from_xyz(pos.x, pos.y, 3.5),
+                FogCell(UVec2::new(col, row)),
+                LevelTile,
+            ));
+        }
+    }
+    let cells = bounds.cols * bounds.rows;
+    commands.insert_resource(FogOfWar { cols: bounds.cols, visible: vec![false; cells], seen: vec![false; cells] });
+}
+
+fn update_vision(
+    cfg: Res<GameConfig>,
+    cache: Res<CollisionCache>,
+    bounds: Option<Res<LevelBounds>>,
+    fog: Option<ResMut<FogOfWar>>,
+    q_player: Query<&Transform, With<Player>>,
+) {
+    let (Some(bounds), Some(mut fog)) = (bounds, fog) else { return; };
+    // Between a death and the respawn the view freezes where it was
+    let Ok(player_t) = q_player.single() else { return; };
+    let eye = player_t.translation.truncate();
+    let r2 = cfg.vision.radius * cfg.vision.radius;
+
+    for row in 0..bounds.rows as u32 {
+        for col in 0..bounds.cols as u32 {
+            let cell = UVec2::new(col, row);
+            let centre = bounds.origin + Vec2::new(col as f32, -(row as f32)) * TILE;
+            let to_eye = eye - centre;
+            // Look as far as the tile's near edge, so a wall tile can itself be seen
+            let edge = centre + to_eye / to_eye.abs().max_element().max(1.0) * (TILE * 0.5 + 1.0);
+            let in_sight = bounds.cell_at(eye) == Some(cell)
+                || (to_eye.length_squared() <= r2 && line_clear(eye, edge, &cache.bullets));
+            let i = fog.index(cell);
+            fog.visible[i] = in_sight;
+            fog.seen[i] |= in_sight;
+        }
+    }
+}
+
+fn shade_fog(time: Res<Time>, fog: Option<Res<FogOfWar>>, mut q: Query<(&FogCell, &mut Sprite)>) {
+    let Some(fog) = fog else { return; };
+    let fade = (FADE_RATE * time.delta_secs()).min(1.0);
+    for (cell, mut sprite) in &mut q {
+        let target = if fog.visible(cell.0) {
+            0.0
+        } else if fog.seen(cell.0) {
+            REMEMBERED_ALPHA
+        } else {
+            UNSEEN_ALPHA
+        };
+        let alpha = sprite.color.alpha();
+        if alpha != target {
+            let next = if (target - alpha).abs() < 0.01 { target } else { alpha + (target - alpha) * fade };
+            sprite.color.set_alpha(next);
+        }
+    }
+}
+
+fn remove_fog(mut commands: Commands) {
+    commands.remove_resource::<FogOfWar>();
+}
@@ -0,0 +1,99 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::feedback::HitEvent;
+
+// Gameplay runs on Bevy's virtual clock (what `Res<Time>` is in Update), and
+// GameTime sets how fast that clock goes: a user speed, a pause, and short
+// timed slow-motion effects. Screen-space effects (camera shake, the minimap
+// refresh, the recap's input delay, time played) read `Time<Real>` instead,
+// so they keep their pace whatever the game speed.
+
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 4.0;
+const DEATH_SLOW_MO: f32 = 0.3;
+const DEATH_SLOW_MO_SECS: f32 = 0.8; // real seconds
+
+#[derive(Resource)]
+pub struct GameTime {
+    pub speed: f32,
+    pub paused: bool,
+    // Factor and how long it lasts in real time
+    slow_mo: Option<(f32, Timer)>,
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self { speed: 1.0, paused: false, slow_mo: None }
+    }
+}
+
+impl GameTime {
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    // A newer slow-mo replaces one already running
+    pub fn slow_mo(&mut self, factor: f32, real_secs: f32) {
+        self.slow_mo = Some((factor, Timer::from_seconds(real_secs, TimerMode::Once)));
+    }
+
+    fn relative_speed(&self) -> f32 {
+        self.speed * self.slow_mo.as_ref().map_or(1.0, |(factor, _)| *factor)
+    }
+}
+
+pub struct GameTimePlugin;
+
+impl Plugin for GameTimePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameTime>()
+            .add_systems(OnExit(AppState::Playing), reset_game_time)
+            .add_systems(PreUpdate, apply_game_time)
+            .add_systems(
+                Update,
+                (speed_keys, slow_mo_on_death).run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn apply_game_time(real: Res<Time<Real>>, mut game_time: ResMut<GameTime>, mut virt: ResMut<Time<Virtual>>) {
+    let expired = game_time
+        .bypass_change_detection()
+        .slow_mo
+        .as_mut()
+        .is_some_and(|(_, timer)| timer.tick(real.delta()).finished());
+    if expired { game_time.slow_mo = None; }
+
+    let speed = game_time.relative_speed();
+    if virt.relative_speed() != speed { virt.set_relative_speed(speed); }
+    if game_time.paused != virt.is_paused() {
+        if game_time.paused { virt.pause() } else { virt.unpause() }
+    }
+}
+
+// Debug controls: F5 halves the game speed, F6 doubles it, F7 pauses; the
+// console's speed and pause commands (see console.rs) do the same
+fn speed_keys(keys: Res<ButtonInput<KeyCode>>, mut game_time: ResMut<GameTime>) {
+    if keys.just_pressed(KeyCode::F5) {
+        let speed = game_time.speed * 0.5;
+        game_time.set_speed(speed);
+    }
+    if keys.just_pressed(KeyCode::F6) {
+        let speed = game_time.speed * 2.0;
+        game_time.set_speed(speed);
+    }
+    if keys.just_pressed(KeyCode::F7) {
+        game_time.paused = !game_time.paused;
+    }
+}
+
+fn slow_mo_on_death(mut hits: EventReader<HitEvent>, mut game_time: ResMut<GameTime>) {
+    if hits.read().any(|h| h.player && h.fatal) {
+        game_time.slow_mo(DEATH_SLOW_MO, DEATH_SLOW_MO_SECS);
+    }
+}
+
+fn reset_game_time(mut game_time: ResMut<GameTime>) {
+    *game_time = GameTime::default();
+}
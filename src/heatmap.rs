@@ -0,0 +1,191 @@
+use bevy::prelude::*;
+use std::fs;
+
+use crate::args::LaunchArgs;
+use crate::event_log::{RUNS_DIR, level_name};
+use crate::locale::Locale;
+use crate::packs::LevelPacks;
+use crate::persistence;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, LevelBounds, TILE};
+
+// Heatmap: F4 during a run lays what the event logs (see event_log.rs) have
+// recorded on this level over its maze, cell by cell: first where the player
+// lost tanks, then, on the next press, where enemies were destroyed by the
+// player, then nothing again. Every log in `runs/` counts, so it shows where
+// the choke points are across all the runs played with --event-log; the
+// hotter the colour, the more of it happened there. Levels are told apart by
+// name, and the map is made again when the maze changes.
+
+const TOGGLE_KEY: KeyCode = KeyCode::F4;
+// Over the fog and forest, under floating text
+const Z: f32 = 4.0;
+const COOL: Color = Color::srgba(1.0, 0.9, 0.2, 0.2);
+const HOT: Color = Color::srgba(1.0, 0.1, 0.05, 0.7);
+const HINT_COLOR: Color = Color::srgb(1.0, 0.6, 0.3);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Layer {
+    Deaths,
+    Kills,
+}
+
+impl Layer {
+    fn counts(self, line: &str) -> bool {
+        if field(line, "event").as_deref() != Some("death") { return false; }
+        let side = field(line, "side");
+        match self {
+            Layer::Deaths => side.as_deref() == Some("player"),
+            Layer::Kills => side.as_deref() == Some("enemy") && field(line, "by").as_deref() == Some("player"),
+        }
+    }
+
+    fn hint(self) -> &'static str {
+        match self {
+            Layer::Deaths => "heatmap.deaths",
+            Layer::Kills => "heatmap.kills",
+        }
+    }
+}
+
+// Present while a layer is shown
+#[derive(Resource)]
+struct Heatmap(Layer);
+
+#[derive(Component)]
+struct HeatCell;
+
+#[derive(Component)]
+struct HeatHint;
+
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), hide_heatmap).add_systems(
+            Update,
+            (toggle_heatmap, build_heatmap.run_if(resource_exists::<Heatmap>))
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+fn toggle_heatmap(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    heatmap: Option<Res<Heatmap>>,
+    q_cells: Query<Entity, Or<(With<HeatCell>, With<HeatHint>)>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) { return; }
+    match heatmap.map(|h| h.0) {
+        None => commands.insert_resource(Heatmap(Layer::Deaths)),
+        Some(Layer::Deaths) => commands.insert_resource(Heatmap(Layer::Kills)),
+        Some(Layer::Kills) => {
+            commands.remove_resource::<Heatmap>();
+            for e in &q_cells { commands.entity(e).despawn(); }
+        }
+    }
+}
+
+fn hide_heatmap(mut commands: Commands, q_cells: Query<Entity, Or<(With<HeatCell>, With<HeatHint>)>>) {
+    commands.remove_resource::<Heatmap>();
+    for e in &q_cells { commands.entity(e).despawn(); }
+}
+
+// The value of `key` in one of the log's lines, a string's quotes and
+// escapes taken off. The log is written by event_log.rs, which puts no
+// nesting in a line, so this needs no more of JSON than that.
+fn field(line: &str, key: &str) -> Option<String> {
+    let pattern = format!("\"{key}\":");
+    let rest = &line[line.find(&pattern)? + pattern.len()..];
+    let Some(text) = rest.strip_prefix('"') else {
+        return rest.split([',', '}']).next().map(|v| v.trim().to_string());
+    };
+    let mut value = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some(value),
+            '\\' => match chars.next()? {
+                'u' => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    value.push(u32::from_str_radix(&code, 16).ok().and_then(char::from_u32)?);
+                }
+                c => value.push(c),
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+// Events per cell, row by row, from every log there is; and how many runs
+// played this level
+fn tally(level: &str, layer: Layer, bounds: &LevelBounds) -> (Vec<u32>, usize) {
+    let mut counts = vec![0; bounds.cols * bounds.rows];
+    let mut runs = 0;
+    let dir = persistence::data_dir().join(RUNS_DIR);
+    let Ok(entries) = fs::read_dir(&dir) else { return (counts, runs); };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "jsonl") { continue; }
+        let Ok(text) = fs::read_to_string(&path) else {
+            warn!("can't read {}", path.display());
+            continue;
+        };
+        let mut played = false;
+        for line in text.lines().filter(|l| field(l, "level").as_deref() == Some(level)) {
+            played = true;
+            if !layer.counts(line) { continue; }
+            let cell = field(line, "col").zip(field(line, "row"));
+            let Some((col, row)) = cell.and_then(|(c, r)| c.parse::<usize>().ok().zip(r.parse::<usize>().ok())) else {
+                continue;
+            };
+            if col < bounds.cols && row < bounds.rows { counts[row * bounds.cols + col] += 1; }
+        }
+        if played { runs += 1; }
+    }
+    (counts, runs)
+}
+
+// When the layer or the maze changes
+fn build_heatmap(
+    mut commands: Commands,
+    heatmap: Res<Heatmap>,
+    bounds: Option<Res<LevelBounds>>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    mode: Res<GameMode>,
+    stage: Res<CurrentLevel>,
+    locale: Res<Locale>,
+    q_cells: Query<Entity, Or<(With<HeatCell>, With<HeatHint>)>>,
+) {
+    let Some(bounds) = bounds else { return; };
+    if !heatmap.is_changed() && !bounds.is_changed() { return; }
+    for e in &q_cells { commands.entity(e).despawn(); }
+
+    let level = level_name(&args, &packs, *mode, stage.0);
+    let (counts, runs) = tally(&level, heatmap.0, &bounds);
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+    for (i, &count) in counts.iter().enumerate().filter(|(_, c)| **c > 0) {
+        let (col, row) = (i % bounds.cols, i / bounds.cols);
+        let pos = bounds.origin + Vec2::new(col as f32, -(row as f32)) * TILE;
+        let heat = count as f32 / most as f32;
+        let color = Color::from(COOL.to_srgba().mix(&HOT.to_srgba(), heat));
+        commands.spawn((
+            Sprite { color, custom_size: Some(Vec2::splat(TILE)), ..default() },
+            Transform::from_xyz(pos.x, pos.y, Z),
+            HeatCell,
+        ));
+    }
+
+    let events: u32 = counts.iter().sum();
+    commands.spawn((
+        Text::new(locale.f(heatmap.0.hint(), &[("events", &events), ("runs", &runs)])),
+        TextFont { font_size: 16.0, ..default() },
+        TextColor(HINT_COLOR),
+        Node { position_type: PositionType::Absolute, bottom: Val::Px(8.0), right: Val::Px(8.0), ..default() },
+        HeatHint,
+    ));
+}
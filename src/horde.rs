@@ -0,0 +1,353 @@
+use bevy::prelude::*;
+use std::collections::{HashSet, VecDeque};
+
+use crate::attract::Demo;
+use crate::config::GameConfig;
+use crate::console::console_closed;
+use crate::display::alt_held;
+use crate::drops::Coins;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::Locale;
+use crate::recap::StageRecap;
+use crate::settings::settings_closed;
+use crate::themes::LevelTheme;
+use crate::toasts::Notify;
+use crate::waves::{CurrentLevel, GameMode, LevelEnemyQueue};
+use crate::{
+    AppState, Base, Enemy, GameSet, LevelBounds, LevelTile, Player, PlayerStart, Size, SpawnPoints, SpawnTelegraph,
+    TILE, TileKind, Water, aabb_overlap, spawn_wall, start_run,
+};
+
+// Horde mode: the first maze, wave after wave, with a build phase before each
+// one. While it lasts no enemies roll out; the player drives up to a spot and
+// spends coins on a brick or steel tile on the one in front of the tank, then
+// starts the wave when ready. Holding a wave off pays horde.wave_coins on top
+// of whatever coins the kills dropped. Tiles only go on open floor, never on
+// a spawn point or the player's start, and steel may not cut the base off from
+// every spawn point, so the base can't be walled in for good.
+
+const GHOST_ALPHA: f32 = 0.5;
+const BLOCKED_COLOR: Color = Color::srgba(1.0, 0.2, 0.2, GHOST_ALPHA);
+const PROBLEM_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+
+// Present between waves
+#[derive(Resource)]
+pub struct BuildPhase {
+    tile: TileKind,
+    // The tile in front of the player's tank, if it has one
+    site: Option<Site>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Site {
+    pos: Vec2,
+    // Why nothing can be built there, if so, as a key into the language files
+    problem: Option<&'static str>,
+}
+
+// Where the next tile would go
+#[derive(Component)]
+struct BuildGhost;
+
+#[derive(Component)]
+struct BuildPanel;
+
+#[derive(Component)]
+struct BuildInfo;
+
+#[derive(Component)]
+struct BuildStatus;
+
+pub struct HordePlugin;
+
+impl Plugin for HordePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            begin_horde.after(start_run).run_if(resource_equals(GameMode::Horde)),
+        )
+        .add_systems(OnExit(AppState::Playing), leave_horde)
+        .add_systems(
+            Update,
+            (
+                (survey_site, build_input.run_if(settings_closed).run_if(console_closed))
+                    .chain()
+                    .in_set(GameSet::Input)
+                    .run_if(resource_exists::<BuildPhase>),
+                (place_ghost, refresh_build_panel).in_set(GameSet::Cleanup).run_if(resource_exists::<BuildPhase>),
+                wave_cleared.in_set(GameSet::Cleanup).run_if(not(resource_exists::<BuildPhase>)),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(resource_equals(GameMode::Horde))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+fn cost(tile: TileKind, cfg: &GameConfig) -> u32 {
+    match tile {
+        TileKind::Brick => cfg.horde.brick_cost,
+        TileKind::Steel => cfg.horde.steel_cost,
+    }
+}
+
+fn tile_name(tile: TileKind) -> &'static str {
+    match tile {
+        TileKind::Brick => "horde.brick",
+        TileKind::Steel => "horde.steel",
+    }
+}
+
+fn open_build_phase(commands: &mut Commands) {
+    commands.insert_resource(BuildPhase { tile: TileKind::Brick, site: None });
+    commands.spawn((
+        Sprite { custom_size: Some(Vec2::splat(TILE)), ..default() },
+        Transform::from_xyz(0.0, 0.0, 0.9),
+        Visibility::Hidden,
+        BuildGhost,
+    ));
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(12.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            BuildPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                Text::new(""),
+                TextFont { font_size: 16.0, ..default() },
+                TextColor(Color::WHITE),
+                Node { padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)), ..default() },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+                BuildInfo,
+            ));
+            panel.spawn((
+                Text::new(""),
+                TextFont { font_size: 14.0, ..default() },
+                TextColor(PROBLEM_COLOR),
+                BuildStatus,
+            ));
+        });
+}
+
+fn close_build_phase(commands: &mut Commands, q_ui: &Query<Entity, Or<(With<BuildGhost>, With<BuildPanel>)>>) {
+    commands.remove_resource::<BuildPhase>();
+    for e in q_ui { commands.entity(e).despawn(); }
+}
+
+// start_run has set coins to nothing; the first build phase comes before the first wave
+fn begin_horde(mut commands: Commands, cfg: Res<GameConfig>, mut coins: ResMut<Coins>) {
+    coins.0 += cfg.horde.start_coins;
+    open_build_phase(&mut commands);
+}
+
+fn leave_horde(mut commands: Commands, q_ui: Query<Entity, Or<(With<BuildGhost>, With<BuildPanel>)>>) {
+    close_build_phase(&mut commands, &q_ui);
+}
+
+// Once the wave's queue is used up and the field is clear, the next wave is
+// lined up and the build phase opens
+fn wave_cleared(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut queue: ResMut<LevelEnemyQueue>,
+    mut level: ResMut<CurrentLevel>,
+    mut coins: ResMut<Coins>,
+    locale: Res<Locale>,
+    mut notify: EventWriter<Notify>,
+    q_enemies: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+) {
+    if queue.has_next() || !q_enemies.is_empty() { return; }
+    coins.0 += cfg.horde.wave_coins;
+    let text = locale.f("horde.wave_held", &[("wave", &(level.0 + 1)), ("coins", &cfg.horde.wave_coins)]);
+    notify.write(Notify::new(text));
+    level.0 += 1;
+    *queue = LevelEnemyQueue::for_run(GameMode::Horde, level.0, &cfg);
+    open_build_phase(&mut commands);
+}
+
+// Closest cardinal to where the tank points, as tiles are on a grid
+fn facing(t: &Transform) -> Vec2 {
+    let forward = t.rotation.mul_vec3(Vec3::X).truncate();
+    if forward.x.abs() >= forward.y.abs() {
+        Vec2::new(forward.x.signum(), 0.0)
+    } else {
+        Vec2::new(0.0, forward.y.signum())
+    }
+}
+
+// Whether tanks can still get from each spawn point to a tile next to the base
+// without crossing a blocked cell. Bricks don't count as blocking, since they
+// can be shot through.
+fn base_reachable(bounds: &LevelBounds, blocked: &HashSet<UVec2>, base: UVec2, spawns: &[UVec2]) -> bool {
+    let mut seen = HashSet::from([base]);
+    let mut open = VecDeque::from([base]);
+    while let Some(cell) = open.pop_front() {
+        for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            let (x, y) = (cell.x as i32 + dx, cell.y as i32 + dy);
+            if x < 0 || y < 0 || x as usize >= bounds.cols || y as usize >= bounds.rows { continue; }
+            let next = UVec2::new(x as u32, y as u32);
+            if blocked.contains(&next) || !seen.insert(next) { continue; }
+            open.push_back(next);
+        }
+    }
+    spawns.iter().all(|s| seen.contains(s))
+}
+
+fn survey_site(
+    cfg: Res<GameConfig>,
+    coins: Res<Coins>,
+    bounds: Option<Res<LevelBounds>>,
+    spawns: Res<SpawnPoints>,
+    start: Option<Res<PlayerStart>>,
+    mut build: ResMut<BuildPhase>,
+    q_player: Query<(&Transform, &Size), With<Player>>,
+    q_tanks: Query<(&Transform, &Size), Or<(With<Player>, With<Enemy>)>>,
+    q_tiles: Query<(&Transform, Option<&TileKind>, Has<Water>), With<LevelTile>>,
+    q_base: Query<&Transform, With<Base>>,
+) {
+    let Some(bounds) = bounds else { return; };
+    let site = q_player.single().ok().and_then(|(t, size)| {
+        let ahead = t.translation.truncate() + facing(t) * (size.0.max_element() * 0.5 + TILE);
+        let pos = bounds.cell_center(ahead)?;
+        let cell = bounds.cell_at(pos)?;
+        let on = |p: Vec2| bounds.cell_at(p) == Some(cell);
+
+        let taken = q_tiles.iter().any(|(tile_t, _, _)| on(tile_t.translation.truncate()));
+        let in_way = q_tanks
+            .iter()
+            .any(|(tank_t, s)| aabb_overlap(pos, Vec2::splat(TILE * 0.45), tank_t.translation.truncate(), s.0 * 0.5));
+        let reserved = spawns.points.iter().any(|&p| on(p)) || start.as_ref().is_some_and(|s| on(s.0));
+        let seals_base = build.tile == TileKind::Steel && {
+            let mut blocked: HashSet<UVec2> = q_tiles
+                .iter()
+                .filter(|(_, kind, water)| *water || *kind == Some(&TileKind::Steel))
+                .filter_map(|(tile_t, _, _)| bounds.cell_at(tile_t.translation.truncate()))
+                .collect();
+            blocked.insert(cell);
+            let spawn_cells: Vec<UVec2> = spawns.points.iter().filter_map(|&p| bounds.cell_at(p)).collect();
+            q_base
+                .single()
+                .ok()
+                .and_then(|b| bounds.cell_at(b.translation.truncate()))
+                .is_some_and(|base| !base_reachable(&bounds, &blocked, base, &spawn_cells))
+        };
+
+        let problem = if taken {
+            Some("horde.tile_taken")
+        } else if in_way {
+            Some("horde.tank_in_way")
+        } else if reserved {
+            Some("horde.keep_clear")
+        } else if seals_base {
+            Some("horde.seals_base")
+        } else if coins.0 < cost(build.tile, &cfg) {
+            Some("horde.no_coins")
+        } else {
+            None
+        };
+        Some(Site { pos, problem })
+    });
+    // Only touch the resource when the site moves, so the panel stays idle
+    if build.site != site { build.site = site; }
+}
+
+fn build_input(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    cfg: Res<GameConfig>,
+    theme: Res<LevelTheme>,
+    level: Res<CurrentLevel>,
+    mut build: ResMut<BuildPhase>,
+    mut coins: ResMut<Coins>,
+    mut notify: EventWriter<Notify>,
+    locale: Res<Locale>,
+    demo: Option<Res<Demo>>,
+    q_ui: Query<Entity, Or<(With<BuildGhost>, With<BuildPanel>)>>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key) || pads.iter().any(|p| p.just_pressed(button))
+    };
+
+    if pressed(KeyCode::Tab, GamepadButton::LeftTrigger) {
+        build.tile = match build.tile {
+            TileKind::Brick => TileKind::Steel,
+            TileKind::Steel => TileKind::Brick,
+        };
+    }
+
+    if pressed(KeyCode::KeyB, GamepadButton::North)
+        && let Some(site) = build.site.filter(|s| s.problem.is_none())
+    {
+        coins.0 -= cost(build.tile, &cfg);
+        spawn_wall(&mut commands, site.pos, build.tile, &theme);
+        // Surveyed again next frame, with the new tile in place
+        build.site = None;
+    }
+
+    // The demo bot doesn't build, so it goes straight on to the wave
+    let start = demo.is_some()
+        || (keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) && !alt_held(&keys))
+        || pads.iter().any(|p| p.just_pressed(GamepadButton::Start));
+    if start {
+        close_build_phase(&mut commands, &q_ui);
+        notify.write(Notify::new(locale.f("horde.wave", &[("wave", &(level.0 + 1))])));
+    }
+}
+
+fn place_ghost(
+    build: Res<BuildPhase>,
+    theme: Res<LevelTheme>,
+    mut q: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<BuildGhost>>,
+) {
+    for (mut t, mut sprite, mut visibility) in &mut q {
+        let Some(site) = build.site else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        t.translation.x = site.pos.x;
+        t.translation.y = site.pos.y;
+        let open = theme.tile(build.tile).with_alpha(GHOST_ALPHA);
+        sprite.color = if site.problem.is_none() { open } else { BLOCKED_COLOR };
+    }
+}
+
+fn refresh_build_panel(
+    build: Res<BuildPhase>,
+    coins: Res<Coins>,
+    level: Res<CurrentLevel>,
+    cfg: Res<GameConfig>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    mut q_info: Query<(&mut Text, Ref<BuildInfo>), Without<BuildStatus>>,
+    mut q_status: Query<&mut Text, With<BuildStatus>>,
+) {
+    let changed = build.is_changed() || coins.is_changed() || device.is_changed() || locale.is_changed();
+    for (mut text, info) in &mut q_info {
+        if !(changed || info.is_added()) { continue; }
+        text.0 = format!(
+            "{}    {}: {} ({})    {}: {}    {}: {}",
+            locale.f("horde.panel", &[("wave", &(level.0 + 1)), ("coins", &coins.0)]),
+            glyph(Action::SwapTile, *device),
+            locale.t(tile_name(build.tile)),
+            cost(build.tile, &cfg),
+            glyph(Action::Build, *device),
+            locale.t("horde.build"),
+            glyph(Action::StartWave, *device),
+            locale.t("horde.start_wave"),
+        );
+    }
+    if !(build.is_changed() || locale.is_changed()) { return; }
+    for mut text in &mut q_status {
+        text.0 = build.site.and_then(|s| s.problem).map_or("", |problem| locale.t(problem)).to_string();
+    }
+}
@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::settings::ControlScheme;
+
+// Tracks the most recently used input device and renders the matching button
+// prompts. Anything that shows a control hint (HUD, menus, tutorial) should go
+// through `glyph()` so prompts switch live with the device.
+
+const HINT_FONT_SIZE: f32 = 14.0;
+const STICK_ACTIVITY: f32 = 0.5;
+
+// USB vendor ids used to pick a glyph family for a connected pad.
+const VENDOR_SONY: u16 = 0x054C;
+const VENDOR_NINTENDO: u16 = 0x057E;
+
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum InputDevice {
+    #[default]
+    Keyboard,
+    Xbox,
+    PlayStation,
+    Switch,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Move,
+    Fire,
+    Utility,
+    LayMine,
+    Navigate,
+    Confirm,
+    // Leaves a screen or dialog
+    Back,
+    Scores,
+    Stats,
+    Achievements,
+    Mode,
+    Continue,
+    Settings,
+    // The campaign browser
+    Packs,
+    // Opens the settings over a run
+    Pause,
+    // Horde build phase
+    Build,
+    SwapTile,
+    StartWave,
+}
+
+pub fn glyph(action: Action, device: InputDevice) -> &'static str {
+    if device == InputDevice::Keyboard {
+        return match action {
+            Action::Move => "W/A/S/D",
+            Action::Fire => "Space",
+            Action::Utility => "Shift",
+            Action::LayMine => "E",
+            Action::Navigate => "Arrows",
+            Action::Confirm => "Enter",
+            Action::Back => "Esc",
+            Action::Scores => "H",
+            Action::Stats => "T",
+            Action::Achievements => "G",
+            Action::Mode => "M",
+            Action::Continue => "C",
+            Action::Settings => "O",
+            Action::Packs => "P",
+            Action::Pause => "Esc",
+            Action::Build => "B",
+            Action::SwapTile => "Tab",
+            Action::StartWave => "Enter",
+        };
+    }
+    match action {
+        Action::Move => "Left Stick",
+        Action::Navigate => "D-Pad",
+        Action::Fire | Action::Confirm => face_button(GamepadButton::South, device),
+        Action::Utility => face_button(GamepadButton::West, device),
+        Action::LayMine | Action::Back => face_button(GamepadButton::East, device),
+        Action::Scores | Action::Build => face_button(GamepadButton::North, device),
+        Action::Stats | Action::Pause => match device {
+            InputDevice::PlayStation => "(Share)",
+            InputDevice::Switch => "(-)",
+            _ => "(View)",
+        },
+        Action::Achievements => match device {
+            InputDevice::PlayStation => "(R1)",
+            InputDevice::Switch => "(R)",
+            _ => "(RB)",
+        },
+        Action::Mode | Action::SwapTile => match device {
+            InputDevice::PlayStation => "(L1)",
+            InputDevice::Switch => "(L)",
+            _ => "(LB)",
+        },
+        Action::Settings => match device {
+            InputDevice::PlayStation => "(R2)",
+            InputDevice::Switch => "(ZR)",
+            _ => "(RT)",
+        },
+        Action::Packs => match device {
+            InputDevice::PlayStation => "(L2)",
+            InputDevice::Switch => "(ZL)",
+            _ => "(LT)",
+        },
+        Action::Continue | Action::StartWave => match device {
+            InputDevice::PlayStation => "(Options)",
+            InputDevice::Switch => "(+)",
+            _ => "(Menu)",
+        },
+    }
+}
+
+// The tank's own keys follow the control scheme picked in the settings
+pub fn tank_glyph(action: Action, device: InputDevice, scheme: ControlScheme) -> &'static str {
+    if device != InputDevice::Keyboard || scheme == ControlScheme::Standard { return glyph(action, device); }
+    match action {
+        Action::Move => "Arrows",
+        Action::Fire => "Z",
+        Action::Utility => "X",
+        Action::LayMine => "C",
+        _ => glyph(action, device),
+    }
+}
+
+fn face_button(button: GamepadButton, device: InputDevice) -> &'static str {
+    // Nintendo swaps the labels: B sits on the south face, Y on the west, X on the north.
+    match (button, device) {
+        (GamepadButton::South, InputDevice::PlayStation) => "(X)",
+        (GamepadButton::South, InputDevice::Switch) => "(B)",
+        (GamepadButton::South, _) => "(A)",
+        (GamepadButton::West, InputDevice::PlayStation) => "(Square)",
+        (GamepadButton::West, InputDevice::Switch) => "(Y)",
+        (GamepadButton::West, _) => "(X)",
+        (GamepadButton::North, InputDevice::PlayStation) => "(Triangle)",
+        (GamepadButton::North, InputDevice::Switch) => "(X)",
+        (GamepadButton::North, _) => "(Y)",
+        (GamepadButton::East, InputDevice::PlayStation) => "(Circle)",
+        (GamepadButton::East, InputDevice::Switch) => "(A)",
+        (GamepadButton::East, _) => "(B)",
+        _ => "(?)",
+    }
+}
+
+fn device_for_pad(pad: &Gamepad) -> InputDevice {
+    match pad.vendor_id() {
+        Some(VENDOR_SONY) => InputDevice::PlayStation,
+        Some(VENDOR_NINTENDO) => InputDevice::Switch,
+        _ => InputDevice::Xbox,
+    }
+}
+
+#[derive(Component)]
+struct ControlsHint;
+
+pub struct InputGlyphsPlugin;
+
+impl Plugin for InputGlyphsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputDevice>()
+            .add_systems(Startup, spawn_controls_hint)
+            .add_systems(Update, (detect_input_device, update_controls_hint).chain());
+    }
+}
+
+fn detect_input_device(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut device: ResMut<InputDevice>,
+) {
+    let mut latest = None;
+    if keys.get_just_pressed().next().is_some() {
+        latest = Some(InputDevice::Keyboard);
+    }
+    for pad in &pads {
+        let touched = pad.get_just_pressed().next().is_some()
+            || pad.left_stick().length() > STICK_ACTIVITY;
+        if touched {
+            latest = Some(device_for_pad(pad));
+        }
+    }
+
+    // Only write on an actual switch so change detection stays meaningful.
+    if let Some(latest) = latest
+        && *device != latest
+    {
+        *device = latest;
+    }
+}
+
+fn spawn_controls_hint(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: HINT_FONT_SIZE, ..default() },
+        TextColor(Color::srgba(1.0, 1.0, 1.0, 0.7)),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(6.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        ControlsHint,
+    ));
+}
+
+fn update_controls_hint(
+    device: Res<InputDevice>,
+    profile: Res<Profile>,
+    locale: Res<Locale>,
+    mut q: Query<&mut Text, With<ControlsHint>>,
+) {
+    if !(device.is_changed() || profile.is_changed() || locale.is_changed()) { return; }
+    let scheme = profile.controls;
+    let hint = |key: &str, action: Action| format!("{}: {}", locale.t(key), tank_glyph(action, *device, scheme));
+    for mut text in &mut q {
+        text.0 = format!(
+            "{}   {}   {}   {}   {}: {}",
+            hint("hint.move", Action::Move),
+            hint("hint.fire", Action::Fire),
+            hint("hint.utility", Action::Utility),
+            hint("hint.mine", Action::LayMine),
+            locale.t("hint.settings"),
+            glyph(Action::Pause, *device),
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use crate::console::console_closed;
+use crate::controller::PlayerController;
+use crate::photo::photo_mode_off;
+use crate::profile::Profile;
+use crate::settings::{ControlScheme, settings_closed};
+use crate::touch::{read_touch, touch_shown};
+
+// What the player tank is being asked to do this frame, whoever is asking.
+// Keyboard and gamepads fill it in here, with the touch controls adding to
+// them (see touch.rs), unless a PlayerController is installed and drives
+// instead. The player systems only ever read this.
+
+#[derive(Resource, Default)]
+pub struct PlayerIntent {
+    // Raw stick/keys sum; player_input picks the cardinal direction
+    pub movement: Vec2,
+    // Presses, true on the frame they happen
+    pub fire: bool,
+    pub utility: bool,
+    pub lay_mine: bool,
+}
+
+pub struct IntentPlugin;
+
+impl Plugin for IntentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlayerIntent>().add_systems(
+            PreUpdate,
+            (
+                (read_devices, read_touch.run_if(touch_shown))
+                    .chain()
+                    .run_if(not(resource_exists::<PlayerController>)),
+                // The tank sits still under the settings panel, in photo mode and while the console is open
+                clear_intent.run_if(not(settings_closed).or(not(photo_mode_off)).or(not(console_closed))),
+            )
+                .chain()
+                .after(InputSystem),
+        );
+    }
+}
+
+fn read_devices(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    profile: Res<Profile>,
+    mut intent: ResMut<PlayerIntent>,
+) {
+    let arcade = profile.controls == ControlScheme::Arcade;
+    let held = |wasd: KeyCode, arrow: KeyCode| keys.pressed(arrow) || (!arcade && keys.pressed(wasd));
+    let mut dir = Vec2::ZERO;
+    if held(KeyCode::KeyW, KeyCode::ArrowUp) { dir.y += 1.0; }
+    if held(KeyCode::KeyS, KeyCode::ArrowDown) { dir.y -= 1.0; }
+    if held(KeyCode::KeyA, KeyCode::ArrowLeft) { dir.x -= 1.0; }
+    if held(KeyCode::KeyD, KeyCode::ArrowRight) { dir.x += 1.0; }
+    for pad in &pads {
+        dir += profile.controller.movement.apply(pad.left_stick());
+        dir += pad.dpad();
+    }
+
+    let pad_pressed = |button: GamepadButton| pads.iter().any(|pad| pad.just_pressed(button));
+    let (fire, utility, lay_mine) = if arcade {
+        (keys.just_pressed(KeyCode::KeyZ), keys.just_pressed(KeyCode::KeyX), keys.just_pressed(KeyCode::KeyC))
+    } else {
+        (
+            keys.just_pressed(KeyCode::Space),
+            keys.any_just_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]),
+            keys.just_pressed(KeyCode::KeyE),
+        )
+    };
+    *intent = PlayerIntent {
+        movement: dir,
+        fire: fire || pad_pressed(GamepadButton::South),
+        utility: utility || pad_pressed(GamepadButton::West),
+        lay_mine: lay_mine || pad_pressed(GamepadButton::East),
+    };
+}
+
+fn clear_intent(mut intent: ResMut<PlayerIntent>) {
+    *intent = PlayerIntent::default();
+}
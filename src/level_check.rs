@@ -0,0 +1,256 @@
+use bevy::prelude::*;
+
+use crate::AppState;
+use crate::args::LaunchArgs;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::Locale;
+use crate::mods::ContentRegistry;
+use crate::packs::{LevelPacks, run_levels};
+use crate::scores::confirm_pressed;
+use crate::waves::GameMode;
+
+// Level checks: before a run on level files starts, each maze it will play is
+// looked over for what would keep it from playing properly (ragged rows, no
+// player start or more than one, no enemy spawn, no base when the mode has
+// one to defend, a gap in the border, a spawn shut in by steel or water). If
+// anything is wrong the run doesn't start, and a dialog over the loadout
+// screen lists every problem found. Cells are (column,row) from the top left,
+// counted from 0, as in level scripts.
+
+const TITLE_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+const DETAIL_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    Ragged { row: usize, width: usize, expected: usize },
+    NoStart,
+    Starts(usize),
+    NoSpawn,
+    NoBase,
+    OpenBorder { col: usize, row: usize },
+    SpawnShut { col: usize, row: usize },
+}
+
+impl Problem {
+    pub fn describe(&self, locale: &Locale) -> String {
+        match *self {
+            Problem::Ragged { row, width, expected } => {
+                locale.f("check.ragged", &[("row", &row), ("width", &width), ("expected", &expected)])
+            }
+            Problem::NoStart => locale.t("check.no_start").to_string(),
+            Problem::Starts(count) => locale.f("check.starts", &[("count", &count)]),
+            Problem::NoSpawn => locale.t("check.no_spawn").to_string(),
+            Problem::NoBase => locale.t("check.no_base").to_string(),
+            Problem::OpenBorder { col, row } => locale.f("check.open_border", &[("col", &col), ("row", &row)]),
+            Problem::SpawnShut { col, row } => locale.f("check.spawn_shut", &[("col", &col), ("row", &row)]),
+        }
+    }
+}
+
+// Survival has no base to lose
+pub fn needs_base(mode: GameMode) -> bool {
+    mode != GameMode::Survival
+}
+
+// Everything wrong with a maze, in reading order; none means it's fine. Only
+// the first gap in the border is given, as a missing edge would be a long list.
+pub fn problems<S: AsRef<str>>(rows: &[S], needs_base: bool) -> Vec<Problem> {
+    let rows: Vec<&[u8]> = rows.iter().map(|r| r.as_ref().as_bytes()).collect();
+    let mut found = Vec::new();
+    let Some(first) = rows.first() else { return found; };
+    let cols = first.len();
+    for (row, line) in rows.iter().enumerate() {
+        if line.len() != cols { found.push(Problem::Ragged { row, width: line.len(), expected: cols }); }
+    }
+
+    let cells = || {
+        rows.iter().enumerate().flat_map(|(r, line)| line.iter().enumerate().map(move |(c, &g)| (c, r, g)))
+    };
+    match cells().filter(|&(_, _, g)| g == b'P').count() {
+        0 => found.push(Problem::NoStart),
+        1 => {}
+        n => found.push(Problem::Starts(n)),
+    }
+    if !cells().any(|(_, _, g)| g == b'S') { found.push(Problem::NoSpawn); }
+    if needs_base && !cells().any(|(_, _, g)| g == b'E') { found.push(Problem::NoBase); }
+
+    let edge = |c: usize, r: usize| r == 0 || r + 1 == rows.len() || c == 0 || c + 1 == rows[r].len();
+    let gap = cells().find(|&(c, r, g)| edge(c, r) && !matches!(g, b'#' | b'b'));
+    if let Some((col, row, _)) = gap { found.push(Problem::OpenBorder { col, row }); }
+
+    // Bricks can be shot away, so only steel, water and the maze's edge shut a spawn in
+    let shut = |c: Option<usize>, r: Option<usize>| {
+        let tile = r.and_then(|r| rows.get(r)).and_then(|line| c.and_then(|c| line.get(c)));
+        tile.is_none_or(|g| matches!(*g, b'#' | b'~'))
+    };
+    for (col, row, _) in cells().filter(|&(_, _, g)| g == b'S') {
+        let around = [
+            (Some(col), row.checked_sub(1)),
+            (Some(col + 1), Some(row)),
+            (Some(col), Some(row + 1)),
+            (col.checked_sub(1), Some(row)),
+        ];
+        if around.into_iter().all(|(c, r)| shut(c, r)) { found.push(Problem::SpawnShut { col, row }); }
+    }
+    found
+}
+
+// The first level of the coming run that can't be played, and why; shown
+// until it's dismissed
+#[derive(Resource)]
+pub struct UnplayableLevel {
+    name: String,
+    problems: Vec<Problem>,
+}
+
+// A mod's tiles are checked as what they play as
+pub fn check_run(
+    args: &LaunchArgs,
+    packs: &LevelPacks,
+    content: &ContentRegistry,
+    mode: GameMode,
+    resume: bool,
+) -> Option<UnplayableLevel> {
+    run_levels(args, packs, mode, resume).into_iter().find_map(|level| {
+        let problems = problems(&content.base_rows(&level.rows), needs_base(mode));
+        (!problems.is_empty()).then(|| UnplayableLevel { name: level.name.clone(), problems })
+    })
+}
+
+#[derive(Component)]
+struct CheckDialog;
+
+pub struct LevelCheckPlugin;
+
+impl Plugin for LevelCheckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Loadout), close_dialog).add_systems(
+            Update,
+            (spawn_dialog.run_if(resource_added::<UnplayableLevel>), dismiss_dialog)
+                .chain()
+                .run_if(resource_exists::<UnplayableLevel>)
+                .run_if(in_state(AppState::Loadout)),
+        );
+    }
+}
+
+fn spawn_dialog(
+    mut commands: Commands,
+    unplayable: Res<UnplayableLevel>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+            // Over the loadout screen, under the toasts
+            GlobalZIndex(9),
+            CheckDialog,
+        ))
+        .with_children(|root| {
+            let title = locale.f("check.title", &[("name", &unplayable.name)]);
+            root.spawn((Text::new(title), font(28.0), TextColor(TITLE_COLOR)));
+            for problem in &unplayable.problems {
+                root.spawn((Text::new(problem.describe(&locale)), font(18.0), TextColor(Color::WHITE)));
+            }
+            let keys = format!("{}/{}", glyph(Action::Confirm, *device), glyph(Action::Back, *device));
+            let footer = format!("{keys}: {}", locale.t("hint.back"));
+            root.spawn((Text::new(footer), font(16.0), TextColor(DETAIL_COLOR)));
+        });
+}
+
+// The loadout screen's keys are held off until it's gone (see loadout.rs)
+fn dismiss_dialog(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    q: Query<Entity, With<CheckDialog>>,
+) {
+    let back = keys.just_pressed(KeyCode::Escape) || pads.iter().any(|p| p.just_pressed(GamepadButton::East));
+    if !(back || confirm_pressed(&keys, &pads)) { return; }
+    commands.remove_resource::<UnplayableLevel>();
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn close_dialog(mut commands: Commands, q: Query<Entity, With<CheckDialog>>) {
+    commands.remove_resource::<UnplayableLevel>();
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOOD: [&str; 6] = ["#######", "#S...S#", "#.....#", "#..P..#", "#.bEb.#", "#######"];
+
+    fn with(cells: &[(usize, usize, char)]) -> Vec<String> {
+        let mut rows: Vec<Vec<char>> = GOOD.iter().map(|r| r.chars().collect()).collect();
+        for &(col, row, glyph) in cells { rows[row][col] = glyph; }
+        rows.into_iter().map(String::from_iter).collect()
+    }
+
+    #[test]
+    fn a_good_maze_has_no_problems() {
+        assert_eq!(problems(&GOOD, true), vec![]);
+        assert_eq!(problems::<&str>(&[], true), vec![]);
+    }
+
+    #[test]
+    fn finds_ragged_rows() {
+        let mut rows = with(&[]);
+        rows[2].pop();
+        rows[3].push('#');
+        let found = problems(&rows, true);
+        assert_eq!(found[0], Problem::Ragged { row: 2, width: 6, expected: 7 });
+        assert_eq!(found[1], Problem::Ragged { row: 3, width: 8, expected: 7 });
+    }
+
+    #[test]
+    fn finds_missing_and_extra_starts() {
+        assert_eq!(problems(&with(&[(3, 3, '.')]), true), vec![Problem::NoStart]);
+        assert_eq!(problems(&with(&[(1, 2, 'P'), (5, 2, 'P')]), true), vec![Problem::Starts(3)]);
+    }
+
+    #[test]
+    fn finds_a_missing_spawn() {
+        assert_eq!(problems(&with(&[(1, 1, '.'), (5, 1, '.')]), true), vec![Problem::NoSpawn]);
+    }
+
+    #[test]
+    fn wants_a_base_only_where_the_mode_has_one() {
+        let rows = with(&[(3, 4, '.')]);
+        assert_eq!(problems(&rows, true), vec![Problem::NoBase]);
+        assert_eq!(problems(&rows, needs_base(GameMode::Survival)), vec![]);
+        assert!(needs_base(GameMode::Campaign) && needs_base(GameMode::Horde));
+    }
+
+    #[test]
+    fn gives_the_first_gap_in_the_border() {
+        // Brick will do for a border
+        assert_eq!(problems(&with(&[(0, 2, 'b')]), true), vec![]);
+        let rows = with(&[(6, 3, '.'), (2, 5, '.'), (4, 5, '~')]);
+        assert_eq!(problems(&rows, true), vec![Problem::OpenBorder { col: 6, row: 3 }]);
+    }
+
+    #[test]
+    fn finds_spawns_shut_in_by_steel_or_water() {
+        // Left and top are the border; the way out is the cell below and the one to the right
+        assert_eq!(problems(&with(&[(2, 1, '#'), (1, 2, '~')]), true), vec![Problem::SpawnShut { col: 1, row: 1 }]);
+        // Brick can be shot away
+        assert_eq!(problems(&with(&[(2, 1, 'b'), (1, 2, '~')]), true), vec![]);
+        let both = with(&[(4, 1, '~'), (5, 2, '#'), (2, 1, '#'), (1, 2, '#')]);
+        let shut = vec![Problem::SpawnShut { col: 1, row: 1 }, Problem::SpawnShut { col: 5, row: 1 }];
+        assert_eq!(problems(&both, true), shut);
+    }
+}
@@ -0,0 +1,408 @@
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::EnemyKind;
+use crate::args::{LevelFile, parse_level, split_level};
+use crate::patrols::PatrolRoute;
+use crate::script;
+use crate::themes::LevelTheme;
+use crate::triggers::ZoneSpec;
+use crate::waves::WaveSchedule;
+
+// Level format v2: a .ron file describing a level as data rather than as one
+// grid of glyphs. The ground, the walls and the forest cover each get a layer
+// of their own, drawn with the usual glyphs; everything that's a thing rather
+// than a surface (the player's start, enemy spawns, the base, barrels,
+// turrets, teleporters, patrolling barriers and trigger zones) is placed by
+// cell, with its own settings; and the file can carry a name, its author, a
+// theme, its own spawn schedule and a script. Where layers overlap, walls win over forest and
+// forest over the ground. It's read into the same LevelFile as a text level,
+// so everything else, level_check.rs included, plays either kind.
+// `--convert-level` writes a text level out in this format.
+
+const FORMAT: u32 = 2;
+const GROUND_GLYPHS: &str = "~=:<>^v";
+const WALL_GLYPHS: &str = "#b";
+const COVER_GLYPHS: &str = "F";
+// build_maze has a glyph for each of four pairs
+const MAX_PAIRS: usize = 4;
+
+#[derive(Serialize, Deserialize)]
+struct LevelDoc {
+    format: u32,
+    #[serde(default)]
+    meta: Meta,
+    layers: Layers,
+    #[serde(default)]
+    entities: Vec<Placement>,
+    // The wave table's tanks play when left out (campaign and daily runs only)
+    #[serde(default)]
+    waves: Option<WaveSchedule>,
+    // Script lines, as they'd follow a text level's maze (see script.rs)
+    #[serde(default)]
+    script: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Meta {
+    name: String,
+    author: String,
+    // Classic, desert, winter or night, in place of the usual rotation
+    theme: Option<String>,
+}
+
+// Rows of glyphs, top first. A layer with nothing on it can be left out, and
+// a row can stop short: past its end is empty.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Layers {
+    ground: Vec<String>,
+    walls: Vec<String>,
+    cover: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Placement {
+    // (column, row) from the top left, counted from 0
+    at: (usize, usize),
+    kind: Thing,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+enum Thing {
+    PlayerStart,
+    Spawn,
+    Base,
+    Barrel,
+    // Seconds between shots, when not config.ron's
+    Turret {
+        #[serde(default)]
+        fire_secs: Option<f32>,
+    },
+    // The two pads with the same number are a pair
+    Teleporter { pair: u8 },
+    // A patrolling barrier starting on its cell, then on through `route` and
+    // back round, at `speed` pixels a second
+    Patrol { route: Vec<(usize, usize)>, speed: f32 },
+    // A named trigger zone `size` cells across and down, its cell the top left
+    Zone { name: String, size: (usize, usize) },
+}
+
+impl Thing {
+    fn from_glyph(glyph: char) -> Option<Self> {
+        Some(match glyph {
+            'P' => Thing::PlayerStart,
+            'S' => Thing::Spawn,
+            'E' => Thing::Base,
+            'o' => Thing::Barrel,
+            'T' => Thing::Turret { fire_secs: None },
+            '1'..='4' => Thing::Teleporter { pair: glyph as u8 - b'0' },
+            _ => return None,
+        })
+    }
+}
+
+// A level file of either kind, told apart by its name
+pub fn load(path: &str, text: &str) -> Result<LevelFile, String> {
+    if path.ends_with(".ron") { parse_doc(path, text) } else { parse_level(path, text) }
+}
+
+fn parse_doc(path: &str, text: &str) -> Result<LevelFile, String> {
+    let doc: LevelDoc = ron::from_str(text).map_err(|e| format!("{path}: {e}"))?;
+    if doc.format != FORMAT {
+        return Err(format!("{path} is in level format {}, and only {FORMAT} is read", doc.format));
+    }
+    let mut grid = compose(&doc.layers).map_err(|e| format!("{path}: {e}"))?;
+
+    // Pair ids are whatever the author likes; they're numbered 1 to 4 in the maze
+    let mut pairs: Vec<(u8, usize)> = Vec::new();
+    let mut turrets = Vec::new();
+    let mut patrols = Vec::new();
+    let mut zones = Vec::new();
+    let size = UVec2::new(grid[0].len() as u32, grid.len() as u32);
+    for Placement { at: (col, row), kind } in &doc.entities {
+        let (col, row) = (*col, *row);
+        let at = UVec2::new(col as u32, row as u32);
+        // Patrols and zones lie over the maze rather than taking up a cell
+        match kind {
+            Thing::Patrol { route, speed } => {
+                let cells = std::iter::once(at).chain(route.iter().map(|&(c, r)| UVec2::new(c as u32, r as u32)));
+                let route = PatrolRoute { cells: cells.collect(), speed: *speed };
+                if route.cells.len() < 2 {
+                    return Err(format!("{path}: the patrol at ({col},{row}) has nowhere to go"));
+                }
+                if *speed <= 0.0 { return Err(format!("{path}: the patrol at ({col},{row}) never moves")); }
+                script::check_route(&route, size).map_err(|e| format!("{path}: {e}"))?;
+                patrols.push(route);
+                continue;
+            }
+            Thing::Zone { name, size: (w, h) } => {
+                let zone = ZoneSpec { name: name.clone(), at, size: UVec2::new(*w as u32, *h as u32) };
+                script::check_zone(&zone, size).map_err(|e| format!("{path}: {e}"))?;
+                zones.push(zone);
+                continue;
+            }
+            _ => {}
+        }
+        let Some(cell) = grid.get_mut(row).and_then(|line| line.get_mut(col)) else {
+            return Err(format!("{path}: ({col},{row}) is outside the maze"));
+        };
+        if *cell != b' ' {
+            return Err(format!("{path}: ({col},{row}) already has '{}' on it", *cell as char));
+        }
+        *cell = match kind {
+            Thing::PlayerStart => b'P',
+            Thing::Spawn => b'S',
+            Thing::Base => b'E',
+            Thing::Barrel => b'o',
+            Thing::Turret { fire_secs } => {
+                if let Some(secs) = *fire_secs {
+                    if secs <= 0.0 { return Err(format!("{path}: the turret at ({col},{row}) never reloads")); }
+                    turrets.push((at, secs));
+                }
+                b'T'
+            }
+            Thing::Teleporter { pair } => {
+                let index = match pairs.iter().position(|(id, _)| id == pair) {
+                    Some(index) => index,
+                    None if pairs.len() < MAX_PAIRS => {
+                        pairs.push((*pair, 0));
+                        pairs.len() - 1
+                    }
+                    None => return Err(format!("{path}: there can only be {MAX_PAIRS} pairs of teleporters")),
+                };
+                pairs[index].1 += 1;
+                b'1' + index as u8
+            }
+            Thing::Patrol { .. } | Thing::Zone { .. } => continue,
+        };
+    }
+    if let Some((id, pads)) = pairs.iter().find(|(_, pads)| *pads != 2) {
+        return Err(format!("{path}: teleporter {id} has {pads} pads, and a pair has two"));
+    }
+
+    let rows: Vec<String> =
+        grid.into_iter().map(|line| String::from_utf8(line).expect("layer glyphs are ASCII")).collect();
+    let section = script::parse(&doc.script, 1, &rows).map_err(|e| format!("{path}: script {e}"))?;
+    zones.extend(section.zones);
+    patrols.extend(section.patrols);
+    let theme = doc
+        .meta
+        .theme
+        .map(|t| LevelTheme::named(&t).ok_or_else(|| format!("{path}: no theme {t}")))
+        .transpose()?;
+    if let Some(waves) = &doc.waves {
+        if waves.max_on_screen == 0 { return Err(format!("{path}: the waves need room for a tank on screen")); }
+        if waves.tanks.iter().any(|(kind, _)| *kind == EnemyKind::Boss) {
+            return Err(format!("{path}: bosses come on their own levels, not in the waves"));
+        }
+    }
+    let name = if doc.meta.name.is_empty() { path.to_string() } else { doc.meta.name };
+    Ok(LevelFile { name, rows, zones, patrols, script: section.events, theme, turrets, waves: doc.waves, code: None })
+}
+
+// The layers laid over each other, every row as wide as the widest
+fn compose(layers: &Layers) -> Result<Vec<Vec<u8>>, String> {
+    let stack = [
+        ("walls", &layers.walls, WALL_GLYPHS),
+        ("cover", &layers.cover, COVER_GLYPHS),
+        ("ground", &layers.ground, GROUND_GLYPHS),
+    ];
+    let height = stack.iter().map(|(_, rows, _)| rows.len()).max().unwrap_or(0);
+    if height == 0 { return Err("every layer is empty".to_string()); }
+    let width = stack.iter().flat_map(|(_, rows, _)| rows.iter()).map(|r| r.chars().count()).max().unwrap_or(0);
+    let mut grid = vec![vec![b' '; width]; height];
+    for (layer, rows, glyphs) in stack {
+        if rows.is_empty() { continue; }
+        if rows.len() != height {
+            return Err(format!("the {layer} layer has {} rows, and the others {height}", rows.len()));
+        }
+        for (r, line) in rows.iter().enumerate() {
+            for (c, glyph) in line.chars().enumerate() {
+                if glyph == ' ' { continue; }
+                if !glyphs.contains(glyph) {
+                    return Err(format!("the {layer} layer has '{glyph}' at ({c},{r}), and takes only \"{glyphs}\""));
+                }
+                if grid[r][c] == b' ' { grid[r][c] = glyph as u8; }
+            }
+        }
+    }
+    Ok(grid)
+}
+
+// A text level, written out in this format
+pub fn convert(path: &str, text: &str) -> Result<String, String> {
+    // Read first, so a level that wouldn't load isn't converted
+    let level = parse_level(path, text)?;
+    let (lines, _, end) = split_level(text).expect("parse_level found a maze");
+
+    let mut layers = Layers::default();
+    let mut entities = Vec::new();
+    for (r, row) in level.rows.iter().enumerate() {
+        let mut split = [String::new(), String::new(), String::new()];
+        for (c, glyph) in row.chars().enumerate() {
+            if let Some(kind) = Thing::from_glyph(glyph) { entities.push(Placement { at: (c, r), kind }); }
+            for (line, glyphs) in split.iter_mut().zip([GROUND_GLYPHS, WALL_GLYPHS, COVER_GLYPHS]) {
+                line.push(if glyphs.contains(glyph) { glyph } else { ' ' });
+            }
+        }
+        let [ground, walls, cover] = split.map(|line| line.trim_end().to_string());
+        layers.ground.push(ground);
+        layers.walls.push(walls);
+        layers.cover.push(cover);
+    }
+    for ZoneSpec { name, at, size } in &level.zones {
+        let kind = Thing::Zone { name: name.clone(), size: (size.x as usize, size.y as usize) };
+        entities.push(Placement { at: (at.x as usize, at.y as usize), kind });
+    }
+    for PatrolRoute { cells, speed } in &level.patrols {
+        let mut cells = cells.iter().map(|c| (c.x as usize, c.y as usize));
+        let Some(at) = cells.next() else { continue; };
+        entities.push(Placement { at, kind: Thing::Patrol { route: cells.collect(), speed: *speed } });
+    }
+    for rows in [&mut layers.ground, &mut layers.walls, &mut layers.cover] {
+        if rows.iter().all(String::is_empty) { rows.clear(); }
+    }
+
+    let name = Path::new(path).file_stem().map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let doc = LevelDoc {
+        format: FORMAT,
+        meta: Meta { name, ..default() },
+        layers,
+        entities,
+        waves: None,
+        // The zones and patrols are entities now
+        script: lines[end..]
+            .iter()
+            .filter(|l| !l.trim().is_empty() && !l.trim().starts_with("zone ") && !l.trim().starts_with("patrol "))
+            .cloned()
+            .collect(),
+    };
+    ron::ser::to_string_pretty(&doc, PrettyConfig::default()).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+#########
+#P  1  S#
+# ~~ bb #
+#  FF   #
+#1  o  T#
+#  bEb  #
+#########
+
+zone door at (2,3..4)
+patrol (5,3) to (7,3) at 40
+at 5s say hi
+";
+
+    // A v2 level with the walls given and the rest left out
+    fn doc(walls: &[&str], entities: &str) -> String {
+        let walls: Vec<String> = walls.iter().map(|r| format!("{r:?}")).collect();
+        format!("(format: 2, layers: (walls: [{}]), entities: [{entities}])", walls.join(", "))
+    }
+
+    const ROOM: [&str; 4] = ["######", "#    #", "#    #", "######"];
+
+    #[test]
+    fn a_converted_level_reads_back_the_same() {
+        let text = parse_level("maps/keep.txt", TEXT).unwrap();
+        let converted = convert("maps/keep.txt", TEXT).unwrap();
+        let doc = parse_doc("maps/keep.ron", &converted).unwrap();
+        assert_eq!(doc.name, "keep");
+        assert_eq!(doc.rows, text.rows);
+        assert_eq!(doc.zones, text.zones);
+        assert_eq!(doc.patrols, text.patrols);
+        assert_eq!(doc.script.len(), 1);
+    }
+
+    #[test]
+    fn conversion_splits_the_maze_into_layers_and_entities() {
+        let doc: LevelDoc = ron::from_str(&convert("keep.txt", TEXT).unwrap()).unwrap();
+        assert_eq!(doc.layers.walls[2], "#    bb #");
+        assert_eq!(doc.layers.ground[2], "  ~~");
+        assert_eq!(doc.layers.cover[3], "   FF");
+        let at = |kind: fn(&Thing) -> bool| -> Vec<(usize, usize)> {
+            doc.entities.iter().filter(|p| kind(&p.kind)).map(|p| p.at).collect()
+        };
+        assert_eq!(at(|t| matches!(t, Thing::PlayerStart)), [(1, 1)]);
+        assert_eq!(at(|t| matches!(t, Thing::Teleporter { pair: 1 })), [(4, 1), (1, 4)]);
+        assert_eq!(at(|t| matches!(t, Thing::Zone { .. })), [(2, 3)]);
+        assert_eq!(at(|t| matches!(t, Thing::Patrol { .. })), [(5, 3)]);
+        assert_eq!(doc.script, ["at 5s say hi"]);
+    }
+
+    #[test]
+    fn layers_overlap_walls_first() {
+        let layers = Layers {
+            ground: vec!["~~~".into()],
+            walls: vec![" #".into()],
+            cover: vec!["F F".into()],
+        };
+        assert_eq!(compose(&layers).unwrap(), [b"F#F".to_vec()]);
+    }
+
+    #[test]
+    fn rejects_layers_that_dont_fit() {
+        assert_eq!(compose(&Layers::default()).unwrap_err(), "every layer is empty");
+        let short = Layers { walls: vec!["###".into(), "# #".into()], cover: vec!["F".into()], ..default() };
+        assert_eq!(compose(&short).unwrap_err(), "the cover layer has 1 rows, and the others 2");
+        let misplaced = Layers { walls: vec!["#~#".into()], ..default() };
+        assert_eq!(compose(&misplaced).unwrap_err(), "the walls layer has '~' at (1,0), and takes only \"#b\"");
+    }
+
+    #[test]
+    fn numbers_teleporter_pairs_in_order() {
+        let entities = "(at: (1, 1), kind: Teleporter(pair: 9)), (at: (2, 1), kind: Teleporter(pair: 3)), \
+                        (at: (3, 2), kind: Teleporter(pair: 3)), (at: (4, 2), kind: Teleporter(pair: 9))";
+        let level = parse_doc("t.ron", &doc(&ROOM, entities)).unwrap();
+        assert_eq!(level.rows[1], "#12  #");
+        assert_eq!(level.rows[2], "#  21#");
+    }
+
+    #[test]
+    fn rejects_teleporters_without_a_partner() {
+        let lone = doc(&ROOM, "(at: (1, 1), kind: Teleporter(pair: 7))");
+        assert_eq!(parse_doc("t.ron", &lone).unwrap_err(), "t.ron: teleporter 7 has 1 pads, and a pair has two");
+        let three = "(at: (1, 1), kind: Teleporter(pair: 2)), (at: (2, 1), kind: Teleporter(pair: 2)), \
+                     (at: (3, 1), kind: Teleporter(pair: 2))";
+        let three = parse_doc("t.ron", &doc(&ROOM, three)).unwrap_err();
+        assert_eq!(three, "t.ron: teleporter 2 has 3 pads, and a pair has two");
+    }
+
+    #[test]
+    fn rejects_a_fifth_teleporter_pair() {
+        let wide = ["############", "#          #", "#          #", "############"];
+        let entities: Vec<String> = (0..10)
+            .map(|i| format!("(at: ({}, {}), kind: Teleporter(pair: {}))", 1 + i % 5, 1 + i / 5, i % 5))
+            .collect();
+        let err = parse_doc("t.ron", &doc(&wide, &entities.join(", "))).unwrap_err();
+        assert_eq!(err, "t.ron: there can only be 4 pairs of teleporters");
+    }
+
+    #[test]
+    fn rejects_entities_off_the_floor() {
+        let outside = doc(&ROOM, "(at: (6, 1), kind: Spawn)");
+        assert_eq!(parse_doc("t.ron", &outside).unwrap_err(), "t.ron: (6,1) is outside the maze");
+        let on_wall = doc(&ROOM, "(at: (0, 1), kind: Base)");
+        assert_eq!(parse_doc("t.ron", &on_wall).unwrap_err(), "t.ron: (0,1) already has '#' on it");
+        let stacked = doc(&ROOM, "(at: (1, 1), kind: Spawn), (at: (1, 1), kind: Barrel)");
+        assert_eq!(parse_doc("t.ron", &stacked).unwrap_err(), "t.ron: (1,1) already has 'S' on it");
+    }
+
+    #[test]
+    fn rejects_other_formats_and_bad_settings() {
+        let old = doc(&ROOM, "").replace("format: 2", "format: 1");
+        assert_eq!(parse_doc("t.ron", &old).unwrap_err(), "t.ron is in level format 1, and only 2 is read");
+        let turret = doc(&ROOM, "(at: (2, 2), kind: Turret(fire_secs: Some(0.0)))");
+        assert_eq!(parse_doc("t.ron", &turret).unwrap_err(), "t.ron: the turret at (2,2) never reloads");
+        let patrol = doc(&ROOM, "(at: (1, 1), kind: Patrol(route: [], speed: 30.0))");
+        assert_eq!(parse_doc("t.ron", &patrol).unwrap_err(), "t.ron: the patrol at (1,1) has nowhere to go");
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::asset::io::{AssetSourceBuilder, Reader};
+use bevy::asset::{AssetLoadFailedEvent, AssetLoader, LoadContext};
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::args::{LaunchArgs, LevelFile};
+use crate::level_check::{needs_base, problems};
+use crate::level_format;
+use crate::locale::Locale;
+use crate::mods::ContentRegistry;
+use crate::packs::LevelPacks;
+use crate::toasts::Notify;
+use crate::waves::GameMode;
+use crate::{AppState, GameSet, LevelBounds, LevelTile, Player, TILE, build_maze};
+
+// Hot reloading of the --level file, for level design: the file is also
+// loaded as a Bevy asset, from an asset source rooted at its directory, and
+// each time it's saved the maze is rebuilt where it stands. The tanks, shells,
+// score and clock carry on; the player stays put unless the edit walls them
+// in or cuts their cell away, in which case they go to the new start. The
+// level's script starts over with the new file. An edit that doesn't parse is
+// reported on screen and the old maze is kept, as is one that can't be played
+// (see level_check.rs). Bevy only watches files when built with the
+// hot_reload feature (see Cargo.toml); otherwise nothing here ever fires.
+
+const SOURCE: &str = "level";
+// Glyphs a tank can't sit in (see build_maze)
+const SOLID_GLYPHS: &str = "#bE~oT";
+const FAILED_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+
+#[derive(Asset, TypePath)]
+struct LevelAsset(LevelFile);
+
+#[derive(Default)]
+struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = LevelAsset;
+    type Settings = ();
+    type Error = String;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<LevelAsset, String> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await.map_err(|e| e.to_string())?;
+        let text = String::from_utf8(bytes).map_err(|_| "it isn't a text file".to_string())?;
+        level_format::load(&load_context.path().display().to_string(), &text).map(LevelAsset)
+    }
+
+    // Other names still load, as the handle says what it wants
+    fn extensions(&self) -> &[&str] {
+        &["txt", "ron"]
+    }
+}
+
+// Sent when the maze is being rebuilt from an edited file
+#[derive(Event)]
+pub struct LevelReloaded;
+
+#[derive(Resource)]
+struct WatchedLevel(Handle<LevelAsset>);
+
+// The file's directory and its name in it. The directory is made absolute, as
+// Bevy would otherwise look for it next to the executable.
+fn split_path(path: &str) -> (String, String) {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| Path::new(path).to_path_buf());
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let name = path.file_name().map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+    (dir.to_string_lossy().into_owned(), name)
+}
+
+// Asset sources have to be in place before the AssetPlugin is built, so this
+// goes ahead of the default plugins, and LevelReloadPlugin after them
+pub struct LevelSourcePlugin;
+
+impl Plugin for LevelSourcePlugin {
+    fn build(&self, app: &mut App) {
+        let path = app.world().get_resource::<LaunchArgs>().and_then(|a| a.level_path.clone());
+        let Some(path) = path else { return; };
+        let (dir, _) = split_path(&path);
+        app.register_asset_source(SOURCE, AssetSourceBuilder::platform_default(&dir, None));
+    }
+}
+
+pub struct LevelReloadPlugin;
+
+impl Plugin for LevelReloadPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<LevelAsset>()
+            .init_asset_loader::<LevelLoader>()
+            .add_event::<LevelReloaded>()
+            .add_systems(Startup, watch_level)
+            .add_systems(
+                Update,
+                (reload_level, build_maze.run_if(on_event::<LevelReloaded>))
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(resource_exists::<WatchedLevel>),
+            );
+    }
+}
+
+fn watch_level(mut commands: Commands, args: Res<LaunchArgs>, server: Res<AssetServer>) {
+    let Some(path) = &args.level_path else { return; };
+    let (_, name) = split_path(path);
+    let handle = server.load(format!("{SOURCE}://{name}"));
+    commands.insert_resource(WatchedLevel(handle));
+}
+
+// The first load is the file --level already read, so only later ones count
+fn reload_level(
+    mut commands: Commands,
+    mut changed: EventReader<AssetEvent<LevelAsset>>,
+    mut failed: EventReader<AssetLoadFailedEvent<LevelAsset>>,
+    watched: Res<WatchedLevel>,
+    assets: Res<Assets<LevelAsset>>,
+    mut args: ResMut<LaunchArgs>,
+    state: Res<State<AppState>>,
+    packs: Res<LevelPacks>,
+    content: Res<ContentRegistry>,
+    mode: Res<GameMode>,
+    locale: Res<Locale>,
+    q_tiles: Query<Entity, With<LevelTile>>,
+    mut q_player: Query<&mut Transform, With<Player>>,
+    mut reloaded: EventWriter<LevelReloaded>,
+    mut notify: EventWriter<Notify>,
+) {
+    for e in failed.read() {
+        notify.write(Notify::new(locale.f("reload.failed", &[("error", &e.error)])).with_color(FAILED_COLOR));
+    }
+    let modified = changed.read().any(|e| e.is_modified(&watched.0));
+    let Some(LevelAsset(file)) = modified.then(|| assets.get(&watched.0)).flatten() else { return; };
+    let rows = content.base_rows(&file.rows);
+    if let Some(problem) = problems(&rows, needs_base(*mode)).first() {
+        let error = problem.describe(&locale);
+        notify.write(Notify::new(locale.f("reload.failed", &[("error", &error)])).with_color(FAILED_COLOR));
+        return;
+    }
+    // The script beside it is only read at launch
+    let code = args.level.take().and_then(|level| level.code);
+    args.level = Some(LevelFile { code, ..file.clone() });
+
+    // Away from a run, or on a maze of its own, the next run picks it up
+    let playing = *state.get() == AppState::Playing;
+    if !playing || packs.own_levels() || *mode == GameMode::Tutorial { return; }
+
+    for e in &q_tiles { commands.entity(e).despawn(); }
+    let bounds = LevelBounds::new(file.rows[0].len(), file.rows.len());
+    let glyph = |cell: UVec2| rows[cell.y as usize].as_bytes()[cell.x as usize] as char;
+    let start = file
+        .rows
+        .iter()
+        .enumerate()
+        .find_map(|(r, row)| row.find('P').map(|c| UVec2::new(c as u32, r as u32)));
+    for mut t in &mut q_player {
+        let cell = bounds.cell_at(t.translation.truncate());
+        if cell.is_some_and(|c| !SOLID_GLYPHS.contains(glyph(c))) { continue; }
+        let pos = start.map_or(bounds.center(), |c| bounds.origin + Vec2::new(c.x as f32, -(c.y as f32)) * TILE);
+        t.translation.x = pos.x;
+        t.translation.y = pos.y;
+    }
+    reloaded.write(LevelReloaded);
+    notify.write(Notify::new(locale.t("reload.done")));
+}
@@ -0,0 +1,423 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::args::LaunchArgs;
+use crate::bullets::BulletKind;
+use crate::config::BulletConfig;
+use crate::daily::DailyBests;
+use crate::display::alt_held;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::level_check::{UnplayableLevel, check_run};
+use crate::locale::{Locale, Tr};
+use crate::mods::ContentRegistry;
+use crate::packs::LevelPacks;
+use crate::profile::Profile;
+use crate::savegame::SavedRun;
+use crate::settings::settings_closed;
+use crate::waves::GameMode;
+
+// Pre-run tank assembly. The chosen parts live on the `Profile` and are turned
+// into components by `spawn_player`.
+
+pub const LOADOUT_BUDGET: u32 = 6;
+
+pub trait LoadoutPart: Copy + PartialEq + 'static {
+    const ALL: &'static [Self];
+    // Keys into the language files
+    fn label(self) -> &'static str;
+    fn describe(self) -> &'static str;
+    fn cost(self) -> u32;
+
+    fn cycle(self, step: i32) -> Self {
+        let i = Self::ALL.iter().position(|p| *p == self).unwrap_or(0) as i32;
+        let n = Self::ALL.len() as i32;
+        Self::ALL[(i + step).rem_euclid(n) as usize]
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Hull {
+    Light,
+    #[default]
+    Standard,
+    Heavy,
+}
+
+impl Hull {
+    pub fn speed_factor(self) -> f32 {
+        match self {
+            Hull::Light => 1.25,
+            Hull::Standard => 1.0,
+            Hull::Heavy => 0.8,
+        }
+    }
+
+    // Extra enemy hits the hull absorbs before the tank is destroyed.
+    pub fn armor(self) -> u8 {
+        match self {
+            Hull::Light | Hull::Standard => 0,
+            Hull::Heavy => 2,
+        }
+    }
+}
+
+impl LoadoutPart for Hull {
+    const ALL: &'static [Self] = &[Hull::Light, Hull::Standard, Hull::Heavy];
+    fn label(self) -> &'static str {
+        match self {
+            Hull::Light => "hull.light",
+            Hull::Standard => "hull.standard",
+            Hull::Heavy => "hull.heavy",
+        }
+    }
+    fn describe(self) -> &'static str {
+        match self {
+            Hull::Light => "hull.light.describe",
+            Hull::Standard => "hull.standard.describe",
+            Hull::Heavy => "hull.heavy.describe",
+        }
+    }
+    fn cost(self) -> u32 {
+        match self {
+            Hull::Light => 2,
+            Hull::Standard => 1,
+            Hull::Heavy => 3,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Gun {
+    Rapid,
+    #[default]
+    Standard,
+    Cannon,
+    Ricochet,
+}
+
+impl Gun {
+    pub fn cooldown_factor(self) -> f32 {
+        match self {
+            Gun::Rapid => 0.65,
+            Gun::Standard => 1.0,
+            Gun::Cannon => 1.4,
+            Gun::Ricochet => 1.2,
+        }
+    }
+
+    pub fn bullet_speed_factor(self) -> f32 {
+        match self {
+            Gun::Rapid | Gun::Standard | Gun::Ricochet => 1.0,
+            Gun::Cannon => 1.35,
+        }
+    }
+
+    pub fn bullet_scale(self) -> f32 {
+        match self {
+            Gun::Rapid => 0.8,
+            Gun::Standard | Gun::Ricochet => 1.0,
+            Gun::Cannon => 1.5,
+        }
+    }
+
+    pub fn bullet_kind(self, cfg: &BulletConfig) -> BulletKind {
+        match self {
+            Gun::Rapid | Gun::Standard => BulletKind::Standard,
+            Gun::Cannon => BulletKind::piercing(cfg),
+            Gun::Ricochet => BulletKind::bouncing(cfg),
+        }
+    }
+}
+
+impl LoadoutPart for Gun {
+    const ALL: &'static [Self] = &[Gun::Rapid, Gun::Standard, Gun::Cannon, Gun::Ricochet];
+    fn label(self) -> &'static str {
+        match self {
+            Gun::Rapid => "gun.rapid",
+            Gun::Standard => "gun.standard",
+            Gun::Cannon => "gun.cannon",
+            Gun::Ricochet => "gun.ricochet",
+        }
+    }
+    fn describe(self) -> &'static str {
+        match self {
+            Gun::Rapid => "gun.rapid.describe",
+            Gun::Standard => "gun.standard.describe",
+            Gun::Cannon => "gun.cannon.describe",
+            Gun::Ricochet => "gun.ricochet.describe",
+        }
+    }
+    fn cost(self) -> u32 {
+        match self {
+            Gun::Rapid => 2,
+            Gun::Standard => 1,
+            Gun::Cannon => 3,
+            Gun::Ricochet => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Utility {
+    #[default]
+    Dash,
+    Smoke,
+    Mine,
+}
+
+impl Utility {
+    pub fn cooldown_secs(self) -> f32 {
+        match self {
+            Utility::Dash => 3.0,
+            Utility::Smoke => 10.0,
+            Utility::Mine => 6.0,
+        }
+    }
+}
+
+impl LoadoutPart for Utility {
+    const ALL: &'static [Self] = &[Utility::Dash, Utility::Smoke, Utility::Mine];
+    fn label(self) -> &'static str {
+        match self {
+            Utility::Dash => "utility.dash",
+            Utility::Smoke => "utility.smoke",
+            Utility::Mine => "utility.mine",
+        }
+    }
+    fn describe(self) -> &'static str {
+        match self {
+            Utility::Dash => "utility.dash.describe",
+            Utility::Smoke => "utility.smoke.describe",
+            Utility::Mine => "utility.mine.describe",
+        }
+    }
+    fn cost(self) -> u32 {
+        match self {
+            Utility::Dash => 1,
+            Utility::Smoke | Utility::Mine => 2,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(default)]
+pub struct Loadout {
+    pub hull: Hull,
+    pub gun: Gun,
+    pub utility: Utility,
+}
+
+impl Loadout {
+    pub fn cost(&self) -> u32 {
+        self.hull.cost() + self.gun.cost() + self.utility.cost()
+    }
+}
+
+// === Screen ===
+const ROWS: usize = 3;
+
+#[derive(Component)]
+struct LoadoutScreen;
+
+#[derive(Component)]
+struct LoadoutRow(usize);
+
+#[derive(Component)]
+struct LoadoutFooter;
+
+#[derive(Resource, Default)]
+struct LoadoutCursor(usize);
+
+pub struct LoadoutPlugin;
+
+impl Plugin for LoadoutPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadoutCursor>()
+            .add_systems(OnEnter(AppState::Loadout), spawn_loadout_screen)
+            .add_systems(OnExit(AppState::Loadout), despawn_loadout_screen)
+            .add_systems(
+                Update,
+                (
+                    loadout_input.run_if(settings_closed).run_if(not(resource_exists::<UnplayableLevel>)),
+                    refresh_loadout_screen,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Loadout)),
+            );
+    }
+}
+
+fn spawn_loadout_screen(mut commands: Commands) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            LoadoutScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("loadout.title")));
+            for row in 0..ROWS {
+                root.spawn((Text::new(""), font(20.0), TextColor(Color::WHITE), LoadoutRow(row)));
+            }
+            root.spawn((Text::new(""), font(16.0), TextColor(Color::srgb(0.7, 0.7, 0.7)), LoadoutFooter));
+        });
+}
+
+fn despawn_loadout_screen(mut commands: Commands, q: Query<Entity, With<LoadoutScreen>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn loadout_input(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut cursor: ResMut<LoadoutCursor>,
+    mut profile: ResMut<Profile>,
+    mut saved: ResMut<SavedRun>,
+    mut mode: ResMut<GameMode>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    content: Res<ContentRegistry>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let pressed = |key_a: KeyCode, key_b: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key_a) || keys.just_pressed(key_b) || pads.iter().any(|p| p.just_pressed(button))
+    };
+
+    if pressed(KeyCode::ArrowUp, KeyCode::KeyW, GamepadButton::DPadUp) {
+        cursor.0 = (cursor.0 + ROWS - 1) % ROWS;
+    }
+    if pressed(KeyCode::ArrowDown, KeyCode::KeyS, GamepadButton::DPadDown) {
+        cursor.0 = (cursor.0 + 1) % ROWS;
+    }
+
+    let mut step = 0;
+    if pressed(KeyCode::ArrowLeft, KeyCode::KeyA, GamepadButton::DPadLeft) { step -= 1; }
+    if pressed(KeyCode::ArrowRight, KeyCode::KeyD, GamepadButton::DPadRight) { step += 1; }
+    if step != 0 {
+        let loadout = &mut profile.loadout;
+        match cursor.0 {
+            0 => loadout.hull = loadout.hull.cycle(step),
+            1 => loadout.gun = loadout.gun.cycle(step),
+            _ => loadout.utility = loadout.utility.cycle(step),
+        }
+    }
+
+    let affordable = profile.loadout.cost() <= LOADOUT_BUDGET;
+    // A run on a level file that can't be played stays here, with the reasons
+    let mut start = |mode: GameMode, resume: bool| match check_run(&args, &packs, &content, mode, resume) {
+        Some(unplayable) => {
+            commands.insert_resource(unplayable);
+            false
+        }
+        None => {
+            next.set(AppState::Playing);
+            true
+        }
+    };
+
+    if pressed(KeyCode::Enter, KeyCode::NumpadEnter, GamepadButton::South) && affordable && !alt_held(&keys) {
+        profile.save();
+        start(*mode, false);
+    }
+
+    let continue_pressed = keys.just_pressed(KeyCode::KeyC) || pads.iter().any(|p| p.just_pressed(GamepadButton::Start));
+    if continue_pressed && affordable && saved.snapshot.is_some() {
+        // Only campaign runs are ever saved
+        *mode = GameMode::Campaign;
+        profile.save();
+        saved.resume = start(GameMode::Campaign, true);
+    }
+
+    if keys.just_pressed(KeyCode::KeyH) || pads.iter().any(|p| p.just_pressed(GamepadButton::North)) {
+        next.set(AppState::HighScores);
+    }
+
+    if keys.just_pressed(KeyCode::KeyT) || pads.iter().any(|p| p.just_pressed(GamepadButton::Select)) {
+        next.set(AppState::Stats);
+    }
+
+    if keys.just_pressed(KeyCode::KeyM) || pads.iter().any(|p| p.just_pressed(GamepadButton::LeftTrigger)) {
+        *mode = mode.cycled();
+    }
+
+    if keys.just_pressed(KeyCode::KeyG) || pads.iter().any(|p| p.just_pressed(GamepadButton::RightTrigger)) {
+        next.set(AppState::Achievements);
+    }
+
+    if keys.just_pressed(KeyCode::KeyP) || pads.iter().any(|p| p.just_pressed(GamepadButton::LeftTrigger2)) {
+        next.set(AppState::Packs);
+    }
+}
+
+fn refresh_loadout_screen(
+    cursor: Res<LoadoutCursor>,
+    profile: Res<Profile>,
+    saved: Res<SavedRun>,
+    mode: Res<GameMode>,
+    daily: Res<DailyBests>,
+    packs: Res<LevelPacks>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    mut q_rows: Query<(&LoadoutRow, &mut Text, &mut TextColor), Without<LoadoutFooter>>,
+    mut q_footer: Query<(&mut Text, &mut TextColor), With<LoadoutFooter>>,
+) {
+    let changed = cursor.is_changed() || profile.is_changed() || device.is_changed() || saved.is_changed();
+    if !(changed || mode.is_changed() || packs.is_changed() || locale.is_changed()) { return; }
+    let loadout = profile.loadout;
+
+    for (row, mut text, mut color) in &mut q_rows {
+        let (name, label, describe, cost) = match row.0 {
+            0 => ("loadout.hull", loadout.hull.label(), loadout.hull.describe(), loadout.hull.cost()),
+            1 => ("loadout.gun", loadout.gun.label(), loadout.gun.describe(), loadout.gun.cost()),
+            _ => ("loadout.utility", loadout.utility.label(), loadout.utility.describe(), loadout.utility.cost()),
+        };
+        let marker = if row.0 == cursor.0 { ">" } else { " " };
+        let (name, label, describe) = (locale.t(name), locale.t(label), locale.t(describe));
+        text.0 = format!("{marker} {name}: < {label} >  {describe}  [{cost}]");
+        color.0 = if row.0 == cursor.0 { Color::srgb(1.0, 0.9, 0.3) } else { Color::WHITE };
+    }
+
+    let cost = loadout.cost();
+    let resume = saved
+        .snapshot
+        .map(|run| {
+            let key = glyph(Action::Continue, *device);
+            format!("    {}", locale.f("loadout.continue", &[("key", &key), ("level", &(run.level + 1))]))
+        })
+        .unwrap_or_default();
+    let mode_label = match (*mode, packs.selected_name()) {
+        (GameMode::Daily, _) => format!("{} ({})", locale.t(mode.label()), daily.describe_today(&locale)),
+        (GameMode::Campaign, Some(pack)) => format!("{} ({pack})", locale.t(mode.label())),
+        (GameMode::Campaign | GameMode::Survival, _) if packs.random_picked() => {
+            format!("{} ({})", locale.t(mode.label()), locale.t("packs.random"))
+        }
+        (other, _) => locale.t(other.label()).to_string(),
+    };
+    let hint = |action: Action, key: &str| format!("{}: {}", glyph(action, *device), locale.t(key));
+    for (mut text, mut color) in &mut q_footer {
+        text.0 = format!(
+            "{}    {}    {}{resume}    {}    {}    {}    {}    {}    {}",
+            locale.f("loadout.points", &[("cost", &cost), ("budget", &LOADOUT_BUDGET)]),
+            hint(Action::Navigate, "hint.select_change"),
+            locale.f("loadout.start", &[("key", &glyph(Action::Confirm, *device)), ("mode", &mode_label)]),
+            hint(Action::Mode, "loadout.mode"),
+            hint(Action::Packs, "loadout.packs"),
+            hint(Action::Scores, "loadout.scores"),
+            hint(Action::Stats, "loadout.stats"),
+            hint(Action::Achievements, "loadout.achievements"),
+            hint(Action::Settings, "loadout.settings"),
+        );
+        color.0 = if cost > LOADOUT_BUDGET { Color::srgb(1.0, 0.3, 0.3) } else { Color::srgb(0.7, 0.7, 0.7) };
+    }
+}
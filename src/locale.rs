@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::EnemyKind;
+
+// The words on screen, by key, from a language file per language in lang/
+// (a RON map of key to text, built into the game). Text can hold {name}
+// placeholders for `Locale::f` to fill in. A key missing from a translation
+// falls back to English, and one missing from English shows as the key, so a
+// gap is easy to spot. Static text only needs a `Tr` with its key; anything
+// built from game state rebuilds when the language changes.
+
+const ENGLISH: &str = include_str!("../lang/en.ron");
+const GERMAN: &str = include_str!("../lang/de.ron");
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Language {
+    #[default]
+    English,
+    German,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::German];
+
+    // In the language itself, so it can be found whatever is set now
+    pub fn label(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::German => "Deutsch",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Language::English => ENGLISH,
+            Language::German => GERMAN,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct Locale {
+    strings: HashMap<String, String>,
+    english: HashMap<String, String>,
+}
+
+impl Locale {
+    pub fn new(language: Language) -> Self {
+        Self { strings: parse(language), english: parse(Language::English) }
+    }
+
+    pub fn t<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.get(key).or_else(|| self.english.get(key)).map_or(key, String::as_str)
+    }
+
+    // The text for key with each {name} replaced by its value
+    pub fn f(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.t(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        text
+    }
+
+    // Kinds are keyed by their label in save files, so the name on screen is looked up here
+    pub fn kind_name(&self, kind: EnemyKind) -> &str {
+        self.t(match kind {
+            EnemyKind::Basic => "enemy.basic",
+            EnemyKind::Fast => "enemy.fast",
+            EnemyKind::Power => "enemy.power",
+            EnemyKind::Saboteur => "enemy.saboteur",
+            EnemyKind::Armor => "enemy.armor",
+            EnemyKind::Boss => "enemy.boss",
+        })
+    }
+}
+
+fn parse(language: Language) -> HashMap<String, String> {
+    ron::from_str(language.source()).unwrap_or_else(|e| {
+        error!("language file for {} doesn't parse: {e}", language.label());
+        HashMap::new()
+    })
+}
+
+// Static text, filled in from its key as it's spawned and again on a language change
+#[derive(Component)]
+pub struct Tr(pub &'static str);
+
+pub struct LocalePlugin;
+
+impl Plugin for LocalePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, retranslate);
+    }
+}
+
+fn retranslate(locale: Res<Locale>, mut q: Query<(Ref<Tr>, &mut Text)>) {
+    for (tr, mut text) in &mut q {
+        if !(locale.is_changed() || tr.is_added()) { continue; }
+        text.0 = locale.t(tr.0).to_string();
+    }
+}
@@ -1,52 +1,417 @@
+// Systems take a resource or query for everything they touch
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
+use bevy::app::{PluginGroupBuilder, ScheduleRunnerPlugin};
+use bevy::audio::{AudioPlugin, GlobalVolume};
 use bevy::prelude::*;
+use bevy::render::RenderPlugin;
+use bevy::render::camera::ScalingMode;
+use bevy::render::settings::WgpuSettings;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng, thread_rng};
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
-use rand::{Rng, thread_rng};
-
-// === Arena & tiles ===
-const ARENA_W: f32 = 800.0;
-const ARENA_H: f32 = 600.0;
-const TILE: f32 = 40.0; // 20x15 grid
-
-// === Player ===
-const PLAYER_SPEED: f32 = 300.0;
-const PLAYER_SIZE: Vec2 = Vec2::new(28.0, 28.0);
-
-// === Bullets ===
-const BULLET_SPEED: f32 = 600.0;
-const BULLET_SIZE: Vec2 = Vec2::new(6.0, 12.0);
-
-// === Enemies ===
-const ENEMY_SPEED: f32 = 180.0;
-const ENEMY_SIZE: Vec2 = Vec2::new(24.0, 24.0);
-const ENEMY_CAP: usize = 24;
-const ENEMY_SPAWN_SECS: f32 = 1.25;
-const ENEMY_FIRE_SECS: f32 = 1.1;
-const ENEMY_DETECT_RADIUS: f32 = 240.0;
-const CHASE_REACTION_SECS: f32 = 0.45;
-const WANDER_CHANGE_MIN: f32 = 1.2;
-const WANDER_CHANGE_MAX: f32 = 2.2;
-const ROAM_SPEED_FACTOR: f32 = 0.75;
+
+mod accessibility;
+mod achievements;
+mod adaptive;
+mod args;
+mod attract;
+mod base_alarm;
+mod boss;
+mod bullets;
+mod capture;
+mod config;
+mod console;
+mod controller;
+mod daily;
+mod death;
+mod debug;
+mod display;
+mod drops;
+mod error_screen;
+mod event_log;
+mod explosions;
+mod feedback;
+mod floating_text;
+mod fog;
+mod game_time;
+mod heatmap;
+mod horde;
+mod input_glyphs;
+mod intent;
+mod level_check;
+mod level_format;
+mod level_reload;
+mod loadout;
+mod locale;
+mod mazegen;
+mod mines;
+mod minimap;
+mod mods;
+mod packs;
+mod particles;
+mod patrols;
+mod persistence;
+mod photo;
+mod profile;
+mod profiler;
+mod recap;
+mod repairs;
+mod savegame;
+mod scores;
+mod script;
+#[cfg(feature = "scripting")]
+mod scripting;
+mod settings;
+mod sidebar;
+mod siege;
+mod spatial;
+mod speedrun;
+mod squads;
+mod teleporters;
+mod themes;
+mod toasts;
+mod touch;
+mod stats;
+mod status;
+mod stick;
+mod stress;
+mod triggers;
+mod turrets;
+mod tutorial;
+mod unstick;
+mod waves;
+
+use args::LaunchArgs;
+use bullets::{BulletKind, BulletPool, Shot, TankHit};
+use config::{DamageConfig, GameConfig, PlayerConfig};
+use death::{Credit, DeathEvent, DeathReason};
+use display::WorldCamera;
+use drops::Coins;
+use attract::Demo;
+use feedback::{CameraShake, HitEvent};
+use fog::FogOfWar;
+use horde::BuildPhase;
+use intent::PlayerIntent;
+use loadout::Utility;
+use locale::Locale;
+use mines::{Mine, MineCharges, MineLayer};
+use mods::{ContentRegistry, EnemyVariant, PickupVariant};
+use packs::LevelPacks;
+use particles::{ParticleBurst, ParticleKind};
+use profile::Profile;
+use recap::{StageCleared, StageRecap};
+use savegame::SavedRun;
+use scores::Score;
+use siege::SiegeMap;
+use spatial::SpatialGrid;
+use squads::{Flank, Squad};
+use stats::{ShotFired, ShotLanded};
+use status::{StatusEffects, StatusKind};
+use themes::LevelTheme;
+use toasts::Notify;
+use std::collections::HashSet;
+use std::f32::consts::{PI, TAU};
+use triggers::TriggerZone;
+use unstick::Unstick;
+use waves::{CurrentLevel, GameMode, LevelEnemyQueue, LevelOrder};
+
+// === Window & tiles ===
+// Virtual resolution: the camera always shows this much of the level, however
+// the window is sized; levels of any size are followed by the camera
+const WINDOW_W: f32 = 800.0;
+const WINDOW_H: f32 = 600.0;
+const TILE: f32 = 40.0;
+const MIN_SLIDE_SPEED: f32 = 5.0;
+
+// Gameplay tuning (speeds, sizes, timers, caps) lives in GameConfig; what's
+// left here is layout and presentation.
+
+// Sparks thrown up where a shell strikes a wall
+const SPARKS_PER_HIT: usize = 6;
+
+// === Respawn grace ===
+const INVULN_BLINK_SECS: f32 = 0.1;
+// The shield ring starts blinking this long before it drops
+const SHIELD_WARN_SECS: f32 = 0.6;
+// With reduced flashes the ring shrinks to this for the warning instead
+const SHIELD_WARN_SCALE: f32 = 0.75;
+const SHIELD_DOTS: usize = 8;
+const SHIELD_SPIN: f32 = 3.0; // radians per second
+const SHIELD_COLOR: Color = Color::srgb(0.6, 0.9, 1.0);
+
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+enum AppState {
+    #[default]
+    Loadout,
+    Playing,
+    GameOver,
+    HighScores,
+    Stats,
+    Achievements,
+    Packs,
+    // Something went wrong (see error_screen.rs)
+    Error,
+}
+
+// Gameplay in Update runs in these stages, in this order, every frame: what
+// the player and the AI want, then movement against the walls, then what hit
+// what, then bookkeeping on the outcome. Systems outside the sets (HUD, screens,
+// effects) run whenever the scheduler fits them in.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum GameSet {
+    Input,
+    Ai,
+    Physics,
+    Damage,
+    Cleanup,
+}
 
 // === Components ===
 #[derive(Component)] struct Player;
 #[derive(Component)] struct Bullet;
 #[derive(Component)] struct Enemy;
 #[derive(Component)] struct Wall;
+#[derive(Component)] struct Base;
+#[derive(Component)] struct Boundary; // invisible walls framing the maze
+#[derive(Component)] struct Water;
+#[derive(Component)] struct Forest;
+#[derive(Component)] struct Ice;
+#[derive(Component)] struct Tar;
+#[derive(Component)] struct Conveyor(IVec2); // belt direction, one step along x or y
+#[derive(Component)] struct FlameBarrel; // explodes into fire when shot
+#[derive(Component)] struct LevelTile; // anything spawned by build_maze
+
+// What a tile stops; tiles without this component are fully passable
+#[derive(Component, Clone, Copy)]
+struct Blocks {
+    tanks: bool,
+    bullets: bool,
+}
+
+impl Blocks {
+    const SOLID: Blocks = Blocks { tanks: true, bullets: true };
+    const WATER: Blocks = Blocks { tanks: true, bullets: false };
+}
+#[derive(Component)] struct BaseRing; // brick tiles around the base, swapped by the shovel
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum TileKind {
+    Brick,
+    Steel,
+}
 
 #[derive(Component)] struct EnemyGun(Timer);
 
+// Hits left before the tank is destroyed
+#[derive(Component)] struct Health(u8);
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+enum EnemyKind {
+    Basic,
+    Fast,
+    Power,
+    Armor,
+    // Drops mines as it roams (see mines.rs)
+    Saboteur,
+    // Only ever spawned by the boss module, never queued in a wave
+    Boss,
+}
+
+impl EnemyKind {
+    // In the order score breakdowns list them
+    const ALL: [EnemyKind; 6] =
+        [EnemyKind::Basic, EnemyKind::Fast, EnemyKind::Power, EnemyKind::Saboteur, EnemyKind::Armor, EnemyKind::Boss];
+
+    fn label(self) -> &'static str {
+        match self {
+            EnemyKind::Basic => "BASIC",
+            EnemyKind::Fast => "FAST",
+            EnemyKind::Power => "POWER",
+            EnemyKind::Saboteur => "SABOTEUR",
+            EnemyKind::Armor => "ARMOR",
+            EnemyKind::Boss => "BOSS",
+        }
+    }
+
+    fn speed(self, cfg: &GameConfig) -> f32 {
+        match self {
+            EnemyKind::Fast => cfg.enemy.speed * 1.5,
+            EnemyKind::Armor => cfg.enemy.speed * 0.85,
+            EnemyKind::Saboteur => cfg.enemy.speed * 1.2,
+            EnemyKind::Basic | EnemyKind::Power => cfg.enemy.speed,
+            EnemyKind::Boss => cfg.boss.speed,
+        }
+    }
+
+    // Before the shell kind's own speed factor
+    fn bullet_speed(self, cfg: &GameConfig) -> f32 {
+        match self {
+            // Slow enough to weave through a spread
+            EnemyKind::Boss => cfg.bullet.speed * 0.6,
+            _ => cfg.bullet.speed,
+        }
+    }
+
+    fn bullet_kind(self) -> BulletKind {
+        match self {
+            EnemyKind::Power => BulletKind::Fast,
+            _ => BulletKind::Standard,
+        }
+    }
+
+    fn size(self, cfg: &GameConfig) -> Vec2 {
+        match self {
+            EnemyKind::Boss => cfg.boss.size(),
+            _ => cfg.enemy.size(),
+        }
+    }
+
+    fn points(self) -> u32 {
+        match self {
+            EnemyKind::Basic => 100,
+            EnemyKind::Fast => 200,
+            EnemyKind::Power => 300,
+            EnemyKind::Armor => 400,
+            EnemyKind::Saboteur => 300,
+            EnemyKind::Boss => 2000,
+        }
+    }
+
+    fn hit_points(self, cfg: &GameConfig) -> u8 {
+        match self {
+            EnemyKind::Armor => 4,
+            EnemyKind::Boss => cfg.boss.hit_points.max(1),
+            _ => 1,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            EnemyKind::Basic => Color::srgb(0.9, 0.2, 0.2),
+            EnemyKind::Fast => Color::srgb(1.0, 0.5, 0.3),
+            EnemyKind::Power => Color::srgb(0.8, 0.2, 0.6),
+            EnemyKind::Armor => Color::srgb(0.55, 0.1, 0.1),
+            EnemyKind::Saboteur => Color::srgb(0.5, 0.55, 0.15),
+            EnemyKind::Boss => Color::srgb(0.5, 0.15, 0.7),
+        }
+    }
+}
+
+// Placeholder occupying a spawn point until the enemy tank appears
+#[derive(Component)]
+struct SpawnTelegraph {
+    timer: Timer,
+    kind: EnemyKind,
+    bonus: bool,
+}
+
+// A bonus tank blinks (see accessibility.rs for the colours) and always leaves a power-up (see drops.rs)
+#[derive(Component)]
+struct BonusTank {
+    blink: Timer,
+    lit: bool,
+}
+
+// Bullets are absorbed without damage while this is present
+#[derive(Component)] struct Invulnerable(Timer);
+
+// Rotating ring of dots, a child of any tank that is Invulnerable
+#[derive(Component)] struct ShieldRing;
+
 #[derive(Component, Clone, Copy, PartialEq, Eq)]
 enum Faction {
     Player,
     Enemy,
 }
 
+// The damage matrix: what a shell fired by this faction may hurt
+impl Faction {
+    fn hurts(self, target: Faction, rules: &DamageConfig) -> bool {
+        match (self, target) {
+            (Faction::Player, Faction::Enemy) | (Faction::Enemy, Faction::Player) => true,
+            (Faction::Player, Faction::Player) => rules.friendly_fire,
+            (Faction::Enemy, Faction::Enemy) => rules.chaos,
+        }
+    }
+
+    fn breaks_brick(self, rules: &DamageConfig) -> bool {
+        match self {
+            Faction::Player => rules.player_breaks_brick,
+            Faction::Enemy => rules.enemy_breaks_brick,
+        }
+    }
+
+    fn hits_base(self, rules: &DamageConfig) -> bool {
+        match self {
+            Faction::Player => rules.player_hits_base,
+            Faction::Enemy => rules.enemy_hits_base,
+        }
+    }
+}
+
+// The tank that fired a shell, credited with whatever it destroys; its own
+// shells never hit it
+#[derive(Component)]
+struct Owner(Entity);
+
 #[derive(Component, Deref, DerefMut)]
 struct Velocity(Vec2);
 
+// What a tank is actually doing; eases toward `Velocity` based on grip
+#[derive(Component, Default)]
+struct Momentum(Vec2);
+
+// Angle the hull really points at under heavy handling, which turns it toward
+// its velocity a little each frame (see heavy_turn)
+#[derive(Component, Default)]
+struct Heading(f32);
+
+// Surface under a tank's centre, refreshed every frame by track_ground
+#[derive(Component, Clone, Copy, PartialEq, Eq, Default)]
+enum Ground {
+    #[default]
+    Floor,
+    Ice,
+    Tar,
+    // A belt, with the way it runs
+    Conveyor(IVec2),
+}
+
 #[derive(Component)]
 struct Size(Vec2);
 
+// === Player loadout (built from the profile in spawn_player) ===
+#[derive(Component)]
+struct TankSpeed(f32);
+
+#[derive(Component)]
+struct GunStats {
+    cooldown_factor: f32,
+    bullet_speed: f32,
+    bullet_size: Vec2,
+    shell: BulletKind,
+}
+
+// Enemy hits left before the next one is fatal
+#[derive(Component)]
+struct Armor(u8);
+
+#[derive(Component)]
+struct UtilitySlot {
+    kind: Utility,
+    cooldown: Timer,
+}
+
+#[derive(Component)]
+struct Dash(Timer);
+
+#[derive(Component)]
+struct SmokeCloud(Timer);
+
 // === Enemy AI ===
 #[derive(Component)]
 struct EnemyAI {
@@ -54,37 +419,52 @@ struct EnemyAI {
     think: Timer,
     roam_dir: Vec2,
     awareness: f32, // 0.0 .. 1.0
+    home: Vec2,     // spawn point, used as the rally point when retreating
+    shock: f32,     // recent nearby ally deaths, decays over time
+    retreat: Timer,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq)]
 enum EnemyState {
     Roaming,
     Chasing,
+    Retreating,
+    // Making for the base (see siege.rs)
+    Besieging,
 }
 
 // === New: Weapon upgrades and pickups ===
 #[derive(Component)]
-struct UpgradePickup;
+struct Pickup;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
+enum PowerUp {
+    WeaponUpgrade,
+    Shovel,
+    Timer,
+    Emp,
+    Mines,
+}
+
+const BONUS_BLINK_SECS: f32 = 0.25;
+const FREEZE_WARN_SECS: f32 = 2.0;
+const FREEZE_BLINK_SECS: f32 = 0.2;
+const FORTIFY_WARN_SECS: f32 = 3.0;
+const FORTIFY_BLINK_SECS: f32 = 0.25;
 
 #[derive(Resource)]
 struct PlayerUpgradeLevel(u8);
 
-const MAX_UPGRADE_STACK: u8 = 3;
-
-fn fire_cooldown_for(level: u8) -> f32 {
-    // Base cooldown reduced by 20% per stack, up to MAX_UPGRADE_STACK
-    let base = 0.16;
-    let factor = 0.8_f32.powi(level.min(MAX_UPGRADE_STACK) as i32);
-    base * factor
+fn fire_cooldown_for(level: u8, cfg: &PlayerConfig) -> f32 {
+    // Base cooldown reduced per stack, up to the configured maximum
+    let factor = cfg.upgrade_cooldown_factor.powi(level.min(cfg.max_upgrade_stack) as i32);
+    cfg.fire_cooldown * factor
 }
 
 // === Resources ===
 #[derive(Resource)]
 struct FireCooldown(Timer);
 
-#[derive(Resource)]
-struct EnemySpawnTimer(Timer);
-
 #[derive(Resource, Debug)]
 struct SpawnPoints {
     points: Vec<Vec2>,
@@ -94,20 +474,116 @@ struct SpawnPoints {
 #[derive(Resource)]
 struct PlayerStart(Vec2);
 
+// Where the loaded level sits in the world; the level is centred on the origin.
+#[derive(Resource, Clone, Copy)]
+struct LevelBounds {
+    origin: Vec2, // centre of the top-left tile
+    cols: usize,
+    rows: usize,
+}
+
+impl LevelBounds {
+    fn new(cols: usize, rows: usize) -> Self {
+        let size = Vec2::new(cols as f32, rows as f32) * TILE;
+        Self { origin: Vec2::new(-size.x * 0.5 + TILE * 0.5, size.y * 0.5 - TILE * 0.5), cols, rows }
+    }
+
+    fn size(&self) -> Vec2 {
+        Vec2::new(self.cols as f32, self.rows as f32) * TILE
+    }
+
+    fn center(&self) -> Vec2 {
+        self.origin + Vec2::new(self.size().x - TILE, -(self.size().y - TILE)) * 0.5
+    }
+
+    // Grid cell (col, row) under a world position, if it lies inside the level
+    fn cell_at(&self, pos: Vec2) -> Option<UVec2> {
+        let rel = (pos - self.origin) / TILE;
+        let (col, row) = (rel.x.round(), (-rel.y).round());
+        let inside = col >= 0.0 && row >= 0.0 && (col as usize) < self.cols && (row as usize) < self.rows;
+        inside.then(|| UVec2::new(col as u32, row as u32))
+    }
+
+    // Centre of the tile under a world position
+    fn cell_center(&self, pos: Vec2) -> Option<Vec2> {
+        self.cell_at(pos).map(|c| self.origin + Vec2::new(c.x as f32, -(c.y as f32)) * TILE)
+    }
+}
+
+// Tanks left in the current run, including the one on the field
+#[derive(Resource)]
+struct Lives(u8);
+
+// Blocking tile AABBs as (center, half extents) per mover layer,
+// rebuilt only when blocking tiles are added or removed.
+#[derive(Resource, Default)]
+struct CollisionCache {
+    tanks: Vec<(Vec2, Vec2)>,
+    bullets: Vec<(Vec2, Vec2)>,
+    ice: Vec<(Vec2, Vec2)>,
+    tar: Vec<(Vec2, Vec2)>,
+    conveyors: Vec<(Vec2, Vec2, IVec2)>,
+}
+
+// Present while the shovel effect is active; removed when the ring reverts to brick.
+#[derive(Resource)]
+struct Fortification(Timer);
+
+// Present for a moment after each player spawn: enemies may not shoot into or
+// drive into the area around the start tile.
+#[derive(Resource)]
+struct SpawnProtection(Timer);
+
+// Present while the timer power-up holds every enemy in place.
+#[derive(Resource)]
+struct FreezeEnemies(Timer);
+
+// Present between the player tank blowing up and the respawn (or game over).
+#[derive(Resource)]
+struct PlayerRespawn(Timer);
+
+// Tears the level down and builds it again from scratch: sent when the recap
+// of a cleared stage is dismissed, to start the next one.
 #[derive(Event, Default)]
 struct RestartEvent;
 
+// A new player tank after losing one. The level carries on as it is, broken
+// bricks, enemies and all.
+#[derive(Event, Default)]
+struct RespawnEvent;
+
+#[derive(Event)]
+struct EnemyKilled {
+    pos: Vec2,
+    kind: EnemyKind,
+    bonus: bool,
+    credit: Option<Credit>,
+}
+
+impl EnemyKilled {
+    // Score, loot and stats go to the player for everything but enemies
+    // destroying each other
+    fn for_player(&self) -> bool {
+        self.credit.is_none_or(|c| c.faction == Faction::Player)
+    }
+}
+
 fn on_restart_cleanup(
     mut commands: Commands,
     mut ev: EventReader<RestartEvent>,
+    cfg: Res<GameConfig>,
     mut cooldown: ResMut<FireCooldown>,
-    mut enemy_timer: ResMut<EnemySpawnTimer>,
+    mut queue: ResMut<LevelEnemyQueue>,
+    level: Res<CurrentLevel>,
+    order: Res<LevelOrder>,
+    mode: Res<GameMode>,
     mut upgrade: ResMut<PlayerUpgradeLevel>,
     q_players: Query<Entity, With<Player>>,
     q_enemies: Query<Entity, With<Enemy>>,
-    q_walls: Query<Entity, With<Wall>>,
+    q_tiles: Query<Entity, With<LevelTile>>,
     q_bullets: Query<Entity, With<Bullet>>,
-    q_pickups: Query<Entity, With<UpgradePickup>>,
+    q_pickups: Query<Entity, With<Pickup>>,
+    q_effects: Query<Entity, Or<(With<SmokeCloud>, With<Mine>, With<SpawnTelegraph>)>>,
 ) {
     let mut triggered = false;
     for _ in ev.read() { triggered = true; }
@@ -115,136 +591,669 @@ fn on_restart_cleanup(
 
     for e in q_players.iter() { commands.entity(e).despawn(); }
     for e in q_enemies.iter() { commands.entity(e).despawn(); }
-    for e in q_walls.iter() { commands.entity(e).despawn(); }
+    for e in q_tiles.iter() { commands.entity(e).despawn(); }
     for e in q_bullets.iter() { commands.entity(e).despawn(); }
     for e in q_pickups.iter() { commands.entity(e).despawn(); }
+    for e in q_effects.iter() { commands.entity(e).despawn(); }
 
     // Reset upgrade level and timers
+    commands.remove_resource::<Fortification>();
+    commands.remove_resource::<FreezeEnemies>();
+    commands.remove_resource::<PlayerRespawn>();
     upgrade.0 = 0;
-    cooldown.0 = Timer::from_seconds(fire_cooldown_for(0), TimerMode::Once);
-    enemy_timer.0.reset();
+    cooldown.0 = Timer::from_seconds(fire_cooldown_for(0, &cfg.player), TimerMode::Once);
+    *queue = LevelEnemyQueue::for_run(*mode, order.level(level.0), &cfg);
+}
+
+// Fresh resources for a new run (or the saved ones when continuing); the level
+// itself is built right after.
+fn start_run(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut saved: ResMut<SavedRun>,
+    mut cooldown: ResMut<FireCooldown>,
+    mut queue: ResMut<LevelEnemyQueue>,
+    mut level: ResMut<CurrentLevel>,
+    order: Res<LevelOrder>,
+    mode: Res<GameMode>,
+    mut lives: ResMut<Lives>,
+    mut score: ResMut<Score>,
+    mut coins: ResMut<Coins>,
+    mut upgrade: ResMut<PlayerUpgradeLevel>,
+    demo: Option<Res<Demo>>,
+) {
+    commands.remove_resource::<Fortification>();
+    commands.remove_resource::<FreezeEnemies>();
+    commands.remove_resource::<PlayerRespawn>();
+    level.0 = 0;
+    lives.0 = cfg.player.lives;
+    score.0 = 0;
+    coins.0 = 0;
+    upgrade.0 = 0;
+
+    let resumed = saved.snapshot.filter(|_| saved.resume);
+    saved.resume = false;
+    match resumed {
+        Some(run) => {
+            level.0 = run.level;
+            lives.0 = run.lives.max(1);
+            score.0 = run.score;
+            coins.0 = run.coins;
+            upgrade.0 = run.upgrade.min(cfg.player.max_upgrade_stack);
+            saved.snapshot = None;
+        }
+        // Starting over abandons the saved run; a demo or another mode's run leaves it alone
+        None if demo.is_some() || *mode != GameMode::Campaign => {}
+        None => saved.discard(),
+    }
+
+    cooldown.0 = Timer::from_seconds(fire_cooldown_for(upgrade.0, &cfg.player), TimerMode::Once);
+    *queue = LevelEnemyQueue::for_run(*mode, order.level(level.0), &cfg);
+}
+
+// A .ron level's own tanks, in place of the wave table's; other modes have
+// schedules of their own
+fn level_waves(
+    cfg: Res<GameConfig>,
+    level: Res<CurrentLevel>,
+    mode: Res<GameMode>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    mut queue: ResMut<LevelEnemyQueue>,
+) {
+    if !matches!(*mode, GameMode::Campaign | GameMode::Daily) { return; }
+    let Some(waves) = packs::custom_level(&args, &packs, level.0).and_then(|file| file.waves.as_ref()) else { return; };
+    *queue = LevelEnemyQueue::from_schedule(waves, &cfg);
+}
+
+// Clear the field when a run ends so the next OnEnter(Playing) starts clean.
+fn end_run(
+    mut commands: Commands,
+    q_world: Query<
+        Entity,
+        Or<(
+            With<Player>,
+            With<Enemy>,
+            With<LevelTile>,
+            With<Bullet>,
+            With<Pickup>,
+            With<SmokeCloud>,
+            With<Mine>,
+            With<SpawnTelegraph>,
+        )>,
+    >,
+) {
+    for e in &q_world { commands.entity(e).despawn(); }
+    // Menus go back to the standard backdrop
+    commands.insert_resource(ClearColor::default());
 }
 
-fn on_restart_build_maze(mut commands: Commands, mut ev: EventReader<RestartEvent>) {
+fn on_restart_build_maze(
+    commands: Commands,
+    cfg: Res<GameConfig>,
+    level: Res<CurrentLevel>,
+    order: Res<LevelOrder>,
+    mode: Res<GameMode>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    content: Res<ContentRegistry>,
+    locale: Res<Locale>,
+    mut ev: EventReader<RestartEvent>,
+) {
     let mut triggered = false;
     for _ in ev.read() { triggered = true; }
     if !triggered { return; }
-    build_maze(commands);
+    build_maze(commands, cfg, level, order, mode, args, packs, content, locale);
 }
 
 fn on_restart_spawn_player(
-    mut commands: Commands,
+    commands: Commands,
     start: Option<Res<PlayerStart>>,
+    profile: Res<Profile>,
+    cfg: Res<GameConfig>,
     mut ev: EventReader<RestartEvent>,
 ) {
     let mut triggered = false;
     for _ in ev.read() { triggered = true; }
     if !triggered { return; }
-    spawn_player(commands, start);
+    spawn_player(commands, start, profile, cfg);
+}
+
+fn on_respawn_player(
+    mut commands: Commands,
+    start: Option<Res<PlayerStart>>,
+    profile: Res<Profile>,
+    cfg: Res<GameConfig>,
+    mut cooldown: ResMut<FireCooldown>,
+    mut upgrade: ResMut<PlayerUpgradeLevel>,
+    q_bullets: Query<Entity, With<Bullet>>,
+    mut ev: EventReader<RespawnEvent>,
+) {
+    let mut triggered = false;
+    for _ in ev.read() { triggered = true; }
+    if !triggered { return; }
+
+    // Shells still in flight would meet the new tank as it rolls in
+    for e in q_bullets.iter() { commands.entity(e).despawn(); }
+    // Losing a tank loses its weapon upgrades
+    upgrade.0 = 0;
+    cooldown.0 = Timer::from_seconds(fire_cooldown_for(0, &cfg.player), TimerMode::Once);
+    spawn_player(commands, start, profile, cfg);
 }
 
-// 20x15 maze: exactly 20 chars per row
-// '#' = wall, 'S' = enemy spawn, 'P' = player start, ' ' = floor
-const MAZE: [&str; 15] = [
+// Any size works; every row must be the same width
+// '#' = steel wall, 'b' = brick wall, 'E' = base, '~' = water, 'F' = forest,
+// '=' = ice, ':' = tar, '<' '>' '^' 'v' = conveyor running that way, 'o' = flame barrel,
+// 'T' = turret, '1'-'4' = teleporter (paired by digit), 'S' = enemy spawn, 'P' = player start,
+// ' ' = floor
+const MAZE: &[&str] = &[
     "####################",
-    "#P             #  S#",
+    "#P >>>===    1 #  S#",
     "### #### ####### ###",
-    "#   #   #     #   ##",
+    "#   #   # FFF #   ##",
     "# ### # # ### ###  #",
     "# #   #   # #     S#",
-    "#         # # ######", // <- fixed (20 chars)
-    "# #     #   #     ##",
-    "# ##### ###     #  #",
-    "#     #     #   #  #",
+    "#  o ~    # # ######", // <- fixed (20 chars)
+    "# #  :::#   #  ~  ##",
+    "# ##### ###  T  #  #",
+    "# FF  #     #   #  #",
     "### # ### # ### ####",
-    "# S #   # #   #    #",
-    "### ### # ### # ####",
-    "#      S#     #   S#",
+    "# S #  1# #   #  o #",
+    "### ### # bbb # ####",
+    "#      S# bEb #   S#",
     "####################",
 ];
 
+// The built-in maze's named trigger zones, as a level file lays them out (see
+// triggers.rs): (name, top-left (col, row), size in tiles)
+const TRIGGER_ZONES: &[(&str, UVec2, UVec2)] = &[
+    ("base_approach", UVec2::new(9, 11), UVec2::new(5, 3)),
+    ("forest_ambush", UVec2::new(10, 3), UVec2::new(3, 1)),
+];
+
+// The built-in maze's patrolling barriers, as a level file lays them out (see
+// patrols.rs): waypoints as (col, row), looping from the last back to the
+// first, and speed in pixels per second. Two waypoints shuttle back and forth.
+const PATROLS: &[(&[UVec2], f32)] = &[
+    (&[UVec2::new(7, 9), UVec2::new(11, 9)], 60.0),
+];
+
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Battle City (Bevy 0.16.1)".into(),
-                resolution: (ARENA_W, ARENA_H).into(),
-                ..default()
-            }),
-            ..default()
-        }))
+    error_screen::install();
+    let args = LaunchArgs::parse();
+    let profile = Profile::load();
+    let mut config = GameConfig::load();
+    config.apply_difficulty(args.difficulty.unwrap_or(profile.difficulty));
+    let fire_cooldown = fire_cooldown_for(0, &config.player);
+    let first_level = LevelEnemyQueue::for_level(0, &config);
+
+    let mut app = App::new();
+    app
+        // Ahead of the plugins, which may look at it while they're built
+        .insert_resource(args.clone())
+        .add_plugins(level_reload::LevelSourcePlugin)
+        .add_plugins(default_plugins(&args, &profile))
+        .add_plugins((
+            // Menus, screens and the HUD
+            (
+                input_glyphs::InputGlyphsPlugin,
+                intent::IntentPlugin,
+                touch::TouchPlugin,
+                controller::ControllerPlugin,
+                attract::AttractPlugin,
+                loadout::LoadoutPlugin,
+                recap::RecapPlugin,
+                scores::ScoresPlugin,
+                sidebar::SidebarPlugin,
+                stats::StatsPlugin,
+                savegame::SaveGamePlugin,
+                minimap::MinimapPlugin,
+                toasts::ToastsPlugin,
+                achievements::AchievementsPlugin,
+                speedrun::SpeedrunPlugin,
+            ),
+            // Window, settings, language and the error screen
+            (
+                display::DisplayPlugin,
+                settings::SettingsPlugin,
+                locale::LocalePlugin,
+                error_screen::ErrorScreenPlugin,
+            ),
+            // Debugging and the console, profiling, testing, screen capture, photo mode, the event log and its heatmap
+            (
+                debug::DebugPlugin,
+                console::ConsolePlugin,
+                profiler::ProfilerPlugin,
+                stress::StressPlugin,
+                capture::CapturePlugin,
+                photo::PhotoPlugin,
+                event_log::EventLogPlugin,
+                heatmap::HeatmapPlugin,
+            ),
+            // Gameplay subsystems
+            (
+                death::DeathPlugin,
+                status::StatusPlugin,
+                boss::BossPlugin,
+                mines::MinesPlugin,
+                drops::DropsPlugin,
+                game_time::GameTimePlugin,
+                fog::FogPlugin,
+                spatial::SpatialPlugin,
+                siege::SiegePlugin,
+                squads::SquadsPlugin,
+                unstick::UnstickPlugin,
+                repairs::RepairsPlugin,
+                adaptive::AdaptivePlugin,
+            ),
+            // Level furniture, modes and mods
+            (
+                turrets::TurretsPlugin,
+                patrols::PatrolsPlugin,
+                teleporters::TeleportersPlugin,
+                base_alarm::BaseAlarmPlugin,
+                horde::HordePlugin,
+                daily::DailyPlugin,
+                mods::ModsPlugin,
+            ),
+            // Trigger zones, level scripts, level packs, checks, reloading and the tutorial built on them
+            (
+                triggers::TriggersPlugin,
+                script::ScriptPlugin,
+                packs::PacksPlugin,
+                level_check::LevelCheckPlugin,
+                level_reload::LevelReloadPlugin,
+                tutorial::TutorialPlugin,
+            ),
+            // Effects
+            (
+                feedback::FeedbackPlugin,
+                accessibility::AccessibilityPlugin,
+                floating_text::FloatingTextPlugin,
+                explosions::ExplosionsPlugin,
+                particles::ParticlesPlugin,
+            ),
+        ))
+        .init_state::<AppState>()
+        .insert_resource(Locale::new(profile.language))
+        .insert_resource(profile)
+        .insert_resource(Lives(config.player.lives))
+        .insert_resource(config)
         .add_event::<RestartEvent>()
-        .insert_resource(FireCooldown(Timer::from_seconds(0.16, TimerMode::Once)))
-        .insert_resource(EnemySpawnTimer(Timer::from_seconds(
-            ENEMY_SPAWN_SECS,
-            TimerMode::Repeating,
-        )))
+        .add_event::<RespawnEvent>()
+        .add_event::<EnemyKilled>()
+        .insert_resource(FireCooldown(Timer::from_seconds(fire_cooldown, TimerMode::Once)))
+        .init_resource::<CurrentLevel>()
+        .init_resource::<GameMode>()
+        .init_resource::<LevelOrder>()
+        .init_resource::<CollisionCache>()
+        .insert_resource(GameRng::new(0))
+        .init_resource::<BulletPool>()
+        .init_resource::<LevelTheme>()
+        .insert_resource(first_level)
         .insert_resource(PlayerUpgradeLevel(0))
-        // was: .add_systems(Startup, (setup_camera, build_maze, spawn_player))
-        .add_systems(Startup, (setup_camera, build_maze, spawn_player).chain())
+        .add_systems(Startup, setup_camera)
+        // The run starts once a loadout is confirmed
+        .add_systems(OnEnter(AppState::Playing), (seed_run, start_run, level_waves, build_maze, spawn_player).chain())
+        .add_systems(OnExit(AppState::Playing), (end_run, reset_camera))
+        .configure_sets(
+            Update,
+            (GameSet::Input, GameSet::Ai, GameSet::Physics, GameSet::Damage, GameSet::Cleanup).chain(),
+        )
         .add_systems(
             Update,
             (
-                player_input,
-                handle_fire,
-                enemy_handle_fire,
-                move_with_collisions,
-                bullet_hits,
-                bullet_wall_cull,
-                enemy_ai_seek_player,
-                enemy_spawner,      // now mutably advances spawn index
-                clamp_to_arena,
-                pickup_collection,
-            ),
+                (player_input, player_utility, utility_effects, handle_fire).in_set(GameSet::Input),
+                (
+                    ((enemy_morale, enemy_ai_seek_player).chain(), enemy_handle_fire)
+                        .run_if(not(resource_exists::<FreezeEnemies>)),
+                    // Nothing rolls out while a horde build phase is on
+                    enemy_spawner.run_if(not(resource_exists::<BuildPhase>)),
+                    spawn_telegraph_tick,
+                )
+                    .in_set(GameSet::Ai),
+                (refresh_collision_cache, track_ground, heavy_turn.run_if(heavy_handling), move_with_collisions)
+                    .chain()
+                    .in_set(GameSet::Physics),
+                (bullet_hits, barrel_hits, (bullet_tile_damage, bullet_wall_cull).chain(), pickup_collection)
+                    .in_set(GameSet::Damage),
+                (
+                    level_completion,
+                    (attach_shield_ring, invulnerability_tick).chain(),
+                    (spawn_protection_tick, player_respawn_tick),
+                    fortification_tick,
+                    freeze_enemies_tick,
+                    bonus_blink,
+                )
+                    .in_set(GameSet::Cleanup),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
         )
         .add_systems(
             Update,
-            (on_restart_cleanup, on_restart_build_maze, on_restart_spawn_player).chain(),
+            camera_follow
+                .after(feedback::apply_shake)
+                .run_if(in_state(AppState::Playing))
+                .run_if(photo::photo_mode_off),
         )
-        .run();
+        .add_systems(
+            Update,
+            (
+                (
+                    on_restart_cleanup,
+                    level_waves.run_if(on_event::<RestartEvent>),
+                    on_restart_build_maze,
+                    on_restart_spawn_player,
+                )
+                    .chain(),
+                on_respawn_player,
+            )
+                .in_set(GameSet::Cleanup)
+                .run_if(in_state(AppState::Playing)),
+        );
+    // Behind a cargo feature, to keep the base build light
+    #[cfg(feature = "scripting")]
+    app.add_plugins(scripting::ScriptingPlugin);
+    app.run();
+}
+
+fn default_plugins(args: &LaunchArgs, profile: &Profile) -> PluginGroupBuilder {
+    let (canvas, fit_canvas_to_parent) = display::canvas();
+    let window = Window {
+        title: "Battle City (Bevy 0.16.1)".into(),
+        resolution: (profile.display.resolution.0 as f32, profile.display.resolution.1 as f32).into(),
+        mode: display::window_mode(args.fullscreen || profile.display.fullscreen),
+        present_mode: display::present_mode(profile.display.vsync),
+        canvas,
+        fit_canvas_to_parent,
+        ..default()
+    };
+    let volume = settings::master_volume(args, profile);
+    let plugins = DefaultPlugins
+        .set(WindowPlugin {
+            primary_window: (!args.headless).then_some(window),
+            // With no window to close, only the process being stopped ends a headless run
+            exit_condition: if args.headless { ExitCondition::DontExit } else { ExitCondition::OnAllClosed },
+            ..default()
+        })
+        .set(AudioPlugin { global_volume: GlobalVolume::new(volume), ..default() });
+    if !args.headless { return plugins; }
+
+    plugins
+        .disable::<WinitPlugin>()
+        .set(RenderPlugin { render_creation: WgpuSettings { backends: None, ..default() }.into(), ..default() })
+        .add(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(1.0 / 60.0)))
 }
 
 // === Setup ===
 fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera2d::default());
-}
-
-fn build_maze(mut commands: Commands) {
-    // Validate all rows are equal width (defensive)
-    let expected_cols = MAZE[0].len();
-    for (i, row) in MAZE.iter().enumerate() {
-        assert!(
-            row.len() == expected_cols,
-            "MAZE row {i} width {} != {}",
-            row.len(),
-            expected_cols
+    commands.spawn((
+        Camera2d,
+        Projection::Orthographic(OrthographicProjection {
+            scaling_mode: ScalingMode::Fixed { width: WINDOW_W, height: WINDOW_H },
+            ..OrthographicProjection::default_2d()
+        }),
+        WorldCamera,
+    ));
+}
+
+// Keep the player centred, but never show past the level edge; a level smaller
+// than the view on an axis stays centred on that axis. Zooming in shrinks the view.
+fn camera_follow(
+    bounds: Option<Res<LevelBounds>>,
+    shake: Res<CameraShake>,
+    profile: Res<Profile>,
+    q_player: Query<&Transform, (With<Player>, Without<WorldCamera>)>,
+    mut q_camera: Query<&mut Transform, With<WorldCamera>>,
+    mut focus: Local<Vec2>,
+) {
+    let Some(bounds) = bounds else { return; };
+    // Between a death and the respawn there is no player; hold the last focus
+    if let Ok(player) = q_player.single() {
+        let half_view = Vec2::new(WINDOW_W, WINDOW_H) * 0.5 * profile.display.camera_scale();
+        let (min, max) = (bounds.center() - bounds.size() * 0.5, bounds.center() + bounds.size() * 0.5);
+        let axis = |p: f32, lo: f32, hi: f32, half: f32, mid: f32| {
+            if hi - lo <= half * 2.0 { mid } else { p.clamp(lo + half, hi - half) }
+        };
+        let p = player.translation.truncate();
+        *focus = Vec2::new(
+            axis(p.x, min.x, max.x, half_view.x, bounds.center().x),
+            axis(p.y, min.y, max.y, half_view.y, bounds.center().y),
         );
     }
 
-    let mut spawn_points = Vec::new();
-    let mut player_start = Vec2::new(0.0, -ARENA_H * 0.35); // fallback
+    let target = *focus + shake.offset;
+    for mut t in &mut q_camera {
+        t.translation.x = target.x;
+        t.translation.y = target.y;
+    }
+}
+
+fn reset_camera(mut q_camera: Query<&mut Transform, With<WorldCamera>>) {
+    for mut t in &mut q_camera {
+        t.translation.x = 0.0;
+        t.translation.y = 0.0;
+    }
+}
+
+// A brick or steel tile, from the maze or built in horde mode
+fn spawn_wall(commands: &mut Commands, pos: Vec2, kind: TileKind, theme: &LevelTheme) -> Entity {
+    commands
+        .spawn((
+            Sprite {
+                color: theme.tile(kind),
+                custom_size: Some(Vec2::splat(TILE)),
+                ..default()
+            },
+            Transform::from_xyz(pos.x, pos.y, 0.0),
+            Wall,
+            LevelTile,
+            Blocks::SOLID,
+            kind,
+            Size(Vec2::splat(TILE)),
+        ))
+        .id()
+}
 
-    let origin = Vec2::new(-ARENA_W * 0.5 + TILE * 0.5, ARENA_H * 0.5 - TILE * 0.5);
+fn build_maze(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    level: Res<CurrentLevel>,
+    order: Res<LevelOrder>,
+    mode: Res<GameMode>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    content: Res<ContentRegistry>,
+    locale: Res<Locale>,
+) {
+    // A level pack or a --level file replaces the built-in maze (and was
+    // checked before the run started, see level_check.rs), except in the
+    // tutorial, whose steps are laid out for its own
+    let custom = if *mode == GameMode::Tutorial { None } else { packs::custom_level(&args, &packs, level.0) };
+    let maze: Vec<&str> = match custom {
+        _ if *mode == GameMode::Tutorial => tutorial::MAZE.to_vec(),
+        Some(file) => file.rows.iter().map(String::as_str).collect(),
+        None => MAZE.to_vec(),
+    };
+    // A maze made up during the run (random levels) hasn't been through the
+    // checks; one with no rows, or ragged ones, goes to the error screen
+    // rather than being built off the grid
+    let ragged: Vec<String> = level_check::problems(&maze, false)
+        .iter()
+        .filter(|p| matches!(p, level_check::Problem::Ragged { .. }))
+        .map(|p| p.describe(&locale))
+        .collect();
+    if maze.is_empty() || !ragged.is_empty() {
+        let name = custom.map_or("built-in", |file| file.name.as_str());
+        error_screen::report(format!("{}\n{}", locale.f("check.title", &[("name", &name)]), ragged.join("\n")));
+        return;
+    }
+    let expected_cols = maze[0].len();
+
+    let theme = packs
+        .theme()
+        .or(custom.and_then(|file| file.theme))
+        .unwrap_or_else(|| LevelTheme::for_level(order.level(level.0)));
+    commands.insert_resource(theme);
+    commands.insert_resource(ClearColor(theme.floor));
 
-    for (r, line) in MAZE.iter().enumerate() {
+    let mut spawn_points = Vec::new();
+    let bounds = LevelBounds::new(expected_cols, maze.len());
+    let origin = bounds.origin;
+    let mut player_start = bounds.center(); // fallback
+
+    // Bricks touching the base (including diagonals) form the shovel ring.
+    let base_cell = maze
+        .iter()
+        .enumerate()
+        .find_map(|(r, line)| line.find('E').map(|c| (r as i32, c as i32)));
+    let near_base = |r: usize, c: usize| {
+        base_cell.is_some_and(|(br, bc)| (r as i32 - br).abs() <= 1 && (c as i32 - bc).abs() <= 1)
+    };
+
+    for (r, line) in maze.iter().enumerate() {
         for (c, ch) in line.chars().enumerate() {
             let x = origin.x + c as f32 * TILE;
             let y = origin.y - r as f32 * TILE;
+            // A mod's tile plays as the one it's built on, in its own colour (see mods.rs)
+            let (ch, tint) = content.tile(ch).map_or((ch, None), |t| (t.base, Some(t.color)));
+            let shade = |color: Color| theme.shade(tint.map_or(color, |t| t.with_alpha(color.alpha())));
 
             match ch {
-                '#' => {
+                '#' | 'b' => {
+                    let kind = if ch == 'b' { TileKind::Brick } else { TileKind::Steel };
+                    let wall = spawn_wall(&mut commands, Vec2::new(x, y), kind, &theme);
+                    if kind == TileKind::Brick && near_base(r, c) {
+                        commands.entity(wall).insert(BaseRing);
+                    }
+                    if let Some(color) = tint {
+                        let size = Some(Vec2::splat(TILE));
+                        commands.entity(wall).insert(Sprite { color: theme.shade(color), custom_size: size, ..default() });
+                    }
+                }
+                // Survival has no base to lose
+                'E' if *mode == GameMode::Survival => {}
+                'E' => {
                     commands.spawn((
                         Sprite {
-                            color: Color::srgb(0.25, 0.25, 0.3),
-                            custom_size: Some(Vec2::splat(TILE)),
+                            color: theme.shade(Color::srgb(0.95, 0.8, 0.2)),
+                            custom_size: Some(Vec2::splat(TILE * 0.8)),
                             ..default()
                         },
                         Transform::from_xyz(x, y, 0.0),
+                        Base,
                         Wall,
+                        LevelTile,
+                        Blocks::SOLID,
+                        Size(Vec2::splat(TILE)),
+                    ));
+                }
+                '~' => {
+                    commands.spawn((
+                        Sprite {
+                            color: shade(Color::srgb(0.15, 0.35, 0.8)),
+                            custom_size: Some(Vec2::splat(TILE)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, 0.0),
+                        Water,
+                        LevelTile,
+                        Blocks::WATER,
                         Size(Vec2::splat(TILE)),
                     ));
                 }
+                'F' => {
+                    // Drawn above tanks and bullets so anything inside is hidden
+                    commands.spawn((
+                        Sprite {
+                            color: shade(Color::srgba(0.1, 0.45, 0.15, 0.92)),
+                            custom_size: Some(Vec2::splat(TILE)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, 3.0),
+                        Forest,
+                        LevelTile,
+                    ));
+                }
+                '<' | '>' | '^' | 'v' => {
+                    let dir = match ch {
+                        '<' => IVec2::NEG_X,
+                        '>' => IVec2::X,
+                        '^' => IVec2::Y,
+                        _ => IVec2::NEG_Y,
+                    };
+                    commands.spawn((
+                        Sprite {
+                            color: shade(Color::srgb(0.3, 0.3, 0.32)),
+                            custom_size: Some(Vec2::splat(TILE)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, -0.1),
+                        Conveyor(dir),
+                        LevelTile,
+                        Size(Vec2::splat(TILE)),
+                        // A stripe toward the leading edge shows which way it runs
+                        children![(
+                            Sprite {
+                                color: theme.shade(Color::srgb(0.55, 0.55, 0.5)),
+                                custom_size: Some(Vec2::new(TILE * 0.15, TILE * 0.7)),
+                                ..default()
+                            },
+                            Transform::from_translation((dir.as_vec2() * TILE * 0.3).extend(0.01))
+                                .with_rotation(Quat::from_rotation_z(dir.as_vec2().to_angle())),
+                        )],
+                    ));
+                }
+                '=' => {
+                    commands.spawn((
+                        Sprite {
+                            color: shade(Color::srgb(0.75, 0.9, 0.95)),
+                            custom_size: Some(Vec2::splat(TILE)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, -0.1),
+                        Ice,
+                        LevelTile,
+                        Size(Vec2::splat(TILE)),
+                    ));
+                }
+                ':' => {
+                    commands.spawn((
+                        Sprite {
+                            color: shade(Color::srgb(0.18, 0.14, 0.1)),
+                            custom_size: Some(Vec2::splat(TILE)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, -0.1),
+                        Tar,
+                        LevelTile,
+                        Size(Vec2::splat(TILE)),
+                    ));
+                }
+                'o' => {
+                    commands.spawn((
+                        Sprite {
+                            color: theme.shade(Color::srgb(0.85, 0.2, 0.1)),
+                            custom_size: Some(Vec2::splat(TILE * 0.7)),
+                            ..default()
+                        },
+                        Transform::from_xyz(x, y, 0.0),
+                        FlameBarrel,
+                        LevelTile,
+                        Blocks::SOLID,
+                        Size(Vec2::splat(TILE * 0.7)),
+                    ));
+                }
+                'T' => {
+                    let cell = UVec2::new(c as u32, r as u32);
+                    let fire_secs = custom.and_then(|file| file.turret_fire_secs(cell)).unwrap_or(cfg.turret.fire_secs);
+                    turrets::spawn_turret(&mut commands, Vec2::new(x, y), fire_secs, &cfg);
+                }
+                '1'..='4' => {
+                    teleporters::spawn_teleporter(&mut commands, Vec2::new(x, y), ch as u8 - b'0', &cfg);
+                }
                 'S' => spawn_points.push(Vec2::new(x, y)),
                 'P' => player_start = Vec2::new(x, y),
                 _ => {}
@@ -252,22 +1261,98 @@ fn build_maze(mut commands: Commands) {
         }
     }
 
+    // A level file brings its own zones and patrols; the built-in mazes' are above
+    let zones: Vec<(&str, UVec2, UVec2)> = match custom {
+        Some(file) => file.zones.iter().map(|zone| (zone.name.as_str(), zone.at, zone.size)).collect(),
+        None if *mode == GameMode::Tutorial => tutorial::ZONES.to_vec(),
+        None => TRIGGER_ZONES.to_vec(),
+    };
+    for (name, at, tiles) in zones {
+        let size = tiles.as_vec2() * TILE;
+        let x = origin.x + at.x as f32 * TILE + (size.x - TILE) * 0.5;
+        let y = origin.y - at.y as f32 * TILE - (size.y - TILE) * 0.5;
+        commands.spawn((Transform::from_xyz(x, y, 0.0), TriggerZone::new(name), LevelTile, Size(size)));
+    }
+
+    let routes: Vec<(&[UVec2], f32)> = match custom {
+        Some(file) => file.patrols.iter().map(|route| (&route.cells[..], route.speed)).collect(),
+        None if *mode == GameMode::Tutorial => Vec::new(),
+        None => PATROLS.to_vec(),
+    };
+    for (cells, speed) in routes {
+        let points = cells.iter().map(|c| origin + Vec2::new(c.x as f32, -(c.y as f32)) * TILE).collect();
+        patrols::spawn_patrol(&mut commands, points, speed);
+    }
+
+    spawn_boundary(&mut commands, &bounds);
+    commands.insert_resource(bounds);
+
     commands.insert_resource(SpawnPoints { points: spawn_points, next: 0 });
     commands.insert_resource(PlayerStart(player_start));
 }
 
-fn spawn_player(mut commands: Commands, start: Option<Res<PlayerStart>>) {
+// Frame the maze with one ring of wall just outside its outermost tiles, so
+// leaving the level is blocked by the same collision path as any other wall.
+fn spawn_boundary(commands: &mut Commands, bounds: &LevelBounds) {
+    let Vec2 { x: w, y: h } = bounds.size();
+    let center = bounds.center();
+
+    let sides = [
+        (center + Vec2::new(0.0, (h + TILE) * 0.5), Vec2::new(w + 2.0 * TILE, TILE)),
+        (center - Vec2::new(0.0, (h + TILE) * 0.5), Vec2::new(w + 2.0 * TILE, TILE)),
+        (center - Vec2::new((w + TILE) * 0.5, 0.0), Vec2::new(TILE, h)),
+        (center + Vec2::new((w + TILE) * 0.5, 0.0), Vec2::new(TILE, h)),
+    ];
+    for (pos, size) in sides {
+        commands.spawn((
+            Transform::from_xyz(pos.x, pos.y, 0.0),
+            Wall,
+            Boundary,
+            LevelTile,
+            Blocks::SOLID,
+            Size(size),
+        ));
+    }
+}
+
+fn spawn_player(
+    mut commands: Commands,
+    start: Option<Res<PlayerStart>>,
+    profile: Res<Profile>,
+    cfg: Res<GameConfig>,
+) {
     let Some(start) = start else { return; }; // resource not ready yet
+    let loadout = profile.loadout;
+    commands.insert_resource(SpawnProtection(Timer::from_seconds(cfg.player.spawn_protect_secs, TimerMode::Once)));
+    let size = cfg.player.size();
     commands.spawn((
         Sprite {
             color: Color::srgb(0.2, 0.9, 0.2),
-            custom_size: Some(PLAYER_SIZE),
+            custom_size: Some(size),
             ..default()
         },
         Transform::from_xyz(start.0.x, start.0.y, 1.0),
         Player,
         Velocity(Vec2::ZERO),
-        Size(PLAYER_SIZE),
+        Momentum::default(),
+        Heading::default(),
+        Ground::default(),
+        Size(size),
+        TankSpeed(cfg.player.speed * loadout.hull.speed_factor()),
+        Armor(loadout.hull.armor()),
+        GunStats {
+            cooldown_factor: loadout.gun.cooldown_factor(),
+            bullet_speed: cfg.bullet.speed * loadout.gun.bullet_speed_factor(),
+            bullet_size: cfg.bullet.size() * loadout.gun.bullet_scale(),
+            shell: loadout.gun.bullet_kind(&cfg.bullet),
+        },
+        UtilitySlot {
+            kind: loadout.utility,
+            cooldown: Timer::from_seconds(loadout.utility.cooldown_secs(), TimerMode::Once),
+        },
+        Invulnerable(Timer::from_seconds(cfg.player.respawn_invuln_secs, TimerMode::Once)),
+        StatusEffects::default(),
+        MineCharges::default(),
     ));
 }
 
@@ -276,23 +1361,29 @@ fn spawn_player(mut commands: Commands, start: Option<Res<PlayerStart>>) {
 
 fn player_input(
     time: Res<Time>,
-    input: Res<ButtonInput<KeyCode>>,
-    mut q_player: Query<(&mut Transform, &mut Velocity), With<Player>>,
+    intent: Res<PlayerIntent>,
+    cfg: Res<GameConfig>,
+    mut q_player: Query<(&mut Transform, &mut Velocity, &TankSpeed, Option<&Dash>), With<Player>>,
 ) {
-    let Ok((mut transform, mut vel)) = q_player.get_single_mut() else { return; };
+    let Ok((mut transform, mut vel, speed, dash)) = q_player.single_mut() else { return; };
 
-    let mut dir = Vec2::ZERO;
-    if input.pressed(KeyCode::KeyW) || input.pressed(KeyCode::ArrowUp) { dir.y += 1.0; }
-    if input.pressed(KeyCode::KeyS) || input.pressed(KeyCode::ArrowDown) { dir.y -= 1.0; }
-    if input.pressed(KeyCode::KeyA) || input.pressed(KeyCode::ArrowLeft) { dir.x -= 1.0; }
-    if input.pressed(KeyCode::KeyD) || input.pressed(KeyCode::ArrowRight){ dir.x += 1.0; }
+    // A dash locks steering until it runs out
+    if dash.is_some() {
+        let forward = transform.rotation.mul_vec3(Vec3::X).truncate();
+        **vel = forward * speed.0 * cfg.utility.dash_speed_factor;
+        return;
+    }
 
+    let dir = intent.movement;
     if dir.length_squared() > 0.0 {
         // Force a single cardinal direction (tie-breaker favors horizontal when equal)
         let qdir = quantize_to_cardinal(dir);
         let angle = qdir.y.atan2(qdir.x);
         transform.rotation = Quat::from_rotation_z(angle);
-        **vel = qdir * PLAYER_SPEED;
+        // A stick pushed part of the way (after its curve and sensitivity) drives
+        // slower; keys and the D-pad always push all the way
+        let throttle = dir.length().min(1.0);
+        **vel = qdir * speed.0 * throttle;
     } else {
         **vel = Vec2::ZERO;
     }
@@ -303,75 +1394,192 @@ fn player_input(
 
 fn handle_fire(
     time: Res<Time>,
-    input: Res<ButtonInput<KeyCode>>,
+    intent: Res<PlayerIntent>,
+    cfg: Res<GameConfig>,
     mut cooldown: ResMut<FireCooldown>,
     upgrade: Res<PlayerUpgradeLevel>,
-    q_player: Query<(&Transform, &Size), With<Player>>,
+    profile: Res<Profile>,
+    bounds: Option<Res<LevelBounds>>,
+    fog: Option<Res<FogOfWar>>,
+    q_player: Query<(Entity, &Transform, &Size, &GunStats, &StatusEffects), With<Player>>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    mut fired: EventWriter<ShotFired>,
+    mut pool: ResMut<BulletPool>,
     mut commands: Commands,
 ) {
     cooldown.0.tick(time.delta());
-    if !input.just_pressed(KeyCode::Space) || !cooldown.0.finished() { return; }
+    if !intent.fire || !cooldown.0.finished() { return; }
 
-    let Ok((t, psize)) = q_player.get_single() else { return; };
+    let Ok((player_e, t, psize, gun, effects)) = q_player.single() else { return; };
+    if effects.has(StatusKind::Stunned) { return; }
     let forward = t.rotation.mul_vec3(Vec3::X).truncate();
     if forward.length_squared() == 0.0 { return; }
 
     let spawn_pos = t.translation.truncate() + forward * (psize.0.x * 0.6);
-
-    commands.spawn((
-        Sprite {
+    let aim = if profile.accessibility.aim_assist {
+        // Under fog of war only tanks in sight can be locked on to
+        let in_sight = |pos: Vec2| match (&fog, &bounds) {
+            (Some(fog), Some(bounds)) => bounds.cell_at(pos).is_some_and(|c| fog.visible(c)),
+            _ => true,
+        };
+        let targets = q_enemies.iter().map(|e| e.translation.truncate()).filter(|&p| in_sight(p));
+        assisted_aim(spawn_pos, forward, targets, &cfg.player)
+    } else {
+        forward
+    };
+    // Weapon upgrades turn plain shells into fast ones
+    let shell = match &gun.shell {
+        BulletKind::Standard if upgrade.0 > 0 => BulletKind::Fast,
+        other => other.clone(),
+    };
+
+    pool.fire(
+        &mut commands,
+        Shot {
+            pos: spawn_pos,
+            velocity: aim * gun.bullet_speed * shell.speed_factor(&cfg.bullet),
+            faction: Faction::Player,
+            owner: player_e,
+            kind: shell,
+            size: gun.bullet_size,
             color: Color::WHITE,
-            custom_size: Some(BULLET_SIZE),
-            ..default()
         },
-        Transform::from_xyz(spawn_pos.x, spawn_pos.y, 0.5).with_rotation(t.rotation),
-        Bullet,
-        Faction::Player,
-        Velocity(forward * BULLET_SPEED),
-        Size(BULLET_SIZE),
-    ));
+    );
+    fired.write_default();
+
+    // Set next cooldown based on current upgrade level and the equipped gun
+    cooldown.0 = Timer::from_seconds(fire_cooldown_for(upgrade.0, &cfg.player) * gun.cooldown_factor, TimerMode::Once);
+}
+
+// The direction to the nearest target within the assist cone around the
+// barrel, or straight ahead when there is none
+fn assisted_aim(from: Vec2, forward: Vec2, targets: impl Iterator<Item = Vec2>, cfg: &PlayerConfig) -> Vec2 {
+    let min_cos = cfg.aim_assist_degrees.to_radians().cos();
+    targets
+        .map(|target| target - from)
+        .filter(|to| to.length_squared() <= cfg.aim_assist_range * cfg.aim_assist_range)
+        .filter(|to| to.normalize_or_zero().dot(forward) >= min_cos)
+        .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+        .map_or(forward, |to| to.normalize())
+}
+
+fn player_utility(
+    time: Res<Time>,
+    intent: Res<PlayerIntent>,
+    cfg: Res<GameConfig>,
+    bounds: Option<Res<LevelBounds>>,
+    mut q_player: Query<(Entity, &Transform, &mut UtilitySlot), With<Player>>,
+    mut commands: Commands,
+) {
+    let Ok((e, t, mut slot)) = q_player.single_mut() else { return; };
+    slot.cooldown.tick(time.delta());
+
+    if !intent.utility || !slot.cooldown.finished() { return; }
+
+    let pos = t.translation.truncate();
+    let util = &cfg.utility;
+    match slot.kind {
+        Utility::Dash => {
+            commands.entity(e).insert(Dash(Timer::from_seconds(util.dash_secs, TimerMode::Once)));
+        }
+        Utility::Smoke => {
+            commands.spawn((
+                Sprite {
+                    color: Color::srgba(0.7, 0.7, 0.7, 0.6),
+                    custom_size: Some(Vec2::splat(util.smoke_radius * 2.0)),
+                    ..default()
+                },
+                Transform::from_xyz(pos.x, pos.y, 2.0),
+                SmokeCloud(Timer::from_seconds(util.smoke_secs, TimerMode::Once)),
+            ));
+        }
+        Utility::Mine => mines::spawn_mine(&mut commands, pos, e, Faction::Player, &cfg, bounds.as_deref()),
+    }
+    slot.cooldown.reset();
+}
+
+fn utility_effects(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut q_dash: Query<(Entity, &mut Dash)>,
+    mut q_smoke: Query<(Entity, &mut SmokeCloud, &mut Sprite)>,
+) {
+    for (e, mut dash) in &mut q_dash {
+        if dash.0.tick(time.delta()).finished() {
+            commands.entity(e).remove::<Dash>();
+        }
+    }
 
-    // Set next cooldown based on current upgrade level
-    cooldown.0 = Timer::from_seconds(fire_cooldown_for(upgrade.0), TimerMode::Once);
+    for (e, mut smoke, mut sprite) in &mut q_smoke {
+        smoke.0.tick(time.delta());
+        if smoke.0.finished() {
+            commands.entity(e).despawn();
+        } else {
+            sprite.color.set_alpha(0.6 * smoke.0.fraction_remaining());
+        }
+    }
 }
 
 fn enemy_handle_fire(
     time: Res<Time>,
-    mut q_enemies: Query<(&Transform, &Size, &mut EnemyGun, &EnemyAI), With<Enemy>>,
+    cfg: Res<GameConfig>,
+    mut q_enemies: Query<
+        (Entity, &Transform, &Size, &mut EnemyGun, &EnemyAI, &EnemyKind, &StatusEffects, Option<&EnemyVariant>),
+        With<Enemy>,
+    >,
     q_player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    q_smoke: Query<&Transform, With<SmokeCloud>>,
+    protection: Option<Res<SpawnProtection>>,
+    start: Option<Res<PlayerStart>>,
+    siege: Res<SiegeMap>,
+    mut pool: ResMut<BulletPool>,
     mut commands: Commands,
 ) {
-    let Ok(player_t) = q_player.get_single() else { return; };
+    let Ok(player_t) = q_player.single() else { return; };
     let player_pos = player_t.translation.truncate();
-    let detect2 = ENEMY_DETECT_RADIUS * ENEMY_DETECT_RADIUS;
+    let detect2 = cfg.enemy.detect_radius * cfg.enemy.detect_radius;
+    let protected = protection.is_some()
+        && start.is_some_and(|s| s.0.distance(player_pos) <= cfg.player.spawn_protect_radius);
+    let hidden = protected || in_smoke(player_pos, cfg.utility.smoke_radius, &q_smoke);
+    let bullet_size = cfg.bullet.size();
 
-    for (t, esize, mut gun, ai) in &mut q_enemies {
+    for (e, t, esize, mut gun, ai, kind, effects, variant) in &mut q_enemies {
         gun.0.tick(time.delta());
-        if !gun.0.finished() { continue; }
-
-        // Only fire when actively chasing and within detection range
-        if ai.state != EnemyState::Chasing { continue; }
-
-        let to_player = player_pos - t.translation.truncate();
-        if to_player.length_squared() > detect2 { continue; }
+        if !gun.0.finished() || effects.has(StatusKind::Stunned) { continue; }
 
-        let dir = quantize_to_cardinal(to_player);
+        let dir = match ai.state {
+            // At the player only when actively chasing and within detection range
+            EnemyState::Chasing => {
+                let to_player = player_pos - t.translation.truncate();
+                if hidden || to_player.length_squared() > detect2 { continue; }
+                quantize_to_cardinal(to_player)
+            }
+            // At the base, or the brick in the way
+            EnemyState::Besieging => {
+                let Some(dir) = siege.fire_dir(t.translation.truncate()) else { continue; };
+                dir
+            }
+            EnemyState::Roaming | EnemyState::Retreating => continue,
+        };
         if dir.length_squared() == 0.0 { continue; }
-        let angle = dir.y.atan2(dir.x);
         let spawn_pos = t.translation.truncate() + dir * (esize.0.x * 0.6);
 
-        commands.spawn((
-            Sprite {
-                color: Color::srgb(1.0, 0.85, 0.2),
-                custom_size: Some(BULLET_SIZE),
-                ..default()
+        // A mod's tank may fire a shell of its own (see mods.rs)
+        let shell = variant.and_then(|v| v.shell.as_ref());
+        let shell_kind = shell.map_or_else(|| kind.bullet_kind(), |s| s.kind.clone());
+        let speed = kind.bullet_speed(&cfg) * shell_kind.speed_factor(&cfg.bullet) * shell.map_or(1.0, |s| s.speed);
+        pool.fire(
+            &mut commands,
+            Shot {
+                pos: spawn_pos,
+                velocity: dir * speed,
+                faction: Faction::Enemy,
+                owner: e,
+                kind: shell_kind,
+                size: bullet_size,
+                color: shell.and_then(|s| s.color).unwrap_or(Color::srgb(1.0, 0.85, 0.2)),
             },
-            Transform::from_xyz(spawn_pos.x, spawn_pos.y, 0.5).with_rotation(Quat::from_rotation_z(angle)),
-            Bullet,
-            Faction::Enemy,
-            Velocity(dir * BULLET_SPEED),
-            Size(BULLET_SIZE),
-        ));
+        );
 
         gun.0.reset();
     }
@@ -379,25 +1587,50 @@ fn enemy_handle_fire(
 
 fn enemy_ai_seek_player(
     time: Res<Time>,
-    mut q_enemies: Query<(&Transform, &mut Velocity, &mut EnemyAI), With<Enemy>>,
+    cfg: Res<GameConfig>,
+    grid: Res<SpatialGrid>,
+    mut q_enemies: Query<
+        (
+            Entity,
+            &Transform,
+            &mut Velocity,
+            &mut EnemyAI,
+            &EnemyKind,
+            Option<&EnemyVariant>,
+            Option<&Flank>,
+            Option<&Unstick>,
+        ),
+        With<Enemy>,
+    >,
     q_player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    q_smoke: Query<&Transform, With<SmokeCloud>>,
+    protection: Option<Res<SpawnProtection>>,
+    start: Option<Res<PlayerStart>>,
+    siege: Res<SiegeMap>,
+    squad: Res<Squad>,
+    mut rng: ResMut<GameRng>,
 ) {
-    let Ok(player_t) = q_player.get_single() else { return; };
+    // Centre of the no-go area while spawn protection lasts
+    let keep_out = start.filter(|_| protection.is_some()).map(|s| s.0);
+    let Ok(player_t) = q_player.single() else { return; };
     let player_pos = player_t.translation.truncate();
     let dt = time.delta_secs();
-    let detect2 = ENEMY_DETECT_RADIUS * ENEMY_DETECT_RADIUS;
+    let ai_cfg = &cfg.enemy;
+    let detect2 = cfg.enemy.detect_radius * cfg.enemy.detect_radius;
+    let hidden = in_smoke(player_pos, cfg.utility.smoke_radius, &q_smoke);
 
-    for (t, mut v, mut ai) in &mut q_enemies {
+    for (e, t, mut v, mut ai, kind, variant, flank, unstick) in &mut q_enemies {
         ai.think.tick(time.delta());
+        let top_speed = kind.speed(&cfg) * variant.map_or(1.0, |m| m.speed);
 
         let to_player = player_pos - t.translation.truncate();
         let dist2 = to_player.length_squared();
 
-        // Awareness builds when close, decays when far
-        if dist2 <= detect2 {
-            ai.awareness = (ai.awareness + dt / CHASE_REACTION_SECS).clamp(0.0, 1.0);
+        // Awareness builds when close, decays when far or hidden in smoke
+        if dist2 <= detect2 && !hidden {
+            ai.awareness = (ai.awareness + dt / ai_cfg.chase_reaction_secs).clamp(0.0, 1.0);
         } else {
-            ai.awareness = (ai.awareness - dt / (CHASE_REACTION_SECS * 1.25)).clamp(0.0, 1.0);
+            ai.awareness = (ai.awareness - dt / (ai_cfg.chase_reaction_secs * 1.25)).clamp(0.0, 1.0);
         }
 
         match ai.state {
@@ -406,48 +1639,228 @@ fn enemy_ai_seek_player(
                     ai.state = EnemyState::Chasing;
                 }
                 if ai.think.finished() {
-                    ai.roam_dir = random_cardinal();
-                    ai.think = Timer::from_seconds(thread_rng().gen_range(WANDER_CHANGE_MIN..WANDER_CHANGE_MAX), TimerMode::Once);
+                    ai.roam_dir = random_cardinal(&mut rng);
+                    ai.think = Timer::from_seconds(rng.gen_range(ai_cfg.wander_change_min..ai_cfg.wander_change_max), TimerMode::Once);
+                    // Now and then it makes for the base instead
+                    let besiege = rng.r#gen::<f32>() < ai_cfg.siege_chance;
+                    if ai.state == EnemyState::Roaming && besiege && siege.reachable(t.translation.truncate()) {
+                        ai.state = EnemyState::Besieging;
+                        ai.think = Timer::from_seconds(ai_cfg.siege_secs, TimerMode::Once);
+                    }
                 }
                 let qdir = quantize_to_cardinal(ai.roam_dir);
-                **v = qdir * (ENEMY_SPEED * ROAM_SPEED_FACTOR);
+                **v = qdir * (top_speed * ai_cfg.roam_speed_factor);
+            }
+            EnemyState::Besieging => {
+                let heading = siege.heading(t.translation.truncate());
+                if ai.awareness >= 1.0 {
+                    ai.state = EnemyState::Chasing;
+                } else if ai.think.finished() || heading.is_none() {
+                    // Given up, or the way in is shut (the shovel)
+                    ai.state = EnemyState::Roaming;
+                    ai.roam_dir = random_cardinal(&mut rng);
+                    let wander = rng.gen_range(ai_cfg.wander_change_min..ai_cfg.wander_change_max);
+                    ai.think = Timer::from_seconds(wander, TimerMode::Once);
+                }
+                // Kept as the way it's headed, for the debug overlay
+                let dir = heading.unwrap_or(Vec2::ZERO);
+                if dir != Vec2::ZERO { ai.roam_dir = dir; }
+                **v = dir * top_speed;
             }
             EnemyState::Chasing => {
                 if ai.awareness <= 0.0 {
                     ai.state = EnemyState::Roaming;
-                    ai.roam_dir = random_cardinal();
-                    ai.think = Timer::from_seconds(thread_rng().gen_range(WANDER_CHANGE_MIN..WANDER_CHANGE_MAX), TimerMode::Once);
+                    ai.roam_dir = random_cardinal(&mut rng);
+                    ai.think = Timer::from_seconds(rng.gen_range(ai_cfg.wander_change_min..ai_cfg.wander_change_max), TimerMode::Once);
                 }
-                let dir = quantize_to_cardinal(to_player);
-                **v = if dir.length_squared() > 0.0 { dir * ENEMY_SPEED } else { Vec2::ZERO };
+                // Round to its place beside or behind the player first, if it has one (see squads.rs)
+                let round = flank.and_then(|f| squad.heading(f, &siege, t.translation.truncate()));
+                let dir = round.unwrap_or_else(|| quantize_to_cardinal(to_player));
+                **v = if dir.length_squared() > 0.0 { dir * top_speed } else { Vec2::ZERO };
             }
+            EnemyState::Retreating => {
+                ai.retreat.tick(time.delta());
+                if ai.retreat.finished() {
+                    // Regrouped; re-engage from scratch
+                    ai.state = EnemyState::Roaming;
+                    ai.shock = 0.0;
+                    ai.roam_dir = random_cardinal(&mut rng);
+                }
+                // Fall back to the spawn point and hold there
+                let to_home = ai.home - t.translation.truncate();
+                let dir = if to_home.length() > TILE * 0.25 { quantize_to_cardinal(to_home) } else { Vec2::ZERO };
+                **v = dir * top_speed;
+            }
+        }
+
+        // Turned aside from where it was stuck, for a moment (see unstick.rs)
+        if let Some(unstick) = unstick { **v = unstick.dir * top_speed; }
+
+        // Fan out instead of queueing nose to tail behind whoever is in front
+        let push = separation(e, t.translation.truncate(), &grid, ai_cfg.separation_radius);
+        let speed = v.length();
+        if push != Vec2::ZERO && speed > 0.0 {
+            **v = (v.0 / speed + push * ai_cfg.separation_weight).normalize_or_zero() * speed;
+        }
+
+        // Back out of the protected area, and never drive further into it
+        if let Some(centre) = keep_out {
+            let from_centre = t.translation.truncate() - centre;
+            if from_centre.length() <= cfg.player.spawn_protect_radius {
+                **v = quantize_to_cardinal(from_centre) * top_speed;
+            } else if (from_centre + v.0 * dt).length() <= cfg.player.spawn_protect_radius {
+                **v = Vec2::ZERO;
+            }
+        }
+    }
+}
+
+// Away from every other enemy within `radius`, closer ones pushing harder
+fn separation(me: Entity, pos: Vec2, grid: &SpatialGrid, radius: f32) -> Vec2 {
+    if radius <= 0.0 { return Vec2::ZERO; }
+    grid.neighbors(pos, radius)
+        .filter(|&(other, _)| other != me)
+        .map(|(_, other)| {
+            let away = pos - other;
+            let dist = away.length();
+            if dist < f32::EPSILON { return Vec2::ZERO; }
+            away / dist * (1.0 - dist / radius)
+        })
+        .sum()
+}
+
+// Nearby ally deaths pile up as shock; too much in a short window breaks morale.
+fn enemy_morale(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut deaths: EventReader<EnemyKilled>,
+    mut q_enemies: Query<(&Transform, &mut EnemyAI), With<Enemy>>,
+) {
+    let morale = &cfg.enemy;
+    let decay = time.delta_secs() / morale.morale_recover_secs;
+    let r2 = morale.morale_radius * morale.morale_radius;
+    let deaths: Vec<Vec2> = deaths.read().map(|d| d.pos).collect();
+
+    for (t, mut ai) in &mut q_enemies {
+        let pos = t.translation.truncate();
+        let nearby = deaths.iter().filter(|d| d.distance_squared(pos) <= r2).count();
+        ai.shock = (ai.shock - decay).max(0.0) + nearby as f32;
+
+        if ai.shock >= morale.morale_break && ai.state != EnemyState::Retreating {
+            ai.state = EnemyState::Retreating;
+            ai.awareness = 0.0;
+            ai.retreat = Timer::from_seconds(morale.retreat_secs, TimerMode::Once);
+        }
+    }
+}
+
+fn heavy_handling(cfg: Res<GameConfig>) -> bool {
+    cfg.handling.heavy_tanks
+}
+
+// Input and AI point a tank straight at where it wants to go; heavy handling
+// overrides that with a hull that turns at a limited rate toward its velocity.
+fn heavy_turn(time: Res<Time>, cfg: Res<GameConfig>, mut q: Query<(&mut Transform, &Velocity, &mut Heading)>) {
+    let max_turn = cfg.handling.turn_rate_degs.to_radians() * time.delta_secs();
+    for (mut t, v, mut heading) in &mut q {
+        if v.0 != Vec2::ZERO {
+            let diff = (v.0.to_angle() - heading.0 + PI).rem_euclid(TAU) - PI;
+            heading.0 += diff.clamp(-max_turn, max_turn);
         }
+        t.rotation = Quat::from_rotation_z(heading.0);
     }
 }
 
 fn move_with_collisions(
     time: Res<Time>,
-    mut movers: Query<(Entity, &mut Transform, &Velocity, &Size), Without<Wall>>,
-    walls: Query<(&Transform, &Size), With<Wall>>,
+    mut movers: Query<
+        (
+            Entity,
+            &mut Transform,
+            &Velocity,
+            &Size,
+            Has<Bullet>,
+            Option<&mut Momentum>,
+            Option<&Ground>,
+            Option<&StatusEffects>,
+        ),
+        Without<Blocks>,
+    >,
+    cache: Res<CollisionCache>,
+    cfg: Res<GameConfig>,
 ) {
     let dt = time.delta_secs();
+    let grip = cfg.terrain.ice_grip;
+    let heavy = cfg.handling.heavy_tanks;
+    // Within about 5% of the target speed after accel_secs
+    let accel = cfg.handling.accel_secs.max(0.01);
+
+    for (_e, mut t, v, s, is_bullet, mut momentum, ground, effects) in &mut movers {
+        let mut wanted = v.0 * effects.map_or(1.0, |fx| fx.speed_factor(&cfg));
+        // Tanks ease toward their intended velocity; on normal floor that is instant
+        // unless tanks are heavy
+        let mut step = wanted;
+        if let Some(m) = momentum.as_mut() {
+            if heavy {
+                // Only drive along the hull, slowing right down while it swings round
+                let forward = t.rotation.mul_vec3(Vec3::X).truncate();
+                wanted = forward * forward.dot(wanted).max(0.0);
+            }
+            m.0 = match ground.copied().unwrap_or_default() {
+                Ground::Ice => m.0 + (wanted - m.0) * (1.0 - (-grip * dt).exp()),
+                _ if heavy => m.0 + (wanted - m.0) * (1.0 - (-3.0 * dt / accel).exp()),
+                _ => wanted,
+            };
+            if wanted == Vec2::ZERO && m.0.length() < MIN_SLIDE_SPEED { m.0 = Vec2::ZERO; }
+            step = m.0;
+        }
+        // A belt carries whatever sits on it on top of its own driving
+        if let Some(&Ground::Conveyor(dir)) = ground {
+            step += dir.as_vec2() * cfg.terrain.conveyor_speed;
+        }
 
-    for (_e, mut t, v, s) in &mut movers {
+        // Skip resting entities so their Transform isn't marked changed
+        if step == Vec2::ZERO { continue; }
+        let walls = if is_bullet { &cache.bullets } else { &cache.tanks };
         let mut pos = t.translation.truncate();
         let half = s.0 * 0.5;
 
-        // Move X
-        pos.x += v.x * dt;
-        if overlaps_any(pos, half, &walls) {
-            pos.x -= v.x * dt;
-            pos.x += sweep_axis(pos, half, v.x * dt, Axis::X, &walls);
+        // Move X; hitting a wall kills sliding momentum on that axis
+        let mut blocked_x = false;
+        pos.x += step.x * dt;
+        if overlaps_any(pos, half, walls) {
+            pos.x -= step.x * dt;
+            pos.x += sweep_axis(pos, half, step.x * dt, Axis::X, walls);
+            if let Some(m) = momentum.as_mut() { m.0.x = 0.0; }
+            blocked_x = true;
         }
 
         // Move Y
-        pos.y += v.y * dt;
-        if overlaps_any(pos, half, &walls) {
-            pos.y -= v.y * dt;
-            pos.y += sweep_axis(pos, half, v.y * dt, Axis::Y, &walls);
+        let mut blocked_y = false;
+        pos.y += step.y * dt;
+        if overlaps_any(pos, half, walls) {
+            pos.y -= step.y * dt;
+            pos.y += sweep_axis(pos, half, step.y * dt, Axis::Y, walls);
+            if let Some(m) = momentum.as_mut() { m.0.y = 0.0; }
+            blocked_y = true;
+        }
+
+        // Stopped dead against a wall edge: slide toward the open lane
+        if !is_bullet {
+            let tolerance = cfg.terrain.corner_slide_px;
+            if blocked_x && step.y == 0.0 {
+                let reach = step.x.abs() * dt;
+                if let Some(shift) = corner_lane(pos, half, Vec2::new(step.x.signum() * reach, 0.0), tolerance, walls) {
+                    let delta = shift.y.signum() * reach.min(shift.y.abs());
+                    pos.y += sweep_axis(pos, half, delta, Axis::Y, walls);
+                }
+            } else if blocked_y && step.x == 0.0 {
+                let reach = step.y.abs() * dt;
+                if let Some(shift) = corner_lane(pos, half, Vec2::new(0.0, step.y.signum() * reach), tolerance, walls) {
+                    let delta = shift.x.signum() * reach.min(shift.x.abs());
+                    pos.x += sweep_axis(pos, half, delta, Axis::X, walls);
+                }
+            }
         }
 
         t.translation.x = pos.x;
@@ -457,31 +1870,42 @@ fn move_with_collisions(
 
 fn bullet_wall_cull(
     time: Res<Time>,
-    mut commands: Commands,
-    q_bullets: Query<(Entity, &Transform, &Size, &Velocity), With<Bullet>>,
-    walls: Query<(&Transform, &Size), With<Wall>>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut sparks: EventWriter<ParticleBurst>,
+    mut q_bullets: Query<(Entity, &mut Transform, &Size, &mut Velocity, &mut BulletKind), With<Bullet>>,
+    cache: Res<CollisionCache>,
 ) {
     let dt = time.delta_secs();
+    let walls = &cache.bullets;
 
-    for (e, t, s, v) in &q_bullets {
+    for (e, mut t, s, mut v, mut shell) in &mut q_bullets {
         let pos = t.translation.truncate();
         let half = s.0 * 0.5;
 
-        // If somehow inside a wall, cull immediately.
-        if overlaps_any(pos, half, &walls) {
-            commands.entity(e).despawn();
+        // If somehow inside a wall, cull immediately; there is no clean side to bounce off.
+        if overlaps_any(pos, half, walls) {
+            deaths.write(DeathEvent::new(e, DeathReason::Spent));
+            sparks.write(ParticleBurst { pos, kind: ParticleKind::Spark, count: SPARKS_PER_HIT });
             continue;
         }
 
-        // Predict a tiny forward step; if that would collide, treat it as a hit and despawn.
+        // Predict a tiny forward step; if that would collide, the shell's kind decides what happens.
         let speed = v.length();
-        if speed > 0.0 {
-            let dir = Vec2::new(v.x / speed, v.y / speed);
-            // Match sweep_axis granularity (6 steps) with a small safety margin
-            let epsilon = (speed * dt) / 6.0 + 0.5;
-            let ahead_pos = pos + dir * epsilon;
-            if overlaps_any(ahead_pos, half, &walls) {
-                commands.entity(e).despawn();
+        if speed == 0.0 { continue; }
+        // Match sweep_axis granularity (6 steps) with a small safety margin
+        let step = v.0 / speed * ((speed * dt) / 6.0 + 0.5);
+        if !overlaps_any(pos + step, half, walls) { continue; }
+
+        let blocked_x = overlaps_any(pos + Vec2::new(step.x, 0.0), half, walls);
+        let blocked_y = overlaps_any(pos + Vec2::new(0.0, step.y), half, walls);
+        sparks.write(ParticleBurst { pos: pos + step, kind: ParticleKind::Spark, count: SPARKS_PER_HIT });
+        match shell.on_wall_hit(v.0, blocked_x, blocked_y) {
+            Some(bounced) => {
+                v.0 = bounced;
+                t.rotation = Quat::from_rotation_z(bounced.to_angle());
+            }
+            None => {
+                deaths.write(DeathEvent::new(e, DeathReason::Spent));
             }
         }
     }
@@ -489,125 +1913,535 @@ fn bullet_wall_cull(
 
 fn bullet_hits(
     mut commands: Commands,
-    mut restart: EventWriter<RestartEvent>,
-    q_bullets: Query<(Entity, &Transform, &Size, &Faction), With<Bullet>>,
-    q_enemies: Query<(Entity, &Transform, &Size), With<Enemy>>,
-    q_player: Query<(Entity, &Transform, &Size), With<Player>>,
+    cfg: Res<GameConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut landed: EventWriter<ShotLanded>,
+    mut lives: ResMut<Lives>,
+    mut q_bullets: Query<(Entity, &Transform, &Size, &Faction, Option<&Owner>, &mut BulletKind), With<Bullet>>,
+    mut q_tanks: Query<
+        (Entity, &Transform, &Size, Has<Player>, Has<Invulnerable>, Option<&mut Health>, Option<&mut Armor>),
+        Or<(With<Enemy>, With<Player>)>,
+    >,
 ) {
-    for (b_e, b_t, b_s, faction) in &q_bullets {
+    // Enemies destroyed earlier this frame; they stay on the field until despawn_dead
+    let mut killed = HashSet::new();
+    // Only one life is lost per frame however many shots land
+    let mut player_down = false;
+
+    for (b_e, b_t, b_s, &faction, owner, mut shell) in &mut q_bullets {
+        let credit = owner.map(|o| Credit { owner: o.0, faction });
         let b_pos = b_t.translation.truncate();
         let b_half = b_s.0 * 0.5;
 
-        match *faction {
-            Faction::Player => {
-                for (e_e, e_t, e_s) in &q_enemies {
-                    if aabb_overlap(b_pos, b_half, e_t.translation.truncate(), e_s.0 * 0.5) {
-                        let drop_pos = e_t.translation.truncate();
-                        commands.entity(b_e).despawn();
-                        commands.entity(e_e).despawn();
-                        // Spawn weapon upgrade pickup at enemy position
-                        commands.spawn((
-                            Sprite {
-                                color: Color::srgb(0.2, 0.6, 1.0),
-                                custom_size: Some(Vec2::splat(16.0)),
-                                ..default()
-                            },
-                            Transform::from_xyz(drop_pos.x, drop_pos.y, 0.6),
-                            UpgradePickup,
-                            Size(Vec2::splat(16.0)),
-                        ));
-                        break;
-                    }
-                }
+        for (t_e, t_t, t_s, is_player, invulnerable, health, armor) in &mut q_tanks {
+            if killed.contains(&t_e) || owner.is_some_and(|o| o.0 == t_e) || shell.has_passed(t_e) { continue; }
+            let target = if is_player { Faction::Player } else { Faction::Enemy };
+            // Shells that can't hurt a tank fly straight through it
+            if !faction.hurts(target, &cfg.damage) { continue; }
+            if !aabb_overlap(b_pos, b_half, t_t.translation.truncate(), t_s.0 * 0.5) { continue; }
+
+            // Shots against a shield are simply absorbed, whatever the shell
+            if invulnerable {
+                deaths.write(DeathEvent::new(b_e, DeathReason::Spent));
+                break;
             }
-            Faction::Enemy => {
-                if let Ok((_p_e, p_t, p_s)) = q_player.get_single() {
-                    if aabb_overlap(b_pos, b_half, p_t.translation.truncate(), p_s.0 * 0.5) {
-                        commands.entity(b_e).despawn();
-                        restart.send_default();
-                    }
+
+            if is_player {
+                if let Some(mut armor) = armor.filter(|_| !player_down) {
+                    player_down = damage_player(&mut commands, &cfg, t_t, t_s, &mut armor, &mut lives);
+                    hit_events.write(HitEvent { target: t_e, fatal: player_down, player: true });
+                    if player_down { deaths.write(DeathEvent::credited(t_e, DeathReason::Shot, credit)); }
                 }
+            } else if let Some(mut hp) = health {
+                hp.0 = hp.0.saturating_sub(1);
+                hit_events.write(HitEvent { target: t_e, fatal: hp.0 == 0, player: false });
+                if faction == Faction::Player && shell.first_hit() { landed.write_default(); }
+                if hp.0 == 0 {
+                    killed.insert(t_e);
+                    deaths.write(DeathEvent::credited(t_e, DeathReason::Shot, credit));
+                }
+            }
+
+            if let TankHit::Stop = shell.on_tank_hit(t_e) {
+                deaths.write(DeathEvent::new(b_e, DeathReason::Spent));
+                break;
             }
         }
     }
 }
 
-fn clamp_to_arena(mut q: Query<&mut Transform, Or<(With<Player>, With<Enemy>, With<Bullet>)>>) {
-    let half_w = ARENA_W * 0.5;
-    let half_h = ARENA_H * 0.5;
-    for mut t in &mut q {
-        t.translation.x = t.translation.x.clamp(-half_w, half_w);
-        t.translation.y = t.translation.y.clamp(-half_h, half_h);
+// Shells reaching a brick or the base (with bullet_wall_cull's lookahead)
+// break it when the damage rules let their faction; the cull then removes the
+// shell as for any wall. Losing the base ends the run.
+fn bullet_tile_damage(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut hits: EventWriter<HitEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+    q_bullets: Query<(&Transform, &Size, &Velocity, &Faction), With<Bullet>>,
+    q_tiles: Query<(Entity, &Transform, &Size, Option<&TileKind>, Has<Base>), With<Blocks>>,
+) {
+    let dt = time.delta_secs();
+    let mut broken = HashSet::new();
+
+    for (b_t, b_s, v, faction) in &q_bullets {
+        let breaks_brick = faction.breaks_brick(&cfg.damage);
+        let hits_base = faction.hits_base(&cfg.damage);
+        if !(breaks_brick || hits_base) { continue; }
+
+        let pos = b_t.translation.truncate();
+        let half = b_s.0 * 0.5;
+        let speed = v.length();
+        let ahead = if speed > 0.0 { pos + v.0 / speed * ((speed * dt) / 6.0 + 0.5) } else { pos };
+
+        for (tile_e, tile_t, tile_s, kind, is_base) in &q_tiles {
+            let breakable = (breaks_brick && kind == Some(&TileKind::Brick)) || (hits_base && is_base);
+            if !breakable || broken.contains(&tile_e) { continue; }
+            let tile_pos = tile_t.translation.truncate();
+            let tile_half = tile_s.0 * 0.5;
+            if !(aabb_overlap(pos, half, tile_pos, tile_half) || aabb_overlap(ahead, half, tile_pos, tile_half)) {
+                continue;
+            }
+
+            broken.insert(tile_e);
+            deaths.write(DeathEvent::new(tile_e, DeathReason::Shot));
+            if is_base {
+                hits.write(HitEvent { target: tile_e, fatal: true, player: false });
+                next_state.set(AppState::GameOver);
+            }
+        }
+    }
+}
+
+// One hit on the player from any source: armor soaks it, otherwise a life is
+// lost, the tank blows up and player_respawn_tick takes over. Returns true
+// when the tank was destroyed; the caller then sends its DeathEvent.
+fn damage_player(
+    commands: &mut Commands,
+    cfg: &GameConfig,
+    transform: &Transform,
+    size: &Size,
+    armor: &mut Armor,
+    lives: &mut Lives,
+) -> bool {
+    if armor.0 > 0 {
+        armor.0 -= 1;
+        return false;
+    }
+    lives.0 = lives.0.saturating_sub(1);
+    explosions::spawn_explosion(commands, transform.translation.truncate(), size.0);
+    commands.insert_resource(PlayerRespawn(Timer::from_seconds(cfg.player.respawn_delay_secs, TimerMode::Once)));
+    true
+}
+
+// With no tank on the field there is nothing to steer or fire, so input is
+// simply ignored until the new tank rolls in.
+fn player_respawn_tick(
+    time: Res<Time>,
+    mut commands: Commands,
+    respawn: Option<ResMut<PlayerRespawn>>,
+    lives: Res<Lives>,
+    mut respawned: EventWriter<RespawnEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(mut respawn) = respawn else { return; };
+    if !respawn.0.tick(time.delta()).finished() { return; }
+    commands.remove_resource::<PlayerRespawn>();
+    if lives.0 > 0 {
+        respawned.write_default();
+    } else {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+// Shells touching a barrel (or about to, matching bullet_wall_cull's lookahead)
+// set it off; every tank in the blast catches fire.
+fn barrel_hits(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+    q_barrels: Query<(Entity, &Transform, &Size), With<FlameBarrel>>,
+    q_bullets: Query<(Entity, &Transform, &Size, &Velocity), With<Bullet>>,
+    mut q_tanks: Query<(&Transform, &mut StatusEffects)>,
+) {
+    let dt = time.delta_secs();
+    let r2 = cfg.status.barrel_blast_radius * cfg.status.barrel_blast_radius;
+
+    for (barrel_e, barrel_t, barrel_s) in &q_barrels {
+        let barrel_pos = barrel_t.translation.truncate();
+        let barrel_half = barrel_s.0 * 0.5;
+        let hit = q_bullets.iter().find(|(_, t, s, v)| {
+            let pos = t.translation.truncate();
+            aabb_overlap(pos, s.0 * 0.5, barrel_pos, barrel_half)
+                || aabb_overlap(pos + v.0 * dt, s.0 * 0.5, barrel_pos, barrel_half)
+        });
+        let Some((bullet_e, ..)) = hit else { continue; };
+
+        deaths.write(DeathEvent::new(bullet_e, DeathReason::Spent));
+        deaths.write(DeathEvent::new(barrel_e, DeathReason::Shot));
+        for (t, mut effects) in &mut q_tanks {
+            if t.translation.truncate().distance_squared(barrel_pos) <= r2 {
+                effects.apply(StatusKind::Burning, cfg.status.burn_secs, &cfg);
+            }
+        }
     }
 }
 
 fn pickup_collection(
     mut commands: Commands,
+    mut deaths: EventWriter<DeathEvent>,
+    mut notify: EventWriter<Notify>,
+    cfg: Res<GameConfig>,
     mut upgrade: ResMut<PlayerUpgradeLevel>,
-    mut q_player: Query<(&Transform, &Size, &mut Sprite), With<Player>>,
-    q_pickups: Query<(Entity, &Transform, &Size), With<UpgradePickup>>,
+    mut q_player: Query<(&Transform, &Size, &mut MineCharges), With<Player>>,
+    q_pickups: Query<(Entity, &Transform, &Size, &PowerUp, Option<&PickupVariant>), With<Pickup>>,
+    mut q_ring: Query<(&mut TileKind, &mut Sprite), With<BaseRing>>,
+    mut q_enemies: Query<(&Transform, &mut StatusEffects), With<Enemy>>,
+    theme: Res<LevelTheme>,
+    locale: Res<Locale>,
 ) {
-    let Ok((p_t, p_s, mut p_sprite)) = q_player.get_single_mut() else { return; };
+    let Ok((p_t, p_s, mut mines)) = q_player.single_mut() else { return; };
     let p_pos = p_t.translation.truncate();
     let p_half = p_s.0 * 0.5;
 
-    for (pick_e, pick_t, pick_s) in &q_pickups {
+    for (pick_e, pick_t, pick_s, kind, variant) in &q_pickups {
         let pick_pos = pick_t.translation.truncate();
         let pick_half = pick_s.0 * 0.5;
         if aabb_overlap(p_pos, p_half, pick_pos, pick_half) {
-            // Increase upgrade level up to the maximum stack
-            if upgrade.0 < MAX_UPGRADE_STACK {
-                upgrade.0 += 1;
+            // A mod's pickup can last longer or give more (see mods.rs)
+            let secs = |default: f32| variant.and_then(|v| v.secs).unwrap_or(default);
+            let charges = variant.and_then(|v| v.charges).unwrap_or(cfg.powerups.mine_charges);
+            match kind {
+                PowerUp::WeaponUpgrade => {
+                    // Increase upgrade level up to the maximum stack; the
+                    // player's colour follows it (see accessibility.rs)
+                    if upgrade.0 < cfg.player.max_upgrade_stack {
+                        upgrade.0 += 1;
+                    }
+                }
+                PowerUp::Shovel => {
+                    // Swap the ring to steel; picking up another shovel restarts the timer
+                    for (mut tile, mut sprite) in &mut q_ring {
+                        *tile = TileKind::Steel;
+                        sprite.color = theme.tile(TileKind::Steel);
+                    }
+                    commands.insert_resource(Fortification(Timer::from_seconds(secs(cfg.powerups.fortify_secs), TimerMode::Once)));
+                }
+                PowerUp::Timer => {
+                    // Picking up another timer restarts the freeze
+                    commands.insert_resource(FreezeEnemies(Timer::from_seconds(secs(cfg.powerups.freeze_secs), TimerMode::Once)));
+                }
+                PowerUp::Emp => {
+                    // Stuns only the tanks close to the player, unlike the map-wide timer
+                    let r2 = cfg.powerups.emp_radius * cfg.powerups.emp_radius;
+                    for (e_t, mut effects) in &mut q_enemies {
+                        if e_t.translation.truncate().distance_squared(p_pos) <= r2 {
+                            effects.apply(StatusKind::Stunned, secs(cfg.powerups.emp_stun_secs), &cfg);
+                        }
+                    }
+                }
+                PowerUp::Mines => mines.0 = mines.0.saturating_add(charges),
             }
-            // Change player color based on upgrade level
-            p_sprite.color = match upgrade.0 {
-                0 => Color::srgb(0.2, 0.9, 0.2),
-                1 => Color::srgb(0.2, 0.8, 1.0),
-                2 => Color::srgb(1.0, 0.9, 0.2),
-                _ => Color::srgb(1.0, 0.4, 0.9),
+            let message = match kind {
+                PowerUp::WeaponUpgrade => locale.t("powerup.weapon").to_string(),
+                PowerUp::Shovel => locale.t("powerup.shovel").to_string(),
+                PowerUp::Timer => locale.t("powerup.timer").to_string(),
+                PowerUp::Emp => locale.t("powerup.emp").to_string(),
+                PowerUp::Mines => locale.f("powerup.mines", &[("count", &charges)]),
             };
-            // Remove the pickup
-            commands.entity(pick_e).despawn();
+            let message = variant.and_then(|v| v.message.clone()).unwrap_or(message);
+            let color = variant.and_then(|v| v.color).unwrap_or(powerup_color(*kind));
+            notify.write(Notify::new(message).with_color(color));
+            deaths.write(DeathEvent::new(pick_e, DeathReason::Collected));
         }
     }
 }
 
+fn fortification_tick(
+    time: Res<Time>,
+    mut commands: Commands,
+    fort: Option<ResMut<Fortification>>,
+    theme: Res<LevelTheme>,
+    profile: Res<Profile>,
+    mut q_ring: Query<(&mut TileKind, &mut Sprite), With<BaseRing>>,
+) {
+    let Some(mut fort) = fort else { return; };
+    fort.0.tick(time.delta());
+
+    if fort.0.finished() {
+        for (mut tile, mut sprite) in &mut q_ring {
+            *tile = TileKind::Brick;
+            sprite.color = theme.tile(TileKind::Brick);
+        }
+        commands.remove_resource::<Fortification>();
+        return;
+    }
+
+    // Blink between steel and brick colors as a warning before the revert, or
+    // hold a colour halfway between them with reduced flashes
+    let remaining = fort.0.remaining_secs();
+    if remaining <= FORTIFY_WARN_SECS {
+        let (steel, brick) = (theme.tile(TileKind::Steel), theme.tile(TileKind::Brick));
+        let color = if profile.accessibility.reduced_flashes {
+            Color::from(steel.to_srgba().mix(&brick.to_srgba(), 0.5))
+        } else if (remaining / FORTIFY_BLINK_SECS) as i32 % 2 == 0 {
+            brick
+        } else {
+            steel
+        };
+        for (_, mut sprite) in &mut q_ring {
+            sprite.color = color;
+        }
+    }
+}
+
+fn freeze_enemies_tick(
+    time: Res<Time>,
+    mut commands: Commands,
+    freeze: Option<ResMut<FreezeEnemies>>,
+    profile: Res<Profile>,
+    mut q_enemies: Query<(&mut Velocity, &mut Sprite), With<Enemy>>,
+) {
+    let Some(mut freeze) = freeze else { return; };
+    freeze.0.tick(time.delta());
+
+    if freeze.0.finished() {
+        for (_, mut sprite) in &mut q_enemies {
+            sprite.color.set_alpha(1.0);
+        }
+        commands.remove_resource::<FreezeEnemies>();
+        return;
+    }
+
+    // Blink enemies shortly before they thaw, or just dim them with reduced flashes
+    let remaining = freeze.0.remaining_secs();
+    let blink_off = profile.accessibility.reduced_flashes || (remaining / FREEZE_BLINK_SECS) as i32 % 2 == 0;
+    let dim = remaining <= FREEZE_WARN_SECS && blink_off;
+    for (mut v, mut sprite) in &mut q_enemies {
+        **v = Vec2::ZERO;
+        sprite.color.set_alpha(if dim { 0.35 } else { 1.0 });
+    }
+}
+
 fn enemy_spawner(
     time: Res<Time>,
-    mut timer: ResMut<EnemySpawnTimer>,
+    cfg: Res<GameConfig>,
+    mut queue: ResMut<LevelEnemyQueue>,
     mut spawns: ResMut<SpawnPoints>, // <-- mutate safely
-    q_enemies: Query<Entity, With<Enemy>>,
+    q_enemies: Query<Entity, Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    q_occupants: Query<(&Transform, &Size), Or<(With<Player>, With<Enemy>, With<SpawnTelegraph>)>>,
+    q_player: Query<&Transform, With<Player>>,
+    mut rng: ResMut<GameRng>,
     mut commands: Commands,
 ) {
-    timer.0.tick(time.delta());
-    if !timer.0.finished() { return; }
-    if q_enemies.iter().len() >= ENEMY_CAP { return; }
+    queue.spawn_timer.tick(time.delta());
+    if !queue.spawn_timer.finished() { return; }
+    if !queue.has_next() || queue.held { return; }
+    if q_enemies.iter().len() >= queue.max_on_screen { return; }
     if spawns.points.is_empty() { return; }
 
-    let idx = spawns.next % spawns.points.len();
+    // Usable: nobody on the tile and the player not too close. When none is,
+    // the turn is lost and the next tick tries again.
+    let player = q_player.single().ok().map(|t| t.translation.truncate());
+    let min_dist = cfg.enemy.spawn_min_player_tiles * TILE;
+    let usable = |pos: Vec2| {
+        !spawn_tile_occupied(pos, &q_occupants) && player.is_none_or(|p| p.distance(pos) >= min_dist)
+    };
+    let count = spawns.points.len();
+    let idx = if cfg.enemy.random_spawn_points {
+        let options: Vec<usize> = (0..count).filter(|&i| usable(spawns.points[i])).collect();
+        if options.is_empty() { return; }
+        options[rng.gen_range(0..options.len())]
+    } else {
+        let Some(i) = (0..count).map(|k| (spawns.next + k) % count).find(|&i| usable(spawns.points[i])) else { return; };
+        i
+    };
     let pos = spawns.points[idx];
-    let mut rng = thread_rng();
+    spawns.next = (idx + 1) % count;
+
+    let Some((kind, bonus)) = queue.pop_next(&mut rng) else { return; };
+    spawn_telegraph(&mut commands, pos, kind, bonus, &cfg, cfg.enemy.spawn_telegraph_secs);
+}
 
+// The tank appears once `secs` have passed and the tile is clear
+fn spawn_telegraph(commands: &mut Commands, pos: Vec2, kind: EnemyKind, bonus: bool, cfg: &GameConfig, secs: f32) {
+    let size = cfg.enemy.size();
     commands.spawn((
         Sprite {
-            color: Color::srgb(0.9, 0.2, 0.2),
-            custom_size: Some(ENEMY_SIZE),
+            color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+            custom_size: Some(size),
+            ..default()
+        },
+        Transform::from_xyz(pos.x, pos.y, 0.75),
+        SpawnTelegraph {
+            timer: Timer::from_seconds(secs, TimerMode::Once),
+            kind,
+            bonus,
+        },
+        Size(size),
+    ));
+}
+
+fn spawn_telegraph_tick(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut commands: Commands,
+    mut q_telegraphs: Query<(Entity, &mut Transform, &mut SpawnTelegraph)>,
+    q_tanks: Query<(&Transform, &Size), (Or<(With<Player>, With<Enemy>)>, Without<SpawnTelegraph>)>,
+    mut rng: ResMut<GameRng>,
+) {
+    for (e, mut t, mut telegraph) in &mut q_telegraphs {
+        telegraph.timer.tick(time.delta());
+        let pos = t.translation.truncate();
+
+        // Pulse and spin while waiting
+        let phase = telegraph.timer.elapsed_secs() * 12.0;
+        t.scale = Vec3::splat(0.6 + 0.4 * phase.sin().abs());
+        t.rotation = Quat::from_rotation_z(phase * 0.5);
+
+        // Hold the telegraph until the tile is clear so the tank never appears on top of anyone
+        if !telegraph.timer.finished() || spawn_tile_occupied(pos, &q_tanks) { continue; }
+        commands.entity(e).despawn();
+        let tank = spawn_enemy(&mut commands, &mut rng, pos, telegraph.kind, &cfg);
+        if telegraph.bonus {
+            commands.entity(tank).insert(BonusTank {
+                blink: Timer::from_seconds(BONUS_BLINK_SECS, TimerMode::Repeating),
+                lit: false,
+            });
+        }
+    }
+}
+
+// The tint systems in accessibility.rs colour the tank to match
+fn bonus_blink(time: Res<Time>, mut q: Query<&mut BonusTank>) {
+    for mut bonus in &mut q {
+        if bonus.blink.tick(time.delta()).just_finished() { bonus.lit = !bonus.lit; }
+    }
+}
+
+// The level is over once its queue is exhausted and nothing is left on the field.
+fn level_completion(
+    mut queue: ResMut<LevelEnemyQueue>,
+    mut level: ResMut<CurrentLevel>,
+    mode: Res<GameMode>,
+    respawn: Option<Res<PlayerRespawn>>,
+    q_enemies: Query<(), Or<(With<Enemy>, With<SpawnTelegraph>)>>,
+    mut cleared: EventWriter<StageCleared>,
+) {
+    // Not until a lost tank's respawn has finished; horde waves end in horde.rs
+    if respawn.is_some() || *mode == GameMode::Horde { return; }
+    if queue.cleared || queue.held || queue.boss || queue.has_next() || !q_enemies.is_empty() { return; }
+    queue.cleared = true;
+    level.0 += 1;
+    cleared.write_default();
+}
+
+fn spawn_protection_tick(time: Res<Time>, mut commands: Commands, protection: Option<ResMut<SpawnProtection>>) {
+    let Some(mut protection) = protection else { return; };
+    if protection.0.tick(time.delta()).finished() {
+        commands.remove_resource::<SpawnProtection>();
+    }
+}
+
+fn attach_shield_ring(mut commands: Commands, q: Query<(Entity, &Size), Added<Invulnerable>>) {
+    for (e, size) in &q {
+        let radius = size.0.max_element() * 0.5 + 6.0;
+        commands.entity(e).with_children(|tank| {
+            tank.spawn((Transform::default(), Visibility::default(), ShieldRing)).with_children(|ring| {
+                for i in 0..SHIELD_DOTS {
+                    let offset = Vec2::from_angle(TAU * i as f32 / SHIELD_DOTS as f32) * radius;
+                    ring.spawn((
+                        Sprite { color: SHIELD_COLOR, custom_size: Some(Vec2::splat(4.0)), ..default() },
+                        Transform::from_xyz(offset.x, offset.y, 0.1),
+                    ));
+                }
+            });
+        });
+    }
+}
+
+// Spin the ring while the shield lasts, blink it just before it drops (or,
+// with reduced flashes, draw it smaller), and take it away with the component.
+fn invulnerability_tick(
+    time: Res<Time>,
+    mut commands: Commands,
+    profile: Res<Profile>,
+    mut q: Query<(Entity, &mut Invulnerable, Option<&Children>)>,
+    mut q_rings: Query<(&mut Transform, &mut Visibility), With<ShieldRing>>,
+) {
+    for (e, mut invuln, children) in &mut q {
+        invuln.0.tick(time.delta());
+        let kids = children.map_or(&[][..], |c| &c[..]);
+        if invuln.0.finished() {
+            commands.entity(e).remove::<Invulnerable>();
+            for &kid in kids {
+                if q_rings.contains(kid) { commands.entity(kid).despawn(); }
+            }
+            continue;
+        }
+
+        let remaining = invuln.0.remaining_secs();
+        let warning = remaining <= SHIELD_WARN_SECS;
+        let steady = profile.accessibility.reduced_flashes;
+        let visible = !warning || steady || (remaining / INVULN_BLINK_SECS) as i32 % 2 == 0;
+        let scale = if warning && steady { SHIELD_WARN_SCALE } else { 1.0 };
+        for &kid in kids {
+            let Ok((mut t, mut vis)) = q_rings.get_mut(kid) else { continue; };
+            t.rotate_z(SHIELD_SPIN * time.delta_secs());
+            t.scale = Vec3::splat(scale);
+            *vis = if visible { Visibility::Inherited } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn spawn_enemy(commands: &mut Commands, rng: &mut GameRng, pos: Vec2, kind: EnemyKind, cfg: &GameConfig) -> Entity {
+    let enemy = &cfg.enemy;
+    let size = kind.size(cfg);
+    let mut tank = commands.spawn((
+        Sprite {
+            color: kind.color(),
+            custom_size: Some(size),
             ..default()
         },
         Transform::from_xyz(pos.x, pos.y, 0.75),
         Enemy,
+        kind,
+        Health(kind.hit_points(cfg)),
         Velocity(Vec2::ZERO),
-        Size(ENEMY_SIZE),
-        EnemyGun(Timer::from_seconds(ENEMY_FIRE_SECS, TimerMode::Repeating)),
+        Momentum::default(),
+        Heading::default(),
+        Ground::default(),
+        Size(size),
+        StatusEffects::default(),
+    ));
+    // The boss brings its own brain and guns (see boss.rs)
+    if kind == EnemyKind::Boss { return tank.id(); }
+    if kind == EnemyKind::Saboteur { tank.insert(MineLayer::new(cfg)); }
+    tank.insert((
+        EnemyGun(Timer::from_seconds(enemy.fire_secs, TimerMode::Repeating)),
         EnemyAI {
             state: EnemyState::Roaming,
-            think: Timer::from_seconds(rng.gen_range(WANDER_CHANGE_MIN..WANDER_CHANGE_MAX), TimerMode::Once),
-            roam_dir: random_cardinal(),
+            think: Timer::from_seconds(rng.gen_range(enemy.wander_change_min..enemy.wander_change_max), TimerMode::Once),
+            roam_dir: random_cardinal(rng),
             awareness: 0.0,
+            home: pos,
+            shock: 0.0,
+            retreat: Timer::from_seconds(enemy.retreat_secs, TimerMode::Once),
         },
     ));
+    tank.id()
+}
 
-    spawns.next = (spawns.next + 1) % spawns.points.len();
+fn powerup_color(kind: PowerUp) -> Color {
+    match kind {
+        PowerUp::WeaponUpgrade => Color::srgb(0.2, 0.6, 1.0),
+        PowerUp::Shovel => Color::srgb(0.85, 0.55, 0.2),
+        PowerUp::Timer => Color::srgb(0.85, 0.85, 0.95),
+        PowerUp::Emp => Color::srgb(0.4, 0.8, 1.0),
+        PowerUp::Mines => Color::srgb(0.9, 0.5, 0.1),
+    }
 }
 
 // === Math & Collision Helpers ===
@@ -617,13 +2451,92 @@ fn aabb_overlap(a_pos: Vec2, a_half: Vec2, b_pos: Vec2, b_half: Vec2) -> bool {
         (a_pos.y - b_pos.y).abs() <= (a_half.y + b_half.y)
 }
 
-fn overlaps_any(pos: Vec2, half: Vec2, walls: &Query<(&Transform, &Size), With<Wall>>) -> bool {
-    for (wt, ws) in walls.iter() {
-        if aabb_overlap(pos, half, wt.translation.truncate(), ws.0 * 0.5) {
-            return true;
+fn spawn_tile_occupied<F: bevy::ecs::query::QueryFilter>(pos: Vec2, occupants: &Query<(&Transform, &Size), F>) -> bool {
+    let half = Vec2::splat(TILE * 0.5);
+    occupants.iter().any(|(t, s)| aabb_overlap(pos, half, t.translation.truncate(), s.0 * 0.5))
+}
+
+fn in_smoke(pos: Vec2, radius: f32, smoke: &Query<&Transform, With<SmokeCloud>>) -> bool {
+    let r2 = radius * radius;
+    smoke.iter().any(|t| t.translation.truncate().distance_squared(pos) <= r2)
+}
+
+fn refresh_collision_cache(
+    changed: Query<(), Or<(Changed<Blocks>, Added<Ice>, Added<Tar>, Added<Conveyor>)>>,
+    mut removed_blocks: RemovedComponents<Blocks>,
+    mut removed_ice: RemovedComponents<Ice>,
+    mut removed_tar: RemovedComponents<Tar>,
+    mut removed_conveyors: RemovedComponents<Conveyor>,
+    tiles: Query<(&Transform, &Size, &Blocks)>,
+    ice: Query<(&Transform, &Size), With<Ice>>,
+    tar: Query<(&Transform, &Size), With<Tar>>,
+    conveyors: Query<(&Transform, &Size, &Conveyor)>,
+    mut cache: ResMut<CollisionCache>,
+) {
+    let removed_any = removed_blocks.read().count()
+        + removed_ice.read().count()
+        + removed_tar.read().count()
+        + removed_conveyors.read().count()
+        > 0;
+    if changed.is_empty() && !removed_any { return; }
+
+    cache.tanks.clear();
+    cache.bullets.clear();
+    for (t, s, blocks) in &tiles {
+        let aabb = (t.translation.truncate(), s.0 * 0.5);
+        if blocks.tanks { cache.tanks.push(aabb); }
+        if blocks.bullets { cache.bullets.push(aabb); }
+    }
+    cache.ice = ice.iter().map(|(t, s)| (t.translation.truncate(), s.0 * 0.5)).collect();
+    cache.tar = tar.iter().map(|(t, s)| (t.translation.truncate(), s.0 * 0.5)).collect();
+    cache.conveyors = conveyors.iter().map(|(t, s, belt)| (t.translation.truncate(), s.0 * 0.5, belt.0)).collect();
+}
+
+fn track_ground(cache: Res<CollisionCache>, mut q: Query<(&Transform, &mut Ground)>) {
+    for (t, mut ground) in &mut q {
+        let pos = t.translation.truncate();
+        let belt = cache.conveyors.iter().find(|&&(c, half, _)| aabb_overlap(pos, Vec2::ZERO, c, half));
+        let now = if overlaps_any(pos, Vec2::ZERO, &cache.ice) {
+            Ground::Ice
+        } else if overlaps_any(pos, Vec2::ZERO, &cache.tar) {
+            Ground::Tar
+        } else if let Some(&(.., dir)) = belt {
+            Ground::Conveyor(dir)
+        } else {
+            Ground::Floor
+        };
+        ground.set_if_neq(now);
+    }
+}
+
+fn overlaps_any(pos: Vec2, half: Vec2, walls: &[(Vec2, Vec2)]) -> bool {
+    walls.iter().any(|&(w_pos, w_half)| aabb_overlap(pos, half, w_pos, w_half))
+}
+
+// Nothing in `walls` lies between `from` and `to`, sampled every quarter tile;
+// the end points themselves aren't checked
+fn line_clear(from: Vec2, to: Vec2, walls: &[(Vec2, Vec2)]) -> bool {
+    let steps = (from.distance(to) / (TILE * 0.25)).ceil() as usize;
+    (1..steps).all(|i| !overlaps_any(from.lerp(to, i as f32 / steps as f32), Vec2::ZERO, walls))
+}
+
+// Smallest sideways shift (up to `tolerance`) from which a tank blocked on
+// its frame's `step` could make it, nearest first; None when there is no
+// lane that close.
+fn corner_lane(pos: Vec2, half: Vec2, step: Vec2, tolerance: f32, walls: &[(Vec2, Vec2)]) -> Option<Vec2> {
+    if !overlaps_any(pos + step, half, walls) { return None; }
+    let side = step.normalize_or_zero().perp();
+    let mut offset = 1.0;
+    while offset <= tolerance {
+        for shift in [side * offset, -side * offset] {
+            let p = pos + shift;
+            if !overlaps_any(p, half, walls) && !overlaps_any(p + step, half, walls) {
+                return Some(shift);
+            }
         }
+        offset += 1.0;
     }
-    false
+    None
 }
 
 // Map any vector to a single cardinal unit direction (right, left, up, down) or ZERO if input is zero.
@@ -641,8 +2554,32 @@ fn quantize_to_cardinal(v: Vec2) -> Vec2 {
     }
 }
 
-fn random_cardinal() -> Vec2 {
-    match thread_rng().gen_range(0..4) {
+// Every gameplay roll comes from the one generator, seeded as each run starts
+// (seed_run), so one seed gives the same rolls as long as they're asked for in
+// the same order. Cosmetic rolls (shake, particles, debris) and the attract
+// demo's driver take thread_rng instead: they're made each frame, so sharing
+// the generator would make the run's rolls depend on the frame rate.
+#[derive(Resource, Deref, DerefMut)]
+struct GameRng {
+    // What the run started from, for the event log
+    seed: u64,
+    #[deref]
+    rng: StdRng,
+}
+
+impl GameRng {
+    fn new(seed: u64) -> Self {
+        Self { seed, rng: StdRng::seed_from_u64(seed) }
+    }
+}
+
+// From --seed, or picked at random; a daily run puts its own in after this (see daily.rs)
+fn seed_run(args: Res<LaunchArgs>, mut rng: ResMut<GameRng>) {
+    *rng = GameRng::new(args.seed.unwrap_or_else(|| thread_rng().r#gen()));
+}
+
+fn random_cardinal(rng: &mut GameRng) -> Vec2 {
+    match rng.gen_range(0..4) {
         0 => Vec2::X,
         1 => -Vec2::X,
         2 => Vec2::Y,
@@ -657,7 +2594,7 @@ fn sweep_axis(
     half: Vec2,
     delta: f32,
     axis: Axis,
-    walls: &Query<(&Transform, &Size), With<Wall>>,
+    walls: &[(Vec2, Vec2)],
 ) -> f32 {
     if delta == 0.0 { return 0.0; }
     let steps = 6;
@@ -669,15 +2606,8 @@ fn sweep_axis(
             Axis::X => pos.x += step,
             Axis::Y => pos.y += step,
         }
-        if overlaps_any(pos, half, walls) {
-            match axis {
-                Axis::X => pos.x -= step,
-                Axis::Y => pos.y -= step,
-            }
-            break;
-        } else {
-            moved += step;
-        }
+        if overlaps_any(pos, half, walls) { break; }
+        moved += step;
     }
     moved
 }
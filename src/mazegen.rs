@@ -0,0 +1,190 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+use crate::args::LevelFile;
+
+// Procedural mazes, for random levels: a recursive backtracker carves a
+// perfect maze through the odd cells of a steel-framed grid, a share of the
+// walls left standing is knocked through so there's more than one way round,
+// and the rest become mostly brick with some steel. The base sits in a brick
+// ring in a clearing at the bottom with the player beside it, and the enemy
+// spawns are along the top. Each maze is checked before it's played: every
+// spawn has to reach the player's start, and so the base, over open ground,
+// and one that doesn't is thrown away for the next seed. The same seed always
+// gives the same maze.
+
+// Odd, so the cells line up with the frame; the middle column is a cell too
+const COLS: usize = 19;
+const ROWS: usize = 15;
+// Share of the walls between two corridors knocked through
+const BRAID: f64 = 0.2;
+// Of the walls left, the share that's steel rather than brick
+const STEEL: f64 = 0.2;
+// Of the open tiles, the share that's forest, and then ice
+const FOREST: f64 = 0.06;
+const ICE: f64 = 0.03;
+const BASE: (usize, usize) = (COLS / 2, ROWS - 2);
+const PLAYER: (usize, usize) = (COLS / 2 - 2, ROWS - 2);
+const SPAWNS: [(usize, usize); 3] = [(1, 1), (COLS / 2, 1), (COLS - 2, 1)];
+// The open ground around the base ring, as (col, row) corners
+const CLEARING: ((usize, usize), (usize, usize)) = ((BASE.0 - 2, BASE.1 - 2), (BASE.0 + 2, BASE.1));
+
+const WALL: u8 = b'#';
+const OPEN: u8 = b' ';
+
+pub fn generate(seed: u64) -> LevelFile {
+    let grid = (0..)
+        .map(|n| carve(seed.wrapping_add(n)))
+        .find(|grid| connected(grid))
+        .expect("some seed gives a connected maze");
+    let rows = grid.into_iter().map(|row| String::from_utf8(row).expect("maze glyphs are ASCII")).collect();
+    let name = format!("random maze {seed}");
+    LevelFile {
+        name,
+        rows,
+        zones: Vec::new(),
+        patrols: Vec::new(),
+        script: Vec::new(),
+        theme: None,
+        turrets: Vec::new(),
+        waves: None,
+        code: None,
+    }
+}
+
+fn carve(seed: u64) -> Vec<Vec<u8>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut grid = vec![vec![WALL; COLS]; ROWS];
+
+    // Depth first from the top-left cell, two tiles at a step, opening the wall between
+    grid[1][1] = OPEN;
+    let mut stack = vec![(1, 1)];
+    while let Some(&(c, r)) = stack.last() {
+        let next: Vec<(usize, usize)> = [(0, -2), (2, 0), (0, 2), (-2, 0)]
+            .into_iter()
+            .map(|(dc, dr)| (c as i32 + dc, r as i32 + dr))
+            .filter(|&(nc, nr)| nc > 0 && nr > 0 && nc < COLS as i32 - 1 && nr < ROWS as i32 - 1)
+            .map(|(nc, nr)| (nc as usize, nr as usize))
+            .filter(|&(nc, nr)| grid[nr][nc] == WALL)
+            .collect();
+        if next.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let (nc, nr) = next[rng.gen_range(0..next.len())];
+        grid[(r + nr) / 2][(c + nc) / 2] = OPEN;
+        grid[nr][nc] = OPEN;
+        stack.push((nc, nr));
+    }
+
+    // A wall between two cells has one odd coordinate and one even
+    for (r, row) in grid.iter_mut().enumerate().take(ROWS - 1).skip(1) {
+        for (c, tile) in row.iter_mut().enumerate().take(COLS - 1).skip(1) {
+            if (r + c) % 2 == 1 && *tile == WALL && rng.gen_bool(BRAID) { *tile = OPEN; }
+        }
+    }
+    let ((c0, r0), (c1, r1)) = CLEARING;
+    for row in &mut grid[r0..=r1] {
+        row[c0..=c1].fill(OPEN);
+    }
+
+    // Dress it, leaving the frame steel and the spots filled in below bare
+    let reserved = |c: usize, r: usize| {
+        ((c0..=c1).contains(&c) && (r0..=r1).contains(&r)) || SPAWNS.contains(&(c, r))
+    };
+    for (r, row) in grid.iter_mut().enumerate().take(ROWS - 1).skip(1) {
+        for (c, tile) in row.iter_mut().enumerate().take(COLS - 1).skip(1) {
+            if *tile == WALL {
+                if !rng.gen_bool(STEEL) { *tile = b'b'; }
+            } else if !reserved(c, r) {
+                let roll = rng.r#gen::<f64>();
+                if roll < FOREST {
+                    *tile = b'F';
+                } else if roll < FOREST + ICE {
+                    *tile = b'=';
+                }
+            }
+        }
+    }
+
+    let (bc, br) = BASE;
+    for (c, r) in [(bc - 1, br - 1), (bc, br - 1), (bc + 1, br - 1), (bc - 1, br), (bc + 1, br)] {
+        grid[r][c] = b'b';
+    }
+    grid[br][bc] = b'E';
+    grid[PLAYER.1][PLAYER.0] = b'P';
+    for (c, r) in SPAWNS {
+        grid[r][c] = b'S';
+    }
+    grid
+}
+
+// Every spawn reached from the player's start over ground a tank can cross
+// without shooting; the start is beside the base's ring
+fn connected(grid: &[Vec<u8>]) -> bool {
+    let open = |c: usize, r: usize| matches!(grid[r][c], OPEN | b'F' | b'=' | b'P' | b'S');
+    let mut seen = vec![vec![false; COLS]; ROWS];
+    let mut queue = VecDeque::from([PLAYER]);
+    seen[PLAYER.1][PLAYER.0] = true;
+    while let Some((c, r)) = queue.pop_front() {
+        for (nc, nr) in [(c, r - 1), (c + 1, r), (c, r + 1), (c - 1, r)] {
+            // The frame is steel, so nothing open is on the edge to step off
+            if !seen[nr][nc] && open(nc, nr) {
+                seen[nr][nc] = true;
+                queue.push_back((nc, nr));
+            }
+        }
+    }
+    SPAWNS.iter().all(|&(c, r)| seen[r][c])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level_check::problems;
+
+    // Cells reached from the player's start without shooting, found afresh
+    // rather than with connected(), which generate() already trusts
+    fn reached(rows: &[String]) -> Vec<(usize, usize)> {
+        let grid: Vec<&[u8]> = rows.iter().map(|r| r.as_bytes()).collect();
+        let mut seen = vec![PLAYER];
+        let mut i = 0;
+        while let Some(&(c, r)) = seen.get(i) {
+            i += 1;
+            let around = [(c.wrapping_sub(1), r), (c + 1, r), (c, r.wrapping_sub(1)), (c, r + 1)];
+            for (nc, nr) in around {
+                let Some(&glyph) = grid.get(nr).and_then(|row| row.get(nc)) else { continue; };
+                if !b"#b~E".contains(&glyph) && !seen.contains(&(nc, nr)) { seen.push((nc, nr)); }
+            }
+        }
+        seen
+    }
+
+    #[test]
+    fn every_spawn_reaches_the_player_and_the_base() {
+        for seed in 0..200 {
+            let level = generate(seed);
+            let reached = reached(&level.rows);
+            assert!(SPAWNS.iter().all(|s| reached.contains(s)), "seed {seed}:\n{}", level.rows.join("\n"));
+            // Off the base's ring, though it's shot through to get in
+            let (bc, br) = BASE;
+            assert!(reached.iter().any(|&(c, r)| c.abs_diff(bc) <= 1 && r + 2 == br), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn generated_mazes_pass_the_level_check() {
+        for seed in 0..200 {
+            let level = generate(seed);
+            assert_eq!(problems(&level.rows, true), vec![], "seed {seed}:\n{}", level.rows.join("\n"));
+            assert_eq!(level.rows.len(), ROWS);
+        }
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_maze() {
+        assert_eq!(generate(7).rows, generate(7).rows);
+        assert_ne!(generate(7).rows, generate(8).rows);
+    }
+}
@@ -0,0 +1,220 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::config::GameConfig;
+use crate::death::{Credit, DeathEvent, DeathReason};
+use crate::feedback::{CameraShake, HitEvent};
+use crate::intent::PlayerIntent;
+use crate::recap::StageRecap;
+use crate::status::{StatusEffects, StatusKind};
+use crate::{
+    AppState, Armor, Base, Blocks, Enemy, Faction, FreezeEnemies, GameSet, Health, Invulnerable, LevelBounds,
+    Lives, Player, Size, TileKind, aabb_overlap, damage_player,
+};
+
+// Mines for both sides: the player lays them from power-up charges (or the
+// mine utility), saboteur tanks drop them as they roam. A mine sits on the
+// tile it was dropped on, arms after a short fuse and goes off when a tank it
+// may hurt (by the damage matrix) drives over it. The blast hits every tank,
+// brick and base in its radius that the mine's faction is allowed to damage.
+
+const PLAYER_MINE_COLOR: Color = Color::srgb(0.9, 0.5, 0.1);
+const ENEMY_MINE_COLOR: Color = Color::srgb(0.6, 0.15, 0.6);
+const BLAST_COLOR: Color = Color::srgb(1.0, 0.6, 0.2);
+const BLAST_SECS: f32 = 0.3;
+
+#[derive(Component)]
+pub struct Mine {
+    arm: Timer,
+    // Who laid it, credited with what it destroys
+    credit: Credit,
+}
+
+// Mines the player can still lay, topped up by the mines power-up
+#[derive(Component, Default)]
+pub struct MineCharges(pub u8);
+
+// Saboteurs drop a mine each time this runs out
+#[derive(Component)]
+pub struct MineLayer(Timer);
+
+impl MineLayer {
+    pub fn new(cfg: &GameConfig) -> Self {
+        Self(Timer::from_seconds(cfg.enemy.saboteur_mine_secs, TimerMode::Repeating))
+    }
+}
+
+// Fading flash left by an explosion
+#[derive(Component)]
+struct MineBlast(Timer);
+
+pub struct MinesPlugin;
+
+impl Plugin for MinesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                lay_player_mine.in_set(GameSet::Input),
+                saboteur_mines.in_set(GameSet::Ai).run_if(not(resource_exists::<FreezeEnemies>)),
+                mine_triggers.in_set(GameSet::Damage),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        )
+        .add_systems(Update, fade_blasts);
+    }
+}
+
+pub fn spawn_mine(
+    commands: &mut Commands,
+    pos: Vec2,
+    owner: Entity,
+    faction: Faction,
+    cfg: &GameConfig,
+    bounds: Option<&LevelBounds>,
+) {
+    let pos = bounds.and_then(|b| b.cell_center(pos)).unwrap_or(pos);
+    let size = cfg.utility.mine_size();
+    let color = match faction {
+        Faction::Player => PLAYER_MINE_COLOR,
+        Faction::Enemy => ENEMY_MINE_COLOR,
+    };
+    commands.spawn((
+        Sprite { color: color.with_alpha(0.5), custom_size: Some(size), ..default() },
+        Transform::from_xyz(pos.x, pos.y, 0.4),
+        Mine {
+            arm: Timer::from_seconds(cfg.utility.mine_arm_secs, TimerMode::Once),
+            credit: Credit { owner, faction },
+        },
+        Size(size),
+    ));
+}
+
+fn lay_player_mine(
+    mut commands: Commands,
+    intent: Res<PlayerIntent>,
+    cfg: Res<GameConfig>,
+    bounds: Option<Res<LevelBounds>>,
+    mut q_player: Query<(Entity, &Transform, &mut MineCharges, &StatusEffects), With<Player>>,
+) {
+    if !intent.lay_mine { return; }
+    let Ok((e, t, mut charges, effects)) = q_player.single_mut() else { return; };
+    if charges.0 == 0 || effects.has(StatusKind::Stunned) { return; }
+    charges.0 -= 1;
+    spawn_mine(&mut commands, t.translation.truncate(), e, Faction::Player, &cfg, bounds.as_deref());
+}
+
+fn saboteur_mines(
+    time: Res<Time>,
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    bounds: Option<Res<LevelBounds>>,
+    mut q_layers: Query<(Entity, &Transform, &mut MineLayer, &StatusEffects)>,
+    q_mines: Query<&Mine>,
+) {
+    let mut enemy_mines = q_mines.iter().filter(|m| m.credit.faction == Faction::Enemy).count();
+    for (e, t, mut layer, effects) in &mut q_layers {
+        if !layer.0.tick(time.delta()).just_finished() || effects.has(StatusKind::Stunned) { continue; }
+        if enemy_mines >= cfg.enemy.max_enemy_mines { continue; }
+        enemy_mines += 1;
+        spawn_mine(&mut commands, t.translation.truncate(), e, Faction::Enemy, &cfg, bounds.as_deref());
+    }
+}
+
+fn mine_triggers(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut commands: Commands,
+    mut shake: ResMut<CameraShake>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut hit_events: EventWriter<HitEvent>,
+    mut lives: ResMut<Lives>,
+    mut next_state: ResMut<NextState<AppState>>,
+    mut q_mines: Query<(Entity, &Transform, &Size, &mut Mine, &mut Sprite)>,
+    mut q_tanks: Query<
+        (Entity, &Transform, &Size, Has<Player>, Has<Invulnerable>, Option<&mut Health>, Option<&mut Armor>),
+        Or<(With<Enemy>, With<Player>)>,
+    >,
+    q_tiles: Query<(Entity, &Transform, Option<&TileKind>, Has<Base>), With<Blocks>>,
+) {
+    let radius = cfg.utility.mine_blast_radius;
+    let r2 = radius * radius;
+    let rules = &cfg.damage;
+    let mut killed = HashSet::new();
+    let mut broken = HashSet::new();
+    let mut player_down = false;
+
+    for (m_e, m_t, m_s, mut mine, mut sprite) in &mut q_mines {
+        if !mine.arm.tick(time.delta()).finished() { continue; }
+        if mine.arm.just_finished() { sprite.color.set_alpha(1.0); }
+        let m_pos = m_t.translation.truncate();
+        let credit = mine.credit;
+        let faction = credit.faction;
+
+        let tripped = q_tanks.iter().any(|(t_e, t_t, t_s, is_player, ..)| {
+            let target = if is_player { Faction::Player } else { Faction::Enemy };
+            !killed.contains(&t_e)
+                && faction.hurts(target, rules)
+                && aabb_overlap(m_pos, m_s.0 * 0.5, t_t.translation.truncate(), t_s.0 * 0.5)
+        });
+        if !tripped { continue; }
+
+        deaths.write(DeathEvent::new(m_e, DeathReason::Spent));
+        shake.add(0.35);
+        commands.spawn((
+            Sprite { color: BLAST_COLOR.with_alpha(0.7), custom_size: Some(Vec2::splat(radius * 2.0)), ..default() },
+            Transform::from_xyz(m_pos.x, m_pos.y, 2.5),
+            MineBlast(Timer::from_seconds(BLAST_SECS, TimerMode::Once)),
+        ));
+
+        for (t_e, t_t, t_s, is_player, invulnerable, health, armor) in &mut q_tanks {
+            let target = if is_player { Faction::Player } else { Faction::Enemy };
+            if killed.contains(&t_e) || invulnerable || !faction.hurts(target, rules) { continue; }
+            let t_pos = t_t.translation.truncate();
+            if t_pos.distance_squared(m_pos) > r2 { continue; }
+
+            if is_player {
+                let Some(mut armor) = armor else { continue; };
+                for _ in 0..cfg.utility.mine_damage {
+                    if player_down { break; }
+                    player_down = damage_player(&mut commands, &cfg, t_t, t_s, &mut armor, &mut lives);
+                    hit_events.write(HitEvent { target: t_e, fatal: player_down, player: true });
+                    if player_down { deaths.write(DeathEvent::credited(t_e, DeathReason::Blast, Some(credit))); }
+                }
+            } else if let Some(mut hp) = health {
+                hp.0 = hp.0.saturating_sub(cfg.utility.mine_damage);
+                hit_events.write(HitEvent { target: t_e, fatal: hp.0 == 0, player: false });
+                if hp.0 == 0 {
+                    killed.insert(t_e);
+                    deaths.write(DeathEvent::credited(t_e, DeathReason::Blast, Some(credit)));
+                }
+            }
+        }
+
+        let breaks_brick = faction.breaks_brick(rules);
+        let hits_base = faction.hits_base(rules);
+        for (tile_e, tile_t, tile_kind, is_base) in &q_tiles {
+            let breakable = (breaks_brick && tile_kind == Some(&TileKind::Brick)) || (hits_base && is_base);
+            if !breakable || broken.contains(&tile_e) { continue; }
+            if tile_t.translation.truncate().distance_squared(m_pos) > r2 { continue; }
+            broken.insert(tile_e);
+            deaths.write(DeathEvent::new(tile_e, DeathReason::Blast));
+            if is_base {
+                hit_events.write(HitEvent { target: tile_e, fatal: true, player: false });
+                next_state.set(AppState::GameOver);
+            }
+        }
+    }
+}
+
+// Not tied to a state so a blast on the last frame of a run still fades out
+fn fade_blasts(time: Res<Time>, mut commands: Commands, mut q: Query<(Entity, &mut MineBlast, &mut Sprite)>) {
+    for (e, mut blast, mut sprite) in &mut q {
+        if blast.0.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+        } else {
+            sprite.color.set_alpha(0.7 * blast.0.fraction_remaining());
+        }
+    }
+}
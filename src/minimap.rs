@@ -0,0 +1,145 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::image::ImageSampler;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::fog::FogOfWar;
+use crate::profile::Profile;
+use crate::themes::LevelTheme;
+use crate::{AppState, Base, Blocks, Enemy, EnemyKind, Forest, LevelBounds, Player, TileKind, Water};
+
+// Corner overview of the level: one texel per maze tile, redrawn a few times a
+// second from world positions and scaled up with nearest filtering. Sized from
+// the level's bounds, so the texture is (re)made whenever a level is built.
+
+const REFRESH_SECS: f32 = 0.1;
+const PIXELS_PER_TILE: f32 = 6.0;
+const MARGIN: f32 = 8.0;
+
+const FLOOR: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+const WATER: Color = Color::srgb(0.15, 0.35, 0.8);
+const FOREST: Color = Color::srgb(0.1, 0.45, 0.15);
+const BASE: Color = Color::srgb(0.95, 0.8, 0.2);
+
+#[derive(Resource)]
+struct Minimap {
+    image: Handle<Image>,
+    refresh: Timer,
+}
+
+#[derive(Component)]
+struct MinimapNode;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), despawn_minimap).add_systems(
+            Update,
+            (spawn_minimap.run_if(resource_exists_and_changed::<LevelBounds>), redraw_minimap)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+fn spawn_minimap(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    bounds: Res<LevelBounds>,
+    q_old: Query<Entity, With<MinimapNode>>,
+) {
+    for e in &q_old { commands.entity(e).despawn(); }
+    let size = UVec2::new(bounds.cols as u32, bounds.rows as u32);
+    let mut image = Image::new_fill(
+        Extent3d { width: size.x, height: size.y, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::nearest();
+    let handle = images.add(image);
+
+    commands.spawn((
+        ImageNode::new(handle.clone()),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(MARGIN),
+            right: Val::Px(MARGIN),
+            width: Val::Px(size.x as f32 * PIXELS_PER_TILE),
+            height: Val::Px(size.y as f32 * PIXELS_PER_TILE),
+            ..default()
+        },
+        MinimapNode,
+    ));
+    commands.insert_resource(Minimap {
+        image: handle,
+        refresh: Timer::from_seconds(REFRESH_SECS, TimerMode::Repeating),
+    });
+}
+
+fn despawn_minimap(mut commands: Commands, q: Query<Entity, With<MinimapNode>>) {
+    for e in &q { commands.entity(e).despawn(); }
+    commands.remove_resource::<Minimap>();
+}
+
+fn redraw_minimap(
+    time: Res<Time<Real>>,
+    minimap: Option<ResMut<Minimap>>,
+    bounds: Option<Res<LevelBounds>>,
+    fog: Option<Res<FogOfWar>>,
+    theme: Res<LevelTheme>,
+    profile: Res<Profile>,
+    mut images: ResMut<Assets<Image>>,
+    q_tiles: Query<(&Transform, Option<&TileKind>, Has<Base>, Has<Water>), With<Blocks>>,
+    q_forest: Query<&Transform, With<Forest>>,
+    q_enemies: Query<&Transform, With<Enemy>>,
+    q_player: Query<&Transform, With<Player>>,
+) {
+    let (Some(mut minimap), Some(bounds)) = (minimap, bounds) else { return; };
+    if !minimap.refresh.tick(time.delta()).just_finished() { return; }
+    let Some(image) = images.get_mut(&minimap.image) else { return; };
+
+    for y in 0..bounds.rows as u32 {
+        for x in 0..bounds.cols as u32 {
+            let _ = image.set_color_at(x, y, FLOOR);
+        }
+    }
+
+    // Under fog of war, only what the player has seen is mapped
+    let mut paint = |pos: Vec2, color: Color| {
+        if let Some(cell) = bounds.cell_at(pos).filter(|&c| fog.as_ref().is_none_or(|f| f.seen(c))) {
+            let _ = image.set_color_at(cell.x, cell.y, color);
+        }
+    };
+
+    for (t, kind, is_base, is_water) in &q_tiles {
+        let color = if is_base {
+            BASE
+        } else if is_water {
+            WATER
+        } else {
+            theme.tile(kind.copied().unwrap_or(TileKind::Steel))
+        };
+        paint(t.translation.truncate(), color);
+    }
+
+    let forest: Vec<UVec2> = q_forest.iter().filter_map(|t| bounds.cell_at(t.translation.truncate())).collect();
+    for t in &q_forest {
+        paint(t.translation.truncate(), FOREST);
+    }
+
+    // Tanks in the trees or the fog stay hidden here too, in the palette's team colours
+    let palette = profile.accessibility.palette;
+    for t in &q_enemies {
+        let pos = t.translation.truncate();
+        let shown = |c: UVec2| !forest.contains(&c) && fog.as_ref().is_none_or(|f| f.visible(c));
+        if bounds.cell_at(pos).is_some_and(shown) {
+            paint(pos, palette.enemy(EnemyKind::Basic));
+        }
+    }
+    for t in &q_player {
+        paint(t.translation.truncate(), palette.player(0));
+    }
+}
@@ -0,0 +1,319 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::bullets::BulletKind;
+use crate::{AppState, EnemyGun, EnemyKind, GameRng, Health, PowerUp};
+
+// Mods: RON files in assets/mods/, read at startup, that add content to the
+// game without touching its code. Each piece is built on one of the game's
+// own, so it keeps that one's behaviour and changes its numbers and looks:
+//
+//   - enemies: a tank kind standing in for a share of a built-in kind's
+//     tanks, with its own speed, armour, reload time, colour and shell
+//   - shells: a shell kind (standard, fast, piercing or bouncing) with its own
+//     speed and colour, fired by the mod's enemies
+//   - pickups: a power-up standing in for a share of a built-in one's drops,
+//     with its own colour, message and duration or charges
+//   - tiles: a new maze glyph that plays as a built-in surface in a colour
+//     of its own
+//
+// A file that doesn't parse, or a piece that doesn't make sense, is left out
+// with a warning in the log. Files are read in name order; a later tile
+// glyph doesn't replace an earlier one.
+
+const MODS_DIR: &str = "mods";
+// The surfaces a mod's tile can be built on; the rest are things, not tiles
+const TILE_BASES: &str = "#bF~=:<>^v";
+// Every glyph build_maze already has a meaning for
+const GAME_GLYPHS: &str = "#bF~=:<>^vPSEoT1234 ";
+
+type Rgb = (f32, f32, f32);
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ModFile {
+    name: String,
+    enemies: Vec<EnemyDef>,
+    shells: Vec<ShellDef>,
+    pickups: Vec<PickupDef>,
+    tiles: Vec<TileDef>,
+}
+
+#[derive(Deserialize)]
+struct EnemyDef {
+    id: String,
+    // The kind it drives and scores as; not the boss, which has a brain of its own
+    base: EnemyKind,
+    // Of the base kind's tanks, the share that roll out as this one
+    chance: f32,
+    // Times the base kind's
+    #[serde(default)]
+    speed: Option<f32>,
+    #[serde(default)]
+    hit_points: Option<u8>,
+    #[serde(default)]
+    fire_secs: Option<f32>,
+    #[serde(default)]
+    color: Option<Rgb>,
+    // The id of a shell in the same file
+    #[serde(default)]
+    shell: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ShellDef {
+    id: String,
+    base: ShellBase,
+    // Times the base kind's
+    #[serde(default)]
+    speed: Option<f32>,
+    #[serde(default)]
+    color: Option<Rgb>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum ShellBase {
+    Standard,
+    Fast,
+    // Tanks it flies through
+    Piercing(u8),
+    // Walls it bounces off
+    Bouncing(u8),
+}
+
+impl ShellBase {
+    fn kind(self) -> BulletKind {
+        match self {
+            ShellBase::Standard => BulletKind::Standard,
+            ShellBase::Fast => BulletKind::Fast,
+            ShellBase::Piercing(left) => BulletKind::Piercing { left, passed: Vec::new() },
+            ShellBase::Bouncing(left) => BulletKind::Bouncing { left },
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct PickupDef {
+    id: String,
+    base: PowerUp,
+    // Of the base power-up's drops, the share that are this one
+    chance: f32,
+    #[serde(default)]
+    color: Option<Rgb>,
+    // Shown on pickup in place of the base one's message
+    #[serde(default)]
+    message: Option<String>,
+    // How long a shovel, timer or EMP lasts, in place of config.ron's
+    #[serde(default)]
+    secs: Option<f32>,
+    // Mines a mine pickup gives, in place of config.ron's
+    #[serde(default)]
+    charges: Option<u8>,
+}
+
+#[derive(Deserialize)]
+struct TileDef {
+    glyph: char,
+    base: char,
+    color: Rgb,
+}
+
+// On a tank rolled out as a mod's enemy
+#[derive(Component, Clone)]
+pub struct EnemyVariant {
+    base: EnemyKind,
+    chance: f32,
+    pub speed: f32,
+    pub hit_points: Option<u8>,
+    fire_secs: Option<f32>,
+    pub color: Option<Color>,
+    pub shell: Option<ModShell>,
+}
+
+#[derive(Clone)]
+pub struct ModShell {
+    pub kind: BulletKind,
+    pub speed: f32,
+    pub color: Option<Color>,
+}
+
+// On a power-up dropped as a mod's pickup
+#[derive(Component, Clone)]
+pub struct PickupVariant {
+    base: PowerUp,
+    chance: f32,
+    pub color: Option<Color>,
+    pub message: Option<String>,
+    pub secs: Option<f32>,
+    pub charges: Option<u8>,
+}
+
+pub struct TileVariant {
+    glyph: char,
+    pub base: char,
+    pub color: Color,
+}
+
+#[derive(Resource, Default)]
+pub struct ContentRegistry {
+    enemies: Vec<EnemyVariant>,
+    pickups: Vec<PickupVariant>,
+    tiles: Vec<TileVariant>,
+}
+
+impl ContentRegistry {
+    fn load() -> Self {
+        let mut registry = Self::default();
+        let Ok(entries) = fs::read_dir(mods_dir()) else { return registry; };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ron")))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let file = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| ron::from_str::<ModFile>(&text).map_err(|e| e.to_string()));
+            match file {
+                Ok(file) => registry.add(&path.display().to_string(), file),
+                Err(e) => warn!("skipping mod {}: {e}", path.display()),
+            }
+        }
+        registry
+    }
+
+    fn add(&mut self, path: &str, file: ModFile) {
+        let name = if file.name.is_empty() { path } else { file.name.as_str() };
+        let (enemies, pickups, tiles) = (self.enemies.len(), self.pickups.len(), self.tiles.len());
+        for def in file.enemies {
+            if def.base == EnemyKind::Boss {
+                warn!("{name}: enemy {} can't be built on the boss", def.id);
+                continue;
+            }
+            let shell = match &def.shell {
+                Some(id) => match file.shells.iter().find(|s| s.id == *id) {
+                    Some(shell) => Some(ModShell {
+                        kind: shell.base.kind(),
+                        speed: shell.speed.unwrap_or(1.0).max(0.1),
+                        color: shell.color.map(|(r, g, b)| Color::srgb(r, g, b)),
+                    }),
+                    None => {
+                        warn!("{name}: enemy {} fires shell {id}, which isn't in the file", def.id);
+                        continue;
+                    }
+                },
+                None => None,
+            };
+            self.enemies.push(EnemyVariant {
+                base: def.base,
+                chance: def.chance.clamp(0.0, 1.0),
+                speed: def.speed.unwrap_or(1.0).max(0.1),
+                hit_points: def.hit_points.map(|hp| hp.max(1)),
+                fire_secs: def.fire_secs.map(|secs| secs.max(0.1)),
+                color: def.color.map(|(r, g, b)| Color::srgb(r, g, b)),
+                shell,
+            });
+        }
+        for def in file.pickups {
+            if def.secs.is_some_and(|secs| secs <= 0.0) {
+                warn!("{name}: pickup {} doesn't last any time", def.id);
+                continue;
+            }
+            self.pickups.push(PickupVariant {
+                base: def.base,
+                chance: def.chance.clamp(0.0, 1.0),
+                color: def.color.map(|(r, g, b)| Color::srgb(r, g, b)),
+                message: def.message,
+                secs: def.secs,
+                charges: def.charges,
+            });
+        }
+        for def in file.tiles {
+            let glyph = def.glyph;
+            if GAME_GLYPHS.contains(glyph) || !glyph.is_ascii_graphic() || self.tile(glyph).is_some() {
+                warn!("{name}: tile '{glyph}' is already a maze glyph, or isn't one that can be");
+            } else if !TILE_BASES.contains(def.base) {
+                warn!("{name}: tile '{glyph}' can only be built on one of \"{TILE_BASES}\"");
+            } else {
+                let (r, g, b) = def.color;
+                self.tiles.push(TileVariant { glyph, base: def.base, color: Color::srgb(r, g, b) });
+            }
+        }
+        info!(
+            "mod {name}: {} enemies, {} pickups, {} tiles",
+            self.enemies.len() - enemies,
+            self.pickups.len() - pickups,
+            self.tiles.len() - tiles
+        );
+    }
+
+    // Which of the mods' enemies, if any, a tank of this kind rolls out as
+    fn roll_enemy(&self, rng: &mut GameRng, kind: EnemyKind) -> Option<&EnemyVariant> {
+        let roll = rng.r#gen::<f32>();
+        pick(self.enemies.iter().filter(|v| v.base == kind).map(|v| (v, v.chance)), roll)
+    }
+
+    // Likewise for a power-up about to drop
+    pub fn roll_pickup(&self, rng: &mut GameRng, kind: PowerUp) -> Option<&PickupVariant> {
+        let roll = rng.r#gen::<f32>();
+        pick(self.pickups.iter().filter(|v| v.base == kind).map(|v| (v, v.chance)), roll)
+    }
+
+    pub fn tile(&self, glyph: char) -> Option<&TileVariant> {
+        self.tiles.iter().find(|t| t.glyph == glyph)
+    }
+
+    // The maze with the mods' tiles swapped for what they play as, for the
+    // checks that go by glyph
+    pub fn base_rows(&self, rows: &[String]) -> Vec<String> {
+        rows.iter().map(|row| row.chars().map(|g| self.tile(g).map_or(g, |t| t.base)).collect()).collect()
+    }
+}
+
+// Beside Bevy's own assets; a browser build has no directory to look in
+pub fn mods_dir() -> PathBuf {
+    #[cfg(not(any(target_arch = "wasm32", target_os = "android")))]
+    let base = bevy::asset::io::file::FileAssetReader::get_base_path();
+    #[cfg(any(target_arch = "wasm32", target_os = "android"))]
+    let base = PathBuf::new();
+    base.join("assets").join(MODS_DIR)
+}
+
+// The chances are shares of the same roll, so together they're at most all of it
+fn pick<'a, T>(items: impl Iterator<Item = (&'a T, f32)>, mut roll: f32) -> Option<&'a T> {
+    for (item, chance) in items {
+        if roll < chance { return Some(item); }
+        roll -= chance;
+    }
+    None
+}
+
+pub struct ModsPlugin;
+
+impl Plugin for ModsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ContentRegistry::load())
+            .add_systems(Update, tune_enemies.run_if(in_state(AppState::Playing)));
+    }
+}
+
+// As each tank rolls out; the speed, colour and shell are read from the
+// variant where they're used
+fn tune_enemies(
+    mut commands: Commands,
+    content: Res<ContentRegistry>,
+    mut rng: ResMut<GameRng>,
+    mut q: Query<(Entity, &EnemyKind, &mut Health, Option<&mut EnemyGun>), Added<EnemyKind>>,
+) {
+    for (e, kind, mut health, gun) in &mut q {
+        let Some(variant) = content.roll_enemy(&mut rng, *kind) else { continue; };
+        if let Some(hp) = variant.hit_points { health.0 = hp; }
+        if let (Some(secs), Some(mut gun)) = (variant.fire_secs, gun) {
+            gun.0 = Timer::from_seconds(secs, TimerMode::Repeating);
+        }
+        commands.entity(e).insert(variant.clone());
+    }
+}
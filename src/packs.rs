@@ -0,0 +1,424 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use crate::args::{LaunchArgs, LevelFile};
+use crate::attract::Demo;
+use crate::config::Difficulty;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::level_format;
+use crate::locale::{Locale, Tr};
+use crate::mazegen;
+use crate::persistence;
+use crate::recap::StageCleared;
+use crate::savegame::SavedRun;
+use crate::scores::confirm_pressed;
+use crate::themes::LevelTheme;
+use crate::toasts::Notify;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, GameRng, GameSet, RestartEvent, on_restart_build_maze, seed_run, start_run};
+
+// Level packs ("campaigns"): a directory or a zip archive in campaigns/ in the
+// data directory, holding a manifest.ron and level files in the --level
+// format. The campaign browser (P on the loadout screen) picks one, and a
+// campaign run then plays its levels in order in place of the built-in maze,
+// with the usual waves, ending once the last is cleared. How far each pack
+// has been got through is kept in pack_progress.ron. A pack that can't be
+// read is left out with a warning in the log. Packs aren't saved mid-run.
+// The browser can also pick random levels, which campaign and survival runs
+// then play on mazes made up as each level starts (see mazegen.rs).
+
+const PACKS_DIR: &str = "campaigns";
+const MANIFEST_FILE: &str = "manifest.ron";
+const PROGRESS_FILE: &str = "pack_progress.ron";
+const SELECTED_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+const DETAIL_COLOR: Color = Color::srgb(0.7, 0.7, 0.7);
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct PackManifest {
+    name: String,
+    author: String,
+    // How hard the author rates it; the difficulty setting still applies
+    difficulty: Option<Difficulty>,
+    // A level theme (classic, desert, winter or night) for every level, in
+    // place of the usual rotation
+    palette: Option<String>,
+    // Level files in the order they're played; every level file in name order when left out
+    levels: Vec<String>,
+}
+
+struct LevelPack {
+    // The directory or archive name, which progress is kept under
+    id: String,
+    name: String,
+    author: String,
+    difficulty: Option<Difficulty>,
+    theme: Option<LevelTheme>,
+    levels: Vec<LevelFile>,
+}
+
+impl LevelPack {
+    fn load(path: &Path) -> Result<Self, String> {
+        let files = if path.is_dir() { read_dir_files(path)? } else { read_zip_files(path)? };
+        let manifest = files.get(MANIFEST_FILE).ok_or_else(|| format!("there's no {MANIFEST_FILE}"))?;
+        let manifest: PackManifest = ron::from_str(manifest).map_err(|e| format!("{MANIFEST_FILE}: {e}"))?;
+        let theme = manifest
+            .palette
+            .map(|p| LevelTheme::named(&p).ok_or_else(|| format!("{MANIFEST_FILE}: no palette {p}")))
+            .transpose()?;
+
+        let names: Vec<String> = if manifest.levels.is_empty() {
+            files.keys().filter(|name| is_level_file(name)).cloned().collect()
+        } else {
+            manifest.levels
+        };
+        if names.is_empty() { return Err("there are no levels in it".to_string()); }
+        let mut levels = names
+            .iter()
+            .map(|name| {
+                let text = files.get(name).ok_or_else(|| format!("{MANIFEST_FILE} lists {name}, which isn't there"))?;
+                let mut level = level_format::load(name, text)?;
+                level.code = files.get(&LevelFile::code_name(name)).cloned();
+                Ok::<_, String>(level)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let id = path.file_name().map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        let name = if manifest.name.is_empty() { id.clone() } else { manifest.name };
+        for level in &mut levels {
+            level.name = format!("{name} / {}", level.name);
+        }
+        Ok(Self { id, name, author: manifest.author, difficulty: manifest.difficulty, theme, levels })
+    }
+
+    // Past the last level is the last level: the run ends when its recap is dismissed
+    fn level(&self, level: usize) -> &LevelFile {
+        &self.levels[level.min(self.levels.len() - 1)]
+    }
+}
+
+// The manifest and the level files, by file name
+fn read_dir_files(dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()).filter(|n| is_pack_file(n)) else { continue; };
+        let text = fs::read_to_string(&path).map_err(|e| format!("{name}: {e}"))?;
+        files.insert(name.to_string(), text);
+    }
+    Ok(files)
+}
+
+// A pack zipped up with its folder has everything one folder down, so only file names count
+fn read_zip_files(path: &Path) -> Result<BTreeMap<String, String>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut files = BTreeMap::new();
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(|e| e.to_string())?;
+        let name = entry.name().rsplit('/').next().unwrap_or_default().to_string();
+        if entry.is_dir() || !is_pack_file(&name) { continue; }
+        let mut text = String::new();
+        entry.read_to_string(&mut text).map_err(|e| format!("{name}: {e}"))?;
+        files.insert(name, text);
+    }
+    Ok(files)
+}
+
+// Anything else (a readme, screenshots) is left alone
+fn is_pack_file(name: &str) -> bool {
+    name.ends_with(".txt") || name.ends_with(".ron") || name.ends_with(".rhai")
+}
+
+fn is_level_file(name: &str) -> bool {
+    name != MANIFEST_FILE && (name.ends_with(".txt") || name.ends_with(".ron"))
+}
+
+#[derive(Resource, Default)]
+pub struct LevelPacks {
+    packs: Vec<LevelPack>,
+    // Picked in the browser, for the next campaign run
+    selected: Option<usize>,
+    // Random levels picked in the browser instead
+    random: bool,
+    // The pack of the run being played, if it's on one
+    active: Option<usize>,
+    // The maze of the level being played, on random levels
+    generated: Option<LevelFile>,
+}
+
+impl LevelPacks {
+    fn find() -> Self {
+        let dir = persistence::data_dir().join(PACKS_DIR);
+        let Ok(entries) = fs::read_dir(&dir) else { return Self::default(); };
+        let mut packs: Vec<LevelPack> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir() || path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")))
+            .filter_map(|path| {
+                LevelPack::load(&path).map_err(|e| warn!("skipping level pack {}: {e}", path.display())).ok()
+            })
+            .collect();
+        packs.sort_by(|a, b| a.name.cmp(&b.name));
+        Self { packs, ..default() }
+    }
+
+    fn playing(&self) -> Option<&LevelPack> {
+        self.active.map(|i| &self.packs[i])
+    }
+
+    // A pack's levels or random ones, in place of the built-in maze
+    pub fn own_levels(&self) -> bool {
+        self.active.is_some() || self.generated.is_some()
+    }
+
+    // For the loadout screen's mode line
+    pub fn selected_name(&self) -> Option<&str> {
+        self.selected.map(|i| self.packs[i].name.as_str())
+    }
+
+    pub fn random_picked(&self) -> bool {
+        self.random
+    }
+
+    // The theme the pack plays every level in, if it sets one
+    pub fn theme(&self) -> Option<LevelTheme> {
+        self.playing().and_then(|p| p.theme)
+    }
+}
+
+// Every level a run started from the loadout screen will play from a file,
+// for level_check.rs to look over first; random mazes are made sound
+pub fn run_levels<'a>(args: &'a LaunchArgs, packs: &'a LevelPacks, mode: GameMode, resume: bool) -> Vec<&'a LevelFile> {
+    let random = packs.random && matches!(mode, GameMode::Campaign | GameMode::Survival);
+    match packs.selected.filter(|_| mode == GameMode::Campaign && !resume) {
+        _ if mode == GameMode::Tutorial || (random && !resume) => Vec::new(),
+        Some(i) => packs.packs[i].levels.iter().collect(),
+        None => args.level.iter().collect(),
+    }
+}
+
+// The maze and script a level is played on when it isn't the built-in maze:
+// the pack's own, a random one, or else the --level file
+pub fn custom_level<'a>(args: &'a LaunchArgs, packs: &'a LevelPacks, level: usize) -> Option<&'a LevelFile> {
+    packs.playing().map(|p| p.level(level)).or(packs.generated.as_ref()).or(args.level.as_ref())
+}
+
+// Levels cleared, by pack id; the best run so far
+#[derive(Resource, Serialize, Deserialize, Default)]
+struct PackProgress(BTreeMap<String, usize>);
+
+impl PackProgress {
+    fn load() -> Self {
+        persistence::load(PROGRESS_FILE)
+    }
+
+    fn save(&self) {
+        persistence::save(PROGRESS_FILE, self);
+    }
+}
+
+// Row 0 is the built-in campaign and row 1 random levels, then the packs
+const FIRST_PACK_ROW: usize = 2;
+
+#[derive(Resource, Default)]
+struct BrowserCursor(usize);
+
+#[derive(Component)]
+struct BrowserScreen;
+
+#[derive(Component)]
+struct BrowserRow(usize);
+
+pub struct PacksPlugin;
+
+impl Plugin for PacksPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(LevelPacks::find())
+            .insert_resource(PackProgress::load())
+            .init_resource::<BrowserCursor>()
+            .add_systems(OnEnter(AppState::Playing), start_pack.after(seed_run).before(start_run))
+            .add_systems(
+                Update,
+                (track_pack, end_pack.run_if(on_event::<RestartEvent>)).run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(
+                Update,
+                next_random_level
+                    .run_if(on_event::<RestartEvent>)
+                    .before(on_restart_build_maze)
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(OnEnter(AppState::Packs), spawn_browser)
+            .add_systems(OnExit(AppState::Packs), despawn_browser)
+            .add_systems(Update, (browser_input, refresh_browser).chain().run_if(in_state(AppState::Packs)));
+    }
+}
+
+// Ahead of start_run, which uses up the loadout's continue; a continued run is
+// always the built-in campaign, as pack runs aren't saved. A run starts on
+// level 0 otherwise, which is the one made up here.
+fn start_pack(
+    mode: Res<GameMode>,
+    saved: Res<SavedRun>,
+    demo: Option<Res<Demo>>,
+    mut packs: ResMut<LevelPacks>,
+    mut rng: ResMut<GameRng>,
+) {
+    let fresh = !saved.resume && demo.is_none();
+    packs.active = packs.selected.filter(|_| fresh && *mode == GameMode::Campaign);
+    let random = fresh && packs.random && matches!(*mode, GameMode::Campaign | GameMode::Survival);
+    packs.generated = random.then(|| random_maze(&mut rng, 0));
+}
+
+// The seed is logged, so a good maze can be found again
+fn random_maze(rng: &mut GameRng, level: usize) -> LevelFile {
+    let seed = rng.r#gen::<u64>();
+    info!("random level {}: maze seed {seed}", level + 1);
+    mazegen::generate(seed)
+}
+
+// Each cleared level moves on to a new maze
+fn next_random_level(level: Res<CurrentLevel>, mut packs: ResMut<LevelPacks>, mut rng: ResMut<GameRng>) {
+    if packs.generated.is_some() { packs.generated = Some(random_maze(&mut rng, level.0)); }
+}
+
+// level_completion has moved the level on by the time the stage is cleared
+fn track_pack(
+    mut cleared: EventReader<StageCleared>,
+    level: Res<CurrentLevel>,
+    packs: Res<LevelPacks>,
+    locale: Res<Locale>,
+    mut progress: ResMut<PackProgress>,
+    mut notify: EventWriter<Notify>,
+) {
+    if cleared.read().next().is_none() { return; }
+    let Some(pack) = packs.playing() else { return; };
+    let best = progress.0.entry(pack.id.clone()).or_default();
+    if level.0 > *best {
+        *best = level.0;
+        progress.save();
+    }
+    if level.0 >= pack.levels.len() {
+        notify.write(Notify::new(locale.f("packs.complete", &[("name", &pack.name)])).with_color(SELECTED_COLOR));
+    }
+}
+
+// After the last level's recap; the level built for the restart is the last again, and gone with the run
+fn end_pack(level: Res<CurrentLevel>, packs: Res<LevelPacks>, mut next: ResMut<NextState<AppState>>) {
+    if packs.playing().is_some_and(|p| level.0 >= p.levels.len()) { next.set(AppState::GameOver); }
+}
+
+fn spawn_browser(
+    mut commands: Commands,
+    packs: Res<LevelPacks>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    mut cursor: ResMut<BrowserCursor>,
+) {
+    cursor.0 = match packs.selected {
+        Some(i) => FIRST_PACK_ROW + i,
+        None if packs.random => 1,
+        None => 0,
+    };
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            BrowserScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("packs.title")));
+            for row in 0..FIRST_PACK_ROW + packs.packs.len() {
+                root.spawn((Text::new(""), font(18.0), TextColor(Color::WHITE), BrowserRow(row)));
+            }
+            if packs.packs.is_empty() {
+                let dir = persistence::data_dir().join(PACKS_DIR);
+                let none = locale.f("packs.none", &[("path", &dir.display())]);
+                root.spawn((Text::new(none), font(14.0), TextColor(DETAIL_COLOR)));
+            }
+            let hint = |action: Action, key: &str| format!("{}: {}", glyph(action, *device), locale.t(key));
+            let footer = format!(
+                "{}    {}    {}",
+                hint(Action::Navigate, "hint.select_change"),
+                hint(Action::Confirm, "packs.choose"),
+                hint(Action::Back, "hint.back"),
+            );
+            root.spawn((Text::new(footer), font(16.0), TextColor(DETAIL_COLOR)));
+        });
+}
+
+fn despawn_browser(mut commands: Commands, q: Query<Entity, With<BrowserScreen>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn browser_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut cursor: ResMut<BrowserCursor>,
+    mut packs: ResMut<LevelPacks>,
+    mut mode: ResMut<GameMode>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    let pressed = |key_a: KeyCode, key_b: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key_a) || keys.just_pressed(key_b) || pads.iter().any(|p| p.just_pressed(button))
+    };
+    let rows = FIRST_PACK_ROW + packs.packs.len();
+    if pressed(KeyCode::ArrowUp, KeyCode::KeyW, GamepadButton::DPadUp) { cursor.0 = (cursor.0 + rows - 1) % rows; }
+    if pressed(KeyCode::ArrowDown, KeyCode::KeyS, GamepadButton::DPadDown) { cursor.0 = (cursor.0 + 1) % rows; }
+
+    if confirm_pressed(&keys, &pads) {
+        packs.selected = cursor.0.checked_sub(FIRST_PACK_ROW);
+        packs.random = cursor.0 == 1;
+        // A pack is a campaign to play; random levels can be survival's too
+        let fits = *mode == GameMode::Campaign || (packs.random && *mode == GameMode::Survival);
+        if cursor.0 > 0 && !fits { *mode = GameMode::Campaign; }
+        next.set(AppState::Loadout);
+    }
+    if keys.just_pressed(KeyCode::Escape) || pads.iter().any(|p| p.just_pressed(GamepadButton::East)) {
+        next.set(AppState::Loadout);
+    }
+}
+
+fn refresh_browser(
+    cursor: Res<BrowserCursor>,
+    packs: Res<LevelPacks>,
+    progress: Res<PackProgress>,
+    locale: Res<Locale>,
+    mut q_rows: Query<(&BrowserRow, &mut Text, &mut TextColor)>,
+) {
+    if !(cursor.is_changed() || locale.is_changed()) { return; }
+    for (row, mut text, mut color) in &mut q_rows {
+        let marker = if row.0 == cursor.0 { ">" } else { " " };
+        let line = match row.0.checked_sub(FIRST_PACK_ROW).map(|i| &packs.packs[i]) {
+            None if row.0 == 1 => locale.t("packs.random").to_string(),
+            None => locale.t("packs.builtin").to_string(),
+            Some(pack) => {
+                let mut line = pack.name.clone();
+                if !pack.author.is_empty() {
+                    line += &format!("  {}", locale.f("packs.by", &[("author", &pack.author)]));
+                }
+                if let Some(difficulty) = pack.difficulty { line += &format!("  [{}]", locale.t(difficulty.label())); }
+                let cleared = progress.0.get(&pack.id).copied().unwrap_or(0).min(pack.levels.len());
+                let count = pack.levels.len();
+                format!("{line}  {}", locale.f("packs.levels", &[("cleared", &cleared), ("count", &count)]))
+            }
+        };
+        text.0 = format!("{marker} {line}");
+        color.0 = if row.0 == cursor.0 { SELECTED_COLOR } else { Color::WHITE };
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use rand::{Rng, thread_rng};
+use std::f32::consts::TAU;
+
+use crate::config::GameConfig;
+use crate::recap::StageRecap;
+use crate::{AppState, Enemy, EnemyKind, GameSet, Ground, Health, Player, Size, Velocity};
+
+// Small square particles: sparks where shells strike walls, dust kicked up
+// behind tanks driving on bare ground, and smoke trailing from damaged tanks.
+// Anything can ask for a burst with a `ParticleBurst` event. Particles are
+// pooled, so a finished one is hidden and reused rather than despawned, and
+// there are never more than MAX_PARTICLES; bursts past the cap are dropped.
+
+const MAX_PARTICLES: usize = 600;
+const Z: f32 = 2.7;
+// How often moving tanks drop a puff of dust, and damaged ones a puff of smoke
+const DUST_SECS: f32 = 0.12;
+const SMOKE_SECS: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParticleKind {
+    Spark,
+    Dust,
+    Smoke,
+}
+
+impl ParticleKind {
+    fn color(self) -> Color {
+        match self {
+            ParticleKind::Spark => Color::srgb(1.0, 0.85, 0.4),
+            ParticleKind::Dust => Color::srgba(0.55, 0.48, 0.38, 0.6),
+            ParticleKind::Smoke => Color::srgba(0.3, 0.3, 0.3, 0.7),
+        }
+    }
+
+    fn size(self) -> f32 {
+        match self {
+            ParticleKind::Spark => 2.5,
+            ParticleKind::Dust => 4.0,
+            ParticleKind::Smoke => 6.0,
+        }
+    }
+
+    fn life_secs(self) -> f32 {
+        match self {
+            ParticleKind::Spark => 0.25,
+            ParticleKind::Dust => 0.5,
+            ParticleKind::Smoke => 1.0,
+        }
+    }
+
+    // Launch speed range, pixels per second
+    fn speed(self) -> (f32, f32) {
+        match self {
+            ParticleKind::Spark => (80.0, 180.0),
+            ParticleKind::Dust => (5.0, 20.0),
+            ParticleKind::Smoke => (5.0, 15.0),
+        }
+    }
+
+    // How much the particle grows over its life
+    fn growth(self) -> f32 {
+        match self {
+            ParticleKind::Spark => 0.0,
+            ParticleKind::Dust => 1.0,
+            ParticleKind::Smoke => 2.0,
+        }
+    }
+}
+
+#[derive(Event)]
+pub struct ParticleBurst {
+    pub pos: Vec2,
+    pub kind: ParticleKind,
+    pub count: usize,
+}
+
+#[derive(Component)]
+struct Particle {
+    active: bool,
+    kind: ParticleKind,
+    velocity: Vec2,
+    life: Timer,
+}
+
+// Particle entities waiting to be reused, and how many exist in all
+#[derive(Resource, Default)]
+struct ParticlePool {
+    free: Vec<Entity>,
+    total: usize,
+}
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ParticleBurst>()
+            .init_resource::<ParticlePool>()
+            .add_systems(
+                Update,
+                (emit_dust, emit_smoke)
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(not(resource_exists::<StageRecap>)),
+            )
+            // Not tied to a state, like explosions, so the last sparks of a run finish
+            .add_systems(Update, (spawn_bursts, animate_particles).chain().after(GameSet::Cleanup));
+    }
+}
+
+fn emit_dust(
+    time: Res<Time>,
+    mut next: Local<f32>,
+    mut bursts: EventWriter<ParticleBurst>,
+    q_tanks: Query<(&Transform, &Size, &Velocity, &Ground), Or<(With<Player>, With<Enemy>)>>,
+) {
+    *next -= time.delta_secs();
+    if *next > 0.0 { return; }
+    *next += DUST_SECS;
+    for (t, size, v, ground) in &q_tanks {
+        if *ground != Ground::Floor || v.0 == Vec2::ZERO { continue; }
+        // From the tracks at the back of the tank
+        let behind = t.translation.truncate() - v.0.normalize() * size.0.x * 0.5;
+        bursts.write(ParticleBurst { pos: behind, kind: ParticleKind::Dust, count: 1 });
+    }
+}
+
+fn emit_smoke(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut next: Local<f32>,
+    mut bursts: EventWriter<ParticleBurst>,
+    q_enemies: Query<(&Transform, &Health, &EnemyKind), With<Enemy>>,
+) {
+    *next -= time.delta_secs();
+    if *next > 0.0 { return; }
+    *next += SMOKE_SECS;
+    for (t, hp, kind) in &q_enemies {
+        if hp.0 >= kind.hit_points(&cfg) { continue; }
+        bursts.write(ParticleBurst { pos: t.translation.truncate(), kind: ParticleKind::Smoke, count: 1 });
+    }
+}
+
+fn spawn_bursts(
+    mut commands: Commands,
+    mut bursts: EventReader<ParticleBurst>,
+    mut pool: ResMut<ParticlePool>,
+    mut q: Query<(&mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    let mut rng = thread_rng();
+    for burst in bursts.read() {
+        for _ in 0..burst.count {
+            let (lo, hi) = burst.kind.speed();
+            let particle = Particle {
+                active: true,
+                kind: burst.kind,
+                velocity: Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(lo..hi),
+                life: Timer::from_seconds(burst.kind.life_secs(), TimerMode::Once),
+            };
+            let transform = Transform::from_xyz(burst.pos.x, burst.pos.y, Z);
+            let sprite = Sprite {
+                color: burst.kind.color(),
+                custom_size: Some(Vec2::splat(burst.kind.size())),
+                ..default()
+            };
+
+            if let Some(e) = pool.free.pop() {
+                let Ok((mut p, mut t, mut s, mut vis)) = q.get_mut(e) else { continue; };
+                (*p, *t, *s, *vis) = (particle, transform, sprite, Visibility::Inherited);
+            } else if pool.total < MAX_PARTICLES {
+                pool.total += 1;
+                commands.spawn((particle, transform, sprite));
+            }
+        }
+    }
+}
+
+fn animate_particles(
+    time: Res<Time>,
+    mut pool: ResMut<ParticlePool>,
+    mut q: Query<(Entity, &mut Particle, &mut Transform, &mut Sprite, &mut Visibility)>,
+) {
+    let dt = time.delta_secs();
+    for (e, mut p, mut t, mut sprite, mut vis) in &mut q {
+        if !p.active { continue; }
+        if p.life.tick(time.delta()).finished() {
+            p.active = false;
+            *vis = Visibility::Hidden;
+            pool.free.push(e);
+            continue;
+        }
+        // Slows as it flies
+        let v = p.velocity * p.life.fraction_remaining();
+        t.translation += (v * dt).extend(0.0);
+        t.scale = Vec3::splat(1.0 + p.kind.growth() * p.life.fraction());
+        sprite.color.set_alpha(p.kind.color().alpha() * p.life.fraction_remaining());
+    }
+}
@@ -0,0 +1,126 @@
+use bevy::prelude::*;
+
+use crate::boss::Boss;
+use crate::config::GameConfig;
+use crate::death::{DeathEvent, DeathReason};
+use crate::feedback::HitEvent;
+use crate::recap::StageRecap;
+use crate::{
+    AppState, Armor, Bullet, Enemy, GameSet, Invulnerable, LevelTile, Lives, Player, Size, TILE, aabb_overlap,
+    damage_player,
+};
+
+// Patrolling barriers: heavy blocks that run along a fixed route of waypoints
+// laid out by the level file (a `patrol` line after a text maze, a `Patrol`
+// entity in a .ron one), looping back to the first one, and crush any tank
+// they touch whatever its armor. They soak up shells but can't be destroyed,
+// so getting past one is a matter of timing. The spawn shield still protects,
+// and the boss is too big to crush.
+
+const PATROL_COLOR: Color = Color::srgb(0.85, 0.7, 0.1);
+
+// A route as a level lays it out: waypoint cells, in order, and pixels a second
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatrolRoute {
+    pub cells: Vec<UVec2>,
+    pub speed: f32,
+}
+
+#[derive(Component)]
+struct Patrol {
+    points: Vec<Vec2>,
+    next: usize,
+    speed: f32,
+}
+
+pub struct PatrolsPlugin;
+
+impl Plugin for PatrolsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (follow_paths.in_set(GameSet::Physics), (crush_tanks, stop_shells).in_set(GameSet::Damage))
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+// Starts on the first waypoint, heading for the second
+pub fn spawn_patrol(commands: &mut Commands, points: Vec<Vec2>, speed: f32) {
+    let Some(&start) = points.first() else { return; };
+    let size = Vec2::splat(TILE * 0.9);
+    commands.spawn((
+        Sprite { color: PATROL_COLOR, custom_size: Some(size), ..default() },
+        // Above tanks, so one being crushed disappears underneath
+        Transform::from_xyz(start.x, start.y, 1.0),
+        Patrol { next: 1 % points.len(), points, speed },
+        LevelTile,
+        Size(size),
+    ));
+}
+
+fn follow_paths(time: Res<Time>, mut q: Query<(&mut Transform, &mut Patrol)>) {
+    for (mut t, mut patrol) in &mut q {
+        let mut pos = t.translation.truncate();
+        let mut travel = patrol.speed * time.delta_secs();
+        // A fast patrol may pass more than one waypoint in a frame
+        for _ in 0..patrol.points.len() {
+            let target = patrol.points[patrol.next];
+            let gap = pos.distance(target);
+            if gap > travel {
+                pos += (target - pos) / gap * travel;
+                break;
+            }
+            pos = target;
+            travel -= gap;
+            patrol.next = (patrol.next + 1) % patrol.points.len();
+        }
+        t.translation.x = pos.x;
+        t.translation.y = pos.y;
+    }
+}
+
+fn crush_tanks(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut hits: EventWriter<HitEvent>,
+    mut lives: ResMut<Lives>,
+    q_patrols: Query<(&Transform, &Size), With<Patrol>>,
+    mut q_tanks: Query<
+        (Entity, &Transform, &Size, Has<Player>, Option<&mut Armor>),
+        (Or<(With<Enemy>, With<Player>)>, Without<Invulnerable>, Without<Boss>),
+    >,
+) {
+    for (t_e, t_t, t_s, is_player, armor) in &mut q_tanks {
+        let pos = t_t.translation.truncate();
+        let crushed = q_patrols
+            .iter()
+            .any(|(p_t, p_s)| aabb_overlap(p_t.translation.truncate(), p_s.0 * 0.5, pos, t_s.0 * 0.5));
+        if !crushed { continue; }
+
+        if is_player {
+            let Some(mut armor) = armor else { continue; };
+            // No armor stands up to this
+            armor.0 = 0;
+            damage_player(&mut commands, &cfg, t_t, t_s, &mut armor, &mut lives);
+        }
+        hits.write(HitEvent { target: t_e, fatal: true, player: is_player });
+        deaths.write(DeathEvent::new(t_e, DeathReason::Crushed));
+    }
+}
+
+fn stop_shells(
+    mut deaths: EventWriter<DeathEvent>,
+    q_patrols: Query<(&Transform, &Size), With<Patrol>>,
+    q_bullets: Query<(Entity, &Transform, &Size), With<Bullet>>,
+) {
+    for (b_e, b_t, b_s) in &q_bullets {
+        let pos = b_t.translation.truncate();
+        let hit = q_patrols
+            .iter()
+            .any(|(p_t, p_s)| aabb_overlap(p_t.translation.truncate(), p_s.0 * 0.5, pos, b_s.0 * 0.5));
+        if hit { deaths.write(DeathEvent::new(b_e, DeathReason::Spent)); }
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+use serde::{Serialize, de::DeserializeOwned};
+use std::path::PathBuf;
+
+// Small RON store used by everything that survives a restart (profile,
+// scores, settings). Failures are logged and fall back to defaults; a corrupt
+// save should never keep the game from starting. On the desktop each file is
+// a file in the data directory; in a browser, where there is no file system
+// to write to, it's a localStorage entry under the same name.
+
+const APP_DIR: &str = "BudgetBattleCity";
+
+pub fn data_dir() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|h| PathBuf::from(h).join("Library/Application Support"))
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/share")))
+    };
+    base.unwrap_or_else(|| PathBuf::from(".")).join(APP_DIR)
+}
+
+// Milliseconds since 1970, from the browser's clock on the web, where std has none
+pub fn unix_millis() -> u64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now() as u64
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod store {
+    use std::fs;
+
+    use super::data_dir;
+
+    pub fn read(file: &str) -> Option<String> {
+        fs::read_to_string(data_dir().join(file)).ok()
+    }
+
+    // Returns where it went
+    pub fn write(file: &str, text: &str) -> Result<String, String> {
+        let dir = data_dir();
+        let path = dir.join(file);
+        fs::create_dir_all(&dir).and_then(|_| fs::write(&path, text)).map_err(|e| e.to_string())?;
+        Ok(path.display().to_string())
+    }
+
+    pub fn exists(file: &str) -> bool {
+        data_dir().join(file).exists()
+    }
+
+    pub fn remove(file: &str) -> Result<(), String> {
+        let path = data_dir().join(file);
+        if !path.exists() { return Ok(()); }
+        fs::remove_file(&path).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod store {
+    use web_sys::Storage;
+
+    use super::APP_DIR;
+
+    fn storage() -> Result<Storage, String> {
+        let storage = web_sys::window().and_then(|w| w.local_storage().ok().flatten());
+        storage.ok_or_else(|| "this browser has no localStorage".to_string())
+    }
+
+    fn key(file: &str) -> String {
+        format!("{APP_DIR}/{file}")
+    }
+
+    pub fn read(file: &str) -> Option<String> {
+        storage().ok()?.get_item(&key(file)).ok().flatten()
+    }
+
+    pub fn write(file: &str, text: &str) -> Result<String, String> {
+        storage()?.set_item(&key(file), text).map_err(|e| format!("{e:?}"))?;
+        Ok(format!("localStorage \"{}\"", key(file)))
+    }
+
+    pub fn exists(file: &str) -> bool {
+        read(file).is_some()
+    }
+
+    pub fn remove(file: &str) -> Result<(), String> {
+        storage()?.remove_item(&key(file)).map_err(|e| format!("{e:?}"))
+    }
+}
+
+pub fn exists(file: &str) -> bool {
+    store::exists(file)
+}
+
+pub fn remove(file: &str) {
+    if let Err(e) = store::remove(file) {
+        warn!("failed to remove {file}: {e}");
+    }
+}
+
+pub fn load<T: DeserializeOwned + Default>(file: &str) -> T {
+    let Some(text) = store::read(file) else { return T::default(); };
+    ron::from_str(&text).unwrap_or_else(|e| {
+        warn!("ignoring unreadable {file}: {e}");
+        T::default()
+    })
+}
+
+// Plain text for the player to read or share, rather than for the game to load
+// back; returns where it went
+pub fn save_text(file: &str, text: &str) -> Option<String> {
+    store::write(file, text).map_err(|e| warn!("failed to save {file}: {e}")).ok()
+}
+
+// Text saved with save_text, as it was
+pub fn load_text(file: &str) -> Option<String> {
+    store::read(file)
+}
+
+pub fn save<T: Serialize>(file: &str, value: &T) {
+    let result = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())
+        .and_then(|text| store::write(file, &text));
+    if let Err(e) = result {
+        warn!("failed to save {file}: {e}");
+    }
+}
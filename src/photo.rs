@@ -0,0 +1,195 @@
+use bevy::input::mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseScrollUnit};
+use bevy::prelude::*;
+
+use crate::attract::Demo;
+use crate::game_time::GameTime;
+use crate::locale::Locale;
+use crate::recap::StageRecap;
+use crate::settings::settings_closed;
+use crate::display::WorldCamera;
+use crate::{AppState, LevelBounds, WINDOW_W};
+
+// Photo mode: F2 during a run pauses the game and frees the camera from the
+// player, for screenshots (F12, see capture.rs) and for looking a level over.
+// The arrow keys or W/A/S/D pan it, as does dragging with a mouse button held;
+// the wheel or +/- zooms. H hides the HUD, and F2 again puts the camera, the
+// zoom, the HUD and the game clock back as they were. The camera stays over
+// the level. It all runs on real time, as the game clock is stopped.
+
+const TOGGLE_KEY: KeyCode = KeyCode::F2;
+const HUD_KEY: KeyCode = KeyCode::KeyH;
+// World units a second at a zoom of 1
+const PAN_SPEED: f32 = 480.0;
+// How much the zoom changes a second with +/- held, and per wheel notch
+const ZOOM_PER_SEC: f32 = 2.5;
+const ZOOM_STEP: f32 = 1.15;
+// Touchpads scroll in pixels; about this many make a notch
+const PIXELS_PER_NOTCH: f32 = 40.0;
+// Below 1 is closer in
+const MIN_SCALE: f32 = 0.25;
+const MAX_SCALE: f32 = 4.0;
+const HINT_COLOR: Color = Color::srgb(0.9, 0.9, 0.6);
+
+// Present while photo mode is on
+#[derive(Resource)]
+pub struct PhotoMode {
+    was_paused: bool,
+    hud_hidden: bool,
+    // UI roots hidden with the HUD, and how they were before
+    hidden: Vec<(Entity, Visibility)>,
+}
+
+#[derive(Component)]
+struct PhotoHint;
+
+pub struct PhotoPlugin;
+
+impl Plugin for PhotoPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::Playing), leave_photo_mode).add_systems(
+            Update,
+            (toggle_photo_mode, (photo_camera, photo_hud).run_if(resource_exists::<PhotoMode>))
+                .chain()
+                .run_if(settings_closed)
+                .run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+// The camera follows the player, and gameplay input reaches the tank, only
+// while photo mode is off
+pub fn photo_mode_off(mode: Option<Res<PhotoMode>>) -> bool {
+    mode.is_none()
+}
+
+// Not over the stage recap, and a demo ends on any key anyway
+fn toggle_photo_mode(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    mode: Option<ResMut<PhotoMode>>,
+    recap: Option<Res<StageRecap>>,
+    demo: Option<Res<Demo>>,
+    locale: Res<Locale>,
+    mut game_time: ResMut<GameTime>,
+    mut q_ui: Query<&mut Visibility, With<Node>>,
+    q_hint: Query<Entity, With<PhotoHint>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) { return; }
+    if let Some(mut mode) = mode {
+        restore(&mut mode, &mut game_time, &mut q_ui);
+        commands.remove_resource::<PhotoMode>();
+        for e in &q_hint { commands.entity(e).despawn(); }
+        return;
+    }
+    if recap.is_some() || demo.is_some() { return; }
+
+    commands.insert_resource(PhotoMode { was_paused: game_time.paused, hud_hidden: false, hidden: Vec::new() });
+    game_time.paused = true;
+    commands.spawn((
+        Text::new(locale.t("photo.hint")),
+        TextFont { font_size: 16.0, ..default() },
+        TextColor(HINT_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        PhotoHint,
+    ));
+}
+
+// Should the run end with photo mode still on
+fn leave_photo_mode(
+    mut commands: Commands,
+    mode: Option<ResMut<PhotoMode>>,
+    mut game_time: ResMut<GameTime>,
+    mut q_ui: Query<&mut Visibility, With<Node>>,
+    q_hint: Query<Entity, With<PhotoHint>>,
+) {
+    let Some(mut mode) = mode else { return; };
+    restore(&mut mode, &mut game_time, &mut q_ui);
+    commands.remove_resource::<PhotoMode>();
+    for e in &q_hint { commands.entity(e).despawn(); }
+}
+
+// camera_follow puts the camera back over the player on the next frame, and
+// display.rs the zoom back to the one in the settings
+fn restore(mode: &mut PhotoMode, game_time: &mut GameTime, q_ui: &mut Query<&mut Visibility, With<Node>>) {
+    game_time.paused = mode.was_paused;
+    show_hud(mode, q_ui);
+}
+
+fn show_hud(mode: &mut PhotoMode, q_ui: &mut Query<&mut Visibility, With<Node>>) {
+    for (e, was) in mode.hidden.drain(..) {
+        if let Ok(mut vis) = q_ui.get_mut(e) { *vis = was; }
+    }
+}
+
+fn photo_camera(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<MouseButton>>,
+    motion: Res<AccumulatedMouseMotion>,
+    scroll: Res<AccumulatedMouseScroll>,
+    real: Res<Time<Real>>,
+    bounds: Option<Res<LevelBounds>>,
+    mut q_camera: Query<(&mut Transform, &mut Projection, &Camera), With<WorldCamera>>,
+) {
+    let dt = real.delta_secs();
+    let mut notches = match scroll.unit {
+        MouseScrollUnit::Line => scroll.delta.y,
+        MouseScrollUnit::Pixel => scroll.delta.y / PIXELS_PER_NOTCH,
+    };
+    let held_notches = dt * ZOOM_PER_SEC.ln() / ZOOM_STEP.ln();
+    if keys.any_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) { notches += held_notches; }
+    if keys.any_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) { notches -= held_notches; }
+
+    let mut dir = Vec2::ZERO;
+    if keys.any_pressed([KeyCode::KeyW, KeyCode::ArrowUp]) { dir.y += 1.0; }
+    if keys.any_pressed([KeyCode::KeyS, KeyCode::ArrowDown]) { dir.y -= 1.0; }
+    if keys.any_pressed([KeyCode::KeyA, KeyCode::ArrowLeft]) { dir.x -= 1.0; }
+    if keys.any_pressed([KeyCode::KeyD, KeyCode::ArrowRight]) { dir.x += 1.0; }
+    let dragging = buttons.any_pressed([MouseButton::Left, MouseButton::Right, MouseButton::Middle]);
+
+    for (mut t, mut projection, camera) in &mut q_camera {
+        let Projection::Orthographic(ortho) = &mut *projection else { continue; };
+        ortho.scale = (ortho.scale / ZOOM_STEP.powf(notches)).clamp(MIN_SCALE, MAX_SCALE);
+
+        let mut pan = dir.normalize_or_zero() * PAN_SPEED * ortho.scale * dt;
+        // The scene moves with the pointer, so the camera goes the other way
+        if dragging {
+            let viewport = camera.logical_viewport_size().unwrap_or(Vec2::ONE);
+            let world_per_px = WINDOW_W * ortho.scale / viewport.x.max(1.0);
+            pan += Vec2::new(-motion.delta.x, motion.delta.y) * world_per_px;
+        }
+        let mut pos = t.translation.truncate() + pan;
+        if let Some(bounds) = &bounds {
+            let half = bounds.size() * 0.5;
+            pos = pos.clamp(bounds.center() - half, bounds.center() + half);
+        }
+        t.translation.x = pos.x;
+        t.translation.y = pos.y;
+    }
+}
+
+// Every UI root goes, this hint included, and so do any that appear while
+// the HUD is hidden
+fn photo_hud(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut mode: ResMut<PhotoMode>,
+    mut q_ui: Query<&mut Visibility, With<Node>>,
+    q_roots: Query<Entity, (With<Node>, Without<ChildOf>)>,
+) {
+    if keys.just_pressed(HUD_KEY) {
+        mode.hud_hidden = !mode.hud_hidden;
+        if !mode.hud_hidden { show_hud(&mut mode, &mut q_ui); }
+    }
+    if !mode.hud_hidden { return; }
+    for e in &q_roots {
+        let Ok(mut vis) = q_ui.get_mut(e) else { continue; };
+        if *vis == Visibility::Hidden { continue; }
+        // Something else showed it again; it's still hidden on the list
+        if !mode.hidden.iter().any(|(hidden, _)| *hidden == e) { mode.hidden.push((e, *vis)); }
+        *vis = Visibility::Hidden;
+    }
+}
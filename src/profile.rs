@@ -0,0 +1,44 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::accessibility::AccessibilitySettings;
+use crate::config::Difficulty;
+use crate::display::DisplaySettings;
+use crate::feedback::FeedbackSettings;
+use crate::loadout::Loadout;
+use crate::locale::Language;
+use crate::persistence;
+use crate::settings::{AudioSettings, ControlScheme};
+use crate::speedrun::SpeedrunSettings;
+use crate::stick::ControllerSettings;
+use crate::touch::TouchMode;
+
+const PROFILE_FILE: &str = "profile.ron";
+
+// Per-player preferences that persist between runs.
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Profile {
+    pub loadout: Loadout,
+    pub controller: ControllerSettings,
+    pub feedback: FeedbackSettings,
+    pub display: DisplaySettings,
+    pub speedrun: SpeedrunSettings,
+    pub audio: AudioSettings,
+    pub controls: ControlScheme,
+    pub difficulty: Difficulty,
+    pub adaptive_difficulty: bool,
+    pub language: Language,
+    pub accessibility: AccessibilitySettings,
+    pub touch: TouchMode,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        persistence::load(PROFILE_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(PROFILE_FILE, self);
+    }
+}
@@ -0,0 +1,208 @@
+use bevy::diagnostic::{
+    Diagnostic, DiagnosticPath, Diagnostics, DiagnosticsStore, FrameTimeDiagnosticsPlugin, RegisterDiagnostic,
+};
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+
+use crate::{Bullet, Enemy, GameSet, Wall};
+
+// F11 profiler overlay: frames a second, a graph of the last couple of
+// seconds' frame times, how many entities there are (walls, shells and enemy
+// tanks among them), and how long each of the gameplay stages took this frame.
+// The stages are timed from stamps between the GameSets, so each figure is
+// the wall time from the start of one stage to the start of the next.
+// Everything shown goes through Bevy's diagnostics, so it's smoothed the same
+// way as the frame time, and is there for anything else that reads them. It's
+// measured only while the overlay is up, and in any state, though the stages
+// only run during a run.
+
+const TOGGLE_KEY: KeyCode = KeyCode::F11;
+const STAGE_COUNT: usize = 5;
+// In GameSet order
+const STAGES: [(&str, DiagnosticPath); STAGE_COUNT] = [
+    ("input", DiagnosticPath::const_new("game/stage/input")),
+    ("ai", DiagnosticPath::const_new("game/stage/ai")),
+    ("physics", DiagnosticPath::const_new("game/stage/physics")),
+    ("damage", DiagnosticPath::const_new("game/stage/damage")),
+    ("cleanup", DiagnosticPath::const_new("game/stage/cleanup")),
+];
+const ENTITIES: DiagnosticPath = DiagnosticPath::const_new("game/entities");
+const WALLS: DiagnosticPath = DiagnosticPath::const_new("game/walls");
+const BULLETS: DiagnosticPath = DiagnosticPath::const_new("game/bullets");
+const ENEMIES: DiagnosticPath = DiagnosticPath::const_new("game/enemies");
+// One bar per frame, newest on the right
+const GRAPH_BARS: usize = 120;
+const GRAPH_HEIGHT: f32 = 48.0;
+// Frame time at the top of the graph; longer ones are cut off
+const GRAPH_MS: f64 = 50.0;
+const SMOOTH_MS: f64 = 1000.0 / 60.0;
+const SLOW_MS: f64 = 1000.0 / 30.0;
+const SMOOTH_COLOR: Color = Color::srgb(0.3, 0.9, 0.4);
+const SLOW_COLOR: Color = Color::srgb(1.0, 0.8, 0.2);
+const STALL_COLOR: Color = Color::srgb(1.0, 0.3, 0.3);
+
+// Present while the overlay is shown
+#[derive(Resource)]
+struct Profiler;
+
+// When each stage started this frame, and when the last one ended
+#[derive(Resource, Default)]
+struct StageClock([Option<Instant>; STAGE_COUNT + 1]);
+
+#[derive(Component)]
+struct ProfilerPanel;
+
+#[derive(Component)]
+struct ProfilerText;
+
+#[derive(Component)]
+struct GraphBar(usize);
+
+pub struct ProfilerPlugin;
+
+impl Plugin for ProfilerPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin::default());
+        }
+        for (_, path) in STAGES {
+            app.register_diagnostic(Diagnostic::new(path).with_suffix("ms"));
+        }
+        for path in [ENTITIES, WALLS, BULLETS, ENEMIES] {
+            app.register_diagnostic(Diagnostic::new(path));
+        }
+        app.init_resource::<StageClock>()
+            .add_systems(Update, toggle_profiler)
+            .add_systems(
+                Update,
+                (
+                    stamp::<0>.before(GameSet::Input),
+                    stamp::<1>.after(GameSet::Input).before(GameSet::Ai),
+                    stamp::<2>.after(GameSet::Ai).before(GameSet::Physics),
+                    stamp::<3>.after(GameSet::Physics).before(GameSet::Damage),
+                    stamp::<4>.after(GameSet::Damage).before(GameSet::Cleanup),
+                    (stamp::<5>, measure, update_panel).chain().after(GameSet::Cleanup),
+                )
+                    .run_if(resource_exists::<Profiler>),
+            );
+    }
+}
+
+fn toggle_profiler(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    profiler: Option<Res<Profiler>>,
+    q_panel: Query<Entity, With<ProfilerPanel>>,
+) {
+    if !keys.just_pressed(TOGGLE_KEY) { return; }
+    if profiler.is_some() {
+        commands.remove_resource::<Profiler>();
+        for e in &q_panel { commands.entity(e).despawn(); }
+        return;
+    }
+    commands.insert_resource(Profiler);
+    let font = TextFont { font_size: 14.0, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                left: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            // Over the HUD and menus, under the toasts
+            GlobalZIndex(8),
+            ProfilerPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((Text::new(""), font, TextColor(Color::WHITE), ProfilerText));
+            panel
+                .spawn(Node {
+                    height: Val::Px(GRAPH_HEIGHT),
+                    align_items: AlignItems::FlexEnd,
+                    ..default()
+                })
+                .with_children(|graph| {
+                    for i in 0..GRAPH_BARS {
+                        graph.spawn((
+                            Node { width: Val::Px(2.0), height: Val::Px(0.0), ..default() },
+                            BackgroundColor(SMOOTH_COLOR),
+                            GraphBar(i),
+                        ));
+                    }
+                });
+        });
+}
+
+fn stamp<const N: usize>(mut clock: ResMut<StageClock>) {
+    clock.0[N] = Some(Instant::now());
+}
+
+fn measure(
+    mut clock: ResMut<StageClock>,
+    mut diagnostics: Diagnostics,
+    q_all: Query<()>,
+    q_walls: Query<(), With<Wall>>,
+    q_bullets: Query<(), With<Bullet>>,
+    q_enemies: Query<(), With<Enemy>>,
+) {
+    for (i, (_, path)) in STAGES.iter().enumerate() {
+        let (Some(start), Some(end)) = (clock.0[i], clock.0[i + 1]) else { continue; };
+        diagnostics.add_measurement(path, || end.duration_since(start).as_secs_f64() * 1000.0);
+    }
+    clock.0 = Default::default();
+    diagnostics.add_measurement(&ENTITIES, || q_all.iter().count() as f64);
+    diagnostics.add_measurement(&WALLS, || q_walls.iter().count() as f64);
+    diagnostics.add_measurement(&BULLETS, || q_bullets.iter().count() as f64);
+    diagnostics.add_measurement(&ENEMIES, || q_enemies.iter().count() as f64);
+}
+
+// Measurements added this frame are in the store from the next one
+fn update_panel(
+    store: Res<DiagnosticsStore>,
+    mut q_text: Query<&mut Text, With<ProfilerText>>,
+    mut q_bars: Query<(&GraphBar, &mut Node, &mut BackgroundColor)>,
+) {
+    let smoothed = |path: &DiagnosticPath| store.get(path).and_then(|d| d.smoothed()).unwrap_or(0.0);
+    let count = |path: &DiagnosticPath| store.get(path).and_then(|d| d.value()).unwrap_or(0.0) as u64;
+    let mut lines = vec![
+        format!(
+            "{:.0} fps  {:.2} ms",
+            smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+            smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME)
+        ),
+        format!(
+            "entities {}  walls {}  shells {}  enemies {}",
+            count(&ENTITIES),
+            count(&WALLS),
+            count(&BULLETS),
+            count(&ENEMIES)
+        ),
+    ];
+    for (label, path) in &STAGES {
+        lines.push(format!("{label:<8} {:.3} ms", smoothed(path)));
+    }
+    for mut text in &mut q_text {
+        text.0 = lines.join("\n");
+    }
+
+    let Some(frames) = store.get(&FrameTimeDiagnosticsPlugin::FRAME_TIME) else { return; };
+    let history: Vec<f64> = frames.values().copied().collect();
+    let skipped = GRAPH_BARS.saturating_sub(history.len());
+    let newest = &history[history.len().saturating_sub(GRAPH_BARS)..];
+    for (bar, mut node, mut color) in &mut q_bars {
+        let ms = bar.0.checked_sub(skipped).and_then(|i| newest.get(i)).copied().unwrap_or(0.0);
+        node.height = Val::Px((ms / GRAPH_MS).min(1.0) as f32 * GRAPH_HEIGHT);
+        color.0 = if ms <= SMOOTH_MS + 1.0 {
+            SMOOTH_COLOR
+        } else if ms <= SLOW_MS + 1.0 {
+            SLOW_COLOR
+        } else {
+            STALL_COLOR
+        };
+    }
+}
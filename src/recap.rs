@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::feedback::HitEvent;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::{Locale, Tr};
+use crate::scores::{Score, confirm_pressed};
+use crate::{AppState, EnemyKilled, EnemyKind, Player, RestartEvent};
+
+// Stage recorder and post-stage recap. While `StageRecap` exists gameplay is
+// paused, the player's route plus kill sites are traced over the map and a
+// breakdown of the stage's kills and bonuses waits for a key press.
+
+const PATH_SAMPLE_SECS: f32 = 0.15;
+const PATH_MIN_STEP: f32 = 4.0;
+// Input is ignored this long so a held fire button doesn't skip the recap
+const RECAP_MIN_SECS: f32 = 1.0;
+const KILL_MARK: f32 = 7.0;
+const FLAWLESS_BONUS: u32 = 1000;
+const QUICK_BONUS: u32 = 500;
+const QUICK_CLEAR_SECS: f32 = 90.0;
+const BONUS_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+
+// Per-stage record of where the player went, what they destroyed and how it went.
+#[derive(Resource, Default)]
+pub struct StageStats {
+    pub path: Vec<Vec2>,
+    pub kills: Vec<(Vec2, EnemyKind)>,
+    pub secs: f32,
+    // Hits the player took, armor included
+    pub hits_taken: u32,
+}
+
+// Every kill of the run, for the game-over breakdown, and how long it has
+// been going (not counting the recaps)
+#[derive(Resource, Default)]
+pub struct RunStats {
+    pub kills: Vec<EnemyKind>,
+    pub secs: f32,
+}
+
+// m:ss, as survival times are shown
+pub fn clock(secs: f32) -> String {
+    let secs = secs as u32;
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// One line per kind destroyed: name, count and the points they were worth
+pub fn kill_breakdown(kills: impl Iterator<Item = EnemyKind> + Clone, locale: &Locale) -> Vec<String> {
+    EnemyKind::ALL
+        .iter()
+        .filter_map(|&kind| {
+            let count = kills.clone().filter(|&k| k == kind).count() as u32;
+            (count > 0).then(|| format!("{:<9} x{:>3}   {:>6}", locale.kind_name(kind), count, count * kind.points()))
+        })
+        .collect()
+}
+
+// Keyed by the bonus's name in the language files
+fn stage_bonuses(stats: &StageStats) -> Vec<(&'static str, u32)> {
+    let mut bonuses = Vec::new();
+    if stats.hits_taken == 0 { bonuses.push(("recap.flawless", FLAWLESS_BONUS)); }
+    if stats.secs <= QUICK_CLEAR_SECS { bonuses.push(("recap.quick_clear", QUICK_BONUS)); }
+    bonuses
+}
+
+#[derive(Event, Default)]
+pub struct StageCleared;
+
+#[derive(Resource)]
+pub struct StageRecap(Timer);
+
+#[derive(Resource)]
+struct PathSampler(Timer);
+
+#[derive(Component)]
+struct RecapOverlay;
+
+pub struct RecapPlugin;
+
+impl Plugin for RecapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<StageStats>()
+            .init_resource::<RunStats>()
+            .insert_resource(PathSampler(Timer::from_seconds(PATH_SAMPLE_SECS, TimerMode::Repeating)))
+            .add_event::<StageCleared>()
+            .add_systems(OnEnter(AppState::Playing), (reset_stats, reset_run_stats))
+            .add_systems(
+                Update,
+                (
+                    (record_path, record_stage).run_if(not(resource_exists::<StageRecap>)),
+                    (record_kills, start_recap).chain(),
+                    draw_recap.run_if(resource_exists::<StageRecap>),
+                    finish_recap.run_if(resource_exists::<StageRecap>),
+                    reset_on_restart,
+                )
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn record_path(
+    time: Res<Time>,
+    mut sampler: ResMut<PathSampler>,
+    mut stats: ResMut<StageStats>,
+    q_player: Query<&Transform, With<Player>>,
+) {
+    if !sampler.0.tick(time.delta()).just_finished() { return; }
+    let Ok(t) = q_player.single() else { return; };
+    let pos = t.translation.truncate();
+    let moved = stats.path.last().is_none_or(|last| last.distance(pos) >= PATH_MIN_STEP);
+    if moved {
+        stats.path.push(pos);
+    }
+}
+
+fn record_stage(
+    time: Res<Time>,
+    mut hits: EventReader<HitEvent>,
+    mut stats: ResMut<StageStats>,
+    mut run: ResMut<RunStats>,
+) {
+    stats.secs += time.delta_secs();
+    run.secs += time.delta_secs();
+    stats.hits_taken += hits.read().filter(|h| h.player).count() as u32;
+}
+
+fn record_kills(mut ev: EventReader<EnemyKilled>, mut stats: ResMut<StageStats>, mut run: ResMut<RunStats>) {
+    for kill in ev.read().filter(|k| k.for_player()) {
+        stats.kills.push((kill.pos, kill.kind));
+        run.kills.push(kill.kind);
+    }
+}
+
+// Bonuses are paid into the score as the recap opens
+fn start_recap(
+    mut commands: Commands,
+    mut ev: EventReader<StageCleared>,
+    stats: Res<StageStats>,
+    mut score: ResMut<Score>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    if ev.read().next().is_none() { return; }
+    ev.clear();
+
+    let bonuses = stage_bonuses(&stats);
+    let kill_points: u32 = stats.kills.iter().map(|(_, k)| k.points()).sum();
+    let bonus_points: u32 = bonuses.iter().map(|(_, points)| points).sum();
+    score.0 += bonus_points;
+
+    commands.insert_resource(StageRecap(Timer::from_seconds(RECAP_MIN_SECS, TimerMode::Once)));
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(16.0),
+                width: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            RecapOverlay,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(32.0), TextColor(BONUS_COLOR), Tr("recap.title")));
+            root.spawn((
+                Node { flex_direction: FlexDirection::Column, padding: UiRect::all(Val::Px(8.0)), ..default() },
+                BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            ))
+            .with_children(|panel| {
+                for line in kill_breakdown(stats.kills.iter().map(|&(_, k)| k), &locale) {
+                    panel.spawn((Text::new(line), font(16.0), TextColor(Color::WHITE)));
+                }
+                for (name, points) in &bonuses {
+                    let line = format!("{:<14} {points:>6}", locale.t(name));
+                    panel.spawn((Text::new(line), font(16.0), TextColor(BONUS_COLOR)));
+                }
+                let total = format!("{:<14} {:>6}", locale.t("recap.total"), kill_points + bonus_points);
+                panel.spawn((Text::new(total), font(18.0), TextColor(Color::WHITE)));
+            });
+            root.spawn((
+                Text::new(format!("{}: {}", glyph(Action::Confirm, *device), locale.t("hint.continue"))),
+                font(14.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn draw_recap(stats: Res<StageStats>, mut gizmos: Gizmos) {
+    let route = Color::srgb(0.3, 1.0, 0.4);
+    let kill = Color::srgb(1.0, 0.25, 0.25);
+
+    gizmos.linestrip_2d(stats.path.iter().copied(), route);
+    for &(p, _) in &stats.kills {
+        gizmos.line_2d(p + Vec2::new(-KILL_MARK, -KILL_MARK), p + Vec2::new(KILL_MARK, KILL_MARK), kill);
+        gizmos.line_2d(p + Vec2::new(-KILL_MARK, KILL_MARK), p + Vec2::new(KILL_MARK, -KILL_MARK), kill);
+    }
+}
+
+fn finish_recap(
+    time: Res<Time<Real>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut commands: Commands,
+    mut recap: ResMut<StageRecap>,
+    mut restart: EventWriter<RestartEvent>,
+    q_overlay: Query<Entity, With<RecapOverlay>>,
+) {
+    if !recap.0.tick(time.delta()).finished() || !confirm_pressed(&keys, &pads) { return; }
+    for e in &q_overlay { commands.entity(e).despawn(); }
+    commands.remove_resource::<StageRecap>();
+    restart.write_default();
+}
+
+fn reset_on_restart(mut ev: EventReader<RestartEvent>, stats: ResMut<StageStats>) {
+    if ev.read().next().is_none() { return; }
+    ev.clear();
+    reset_stats(stats);
+}
+
+fn reset_stats(mut stats: ResMut<StageStats>) {
+    *stats = StageStats::default();
+}
+
+fn reset_run_stats(mut run: ResMut<RunStats>) {
+    *run = RunStats::default();
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::floating_text::spawn_floating_text;
+use crate::locale::Locale;
+use crate::mods::EnemyVariant;
+use crate::{AppState, Enemy, EnemyAI, EnemyKind, EnemyState, GameSet, Health, SpawnPoints};
+
+// Repairs: an armoured tank (one that takes more than one hit, the boss
+// aside) knocked down to its last hit point breaks off and falls back to the
+// nearest spawn point, holding its fire, and after `repair_secs` (config.ron)
+// it's back to full armour and in the fight again, unless it's finished off
+// first. Chasing one down or leaving it for the tanks still shooting is the
+// player's call. A repair_secs of 0 turns it off.
+
+const REPAIRED_COLOR: Color = Color::srgb(0.6, 0.8, 1.0);
+
+// On a tank falling back to repair; back to this much health when it's done
+#[derive(Component)]
+struct Repairing {
+    timer: Timer,
+    full: u8,
+}
+
+pub struct RepairsPlugin;
+
+impl Plugin for RepairsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (start_repairs, finish_repairs).chain().in_set(GameSet::Cleanup).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+// What a tank rolled out with, a mod's armour included
+fn full_health(kind: EnemyKind, variant: Option<&EnemyVariant>, cfg: &GameConfig) -> u8 {
+    variant.and_then(|v| v.hit_points).unwrap_or_else(|| kind.hit_points(cfg))
+}
+
+fn start_repairs(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    spawns: Option<Res<SpawnPoints>>,
+    mut q: Query<
+        (Entity, &Transform, &EnemyKind, &Health, &mut EnemyAI, Option<&EnemyVariant>),
+        (With<Enemy>, Changed<Health>, Without<Repairing>),
+    >,
+) {
+    let secs = cfg.enemy.repair_secs;
+    if secs <= 0.0 { return; }
+    for (e, t, kind, health, mut ai, variant) in &mut q {
+        let full = full_health(*kind, variant, &cfg);
+        if *kind == EnemyKind::Boss || health.0 != 1 || full <= 1 { continue; }
+
+        let pos = t.translation.truncate();
+        let nearest = spawns.iter().flat_map(|s| &s.points).min_by(|a, b| {
+            a.distance_squared(pos).total_cmp(&b.distance_squared(pos))
+        });
+        if let Some(&spawn) = nearest { ai.home = spawn; }
+        ai.state = EnemyState::Retreating;
+        ai.awareness = 0.0;
+        ai.retreat = Timer::from_seconds(secs, TimerMode::Once);
+        commands.entity(e).insert(Repairing { timer: Timer::from_seconds(secs, TimerMode::Once), full });
+    }
+}
+
+fn finish_repairs(
+    mut commands: Commands,
+    time: Res<Time>,
+    locale: Res<Locale>,
+    mut q: Query<(Entity, &Transform, &mut Health, &mut Repairing)>,
+) {
+    for (e, t, mut health, mut repair) in &mut q {
+        if !repair.timer.tick(time.delta()).finished() { continue; }
+        health.0 = repair.full;
+        commands.entity(e).remove::<Repairing>();
+        let text = locale.t("enemy.repaired");
+        spawn_floating_text(&mut commands, t.translation.truncate(), text, REPAIRED_COLOR, 12.0, 1.0, 24.0);
+    }
+}
@@ -0,0 +1,86 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::attract::Demo;
+use crate::drops::Coins;
+use crate::packs::LevelPacks;
+use crate::scores::Score;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, Lives, PlayerUpgradeLevel, persistence};
+
+// Run progress written when the game is closed mid-run, offered as "Continue"
+// on the loadout screen. A resumed run restarts the saved level from the top;
+// only the run-wide counters are kept. Only campaign runs of the built-in
+// maze are saved; a run in any other mode, or on a level pack or random
+// levels, leaves a saved campaign run where it is.
+
+const SAVE_FILE: &str = "savegame.ron";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct RunSnapshot {
+    pub level: usize,
+    pub score: u32,
+    pub lives: u8,
+    pub upgrade: u8,
+    // Missing from saves written before coins existed
+    #[serde(default)]
+    pub coins: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct SavedRun {
+    pub snapshot: Option<RunSnapshot>,
+    // Set by the loadout screen; consumed by start_run
+    pub resume: bool,
+}
+
+impl SavedRun {
+    fn load() -> Self {
+        let snapshot: Option<RunSnapshot> = persistence::load(SAVE_FILE);
+        Self { snapshot, resume: false }
+    }
+
+    // Forget the saved run, on disk too
+    pub fn discard(&mut self) {
+        self.snapshot = None;
+        persistence::remove(SAVE_FILE);
+    }
+}
+
+pub struct SaveGamePlugin;
+
+impl Plugin for SaveGamePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SavedRun::load())
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                discard_on_game_over
+                    .run_if(not(resource_exists::<Demo>))
+                    .run_if(resource_equals(GameMode::Campaign)),
+            )
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+fn save_on_exit(
+    mut exit: EventReader<AppExit>,
+    state: Res<State<AppState>>,
+    level: Res<CurrentLevel>,
+    score: Res<Score>,
+    coins: Res<Coins>,
+    lives: Res<Lives>,
+    upgrade: Res<PlayerUpgradeLevel>,
+    mode: Res<GameMode>,
+    packs: Res<LevelPacks>,
+    demo: Option<Res<Demo>>,
+) {
+    if exit.read().next().is_none() || *state.get() != AppState::Playing || demo.is_some() { return; }
+    if *mode != GameMode::Campaign || packs.own_levels() { return; }
+    let snapshot = RunSnapshot { level: level.0, score: score.0, lives: lives.0, upgrade: upgrade.0, coins: coins.0 };
+    persistence::save(SAVE_FILE, &Some(snapshot));
+}
+
+fn discard_on_game_over(packs: Res<LevelPacks>, mut saved: ResMut<SavedRun>) {
+    if packs.own_levels() { return; }
+    saved.discard();
+}
@@ -0,0 +1,485 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::args::LaunchArgs;
+use crate::attract::Demo;
+use crate::config::Difficulty;
+use crate::display::alt_held;
+use crate::drops::Coins;
+use crate::floating_text::spawn_floating_text;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::{Locale, Tr};
+use crate::persistence;
+use crate::profile::Profile;
+use crate::recap::{RunStats, clock, kill_breakdown};
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, EnemyKilled};
+
+// Run score, the persisted high-score tables, and the two screens around them:
+// game over (with name entry when the run makes a table) and the tables
+// themselves. Each difficulty keeps tables of its own: campaign runs, horde
+// runs ranked by wave, and survival runs ranked on time survived. Daily runs
+// keep their best per day in daily.ron instead, and the tutorial isn't ranked.
+
+const SCORES_FILE: &str = "scores.ron";
+const TABLE_SIZE: usize = 10;
+const NAME_MAX_LEN: usize = 10;
+const DEFAULT_NAME: &str = "PLAYER";
+const POPUP_SECS: f32 = 0.8;
+const POPUP_RISE: f32 = 28.0;
+const POPUP_COLOR: Color = Color::srgb(1.0, 0.95, 0.6);
+// A kill within COMBO_SECS of the last one carries on the combo; each kill
+// after the first in a combo is worth COMBO_BONUS more than the one before
+const COMBO_SECS: f32 = 2.0;
+const COMBO_BONUS: u32 = 50;
+const COMBO_COLOR: Color = Color::srgb(1.0, 0.55, 0.2);
+
+#[derive(Resource, Default)]
+pub struct Score(pub u32);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ScoreEntry {
+    pub name: String,
+    pub score: u32,
+    // 1-based level the run ended on
+    pub level: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SurvivalEntry {
+    pub name: String,
+    pub secs: u32,
+    pub kills: u32,
+}
+
+// One difficulty's best campaign and horde runs, highest score first (a horde
+// entry's level is the wave reached); best survival runs, longest first, then most kills.
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct Tables {
+    pub entries: Vec<ScoreEntry>,
+    pub horde: Vec<ScoreEntry>,
+    pub survival: Vec<SurvivalEntry>,
+}
+
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct HighScores {
+    pub tables: BTreeMap<Difficulty, Tables>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        persistence::load(SCORES_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(SCORES_FILE, self);
+    }
+
+    pub fn of(&mut self, difficulty: Difficulty) -> &mut Tables {
+        self.tables.entry(difficulty).or_default()
+    }
+}
+
+// The tables a run goes in are its difficulty's
+fn run_difficulty(args: &LaunchArgs, profile: &Profile) -> Difficulty {
+    args.difficulty.unwrap_or(profile.difficulty)
+}
+
+impl Tables {
+    // The table a mode's runs are ranked on by score; survival has a table of its own
+    fn runs(&mut self, mode: GameMode) -> Option<&mut Vec<ScoreEntry>> {
+        match mode {
+            GameMode::Campaign => Some(&mut self.entries),
+            GameMode::Horde => Some(&mut self.horde),
+            GameMode::Survival | GameMode::Daily | GameMode::Tutorial => None,
+        }
+    }
+
+    pub fn qualifies(&mut self, mode: GameMode, score: u32) -> bool {
+        let Some(runs) = self.runs(mode) else { return false; };
+        score > 0 && (runs.len() < TABLE_SIZE || runs.last().is_some_and(|e| score > e.score))
+    }
+
+    pub fn insert(&mut self, mode: GameMode, entry: ScoreEntry) {
+        let Some(runs) = self.runs(mode) else { return; };
+        runs.push(entry);
+        // Stable sort keeps the earlier run ahead on ties
+        runs.sort_by_key(|e| std::cmp::Reverse(e.score));
+        runs.truncate(TABLE_SIZE);
+    }
+
+    pub fn qualifies_survival(&self, secs: u32, kills: u32) -> bool {
+        let beats = |e: &SurvivalEntry| (secs, kills) > (e.secs, e.kills);
+        secs > 0 && (self.survival.len() < TABLE_SIZE || self.survival.last().is_some_and(beats))
+    }
+
+    pub fn insert_survival(&mut self, entry: SurvivalEntry) {
+        self.survival.push(entry);
+        self.survival.sort_by_key(|e| std::cmp::Reverse((e.secs, e.kills)));
+        self.survival.truncate(TABLE_SIZE);
+    }
+}
+
+// Kills in the current combo, and the time left to extend it
+#[derive(Resource)]
+struct Combo {
+    chain: u32,
+    window: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        let mut window = Timer::from_seconds(COMBO_SECS, TimerMode::Once);
+        window.tick(window.duration());
+        Self { chain: 0, window }
+    }
+}
+
+// Name typed on the game-over screen; None when the run didn't make the table.
+#[derive(Resource, Default)]
+struct NameEntry(Option<String>);
+
+#[derive(Component)]
+struct ScoreHud;
+
+#[derive(Component)]
+struct ComboHud;
+
+#[derive(Component)]
+struct GameOverScreen;
+
+#[derive(Component)]
+struct NameText;
+
+#[derive(Component)]
+struct HighScoreScreen;
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Score>()
+            .init_resource::<NameEntry>()
+            .init_resource::<Combo>()
+            .insert_resource(HighScores::load())
+            .add_systems(OnEnter(AppState::Playing), (reset_combo, spawn_score_hud))
+            .add_systems(OnExit(AppState::Playing), (despawn_screen::<ScoreHud>, despawn_screen::<ComboHud>))
+            .add_systems(OnEnter(AppState::GameOver), spawn_game_over_screen.run_if(not(resource_exists::<Demo>)))
+            .add_systems(OnExit(AppState::GameOver), despawn_screen::<GameOverScreen>)
+            .add_systems(OnEnter(AppState::HighScores), spawn_high_score_screen)
+            .add_systems(OnExit(AppState::HighScores), despawn_screen::<HighScoreScreen>)
+            .add_systems(
+                Update,
+                (
+                    (award_kills, update_score_hud, update_combo_hud).chain().run_if(in_state(AppState::Playing)),
+                    (name_entry_input, refresh_name_text).chain().run_if(in_state(AppState::GameOver)),
+                    high_score_input.run_if(in_state(AppState::HighScores)),
+                ),
+            );
+    }
+}
+
+fn despawn_screen<T: Component>(mut commands: Commands, q: Query<Entity, With<T>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+pub fn confirm_pressed(keys: &ButtonInput<KeyCode>, pads: &Query<&Gamepad>) -> bool {
+    (keys.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) && !alt_held(keys))
+        || pads.iter().any(|p| p.just_pressed(GamepadButton::South))
+}
+
+// === Playing ===
+// Each kill also pops its points up where the tank died, with the combo count
+// once one is going
+fn award_kills(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut ev: EventReader<EnemyKilled>,
+    mut score: ResMut<Score>,
+    mut combo: ResMut<Combo>,
+) {
+    if combo.window.tick(time.delta()).finished() { combo.chain = 0; }
+    for kill in ev.read().filter(|k| k.for_player()) {
+        combo.chain += 1;
+        combo.window.reset();
+        let bonus = COMBO_BONUS * (combo.chain - 1);
+        let points = kill.kind.points() + bonus;
+        score.0 += points;
+        let (text, color) = if bonus > 0 {
+            (format!("{points} x{}", combo.chain), COMBO_COLOR)
+        } else {
+            (points.to_string(), POPUP_COLOR)
+        };
+        spawn_floating_text(&mut commands, kill.pos, text, color, 14.0, POPUP_SECS, POPUP_RISE);
+    }
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+fn spawn_score_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 16.0, ..default() },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(6.0),
+            right: Val::Px(8.0),
+            ..default()
+        },
+        ScoreHud,
+    ));
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 16.0, ..default() },
+        TextColor(COMBO_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(6.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        ComboHud,
+    ));
+}
+
+fn update_score_hud(
+    score: Res<Score>,
+    coins: Res<Coins>,
+    locale: Res<Locale>,
+    mut q: Query<(&mut Text, Ref<ScoreHud>)>,
+) {
+    for (mut text, hud) in &mut q {
+        // Freshly spawned text needs filling even if nothing changed
+        if !(score.is_changed() || coins.is_changed() || locale.is_changed() || hud.is_added()) { continue; }
+        text.0 = locale.f("hud.score", &[("score", &score.0), ("coins", &coins.0)]);
+    }
+}
+
+// Shown from the second kill of a combo, fading as the time to extend it runs out
+fn update_combo_hud(combo: Res<Combo>, locale: Res<Locale>, mut q: Query<(&mut Text, &mut TextColor), With<ComboHud>>) {
+    for (mut text, mut color) in &mut q {
+        if combo.chain < 2 || combo.window.finished() {
+            text.0.clear();
+            continue;
+        }
+        text.0 = locale.f("hud.combo", &[("chain", &combo.chain)]);
+        color.0 = COMBO_COLOR.with_alpha(combo.window.fraction_remaining());
+    }
+}
+
+// === Game over ===
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    score: Res<Score>,
+    level: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    mode: Res<GameMode>,
+    args: Res<LaunchArgs>,
+    profile: Res<Profile>,
+    mut scores: ResMut<HighScores>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    mut entry: ResMut<NameEntry>,
+) {
+    let table = scores.of(run_difficulty(&args, &profile));
+    let survival = *mode == GameMode::Survival;
+    let qualifies = if survival {
+        table.qualifies_survival(run.secs as u32, run.kills.len() as u32)
+    } else {
+        table.qualifies(*mode, score.0)
+    };
+    entry.0 = qualifies.then(String::new);
+
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(12.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.9)),
+            GameOverScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::srgb(1.0, 0.3, 0.3)), Tr("game_over.title")));
+            root.spawn(Node { flex_direction: FlexDirection::Column, ..default() }).with_children(|breakdown| {
+                for line in kill_breakdown(run.kills.iter().copied(), &locale) {
+                    breakdown.spawn((Text::new(line), font(16.0), TextColor(Color::srgb(0.85, 0.85, 0.85))));
+                }
+            });
+            let result = if survival {
+                let args: [(&str, &dyn std::fmt::Display); 3] =
+                    [("time", &clock(run.secs)), ("kills", &run.kills.len()), ("score", &score.0)];
+                locale.f("game_over.survival", &args)
+            } else if *mode == GameMode::Horde {
+                locale.f("game_over.horde", &[("score", &score.0), ("wave", &(level.0 + 1))])
+            } else {
+                locale.f("game_over.campaign", &[("score", &score.0), ("level", &(level.0 + 1))])
+            };
+            root.spawn((Text::new(result), font(20.0), TextColor(Color::WHITE)));
+            if qualifies {
+                let banner = if survival { "game_over.best_survival" } else { "game_over.high_score" };
+                root.spawn((Text::new(""), font(20.0), TextColor(Color::srgb(1.0, 0.9, 0.3)), Tr(banner)));
+                root.spawn((Text::new(""), font(20.0), TextColor(Color::WHITE), NameText));
+            }
+            root.spawn((
+                Text::new(format!("{}: {}", glyph(Action::Confirm, *device), locale.t("hint.continue"))),
+                font(16.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn name_entry_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut typed: EventReader<KeyboardInput>,
+    mut entry: ResMut<NameEntry>,
+    mut scores: ResMut<HighScores>,
+    score: Res<Score>,
+    level: Res<CurrentLevel>,
+    run: Res<RunStats>,
+    mode: Res<GameMode>,
+    args: Res<LaunchArgs>,
+    profile: Res<Profile>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    // Only flag the entry as changed on actual key presses so refresh_name_text stays idle
+    let presses: Vec<Key> = typed.read().filter(|ev| ev.state.is_pressed()).map(|ev| ev.logical_key.clone()).collect();
+    let edited = match entry.bypass_change_detection().0.as_mut() {
+        Some(name) if !presses.is_empty() => {
+            for key in &presses {
+                match key {
+                    Key::Backspace => { name.pop(); }
+                    Key::Character(s) => {
+                        for c in s.chars().filter(|c| c.is_ascii_alphanumeric() || *c == ' ') {
+                            if name.len() < NAME_MAX_LEN { name.push(c.to_ascii_uppercase()); }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            true
+        }
+        _ => false,
+    };
+    if edited { entry.set_changed(); }
+
+    if !confirm_pressed(&keys, &pads) { return; }
+    if let Some(name) = entry.0.take() {
+        let name = name.trim();
+        let name = if name.is_empty() { DEFAULT_NAME.to_string() } else { name.to_string() };
+        let table = scores.of(run_difficulty(&args, &profile));
+        match *mode {
+            GameMode::Survival => {
+                table.insert_survival(SurvivalEntry { name, secs: run.secs as u32, kills: run.kills.len() as u32 });
+            }
+            _ => table.insert(*mode, ScoreEntry { name, score: score.0, level: level.0 + 1 }),
+        }
+        scores.save();
+    }
+    next.set(AppState::HighScores);
+}
+
+fn refresh_name_text(entry: Res<NameEntry>, locale: Res<Locale>, mut q: Query<&mut Text, With<NameText>>) {
+    if !entry.is_changed() { return; }
+    let Some(name) = entry.0.as_ref() else { return; };
+    for mut text in &mut q {
+        text.0 = locale.f("game_over.name", &[("name", name)]);
+    }
+}
+
+// === High-score table ===
+// The tables for the difficulty that's set, which is the one a run would be played on
+fn spawn_high_score_screen(
+    mut commands: Commands,
+    mut scores: ResMut<HighScores>,
+    args: Res<LaunchArgs>,
+    profile: Res<Profile>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    let difficulty = run_difficulty(&args, &profile);
+    let table = scores.of(difficulty);
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            HighScoreScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("scores.title")));
+            root.spawn((
+                Text::new(format!("{}: {}", locale.t("settings.difficulty"), locale.t(difficulty.label()))),
+                font(16.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+            if table.entries.is_empty() {
+                root.spawn((Text::new(""), font(20.0), TextColor(Color::srgb(0.7, 0.7, 0.7)), Tr("scores.empty")));
+            }
+            for (i, e) in table.entries.iter().enumerate() {
+                root.spawn((
+                    Text::new(format!("{:>2}. {:<10} {:>7}   L{}", i + 1, e.name, e.score, e.level)),
+                    font(20.0),
+                    TextColor(if i == 0 { Color::srgb(1.0, 0.9, 0.3) } else { Color::WHITE }),
+                ));
+            }
+            if !table.horde.is_empty() {
+                root.spawn((Text::new(""), font(24.0), TextColor(Color::WHITE), Tr("scores.horde")));
+            }
+            for (i, e) in table.horde.iter().enumerate() {
+                root.spawn((
+                    Text::new(format!("{:>2}. {:<10} {:>7}   W{}", i + 1, e.name, e.score, e.level)),
+                    font(20.0),
+                    TextColor(if i == 0 { Color::srgb(1.0, 0.9, 0.3) } else { Color::WHITE }),
+                ));
+            }
+            if !table.survival.is_empty() {
+                root.spawn((Text::new(""), font(24.0), TextColor(Color::WHITE), Tr("scores.survival")));
+            }
+            for (i, e) in table.survival.iter().enumerate() {
+                let kills = locale.f("scores.kills", &[("kills", &e.kills)]);
+                root.spawn((
+                    Text::new(format!("{:>2}. {:<10} {:>7}   {kills}", i + 1, e.name, clock(e.secs as f32))),
+                    font(20.0),
+                    TextColor(if i == 0 { Color::srgb(1.0, 0.9, 0.3) } else { Color::WHITE }),
+                ));
+            }
+            root.spawn((
+                Text::new(format!("{}: {}", glyph(Action::Confirm, *device), locale.t("hint.back"))),
+                font(16.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn high_score_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if confirm_pressed(&keys, &pads) || keys.just_pressed(KeyCode::Escape) {
+        next.set(AppState::Loadout);
+    }
+}
@@ -0,0 +1,323 @@
+use bevy::ecs::schedule::Condition as _;
+use bevy::prelude::*;
+
+use crate::args::LaunchArgs;
+use crate::config::GameConfig;
+use crate::level_reload::LevelReloaded;
+use crate::packs::{LevelPacks, custom_level};
+use crate::patrols::PatrolRoute;
+use crate::recap::StageRecap;
+use crate::toasts::Notify;
+use crate::triggers::ZoneSpec;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, EnemyKind, GameSet, LevelBounds, RestartEvent, SpawnPoints, TileKind, spawn_telegraph, start_run};
+
+// Level scripts: a --level file, or a level in a pack, can follow its maze
+// with a blank line and a list of events, one per line, each a trigger and an
+// action:
+//
+//     at 30s spawn 4 fast tanks at spawn 2
+//     when bricks at (5,7..9) destroyed, open gate at (6,3)
+//     at 5s say Watch the left flank!
+//
+// Triggers are a time into the level (game time, so a pause stops the clock),
+// or every brick in an area being gone. Actions roll extra tanks out of a
+// spawn point (counted from 1, in reading order of the maze), clear whatever
+// walls stand in an area, or show a message. Cells are (column,row) from the
+// top left, counted from 0, and either can be a range such as 7..9. Each event
+// fires once per level and they're checked every tick, in the order written.
+// The file is checked as it's read, so a script that can't run never starts.
+//
+// The same list can lay out named trigger zones (see triggers.rs) and
+// patrolling barriers (see patrols.rs), which are part of the level rather
+// than events: a zone's name and its area, and a route of two cells or more,
+// in the order they're driven, back round to the first, and pixels a second:
+//
+//     zone base_approach at (9..13,11..13)
+//     patrol (7,9) to (11,9) at 60
+
+#[derive(Clone, Copy, Debug)]
+struct Area {
+    min: UVec2,
+    max: UVec2,
+}
+
+impl Area {
+    fn contains(&self, cell: UVec2) -> bool {
+        cell.cmpge(self.min).all() && cell.cmple(self.max).all()
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Condition {
+    // Seconds into the level
+    After(f32),
+    BricksGone(Area),
+}
+
+#[derive(Clone, Debug)]
+enum Effect {
+    // Spawn point index from 0
+    Spawn { count: usize, kind: EnemyKind, point: usize },
+    Open(Area),
+    Say(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct ScriptEvent {
+    condition: Condition,
+    effect: Effect,
+}
+
+// What the lines after a maze hold
+#[derive(Default)]
+pub struct Section {
+    pub events: Vec<ScriptEvent>,
+    pub zones: Vec<ZoneSpec>,
+    pub patrols: Vec<PatrolRoute>,
+}
+
+// `first_line` is the file's line number of lines[0], for the messages
+pub fn parse(lines: &[String], first_line: usize, maze: &[String]) -> Result<Section, String> {
+    let spawns = maze.iter().map(|r| r.matches('S').count()).sum::<usize>();
+    let size = UVec2::new(maze[0].len() as u32, maze.len() as u32);
+    let mut section = Section::default();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") { continue; }
+        let at_line = |e: String| format!("line {}: {e}", first_line + i);
+        if let Some(zone) = line.strip_prefix("zone ") {
+            let zone = parse_zone(zone).and_then(|zone| check_zone(&zone, size).map(|()| zone));
+            section.zones.push(zone.map_err(at_line)?);
+            continue;
+        }
+        if let Some(route) = line.strip_prefix("patrol ") {
+            let route = parse_patrol(route).and_then(|route| check_route(&route, size).map(|()| route));
+            section.patrols.push(route.map_err(at_line)?);
+            continue;
+        }
+        let event = parse_event(line).and_then(|event| event.check(spawns, size).map(|()| event)).map_err(at_line)?;
+        section.events.push(event);
+    }
+    Ok(section)
+}
+
+fn parse_event(line: &str) -> Result<ScriptEvent, String> {
+    let (condition, action) = if let Some(rest) = line.strip_prefix("at ") {
+        let (time, action) = rest.split_once(' ').ok_or("'at' needs a time and an action")?;
+        let secs = time.strip_suffix('s').and_then(|t| t.parse::<f32>().ok());
+        let secs = secs.filter(|s| *s >= 0.0).ok_or_else(|| format!("{time} isn't a time like 30s"))?;
+        (Condition::After(secs), action)
+    } else if let Some(rest) = line.strip_prefix("when ") {
+        let (condition, action) = rest.split_once(',').ok_or("'when' needs a comma before its action")?;
+        let area = condition
+            .trim()
+            .strip_prefix("bricks at ")
+            .and_then(|c| c.strip_suffix(" destroyed"))
+            .ok_or_else(|| format!("no trigger '{}'", condition.trim()))?;
+        (Condition::BricksGone(parse_area(area)?), action)
+    } else {
+        return Err(format!("'{line}' doesn't start with 'at' or 'when'"));
+    };
+    Ok(ScriptEvent { condition, effect: parse_effect(action.trim())? })
+}
+
+fn parse_effect(action: &str) -> Result<Effect, String> {
+    if let Some(text) = action.strip_prefix("say ") {
+        return Ok(Effect::Say(text.trim().to_string()));
+    }
+    if let Some(area) = action.strip_prefix("open gate at ") {
+        return Ok(Effect::Open(parse_area(area)?));
+    }
+    // spawn <count> <kind> tank(s) at spawn <n>
+    let words: Vec<&str> = action.split_whitespace().collect();
+    let ["spawn", count, kind, "tank" | "tanks", "at", "spawn", point] = words[..] else {
+        return Err(format!("no action '{action}'"));
+    };
+    let tanks = count.parse::<usize>().ok().filter(|&n| n > 0);
+    let count = tanks.ok_or_else(|| format!("{count} isn't a number of tanks"))?;
+    let kind = kind_named(kind).ok_or_else(|| format!("no enemy kind {kind}"))?;
+    let point = point.parse::<usize>().ok().filter(|&n| n > 0).ok_or_else(|| format!("no spawn {point}"))?;
+    Ok(Effect::Spawn { count, kind, point: point - 1 })
+}
+
+// <name> at (c,r), where either may be a range
+fn parse_zone(text: &str) -> Result<ZoneSpec, String> {
+    let (name, area) = text.split_once(" at ").ok_or("a zone needs a name and an area, as in 'door at (3,4..5)'")?;
+    let name = name.trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(format!("'{name}' isn't a one-word zone name"));
+    }
+    let Area { min, max } = parse_area(area)?;
+    Ok(ZoneSpec { name: name.to_string(), at: min, size: max - min + UVec2::ONE })
+}
+
+pub fn check_zone(zone: &ZoneSpec, size: UVec2) -> Result<(), String> {
+    if zone.size.cmpgt(UVec2::ZERO).all() && (zone.at + zone.size).cmple(size).all() {
+        Ok(())
+    } else {
+        Err(format!("zone {} is outside the maze", zone.name))
+    }
+}
+
+// (c,r) to (c,r) ... at <speed>
+fn parse_patrol(text: &str) -> Result<PatrolRoute, String> {
+    let (route, speed) = text.rsplit_once(" at ").ok_or("a patrol needs a speed, as in 'at 60'")?;
+    let speed = speed.trim();
+    let speed = speed.parse::<f32>().ok().filter(|s| *s > 0.0).ok_or_else(|| format!("{speed} isn't a speed"))?;
+    let cells = route
+        .split(" to ")
+        .map(|cell| match parse_area(cell)? {
+            Area { min, max } if min == max => Ok(min),
+            _ => Err(format!("{} is more than one cell", cell.trim())),
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    if cells.len() < 2 { return Err("a patrol needs two waypoints or more".to_string()); }
+    Ok(PatrolRoute { cells, speed })
+}
+
+// A route off the maze would take the barrier through the boundary wall
+pub fn check_route(route: &PatrolRoute, size: UVec2) -> Result<(), String> {
+    match route.cells.iter().find(|cell| !cell.cmplt(size).all()) {
+        Some(cell) => Err(format!("the patrol's waypoint ({},{}) is outside the maze", cell.x, cell.y)),
+        None => Ok(()),
+    }
+}
+
+// The kinds a script can roll out; the boss only comes on its own levels
+pub fn kind_named(name: &str) -> Option<EnemyKind> {
+    match name {
+        "basic" => Some(EnemyKind::Basic),
+        "fast" => Some(EnemyKind::Fast),
+        "power" => Some(EnemyKind::Power),
+        "armor" | "armored" => Some(EnemyKind::Armor),
+        "saboteur" => Some(EnemyKind::Saboteur),
+        _ => None,
+    }
+}
+
+// (c,r), where either may be a range a..b; spaces are allowed inside
+fn parse_area(text: &str) -> Result<Area, String> {
+    let inner = text.trim().strip_prefix('(').and_then(|t| t.strip_suffix(')'));
+    let (col, row) = inner.and_then(|t| t.split_once(',')).ok_or_else(|| format!("{text} isn't a cell like (5,7)"))?;
+    let range = |part: &str| -> Result<(u32, u32), String> {
+        let part = part.trim();
+        let (a, b) = part.split_once("..").unwrap_or((part, part));
+        match (a.trim().parse(), b.trim().parse()) {
+            (Ok(a), Ok(b)) if a <= b => Ok((a, b)),
+            _ => Err(format!("{part} isn't a cell number or a range like 7..9")),
+        }
+    };
+    let ((c0, c1), (r0, r1)) = (range(col)?, range(row)?);
+    Ok(Area { min: UVec2::new(c0, r0), max: UVec2::new(c1, r1) })
+}
+
+impl ScriptEvent {
+    // Against the maze the script came with
+    fn check(&self, spawns: usize, size: UVec2) -> Result<(), String> {
+        let inside = |area: &Area| {
+            if area.max.cmplt(size).all() { Ok(()) } else { Err("that area is outside the maze".to_string()) }
+        };
+        if let Condition::BricksGone(area) = &self.condition { inside(area)?; }
+        match &self.effect {
+            Effect::Open(area) => inside(area),
+            Effect::Spawn { point, .. } if *point >= spawns => Err(format!("the maze has {spawns} spawn points")),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Present while a level with a script is played
+#[derive(Resource)]
+struct LevelScript {
+    events: Vec<ScriptEvent>,
+    elapsed: f32,
+    fired: Vec<bool>,
+}
+
+pub struct ScriptPlugin;
+
+impl Plugin for ScriptPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), start_script.after(start_run))
+            .add_systems(OnExit(AppState::Playing), end_script)
+            .add_systems(
+                Update,
+                (
+                    // A new level, or an edit to its file, starts the script over
+                    start_script.run_if(on_event::<RestartEvent>.or(on_event::<LevelReloaded>)),
+                    run_script.run_if(resource_exists::<LevelScript>).run_if(not(resource_exists::<StageRecap>)),
+                )
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+// The tutorial plays its own maze, so the script doesn't go with it
+fn start_script(
+    mut commands: Commands,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    level: Res<CurrentLevel>,
+    mode: Res<GameMode>,
+) {
+    let events = custom_level(&args, &packs, level.0).map_or(&[][..], |l| &l.script[..]);
+    if events.is_empty() || *mode == GameMode::Tutorial {
+        commands.remove_resource::<LevelScript>();
+    } else {
+        let fired = vec![false; events.len()];
+        commands.insert_resource(LevelScript { events: events.to_vec(), elapsed: 0.0, fired });
+    }
+}
+
+fn end_script(mut commands: Commands) {
+    commands.remove_resource::<LevelScript>();
+}
+
+// Gates are brick and steel walls; the base and the level's edges stay put
+fn run_script(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    bounds: Res<LevelBounds>,
+    spawns: Res<SpawnPoints>,
+    mut script: ResMut<LevelScript>,
+    mut notify: EventWriter<Notify>,
+    q_tiles: Query<(Entity, &Transform, &TileKind)>,
+) {
+    let script = &mut *script;
+    script.elapsed += time.delta_secs();
+    let cell = |t: &Transform| bounds.cell_at(t.translation.truncate());
+    for (event, fired) in script.events.iter().zip(&mut script.fired) {
+        if *fired { continue; }
+        let due = match &event.condition {
+            Condition::After(secs) => script.elapsed >= *secs,
+            Condition::BricksGone(area) => !q_tiles
+                .iter()
+                .any(|(_, t, kind)| *kind == TileKind::Brick && cell(t).is_some_and(|c| area.contains(c))),
+        };
+        if !due { continue; }
+        *fired = true;
+
+        match &event.effect {
+            // One after another, so they don't all appear on the tile at once
+            Effect::Spawn { count, kind, point } => {
+                let Some(&pos) = spawns.points.get(*point) else { continue; };
+                for n in 0..*count {
+                    let secs = cfg.enemy.spawn_telegraph_secs * (n + 1) as f32;
+                    spawn_telegraph(&mut commands, pos, *kind, false, &cfg, secs);
+                }
+            }
+            Effect::Open(area) => {
+                for (e, t, _) in &q_tiles {
+                    if cell(t).is_some_and(|c| area.contains(c)) { commands.entity(e).despawn(); }
+                }
+            }
+            Effect::Say(text) => {
+                notify.write(Notify::new(text.clone()));
+            }
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use bevy::prelude::*;
+use rhai::{AST, Array, Dynamic, Engine, EvalAltResult, FLOAT, FuncArgs, INT, ImmutableString, Scope};
+use std::collections::HashSet;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use crate::args::{LaunchArgs, LevelFile};
+use crate::config::GameConfig;
+use crate::feedback::HitEvent;
+use crate::level_reload::LevelReloaded;
+use crate::locale::Locale;
+use crate::mods::mods_dir;
+use crate::packs::{LevelPacks, custom_level};
+use crate::recap::StageRecap;
+use crate::script::kind_named;
+use crate::themes::LevelTheme;
+use crate::toasts::Notify;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{
+    AppState, Base, Enemy, EnemyKilled, EnemyKind, GameSet, LevelBounds, Player, RestartEvent, SpawnPoints, TILE,
+    TileKind, Wall, build_maze, spawn_telegraph, spawn_wall,
+};
+
+// Rhai scripting, for builds with the scripting feature (see Cargo.toml): a
+// level can have a `.rhai` file beside it (`name.rhai` for `name.txt`, in a
+// level pack too), and every `.rhai` file in assets/mods/ runs on every level.
+// A script defines any of these, and the game calls them:
+//
+//     fn on_start() { ... }                   // the level has been built
+//     fn on_tick(secs) { ... }                // every tick, with the level time
+//     fn on_hit(target, col, row, fatal) { }  // "player", "enemy", "base", "wall" or "other"
+//     fn on_kill(kind, col, row) { ... }      // an enemy tank was destroyed
+//     fn on_signal(name) { ... }              // some script called emit(name)
+//
+// What a script can do is kept to what a level script could: say(text),
+// spawn(kind, point) from a spawn point counted from 1 (the boss too),
+// open(col, row) to clear a wall, build("brick" or "steel", col, row) on an
+// empty cell, emit(name), and read player() and enemies() for the cells
+// they're in. Calls only queue the change, which is made once the script
+// returns, so a script never sees the world half changed. There's no file or
+// network access, and each call is cut off after a budget of operations; a
+// script that fails is stopped for the rest of the level, with a message on
+// screen. Cells are (column,row) from the top left, counted from 0.
+
+// Per call into a script, so a runaway loop can't hang a frame
+const MAX_OPERATIONS: u64 = 50_000;
+const HOOKS: [&str; 5] = ["on_start", "on_tick", "on_hit", "on_kill", "on_signal"];
+const FAILED_COLOR: Color = Color::srgb(1.0, 0.4, 0.3);
+
+enum Request {
+    Say(String),
+    Spawn { kind: EnemyKind, point: usize },
+    Open(UVec2),
+    Build { kind: TileKind, cell: UVec2 },
+    Emit(String),
+}
+
+// What the API functions read and write, as the engine holds them
+#[derive(Default)]
+struct Shared {
+    requests: Vec<Request>,
+    player: Option<UVec2>,
+    enemies: Vec<UVec2>,
+}
+
+// Sent for every emit() a script makes; scripts hear it on the next tick
+#[derive(Event)]
+pub struct ScriptSignal(pub String);
+
+struct Loaded {
+    name: String,
+    ast: AST,
+    scope: Scope<'static>,
+    // The hooks it defines
+    hooks: HashSet<String>,
+    failed: bool,
+}
+
+#[derive(Resource)]
+struct Scripting {
+    engine: Engine,
+    shared: Arc<Mutex<Shared>>,
+    mods: Vec<Loaded>,
+    level: Option<Loaded>,
+    elapsed: f32,
+}
+
+impl Scripting {
+    fn new() -> Self {
+        let shared = Arc::new(Mutex::new(Shared::default()));
+        let engine = engine(&shared);
+        let mut scripting = Self { engine, shared, mods: Vec::new(), level: None, elapsed: 0.0 };
+        let Ok(entries) = fs::read_dir(mods_dir()) else { return scripting; };
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("rhai")))
+            .collect();
+        paths.sort();
+        for path in paths {
+            let name = path.display().to_string();
+            let src = fs::read_to_string(&path).map_err(|e| e.to_string());
+            match src.and_then(|src| scripting.load(&name, &src)) {
+                Ok(script) => scripting.mods.push(script),
+                Err(e) => warn!("skipping mod script {name}: {e}"),
+            }
+        }
+        scripting
+    }
+
+    // Compiled, and its top level run once
+    fn load(&self, name: &str, src: &str) -> Result<Loaded, String> {
+        let ast = self.engine.compile(src).map_err(|e| e.to_string())?;
+        let mut scope = Scope::new();
+        self.engine.run_ast_with_scope(&mut scope, &ast).map_err(|e| e.to_string())?;
+        let hooks = ast.iter_functions().map(|f| f.name.to_string()).filter(|n| HOOKS.contains(&n.as_str())).collect();
+        Ok(Loaded { name: name.to_string(), ast, scope, hooks, failed: false })
+    }
+
+    // Into every script that defines the hook; the failures, for the screen
+    fn call(&mut self, hook: &str, args: impl FuncArgs + Clone) -> Vec<String> {
+        let mut failures = Vec::new();
+        for script in self.mods.iter_mut().chain(self.level.as_mut()) {
+            if script.failed || !script.hooks.contains(hook) { continue; }
+            let result = self.engine.call_fn::<Dynamic>(&mut script.scope, &script.ast, hook, args.clone());
+            if let Err(e) = result {
+                script.failed = true;
+                warn!("script {} stopped in {hook}: {e}", script.name);
+                failures.push(format!("{}: {e}", script.name));
+            }
+        }
+        failures
+    }
+}
+
+fn engine(shared: &Arc<Mutex<Shared>>) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(1024);
+    engine.on_print(|text| info!("script: {text}"));
+
+    let cell = |col: INT, row: INT| -> Result<UVec2, Box<EvalAltResult>> {
+        match (u32::try_from(col), u32::try_from(row)) {
+            (Ok(c), Ok(r)) => Ok(UVec2::new(c, r)),
+            _ => Err(format!("({col},{row}) isn't a cell").into()),
+        }
+    };
+    let queue = |shared: &Arc<Mutex<Shared>>, request: Request| {
+        shared.lock().expect("script state lock").requests.push(request);
+    };
+
+    let s = shared.clone();
+    engine.register_fn("say", move |text: ImmutableString| queue(&s, Request::Say(text.to_string())));
+    let s = shared.clone();
+    engine.register_fn("emit", move |name: ImmutableString| queue(&s, Request::Emit(name.to_string())));
+    let s = shared.clone();
+    engine.register_fn("spawn", move |kind: ImmutableString, point: INT| -> Result<(), Box<EvalAltResult>> {
+        let named = if kind.as_str() == "boss" { Some(EnemyKind::Boss) } else { kind_named(kind.as_str()) };
+        let kind = named.ok_or_else(|| format!("no enemy kind {kind}"))?;
+        let point = usize::try_from(point).ok().filter(|&n| n > 0).ok_or_else(|| format!("no spawn {point}"))?;
+        queue(&s, Request::Spawn { kind, point: point - 1 });
+        Ok(())
+    });
+    let s = shared.clone();
+    engine.register_fn("open", move |col: INT, row: INT| -> Result<(), Box<EvalAltResult>> {
+        queue(&s, Request::Open(cell(col, row)?));
+        Ok(())
+    });
+    let s = shared.clone();
+    engine.register_fn("build", move |kind: ImmutableString, col: INT, row: INT| -> Result<(), Box<EvalAltResult>> {
+        let kind = match kind.as_str() {
+            "brick" => TileKind::Brick,
+            "steel" => TileKind::Steel,
+            _ => return Err(format!("can't build {kind}, only brick or steel").into()),
+        };
+        queue(&s, Request::Build { kind, cell: cell(col, row)? });
+        Ok(())
+    });
+    let pair = |c: UVec2| -> Dynamic { Dynamic::from_array(vec![(c.x as INT).into(), (c.y as INT).into()]) };
+    let s = shared.clone();
+    engine.register_fn("player", move || -> Dynamic {
+        s.lock().expect("script state lock").player.map_or(Dynamic::UNIT, pair)
+    });
+    let s = shared.clone();
+    engine.register_fn("enemies", move || -> Array {
+        s.lock().expect("script state lock").enemies.iter().map(|&c| pair(c)).collect()
+    });
+    engine
+}
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Scripting::new())
+            .add_event::<ScriptSignal>()
+            .add_systems(OnEnter(AppState::Playing), start_scripts.after(build_maze))
+            .add_systems(
+                Update,
+                (
+                    // A new level, or an edit to its file, starts its script over
+                    start_scripts.run_if(on_event::<RestartEvent>.or(on_event::<LevelReloaded>)),
+                    run_scripts.run_if(not(resource_exists::<StageRecap>)),
+                )
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+// The tutorial plays its own maze, so a level's script doesn't go with it
+fn start_scripts(
+    mut scripting: ResMut<Scripting>,
+    args: Res<LaunchArgs>,
+    packs: Res<LevelPacks>,
+    level: Res<CurrentLevel>,
+    mode: Res<GameMode>,
+    locale: Res<Locale>,
+    mut notify: EventWriter<Notify>,
+) {
+    let mut failures = Vec::new();
+    let file = custom_level(&args, &packs, level.0).filter(|_| *mode != GameMode::Tutorial);
+    scripting.level = file.and_then(|file| {
+        let src = file.code.as_deref()?;
+        let name = LevelFile::code_name(&file.name);
+        scripting.load(&name, src).map_err(|e| failures.push(format!("{name}: {e}"))).ok()
+    });
+    scripting.elapsed = 0.0;
+    failures.extend(scripting.call("on_start", ()));
+    for error in failures {
+        notify.write(Notify::new(locale.f("scripting.failed", &[("error", &error)])).with_color(FAILED_COLOR));
+    }
+}
+
+fn run_scripts(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    bounds: Res<LevelBounds>,
+    spawns: Res<SpawnPoints>,
+    theme: Res<LevelTheme>,
+    locale: Res<Locale>,
+    mut scripting: ResMut<Scripting>,
+    mut hits: EventReader<HitEvent>,
+    mut kills: EventReader<EnemyKilled>,
+    // Heard, then sent: the same events both ways
+    mut signals: ParamSet<(EventReader<ScriptSignal>, EventWriter<ScriptSignal>)>,
+    mut notify: EventWriter<Notify>,
+    q_targets: Query<(&Transform, Has<Player>, Has<Enemy>, Has<Base>, Has<Wall>)>,
+    q_walls: Query<(Entity, &Transform), (With<Wall>, Without<Base>)>,
+) {
+    let cell = |t: &Transform| bounds.cell_at(t.translation.truncate());
+    let player = q_targets.iter().find(|(_, player, ..)| *player).and_then(|(t, ..)| cell(t));
+    let enemies: Vec<UVec2> =
+        q_targets.iter().filter(|(_, _, enemy, ..)| *enemy).filter_map(|(t, ..)| cell(t)).collect();
+    {
+        let mut shared = scripting.shared.lock().expect("script state lock");
+        shared.player = player;
+        shared.enemies.clone_from(&enemies);
+    }
+
+    let scripting = &mut *scripting;
+    scripting.elapsed += time.delta_secs();
+    let mut failures = scripting.call("on_tick", (scripting.elapsed as FLOAT,));
+    for hit in hits.read() {
+        // Gone already if it was destroyed by an earlier hit this tick
+        let Ok((t, player, enemy, base, wall)) = q_targets.get(hit.target) else { continue; };
+        let Some(at) = cell(t) else { continue; };
+        let target = match (player, enemy, base, wall) {
+            (true, ..) => "player",
+            (_, true, ..) => "enemy",
+            (_, _, true, _) => "base",
+            (.., true) => "wall",
+            _ => "other",
+        };
+        failures.extend(scripting.call("on_hit", (target.to_string(), at.x as INT, at.y as INT, hit.fatal)));
+    }
+    for kill in kills.read() {
+        let Some(at) = bounds.cell_at(kill.pos) else { continue; };
+        let kind = kill.kind.label().to_lowercase();
+        failures.extend(scripting.call("on_kill", (kind, at.x as INT, at.y as INT)));
+    }
+    let heard: Vec<String> = signals.p0().read().map(|ScriptSignal(name)| name.clone()).collect();
+    for name in heard {
+        failures.extend(scripting.call("on_signal", (name,)));
+    }
+    for error in failures {
+        notify.write(Notify::new(locale.f("scripting.failed", &[("error", &error)])).with_color(FAILED_COLOR));
+    }
+
+    let requests = std::mem::take(&mut scripting.shared.lock().expect("script state lock").requests);
+    let center = |c: UVec2| bounds.origin + Vec2::new(c.x as f32, -(c.y as f32)) * TILE;
+    let inside = |c: UVec2| (c.x as usize) < bounds.cols && (c.y as usize) < bounds.rows;
+    for request in requests {
+        match request {
+            Request::Say(text) => {
+                notify.write(Notify::new(text));
+            }
+            Request::Emit(name) => {
+                signals.p1().write(ScriptSignal(name));
+            }
+            Request::Spawn { kind, point } => match spawns.points.get(point) {
+                Some(&pos) => spawn_telegraph(&mut commands, pos, kind, false, &cfg, cfg.enemy.spawn_telegraph_secs),
+                None => warn!("a script spawned from spawn {}, and the maze has {}", point + 1, spawns.points.len()),
+            },
+            // The base and the level's edges stay put, as for level scripts
+            Request::Open(at) => {
+                let (col, row) = (at.x as usize, at.y as usize);
+                if col == 0 || row == 0 || col + 1 >= bounds.cols || row + 1 >= bounds.rows { continue; }
+                for (e, t) in &q_walls {
+                    if cell(t) == Some(at) { commands.entity(e).despawn(); }
+                }
+            }
+            // Only on an empty cell, so nothing is walled in
+            Request::Build { kind, cell: at } => {
+                let taken = q_walls.iter().any(|(_, t)| cell(t) == Some(at));
+                if !inside(at) || taken || player == Some(at) || enemies.contains(&at) { continue; }
+                spawn_wall(&mut commands, center(at), kind, &theme);
+            }
+        }
+    }
+}
@@ -0,0 +1,399 @@
+use bevy::audio::{GlobalVolume, Volume};
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowMode};
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::accessibility::Palette;
+use crate::adaptive::Pacing;
+use crate::args::LaunchArgs;
+use crate::attract::Demo;
+use crate::config::{Difficulty, GameConfig};
+use crate::display::{DisplaySettings, MAX_ZOOM, RESOLUTIONS, present_mode, window_mode};
+use crate::game_time::GameTime;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::{Language, Locale, Tr};
+use crate::profile::Profile;
+use crate::recap::StageRecap;
+use crate::scores::confirm_pressed;
+use crate::touch::TouchMode;
+
+// Settings: video, audio, accessibility, gameplay and language options on one
+// panel, opened from the loadout screen or over a run (which pauses while it's
+// up). Every change takes effect as it's made and is saved to the profile when
+// the panel closes. A new difficulty rescales config.ron straight away, but the lives
+// it gives only come with the next run.
+
+const VOLUME_STEP: f32 = 0.1;
+const SELECTED_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct AudioSettings {
+    // Both from 0.0 to 1.0; effects are scaled by the master level too
+    pub master: f32,
+    pub sfx: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self { master: 1.0, sfx: 1.0 }
+    }
+}
+
+// Which keyboard keys drive the tank; gamepads are the same either way
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ControlScheme {
+    // W/A/S/D or arrows, Space fires, Shift for the utility, E lays a mine
+    #[default]
+    Standard,
+    // Arrows only, with Z/X/C for fire, utility and mine
+    Arcade,
+}
+
+impl ControlScheme {
+    // Key into the language files
+    pub fn label(self) -> &'static str {
+        match self {
+            ControlScheme::Standard => "controls.standard",
+            ControlScheme::Arcade => "controls.arcade",
+        }
+    }
+
+    fn cycled(self) -> Self {
+        match self {
+            ControlScheme::Standard => ControlScheme::Arcade,
+            ControlScheme::Arcade => ControlScheme::Standard,
+        }
+    }
+}
+
+pub fn master_volume(args: &LaunchArgs, profile: &Profile) -> Volume {
+    Volume::Linear(if args.mute { 0.0 } else { profile.audio.master })
+}
+
+// For one-shot sound effects, at the level set for them
+pub fn sfx(profile: &Profile) -> PlaybackSettings {
+    PlaybackSettings::DESPAWN.with_volume(Volume::Linear(profile.audio.sfx))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Row {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    Zoom,
+    PixelPerfect,
+    Master,
+    Sfx,
+    Shake,
+    Palette,
+    Outlines,
+    ReducedFlashes,
+    AimAssist,
+    Controls,
+    Touch,
+    Difficulty,
+    Adaptive,
+    Language,
+}
+
+impl Row {
+    const ALL: [Row; 17] = [
+        Row::Resolution,
+        Row::Fullscreen,
+        Row::Vsync,
+        Row::Zoom,
+        Row::PixelPerfect,
+        Row::Master,
+        Row::Sfx,
+        Row::Shake,
+        Row::Palette,
+        Row::Outlines,
+        Row::ReducedFlashes,
+        Row::AimAssist,
+        Row::Controls,
+        Row::Touch,
+        Row::Difficulty,
+        Row::Adaptive,
+        Row::Language,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Row::Resolution => "settings.resolution",
+            Row::Fullscreen => "settings.fullscreen",
+            Row::Vsync => "settings.vsync",
+            Row::Zoom => "settings.zoom",
+            Row::PixelPerfect => "settings.pixel_perfect",
+            Row::Master => "settings.master",
+            Row::Sfx => "settings.sfx",
+            Row::Shake => "settings.shake",
+            Row::Palette => "settings.palette",
+            Row::Outlines => "settings.outlines",
+            Row::ReducedFlashes => "settings.reduced_flashes",
+            Row::AimAssist => "settings.aim_assist",
+            Row::Controls => "settings.controls",
+            Row::Touch => "settings.touch",
+            Row::Difficulty => "settings.difficulty",
+            Row::Adaptive => "settings.adaptive",
+            Row::Language => "settings.language",
+        }
+    }
+
+    fn value(self, profile: &Profile, locale: &Locale) -> String {
+        let on_off = |on: bool| locale.t(if on { "settings.on" } else { "settings.off" }).to_string();
+        let percent = |level: f32| format!("{}%", (level * 100.0).round() as u32);
+        match self {
+            Row::Resolution => format!("{}x{}", profile.display.resolution.0, profile.display.resolution.1),
+            Row::Fullscreen => on_off(profile.display.fullscreen),
+            Row::Vsync => on_off(profile.display.vsync),
+            Row::Zoom => format!("{}x", profile.display.zoom),
+            Row::PixelPerfect => on_off(profile.display.pixel_perfect),
+            Row::Master => percent(profile.audio.master),
+            Row::Sfx => percent(profile.audio.sfx),
+            Row::Shake => on_off(profile.feedback.screen_shake),
+            Row::Palette => locale.t(profile.accessibility.palette.label()).to_string(),
+            Row::Outlines => on_off(profile.accessibility.outlines),
+            Row::ReducedFlashes => on_off(profile.accessibility.reduced_flashes),
+            Row::AimAssist => on_off(profile.accessibility.aim_assist),
+            Row::Controls => locale.t(profile.controls.label()).to_string(),
+            Row::Touch => locale.t(profile.touch.label()).to_string(),
+            Row::Difficulty => locale.t(profile.difficulty.label()).to_string(),
+            Row::Adaptive => on_off(profile.adaptive_difficulty),
+            Row::Language => profile.language.label().to_string(),
+        }
+    }
+}
+
+// Present while the panel is open
+#[derive(Resource)]
+pub struct SettingsMenu {
+    row: usize,
+    // Whether the game was already paused when the panel opened over a run
+    was_paused: bool,
+}
+
+#[derive(Component)]
+struct SettingsPanel;
+
+#[derive(Component)]
+struct SettingsRow(usize);
+
+#[derive(Component)]
+struct SettingsFooter;
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        // Opened and closed after Update, so the key that does it never reaches the
+        // menu or the run underneath as well
+        app.add_systems(Update, (settings_input, refresh_settings_panel).chain())
+            .add_systems(PostUpdate, toggle_settings);
+    }
+}
+
+// The panel blocks the menu and gameplay input under it while it's open
+pub fn settings_closed(menu: Option<Res<SettingsMenu>>) -> bool {
+    menu.is_none()
+}
+
+// O (gamepad RT) on the loadout screen, Escape (gamepad View) during a run;
+// the same key, Escape or confirm closes it again
+fn toggle_settings(
+    mut commands: Commands,
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    state: Res<State<AppState>>,
+    menu: Option<Res<SettingsMenu>>,
+    recap: Option<Res<StageRecap>>,
+    demo: Option<Res<Demo>>,
+    mut game_time: ResMut<GameTime>,
+    mut profile: ResMut<Profile>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    q_panel: Query<Entity, With<SettingsPanel>>,
+) {
+    let pressed = |key: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key) || pads.iter().any(|p| p.just_pressed(button))
+    };
+    let in_run = *state.get() == AppState::Playing;
+    let open_pressed = match state.get() {
+        AppState::Loadout => pressed(KeyCode::KeyO, GamepadButton::RightTrigger2),
+        // Not over the stage recap, and a demo ends on any key anyway
+        AppState::Playing => recap.is_none() && demo.is_none() && pressed(KeyCode::Escape, GamepadButton::Select),
+        _ => false,
+    };
+
+    let Some(menu) = menu else {
+        if !open_pressed { return; }
+        // From what's on screen, which --fullscreen may have set without the profile
+        if let Ok(window) = q_window.single() {
+            profile.display.fullscreen = window.mode != WindowMode::Windowed;
+        }
+        commands.insert_resource(SettingsMenu { row: 0, was_paused: game_time.paused });
+        if in_run { game_time.paused = true; }
+        spawn_settings_panel(&mut commands);
+        return;
+    };
+
+    if open_pressed || keys.just_pressed(KeyCode::Escape) || confirm_pressed(&keys, &pads) {
+        profile.save();
+        if in_run { game_time.paused = menu.was_paused; }
+        commands.remove_resource::<SettingsMenu>();
+        for e in &q_panel { commands.entity(e).despawn(); }
+    }
+}
+
+fn settings_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    pads: Query<&Gamepad>,
+    args: Res<LaunchArgs>,
+    menu: Option<ResMut<SettingsMenu>>,
+    mut profile: ResMut<Profile>,
+    mut cfg: ResMut<GameConfig>,
+    pacing: Res<Pacing>,
+    mut locale: ResMut<Locale>,
+    mut global_volume: ResMut<GlobalVolume>,
+    mut q_window: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    let Some(mut menu) = menu else { return; };
+    let pressed = |key_a: KeyCode, key_b: KeyCode, button: GamepadButton| {
+        keys.just_pressed(key_a) || keys.just_pressed(key_b) || pads.iter().any(|p| p.just_pressed(button))
+    };
+    let rows = Row::ALL.len();
+    if pressed(KeyCode::ArrowUp, KeyCode::KeyW, GamepadButton::DPadUp) {
+        menu.row = (menu.row + rows - 1) % rows;
+    }
+    if pressed(KeyCode::ArrowDown, KeyCode::KeyS, GamepadButton::DPadDown) {
+        menu.row = (menu.row + 1) % rows;
+    }
+
+    let mut step = 0;
+    if pressed(KeyCode::ArrowLeft, KeyCode::KeyA, GamepadButton::DPadLeft) { step -= 1; }
+    if pressed(KeyCode::ArrowRight, KeyCode::KeyD, GamepadButton::DPadRight) { step += 1; }
+    if step == 0 { return; }
+
+    let volume_step = |level: f32| ((level + VOLUME_STEP * step as f32).clamp(0.0, 1.0) * 10.0).round() / 10.0;
+    let cycle = |i: usize, n: usize| (i as i32 + step).rem_euclid(n as i32) as usize;
+    let row = Row::ALL[menu.row];
+    match row {
+        Row::Resolution => {
+            let i = RESOLUTIONS.iter().position(|r| *r == profile.display.resolution).unwrap_or(0);
+            profile.display.resolution = RESOLUTIONS[cycle(i, RESOLUTIONS.len())];
+        }
+        Row::Fullscreen => profile.display.fullscreen = !profile.display.fullscreen,
+        Row::Vsync => profile.display.vsync = !profile.display.vsync,
+        Row::Zoom => {
+            let i = profile.display.zoom.clamp(1, MAX_ZOOM) as usize - 1;
+            profile.display.zoom = cycle(i, MAX_ZOOM as usize) as u8 + 1;
+        }
+        Row::PixelPerfect => profile.display.pixel_perfect = !profile.display.pixel_perfect,
+        Row::Master => profile.audio.master = volume_step(profile.audio.master),
+        Row::Sfx => profile.audio.sfx = volume_step(profile.audio.sfx),
+        Row::Shake => profile.feedback.screen_shake = !profile.feedback.screen_shake,
+        Row::Palette => {
+            let palettes = Palette::ALL;
+            let i = palettes.iter().position(|p| *p == profile.accessibility.palette).unwrap_or(0);
+            profile.accessibility.palette = palettes[cycle(i, palettes.len())];
+        }
+        Row::Outlines => profile.accessibility.outlines = !profile.accessibility.outlines,
+        Row::ReducedFlashes => profile.accessibility.reduced_flashes = !profile.accessibility.reduced_flashes,
+        Row::AimAssist => profile.accessibility.aim_assist = !profile.accessibility.aim_assist,
+        Row::Controls => profile.controls = profile.controls.cycled(),
+        Row::Touch => {
+            let i = TouchMode::ALL.iter().position(|m| *m == profile.touch).unwrap_or(0);
+            profile.touch = TouchMode::ALL[cycle(i, TouchMode::ALL.len())];
+        }
+        Row::Difficulty => {
+            let i = Difficulty::ALL.iter().position(|d| *d == profile.difficulty).unwrap_or(0);
+            profile.difficulty = Difficulty::ALL[cycle(i, Difficulty::ALL.len())];
+            // From the file again, so the new difficulty doesn't stack on the old one
+            let mut fresh = GameConfig::load();
+            fresh.apply_difficulty(profile.difficulty);
+            pacing.apply(&mut fresh);
+            *cfg = fresh;
+        }
+        Row::Adaptive => profile.adaptive_difficulty = !profile.adaptive_difficulty,
+        Row::Language => {
+            let i = Language::ALL.iter().position(|l| *l == profile.language).unwrap_or(0);
+            profile.language = Language::ALL[cycle(i, Language::ALL.len())];
+            *locale = Locale::new(profile.language);
+        }
+    }
+
+    match row {
+        Row::Resolution | Row::Fullscreen | Row::Vsync => {
+            if let Ok(mut window) = q_window.single_mut() { apply_display(&profile.display, &mut window); }
+        }
+        Row::Master => global_volume.volume = master_volume(&args, &profile),
+        _ => {}
+    }
+}
+
+fn apply_display(display: &DisplaySettings, window: &mut Window) {
+    window.mode = window_mode(display.fullscreen);
+    window.present_mode = present_mode(display.vsync);
+    if !display.fullscreen {
+        let (width, height) = display.resolution;
+        window.resolution.set(width as f32, height as f32);
+    }
+}
+
+fn spawn_settings_panel(commands: &mut Commands) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.05, 0.05, 0.08, 0.92)),
+            // Over the HUD and any screen, under the toasts
+            GlobalZIndex(8),
+            SettingsPanel,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("settings.title")));
+            root.spawn(Node { flex_direction: FlexDirection::Column, row_gap: Val::Px(4.0), ..default() })
+                .with_children(|list| {
+                    for i in 0..Row::ALL.len() {
+                        list.spawn((Text::new(""), font(18.0), TextColor(Color::WHITE), SettingsRow(i)));
+                    }
+                });
+            root.spawn((Text::new(""), font(16.0), TextColor(Color::srgb(0.7, 0.7, 0.7)), SettingsFooter));
+        });
+}
+
+fn refresh_settings_panel(
+    menu: Option<Res<SettingsMenu>>,
+    profile: Res<Profile>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+    mut q_rows: Query<(&SettingsRow, &mut Text, &mut TextColor), Without<SettingsFooter>>,
+    mut q_footer: Query<&mut Text, With<SettingsFooter>>,
+) {
+    let Some(menu) = menu else { return; };
+    if !(menu.is_changed() || profile.is_changed() || device.is_changed() || locale.is_changed()) { return; }
+    for (row, mut text, mut color) in &mut q_rows {
+        let kind = Row::ALL[row.0];
+        let selected = row.0 == menu.row;
+        let marker = if selected { ">" } else { " " };
+        text.0 = format!("{marker} {:<18} < {} >", locale.t(kind.label()), kind.value(&profile, &locale));
+        color.0 = if selected { SELECTED_COLOR } else { Color::WHITE };
+    }
+    for mut text in &mut q_footer {
+        text.0 = format!(
+            "{}: {}    {}: {}",
+            glyph(Action::Navigate, *device),
+            locale.t("hint.select_change"),
+            glyph(Action::Confirm, *device),
+            locale.t("hint.back"),
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::recap::{RunStats, clock};
+use crate::waves::{CurrentLevel, GameMode, LevelEnemyQueue};
+use crate::{AppState, EnemyKind, Lives};
+
+// The classic right-hand column: one small icon per enemy still to spawn this
+// level (in the order they will roll out, the next one first), the tanks the
+// player has left and the stage (or horde wave) number, or the time survived so
+// far in survival.
+
+const ICON_SIZE: f32 = 10.0;
+const ICON_GAP: f32 = 3.0;
+const ICON_COLUMNS: usize = 2;
+const PLAYER_ICON: Color = Color::srgb(0.2, 0.9, 0.2);
+
+#[derive(Component)]
+struct Sidebar;
+
+#[derive(Component)]
+struct EnemyIcons;
+
+#[derive(Component)]
+struct LivesText;
+
+#[derive(Component)]
+struct StageText;
+
+pub struct SidebarPlugin;
+
+impl Plugin for SidebarPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::Playing), spawn_sidebar)
+            .add_systems(OnExit(AppState::Playing), despawn_sidebar)
+            .add_systems(
+                Update,
+                (refresh_enemy_icons, refresh_lives, refresh_stage).run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+fn label(text: impl Into<String>) -> (Text, TextFont, TextColor) {
+    (Text::new(text), TextFont { font_size: 14.0, ..default() }, TextColor(Color::WHITE))
+}
+
+fn spawn_sidebar(mut commands: Commands) {
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(32.0),
+                right: Val::Px(8.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            Sidebar,
+        ))
+        .with_children(|bar| {
+            bar.spawn((
+                Node {
+                    width: Val::Px(ICON_COLUMNS as f32 * (ICON_SIZE + ICON_GAP)),
+                    flex_wrap: FlexWrap::Wrap,
+                    column_gap: Val::Px(ICON_GAP),
+                    row_gap: Val::Px(ICON_GAP),
+                    ..default()
+                },
+                EnemyIcons,
+            ));
+            bar.spawn(Node { column_gap: Val::Px(4.0), align_items: AlignItems::Center, ..default() })
+                .with_children(|row| {
+                    row.spawn((
+                        Node { width: Val::Px(ICON_SIZE), height: Val::Px(ICON_SIZE), ..default() },
+                        BackgroundColor(PLAYER_ICON),
+                    ));
+                    row.spawn((label(""), LivesText));
+                });
+            bar.spawn((label(""), StageText));
+        });
+}
+
+fn despawn_sidebar(mut commands: Commands, q: Query<Entity, With<Sidebar>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+// The queue ticks its spawn timer every frame, so change detection on it says
+// nothing; rebuild only when what is left to spawn is different.
+fn refresh_enemy_icons(
+    mut commands: Commands,
+    queue: Res<LevelEnemyQueue>,
+    q_icons: Query<(Entity, Ref<EnemyIcons>)>,
+    mut shown: Local<Option<(usize, bool)>>,
+) {
+    let Ok((container, icons)) = q_icons.single() else { return; };
+    let now = (queue.remaining.len(), queue.boss);
+    if *shown == Some(now) && !icons.is_added() { return; }
+    *shown = Some(now);
+
+    let boss = queue.boss.then_some(EnemyKind::Boss);
+    commands.entity(container).despawn_related::<Children>().with_children(|icons| {
+        for kind in boss.into_iter().chain(queue.remaining.iter().copied()) {
+            icons.spawn((
+                Node { width: Val::Px(ICON_SIZE), height: Val::Px(ICON_SIZE), ..default() },
+                BackgroundColor(kind.color()),
+            ));
+        }
+    });
+}
+
+fn refresh_lives(lives: Res<Lives>, mut q: Query<(&mut Text, Ref<LivesText>)>) {
+    for (mut text, hud) in &mut q {
+        if !(lives.is_changed() || hud.is_added()) { continue; }
+        text.0 = format!("x {}", lives.0);
+    }
+}
+
+fn refresh_stage(
+    level: Res<CurrentLevel>,
+    mode: Res<GameMode>,
+    run: Res<RunStats>,
+    locale: Res<Locale>,
+    mut q: Query<(&mut Text, Ref<StageText>)>,
+) {
+    for (mut text, hud) in &mut q {
+        if *mode == GameMode::Survival {
+            text.0 = format!("{}\n{}", locale.t("sidebar.time"), clock(run.secs));
+            continue;
+        }
+        if !(level.is_changed() || hud.is_added() || locale.is_changed()) { continue; }
+        let name = if *mode == GameMode::Horde { "sidebar.wave" } else { "sidebar.stage" };
+        text.0 = format!("{}\n{}", locale.t(name), level.0 + 1);
+    }
+}
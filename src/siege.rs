@@ -0,0 +1,223 @@
+use bevy::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::{AppState, Base, Blocks, GameSet, LevelBounds, TILE, TileKind, Water, quantize_to_cardinal};
+
+// Sieges: now and then a roaming enemy gives up on the player and makes for
+// the base instead, shooting its way through the brick ring around it, as in
+// the original game. The way there comes from a map of the maze rebuilt as
+// walls change: every cell holds what it costs to reach the base from it,
+// with open ground costing one step and brick a few more, for the shots it
+// takes, and steel, water and the like no way through at all. A besieger
+// rolls downhill on it, stops at brick to shoot it away, and opens fire as
+// soon as the base is lined up with nothing but brick between. While the
+// shovel has the ring turned to steel there's no way in, and it goes back to
+// roaming, as it does after a while without getting there. The same map of
+// the maze gives the squads' flankers their ways round (see squads.rs).
+
+// Steps a brick cell is worth: about the time to shoot it and drive through
+const BRICK_COST: u32 = 4;
+// Off a cell's centre by more than this across the way out, a tank lines up first
+const LINE_UP: f32 = TILE * 0.2;
+// In grid order: rows count down the screen
+const STEPS: [IVec2; 4] = [IVec2::new(0, -1), IVec2::new(1, 0), IVec2::new(0, 1), IVec2::new(-1, 0)];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Cell {
+    Open,
+    Brick,
+    // Stops tanks, not shells
+    Water,
+    // Stops both, for good
+    Solid,
+    Base,
+}
+
+// Costs to one cell, laid over the same map
+pub struct Route(Vec<u32>);
+
+#[derive(Resource, Default)]
+pub struct SiegeMap {
+    bounds: Option<LevelBounds>,
+    cells: Vec<Cell>,
+    // Cost to the base, u32::MAX where there's no way
+    cost: Vec<u32>,
+}
+
+impl SiegeMap {
+    fn index(&self, cell: IVec2) -> Option<usize> {
+        let bounds = self.bounds?;
+        let inside = cell.x >= 0 && cell.y >= 0 && (cell.x as usize) < bounds.cols && (cell.y as usize) < bounds.rows;
+        inside.then(|| cell.y as usize * bounds.cols + cell.x as usize)
+    }
+
+    fn cell(&self, cell: IVec2) -> Cell {
+        self.index(cell).map_or(Cell::Solid, |i| self.cells[i])
+    }
+
+    fn cost_at(&self, cost: &[u32], cell: IVec2) -> u32 {
+        self.index(cell).map_or(u32::MAX, |i| cost[i])
+    }
+
+    fn cell_of(&self, pos: Vec2) -> Option<(IVec2, Vec2)> {
+        let bounds = self.bounds?;
+        let cell = bounds.cell_at(pos)?.as_ivec2();
+        Some((cell, bounds.origin + Vec2::new(cell.x as f32, -(cell.y as f32)) * TILE))
+    }
+
+    // Whether the base can be reached from here at all
+    pub fn reachable(&self, pos: Vec2) -> bool {
+        self.cell_of(pos).is_some_and(|(cell, _)| self.cost_at(&self.cost, cell) != u32::MAX)
+    }
+
+    // The cell next on the cheapest way down `cost`, as a step, and what's in it
+    fn next_step(&self, cost: &[u32], pos: Vec2) -> Option<(IVec2, Cell)> {
+        let (cell, _) = self.cell_of(pos)?;
+        let here = self.cost_at(cost, cell);
+        if here == u32::MAX { return None; }
+        STEPS
+            .into_iter()
+            .filter(|&step| self.cost_at(cost, cell + step) < here)
+            .min_by_key(|&step| self.cost_at(cost, cell + step))
+            .map(|step| (step, self.cell(cell + step)))
+    }
+
+    // Which way to drive down `cost`, lined up across the way first. None at
+    // the bottom, or with no way from here.
+    fn steer(&self, cost: &[u32], pos: Vec2) -> Option<Vec2> {
+        let (step, _) = self.next_step(cost, pos)?;
+        let (_, centre) = self.cell_of(pos)?;
+        let off = pos - centre;
+        let across = if step.x != 0 { Vec2::new(0.0, off.y) } else { Vec2::new(off.x, 0.0) };
+        if across.length() > LINE_UP { return Some(quantize_to_cardinal(-across)); }
+        Some(world_dir(step))
+    }
+
+    // Where a besieger drives: along the way to the base, and not at all when
+    // brick or the base is in the way, as that's to be shot. None when there's
+    // no way from here.
+    pub fn heading(&self, pos: Vec2) -> Option<Vec2> {
+        let dir = self.steer(&self.cost, pos)?;
+        let (step, next) = self.next_step(&self.cost, pos)?;
+        if dir == world_dir(step) && matches!(next, Cell::Brick | Cell::Base) { return Some(Vec2::ZERO); }
+        Some(dir)
+    }
+
+    // A way over open ground to the cell at `goal`, if it's open ground itself
+    pub fn route_to(&self, goal: Vec2) -> Option<Route> {
+        let (cell, _) = self.cell_of(goal)?;
+        let i = self.index(cell)?;
+        (self.cells[i] == Cell::Open).then(|| Route(self.costs_to(&[i], None)))
+    }
+
+    // Which way to drive along a route; None once there, or with no way from here
+    pub fn follow(&self, route: &Route, pos: Vec2) -> Option<Vec2> {
+        self.steer(&route.0, pos)
+    }
+
+    // Outward from the goal cells, cheapest first. Brick is a way through at
+    // `brick` steps a cell, if at all.
+    fn costs_to(&self, goals: &[usize], brick: Option<u32>) -> Vec<u32> {
+        let Some(bounds) = self.bounds else { return Vec::new(); };
+        let mut cost = vec![u32::MAX; self.cells.len()];
+        let mut queue = BinaryHeap::new();
+        for &i in goals {
+            cost[i] = 0;
+            queue.push(Reverse((0, i)));
+        }
+        while let Some(Reverse((here, i))) = queue.pop() {
+            if here > cost[i] { continue; }
+            let cell = IVec2::new((i % bounds.cols) as i32, (i / bounds.cols) as i32);
+            for step in STEPS {
+                let Some(j) = self.index(cell + step) else { continue; };
+                let through = match (self.cells[j], brick) {
+                    (Cell::Open, _) => here + 1,
+                    (Cell::Brick, Some(brick)) => here + brick,
+                    _ => continue,
+                };
+                if through < cost[j] {
+                    cost[j] = through;
+                    queue.push(Reverse((through, j)));
+                }
+            }
+        }
+        cost
+    }
+
+    // Which way to shoot, if at all: at the base when it's lined up with only
+    // brick and water between, otherwise at brick standing in the way
+    pub fn fire_dir(&self, pos: Vec2) -> Option<Vec2> {
+        let (cell, centre) = self.cell_of(pos)?;
+        let off = pos - centre;
+        for step in STEPS {
+            let across = if step.x != 0 { off.y } else { off.x };
+            if across.abs() > LINE_UP { continue; }
+            let mut at = cell + step;
+            loop {
+                match self.cell(at) {
+                    Cell::Base => return Some(world_dir(step)),
+                    Cell::Solid => break,
+                    Cell::Open | Cell::Brick | Cell::Water => at += step,
+                }
+            }
+        }
+        match self.next_step(&self.cost, pos)? {
+            (step, Cell::Brick) => Some(world_dir(step)),
+            _ => None,
+        }
+    }
+}
+
+// A grid step as a direction in the world, where rows count up the screen
+fn world_dir(step: IVec2) -> Vec2 {
+    Vec2::new(step.x as f32, -(step.y as f32))
+}
+
+pub struct SiegePlugin;
+
+impl Plugin for SiegePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SiegeMap>().add_systems(
+            Update,
+            rebuild_siege_map.before(GameSet::Ai).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+// Only when walls come or go, change kind (the shovel), or the level changes
+fn rebuild_siege_map(
+    bounds: Option<Res<LevelBounds>>,
+    changed: Query<(), Or<(Changed<Blocks>, Changed<TileKind>)>>,
+    mut removed: RemovedComponents<Blocks>,
+    q_tiles: Query<(&Transform, &Blocks, Option<&TileKind>, Has<Water>, Has<Base>)>,
+    mut map: ResMut<SiegeMap>,
+) {
+    let removed_any = removed.read().count() > 0;
+    let Some(bounds) = bounds else {
+        if map.bounds.is_some() { *map = SiegeMap::default(); }
+        return;
+    };
+    if !(bounds.is_changed() || removed_any || !changed.is_empty()) { return; }
+
+    let cells = bounds.cols * bounds.rows;
+    *map = SiegeMap { bounds: Some(*bounds), cells: vec![Cell::Open; cells], cost: Vec::new() };
+    for (t, blocks, kind, water, base) in &q_tiles {
+        let Some(cell) = bounds.cell_at(t.translation.truncate()) else { continue; };
+        let i = cell.y as usize * bounds.cols + cell.x as usize;
+        map.cells[i] = if base {
+            Cell::Base
+        } else if kind == Some(&TileKind::Brick) {
+            Cell::Brick
+        } else if water {
+            Cell::Water
+        } else if blocks.tanks {
+            Cell::Solid
+        } else {
+            continue;
+        };
+    }
+
+    let bases: Vec<usize> = (0..cells).filter(|&i| map.cells[i] == Cell::Base).collect();
+    map.cost = map.costs_to(&bases, Some(BRICK_COST));
+}
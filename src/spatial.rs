@@ -0,0 +1,52 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::{AppState, Enemy, TILE};
+
+// Uniform hash grid of enemy tank positions, rebuilt once a frame before
+// Update, so neighbour lookups only look at the few cells around a point
+// rather than every tank on the field.
+
+const CELL: f32 = TILE;
+
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<(Entity, Vec2)>>,
+}
+
+impl SpatialGrid {
+    fn cell(pos: Vec2) -> IVec2 {
+        (pos / CELL).floor().as_ivec2()
+    }
+
+    // Everything within `radius` of `pos`, with its position
+    pub fn neighbors(&self, pos: Vec2, radius: f32) -> impl Iterator<Item = (Entity, Vec2)> + '_ {
+        let min = Self::cell(pos - Vec2::splat(radius));
+        let max = Self::cell(pos + Vec2::splat(radius));
+        let r2 = radius * radius;
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|c| self.cells.get(&c))
+            .flatten()
+            .copied()
+            .filter(move |(_, p)| p.distance_squared(pos) <= r2)
+    }
+}
+
+pub struct SpatialPlugin;
+
+impl Plugin for SpatialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpatialGrid>()
+            .add_systems(PreUpdate, rebuild_grid.run_if(in_state(AppState::Playing)));
+    }
+}
+
+fn rebuild_grid(mut grid: ResMut<SpatialGrid>, q_enemies: Query<(Entity, &Transform), With<Enemy>>) {
+    // Keep the cell vectors' capacity from frame to frame
+    for cell in grid.cells.values_mut() { cell.clear(); }
+    for (e, t) in &q_enemies {
+        let pos = t.translation.truncate();
+        grid.cells.entry(SpatialGrid::cell(pos)).or_default().push((e, pos));
+    }
+}
@@ -0,0 +1,216 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::AppState;
+use crate::attract::Demo;
+use crate::locale::{Locale, Tr};
+use crate::persistence;
+use crate::profile::Profile;
+use crate::recap::{StageCleared, StageRecap};
+use crate::toasts::Notify;
+use crate::waves::CurrentLevel;
+
+// Speedrun timer: an optional clock under the combo counter (F8 shows or hides
+// it, remembered in the profile) with a split for every level cleared. It
+// counts real time, so the game speed keys and slow motion don't bend it, and
+// only while a level is being played: not in the menus, not on the stage recap
+// and not while the game is paused. The game-over screen lists the splits, and
+// F9 there writes them to splits.txt in the data directory. Demo runs aren't timed.
+
+const SPLITS_FILE: &str = "splits.txt";
+const CLOCK_COLOR: Color = Color::srgb(0.6, 0.9, 1.0);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct SpeedrunSettings {
+    pub timer: bool,
+}
+
+struct Split {
+    // 1-based level cleared
+    level: usize,
+    segment: Duration,
+    total: Duration,
+}
+
+#[derive(Resource, Default)]
+struct RunClock {
+    elapsed: Duration,
+    splits: Vec<Split>,
+}
+
+impl RunClock {
+    fn segment(&self) -> Duration {
+        self.elapsed - self.splits.last().map_or(Duration::ZERO, |s| s.total)
+    }
+
+    // One line per split, the level the run ended on, then the total
+    fn summary(&self, level: usize, locale: &Locale) -> Vec<String> {
+        let name = |level: usize| locale.f("speedrun.level", &[("level", &level)]);
+        let mut lines: Vec<String> = self
+            .splits
+            .iter()
+            .map(|s| format!("{:<9} {:>10} {:>10}", name(s.level), split_time(s.segment), split_time(s.total)))
+            .collect();
+        if self.segment() > Duration::ZERO {
+            let (segment, total) = (split_time(self.segment()), split_time(self.elapsed));
+            lines.push(format!("{:<9} {segment:>10} {total:>10}  ({})", name(level), locale.t("speedrun.lost")));
+        }
+        lines.push(format!("{:<9} {:>21}", locale.t("speedrun.total"), split_time(self.elapsed)));
+        lines
+    }
+}
+
+// m:ss.cc
+fn split_time(d: Duration) -> String {
+    let centis = d.as_millis() / 10;
+    format!("{}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+#[derive(Component)]
+struct ClockHud;
+
+#[derive(Component)]
+struct SplitsPanel;
+
+pub struct SpeedrunPlugin;
+
+impl Plugin for SpeedrunPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunClock>()
+            .add_systems(OnEnter(AppState::Playing), (reset_clock, spawn_clock_hud))
+            .add_systems(OnExit(AppState::Playing), despawn_clock_hud)
+            .add_systems(OnEnter(AppState::GameOver), spawn_splits_panel.run_if(not(resource_exists::<Demo>)))
+            .add_systems(OnExit(AppState::GameOver), despawn_splits_panel)
+            .add_systems(Update, toggle_timer)
+            .add_systems(
+                Update,
+                (
+                    (tick_clock.run_if(not(resource_exists::<StageRecap>)), take_split)
+                        .chain()
+                        .run_if(not(resource_exists::<Demo>)),
+                    update_clock_hud,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
+            )
+            .add_systems(Update, export_splits.run_if(in_state(AppState::GameOver)));
+    }
+}
+
+fn reset_clock(mut clock: ResMut<RunClock>) {
+    *clock = RunClock::default();
+}
+
+fn tick_clock(real: Res<Time<Real>>, virt: Res<Time<Virtual>>, mut clock: ResMut<RunClock>) {
+    if virt.is_paused() { return; }
+    clock.elapsed += real.delta();
+}
+
+// level_completion has moved the level on by the time the stage is cleared
+fn take_split(mut cleared: EventReader<StageCleared>, level: Res<CurrentLevel>, mut clock: ResMut<RunClock>) {
+    if cleared.read().next().is_none() { return; }
+    let split = Split { level: level.0, segment: clock.segment(), total: clock.elapsed };
+    clock.splits.push(split);
+}
+
+fn toggle_timer(keys: Res<ButtonInput<KeyCode>>, mut profile: ResMut<Profile>) {
+    if !keys.just_pressed(KeyCode::F8) { return; }
+    profile.speedrun.timer = !profile.speedrun.timer;
+    profile.save();
+}
+
+fn spawn_clock_hud(mut commands: Commands) {
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 16.0, ..default() },
+        TextColor(CLOCK_COLOR),
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(26.0),
+            left: Val::Px(8.0),
+            ..default()
+        },
+        ClockHud,
+    ));
+}
+
+fn despawn_clock_hud(mut commands: Commands, q: Query<Entity, With<ClockHud>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn update_clock_hud(
+    clock: Res<RunClock>,
+    profile: Res<Profile>,
+    locale: Res<Locale>,
+    demo: Option<Res<Demo>>,
+    mut q: Query<&mut Text, With<ClockHud>>,
+) {
+    for mut text in &mut q {
+        if !profile.speedrun.timer || demo.is_some() {
+            text.0.clear();
+            continue;
+        }
+        let segment = locale.f("speedrun.segment", &[("time", &split_time(clock.segment()))]);
+        text.0 = format!("{}  ({segment})", split_time(clock.elapsed));
+    }
+}
+
+fn spawn_splits_panel(
+    mut commands: Commands,
+    clock: Res<RunClock>,
+    level: Res<CurrentLevel>,
+    profile: Res<Profile>,
+    locale: Res<Locale>,
+) {
+    if !profile.speedrun.timer { return; }
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                top: Val::Px(16.0),
+                left: Val::Px(16.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(8.0)),
+                row_gap: Val::Px(2.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+            GlobalZIndex(5),
+            SplitsPanel,
+        ))
+        .with_children(|panel| {
+            panel.spawn((Text::new(""), font(18.0), TextColor(CLOCK_COLOR), Tr("speedrun.splits")));
+            for line in clock.summary(level.0 + 1, &locale) {
+                panel.spawn((Text::new(line), font(14.0), TextColor(Color::WHITE)));
+            }
+            let save = format!("F9: {}", locale.t("speedrun.save"));
+            panel.spawn((Text::new(save), font(14.0), TextColor(Color::srgb(0.7, 0.7, 0.7))));
+        });
+}
+
+fn despawn_splits_panel(mut commands: Commands, q: Query<Entity, With<SplitsPanel>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn export_splits(
+    keys: Res<ButtonInput<KeyCode>>,
+    clock: Res<RunClock>,
+    level: Res<CurrentLevel>,
+    locale: Res<Locale>,
+    mut notify: EventWriter<Notify>,
+    q_panel: Query<(), With<SplitsPanel>>,
+) {
+    if !keys.just_pressed(KeyCode::F9) || q_panel.is_empty() { return; }
+    let (segment, total) = (locale.t("speedrun.segment_header"), locale.t("speedrun.total"));
+    let mut text = format!("{:<9} {segment:>10} {total:>10}\n", "");
+    for line in clock.summary(level.0 + 1, &locale) {
+        text.push_str(&line);
+        text.push('\n');
+    }
+    if let Some(path) = persistence::save_text(SPLITS_FILE, &text) {
+        notify.write(Notify::new(locale.f("speedrun.saved", &[("path", &path)])));
+    }
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::siege::{Route, SiegeMap};
+use crate::{AppState, Enemy, EnemyAI, EnemyState, GameSet, Player, TILE, quantize_to_cardinal};
+
+// Squads: enemies chasing the player together don't all queue up along the
+// same shortest way at them. A coordinator hands out places around the player
+// (to their left, to their right and behind them, as they face, a few tiles
+// off), each to the free chasing tank nearest it, while the tank closest to
+// the player comes straight on. A tank given a place drives round to it over
+// open ground, by a route of its own on the maze map (see siege.rs), and
+// closes in directly once it's there or if there's no way round. The places
+// are handed out again a few times a second as the player moves.
+// `flank_tiles` in config.ron sets how far off they are; 0 turns it off.
+
+const REASSIGN_SECS: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Left,
+    Right,
+    Behind,
+}
+
+// On a chasing tank sent round to the player's side
+#[derive(Component)]
+pub struct Flank(Side);
+
+#[derive(Resource)]
+pub struct Squad {
+    // A route to each place that has one this time round
+    routes: Vec<(Side, Vec2, Route)>,
+    timer: Timer,
+}
+
+impl Squad {
+    // Which way a flanker drives; None sends it straight at the player
+    pub fn heading(&self, flank: &Flank, map: &SiegeMap, pos: Vec2) -> Option<Vec2> {
+        let (_, _, route) = self.routes.iter().find(|(side, _, _)| *side == flank.0)?;
+        map.follow(route, pos)
+    }
+}
+
+pub struct SquadsPlugin;
+
+impl Plugin for SquadsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Squad {
+            routes: Vec::new(),
+            timer: Timer::from_seconds(REASSIGN_SECS, TimerMode::Repeating),
+        })
+        .add_systems(Update, assign_flanks.in_set(GameSet::Ai).run_if(in_state(AppState::Playing)));
+    }
+}
+
+fn assign_flanks(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    map: Res<SiegeMap>,
+    mut squad: ResMut<Squad>,
+    q_player: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    q_enemies: Query<(Entity, &Transform, &EnemyAI, Has<Flank>), With<Enemy>>,
+) {
+    if !squad.timer.tick(time.delta()).just_finished() { return; }
+    let tiles = cfg.enemy.flank_tiles;
+    let player = q_player.single().ok().filter(|_| tiles > 0);
+
+    // Nearer in where the full distance is in a wall
+    squad.routes = match player {
+        Some(t) => {
+            let pos = t.translation.truncate();
+            let forward = quantize_to_cardinal(t.rotation.mul_vec3(Vec3::X).truncate());
+            let left = forward.perp();
+            [(Side::Left, left), (Side::Right, -left), (Side::Behind, -forward)]
+                .into_iter()
+                .filter_map(|(side, dir)| {
+                    (1..=tiles).rev().find_map(|n| {
+                        let place = pos + dir * TILE * n as f32;
+                        map.route_to(place).map(|route| (side, place, route))
+                    })
+                })
+                .collect()
+        }
+        None => Vec::new(),
+    };
+
+    // Chasers, nearest the player first; the nearest of all comes straight on
+    let player_pos = player.map_or(Vec2::ZERO, |t| t.translation.truncate());
+    let mut chasers: Vec<(Entity, Vec2)> = q_enemies
+        .iter()
+        .filter(|(_, _, ai, _)| ai.state == EnemyState::Chasing)
+        .map(|(e, t, _, _)| (e, t.translation.truncate()))
+        .collect();
+    chasers.sort_by(|a, b| a.1.distance_squared(player_pos).total_cmp(&b.1.distance_squared(player_pos)));
+    let mut free: Vec<(Side, Vec2)> = squad.routes.iter().map(|(side, place, _)| (*side, *place)).collect();
+    let mut placed = Vec::new();
+    for &(e, pos) in chasers.iter().skip(1) {
+        let nearest = (0..free.len()).min_by(|&a, &b| {
+            free[a].1.distance_squared(pos).total_cmp(&free[b].1.distance_squared(pos))
+        });
+        let Some(i) = nearest else { break; };
+        let (side, _) = free.swap_remove(i);
+        commands.entity(e).insert(Flank(side));
+        placed.push(e);
+    }
+    for (e, _, _, flanking) in &q_enemies {
+        if flanking && !placed.contains(&e) { commands.entity(e).remove::<Flank>(); }
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::attract::Demo;
+use crate::feedback::HitEvent;
+use crate::input_glyphs::{Action, InputDevice, glyph};
+use crate::locale::{Locale, Tr};
+use crate::persistence;
+use crate::recap::StageCleared;
+use crate::scores::confirm_pressed;
+use crate::{AppState, EnemyKilled, EnemyKind};
+
+// Lifetime statistics across every run, kept in stats.ron and shown on their
+// own screen from the loadout menu. Gameplay systems only send events (shots
+// fired and landed, plus the kill/hit/clear events that already exist); the
+// collector here does the counting.
+
+const STATS_FILE: &str = "stats.ron";
+
+#[derive(Resource, Serialize, Deserialize, Clone, Default, Debug)]
+#[serde(default)]
+pub struct PlayerStats {
+    pub shots_fired: u64,
+    // Player shells that damaged an enemy; a piercing shell counts once
+    pub shots_hit: u64,
+    // Keyed by the kind's label so the file stays readable
+    pub kills: BTreeMap<String, u32>,
+    pub deaths: u32,
+    pub levels_cleared: u32,
+    pub secs_played: f64,
+}
+
+impl PlayerStats {
+    pub fn load() -> Self {
+        persistence::load(STATS_FILE)
+    }
+
+    pub fn save(&self) {
+        persistence::save(STATS_FILE, self);
+    }
+
+    fn accuracy(&self) -> f64 {
+        if self.shots_fired == 0 { return 0.0; }
+        self.shots_hit as f64 / self.shots_fired as f64
+    }
+}
+
+// Sent by handle_fire for every shell the player fires
+#[derive(Event, Default)]
+pub struct ShotFired;
+
+// Sent by bullet_hits the first time a player shell damages an enemy
+#[derive(Event, Default)]
+pub struct ShotLanded;
+
+#[derive(Component)]
+struct StatsScreen;
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PlayerStats::load())
+            .add_event::<ShotFired>()
+            .add_event::<ShotLanded>()
+            // Saved when a run ends and when the game closes mid-run
+            .add_systems(OnExit(AppState::Playing), save_stats)
+            .add_systems(OnEnter(AppState::Stats), spawn_stats_screen)
+            .add_systems(OnExit(AppState::Stats), despawn_stats_screen)
+            .add_systems(
+                Update,
+                collect_stats.run_if(in_state(AppState::Playing)).run_if(not(resource_exists::<Demo>)),
+            )
+            .add_systems(Update, stats_screen_input.run_if(in_state(AppState::Stats)))
+            .add_systems(Last, save_on_exit);
+    }
+}
+
+fn collect_stats(
+    time: Res<Time<Real>>,
+    mut stats: ResMut<PlayerStats>,
+    mut fired: EventReader<ShotFired>,
+    mut landed: EventReader<ShotLanded>,
+    mut kills: EventReader<EnemyKilled>,
+    mut hits: EventReader<HitEvent>,
+    mut cleared: EventReader<StageCleared>,
+) {
+    stats.secs_played += time.delta_secs_f64();
+    stats.shots_fired += fired.read().count() as u64;
+    stats.shots_hit += landed.read().count() as u64;
+    for kill in kills.read().filter(|k| k.for_player()) {
+        *stats.kills.entry(kill.kind.label().to_string()).or_default() += 1;
+    }
+    stats.deaths += hits.read().filter(|h| h.player && h.fatal).count() as u32;
+    stats.levels_cleared += cleared.read().count() as u32;
+}
+
+fn save_stats(stats: Res<PlayerStats>) {
+    stats.save();
+}
+
+fn save_on_exit(mut exit: EventReader<AppExit>, stats: Res<PlayerStats>) {
+    if exit.read().next().is_none() { return; }
+    stats.save();
+}
+
+fn spawn_stats_screen(
+    mut commands: Commands,
+    stats: Res<PlayerStats>,
+    device: Res<InputDevice>,
+    locale: Res<Locale>,
+) {
+    let font = |size: f32| TextFont { font_size: size, ..default() };
+    let secs = stats.secs_played as u64;
+    let total_kills: u32 = stats.kills.values().sum();
+    let line = |key: &str, value: String| format!("{:<17} {value}", locale.t(key));
+    let mut lines = vec![
+        line("stats.time_played", format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)),
+        line("stats.levels_cleared", stats.levels_cleared.to_string()),
+        line("stats.tanks_lost", stats.deaths.to_string()),
+        line("stats.shots_fired", stats.shots_fired.to_string()),
+        line("stats.accuracy", format!("{:.0}%", stats.accuracy() * 100.0)),
+        line("stats.enemies_destroyed", total_kills.to_string()),
+    ];
+    for kind in EnemyKind::ALL {
+        let count = stats.kills.get(kind.label()).copied().unwrap_or(0);
+        if count > 0 { lines.push(format!("  {:<10} {count}", locale.kind_name(kind))); }
+    }
+
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            BackgroundColor(Color::srgb(0.05, 0.05, 0.08)),
+            StatsScreen,
+        ))
+        .with_children(|root| {
+            root.spawn((Text::new(""), font(36.0), TextColor(Color::WHITE), Tr("stats.title")));
+            root.spawn(Node { flex_direction: FlexDirection::Column, row_gap: Val::Px(4.0), ..default() })
+                .with_children(|list| {
+                    for line in lines {
+                        list.spawn((Text::new(line), font(18.0), TextColor(Color::WHITE)));
+                    }
+                });
+            root.spawn((
+                Text::new(format!("{}: {}", glyph(Action::Confirm, *device), locale.t("hint.back"))),
+                font(16.0),
+                TextColor(Color::srgb(0.7, 0.7, 0.7)),
+            ));
+        });
+}
+
+fn despawn_stats_screen(mut commands: Commands, q: Query<Entity, With<StatsScreen>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn stats_screen_input(keys: Res<ButtonInput<KeyCode>>, pads: Query<&Gamepad>, mut next: ResMut<NextState<AppState>>) {
+    if confirm_pressed(&keys, &pads) || keys.just_pressed(KeyCode::Escape) {
+        next.set(AppState::Loadout);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+
+use crate::config::GameConfig;
+use crate::death::{DeathEvent, DeathReason};
+use crate::recap::StageRecap;
+use crate::{AppState, Armor, GameSet, Ground, Health, Invulnerable, Lives, Size, damage_player};
+
+// Timed conditions on tanks (burning, EMP stun, tar slow). Sources only call
+// `StatusEffects::apply`; ticking, stacking, cleansing and the icons drawn over
+// affected tanks all live here.
+
+const ICON_SIZE: f32 = 5.0;
+const ICON_GAP: f32 = 2.0;
+const ICON_LIFT: f32 = 6.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatusKind {
+    Burning,
+    Stunned,
+    Slowed,
+}
+
+impl StatusKind {
+    // How many times the effect can pile up; re-applying past the cap only
+    // refreshes the duration.
+    fn max_stacks(self, cfg: &GameConfig) -> u8 {
+        match self {
+            StatusKind::Burning => cfg.status.burn_max_stacks,
+            StatusKind::Stunned | StatusKind::Slowed => 1,
+        }
+    }
+
+    fn icon_color(self) -> Color {
+        match self {
+            StatusKind::Burning => Color::srgb(1.0, 0.45, 0.1),
+            StatusKind::Stunned => Color::srgb(0.4, 0.8, 1.0),
+            StatusKind::Slowed => Color::srgb(0.45, 0.35, 0.25),
+        }
+    }
+}
+
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u8,
+    remaining: Timer,
+    // Periodic effects (burn damage) fire on this
+    pulse: Timer,
+}
+
+#[derive(Component, Default)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn apply(&mut self, kind: StatusKind, secs: f32, cfg: &GameConfig) {
+        if let Some(effect) = self.0.iter_mut().find(|e| e.kind == kind) {
+            effect.stacks = (effect.stacks + 1).min(kind.max_stacks(cfg));
+            // A shorter re-application never cuts an effect short
+            if effect.remaining.remaining_secs() < secs {
+                effect.remaining = Timer::from_seconds(secs, TimerMode::Once);
+            }
+            return;
+        }
+        self.0.push(StatusEffect {
+            kind,
+            stacks: 1,
+            remaining: Timer::from_seconds(secs, TimerMode::Once),
+            pulse: Timer::from_seconds(cfg.status.burn_tick_secs, TimerMode::Repeating),
+        });
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|e| e.kind == kind)
+    }
+
+    pub fn cleanse(&mut self, kind: StatusKind) {
+        self.0.retain(|e| e.kind != kind);
+    }
+
+    // Multiplier on the tank's intended velocity
+    pub fn speed_factor(&self, cfg: &GameConfig) -> f32 {
+        if self.has(StatusKind::Stunned) {
+            0.0
+        } else if self.has(StatusKind::Slowed) {
+            cfg.status.tar_slow_factor
+        } else {
+            1.0
+        }
+    }
+}
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (apply_tar, cleanse_statuses, burn_damage, tick_statuses, draw_status_icons)
+                .chain()
+                .in_set(GameSet::Damage)
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+fn apply_tar(cfg: Res<GameConfig>, mut q: Query<(&Ground, &mut StatusEffects)>) {
+    // Re-applied every frame on tar, so the slow lingers briefly after leaving it
+    for (ground, mut effects) in &mut q {
+        if *ground == Ground::Tar {
+            effects.apply(StatusKind::Slowed, cfg.status.tar_linger_secs, &cfg);
+        }
+    }
+}
+
+// The spawn shield wipes and blocks every effect; ice puts out fire.
+fn cleanse_statuses(mut q: Query<(&mut StatusEffects, Has<Invulnerable>, Option<&Ground>)>) {
+    for (mut effects, shielded, ground) in &mut q {
+        if effects.0.is_empty() { continue; }
+        if shielded {
+            effects.0.clear();
+        } else if ground == Some(&Ground::Ice) {
+            effects.cleanse(StatusKind::Burning);
+        }
+    }
+}
+
+fn burn_damage(
+    time: Res<Time>,
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    mut q: Query<(Entity, &Transform, &Size, &mut StatusEffects, Option<&mut Health>, Option<&mut Armor>)>,
+    mut lives: ResMut<Lives>,
+    mut deaths: EventWriter<DeathEvent>,
+) {
+    for (e, t, size, mut effects, health, armor) in &mut q {
+        let Some(burn) = effects.0.iter_mut().find(|s| s.kind == StatusKind::Burning) else { continue; };
+        let hits = burn.pulse.tick(time.delta()).times_finished_this_tick() as u8 * burn.stacks;
+        if hits == 0 { continue; }
+
+        if let Some(mut hp) = health {
+            hp.0 = hp.0.saturating_sub(hits);
+            if hp.0 == 0 {
+                deaths.write(DeathEvent::new(e, DeathReason::Burned));
+            }
+        } else if let Some(mut armor) = armor {
+            // The player: each burn hit counts like a shell
+            for _ in 0..hits {
+                if damage_player(&mut commands, &cfg, t, size, &mut armor, &mut lives) {
+                    deaths.write(DeathEvent::new(e, DeathReason::Burned));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+fn tick_statuses(time: Res<Time>, mut q: Query<&mut StatusEffects>) {
+    for mut effects in &mut q {
+        if effects.0.is_empty() { continue; }
+        effects.0.retain_mut(|e| !e.remaining.tick(time.delta()).finished());
+    }
+}
+
+fn draw_status_icons(q: Query<(&Transform, &Sprite, &StatusEffects)>, mut gizmos: Gizmos) {
+    // One square per stack, in a row above the tank
+    for (t, sprite, effects) in &q {
+        let count: u8 = effects.0.iter().map(|e| e.stacks).sum();
+        if count == 0 { continue; }
+        let height = sprite.custom_size.map_or(0.0, |s| s.y);
+        let width = count as f32 * (ICON_SIZE + ICON_GAP) - ICON_GAP;
+        let mut x = t.translation.x - width * 0.5 + ICON_SIZE * 0.5;
+        let y = t.translation.y + height * 0.5 + ICON_LIFT;
+        for effect in &effects.0 {
+            for _ in 0..effect.stacks {
+                gizmos.rect_2d(Isometry2d::from_xy(x, y), Vec2::splat(ICON_SIZE), effect.kind.icon_color());
+                x += ICON_SIZE + ICON_GAP;
+            }
+        }
+    }
+}
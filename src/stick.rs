@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Analog stick shaping applied before gameplay sees any stick input. Stored
+// in the profile so a pad that drifts or feels twitchy only needs fixing once.
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct StickSettings {
+    // Per-axis dead zone as (x, y); input inside it reads as zero
+    pub deadzone: [f32; 2],
+    // Exponent applied past the dead zone; 1.0 is linear, higher is finer near the centre
+    pub curve: f32,
+    pub sensitivity: f32,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self { deadzone: [0.3, 0.3], curve: 1.0, sensitivity: 1.0 }
+    }
+}
+
+impl StickSettings {
+    pub fn apply(&self, raw: Vec2) -> Vec2 {
+        Vec2::new(self.axis(raw.x, self.deadzone[0]), self.axis(raw.y, self.deadzone[1]))
+    }
+
+    fn axis(&self, value: f32, deadzone: f32) -> f32 {
+        let deadzone = deadzone.clamp(0.0, 0.95);
+        let magnitude = value.abs();
+        if magnitude <= deadzone { return 0.0; }
+        // Rescale so output starts from zero at the dead zone edge
+        let t = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+        value.signum() * (t.powf(self.curve.max(0.1)) * self.sensitivity).min(1.0)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(default)]
+pub struct ControllerSettings {
+    pub movement: StickSettings,
+}
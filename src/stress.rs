@@ -0,0 +1,214 @@
+use bevy::ecs::schedule::ExecutorKind;
+use bevy::platform::time::Instant;
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, thread_rng};
+use std::hint::black_box;
+use std::time::Duration;
+
+use crate::args::LaunchArgs;
+use crate::attract::Demo;
+use crate::bullets::{BulletKind, BulletPool, Shot};
+use crate::config::GameConfig;
+use crate::{
+    AppState, Base, Bullet, CollisionCache, Enemy, EnemyKind, Faction, GameRng, GameSet, Invulnerable, LevelBounds,
+    Player, TILE, bullet_hits, move_with_collisions, overlaps_any, random_cardinal, refresh_collision_cache,
+    spawn_enemy,
+};
+
+// Stress test (`--stress`): goes straight from the menu
+// into a demo run and keeps the field packed with TANKS enemies and SHELLS
+// shells, with the player's tank made untouchable and enemy shells unable to
+// break the base, so the run never ends.
+// Every few seconds it logs the average frame time and how much of it went on
+// move_with_collisions and bullet_hits, plus what one overlaps_any call against
+// the tank walls costs. Update runs single-threaded meanwhile, so the time
+// between the stamps either side of a system is that system's own. Any key
+// ends it, like any other demo.
+
+const TANKS: usize = 300;
+const SHELLS: usize = 2000;
+const SHELL_SPEED: f32 = 250.0;
+// overlaps_any calls timed per frame, each at a random point in the level
+const PROBES: usize = 10_000;
+const REPORT_SECS: f32 = 5.0;
+const KINDS: [EnemyKind; 4] = [EnemyKind::Basic, EnemyKind::Fast, EnemyKind::Power, EnemyKind::Armor];
+
+// Present while the current run is a stress test, with the base rule it put aside
+#[derive(Resource)]
+struct Stress {
+    enemy_hits_base: bool,
+}
+
+#[derive(Resource, Default)]
+struct Timings {
+    frames: u32,
+    frame: Duration,
+    movement: Duration,
+    hits: Duration,
+    probes: u32,
+    overlap: Duration,
+    movement_start: Option<Instant>,
+    hits_start: Option<Instant>,
+}
+
+pub struct StressPlugin;
+
+impl Plugin for StressPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world().get_resource::<LaunchArgs>().is_some_and(|a| a.stress) { return; }
+        app.init_resource::<Timings>()
+            .edit_schedule(Update, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            })
+            .add_systems(Update, enter_stress.run_if(in_state(AppState::Loadout)))
+            .add_systems(OnExit(AppState::Playing), leave_stress)
+            .add_systems(
+                Update,
+                (
+                    // The walls have to be in the cache before anything is placed among them
+                    fill_field.after(refresh_collision_cache).before(start_movement).in_set(GameSet::Physics),
+                    probe_overlaps.in_set(GameSet::Ai),
+                    start_movement.before(move_with_collisions).in_set(GameSet::Physics),
+                    end_movement.after(move_with_collisions).in_set(GameSet::Physics),
+                    start_hits.before(bullet_hits).in_set(GameSet::Damage),
+                    end_hits.after(bullet_hits).in_set(GameSet::Damage),
+                    report.in_set(GameSet::Cleanup),
+                )
+                    .run_if(in_state(AppState::Playing))
+                    .run_if(resource_exists::<Stress>),
+            );
+    }
+}
+
+// Only the first time the menu comes up; once the test is ended the game plays as usual
+fn enter_stress(
+    mut commands: Commands,
+    mut done: Local<bool>,
+    mut cfg: ResMut<GameConfig>,
+    mut next: ResMut<NextState<AppState>>,
+) {
+    if *done { return; }
+    *done = true;
+    commands.insert_resource(Demo);
+    commands.insert_resource(Stress { enemy_hits_base: cfg.damage.enemy_hits_base });
+    cfg.damage.enemy_hits_base = false;
+    next.set(AppState::Playing);
+}
+
+fn leave_stress(mut commands: Commands, stress: Option<Res<Stress>>, mut cfg: ResMut<GameConfig>) {
+    let Some(stress) = stress else { return; };
+    cfg.damage.enemy_hits_base = stress.enemy_hits_base;
+    commands.remove_resource::<Stress>();
+}
+
+// A random tile centre that tanks can stand on, away from the base
+fn open_tile(rng: &mut GameRng, bounds: &LevelBounds, cache: &CollisionCache, base: Option<Vec2>) -> Option<Vec2> {
+    (0..20).find_map(|_| {
+        let (col, row) = (rng.gen_range(0..bounds.cols), rng.gen_range(0..bounds.rows));
+        let pos = bounds.origin + Vec2::new(col as f32, -(row as f32)) * TILE;
+        let on_base = base.is_some_and(|base| base.distance(pos) < TILE);
+        (!on_base && !overlaps_any(pos, Vec2::splat(TILE * 0.45), &cache.tanks)).then_some(pos)
+    })
+}
+
+fn fill_field(
+    mut commands: Commands,
+    cfg: Res<GameConfig>,
+    cache: Res<CollisionCache>,
+    bounds: Option<Res<LevelBounds>>,
+    mut pool: ResMut<BulletPool>,
+    q_player: Query<Entity, (With<Player>, Without<Invulnerable>)>,
+    q_enemies: Query<(), With<Enemy>>,
+    q_bullets: Query<(), With<Bullet>>,
+    q_base: Query<&Transform, With<Base>>,
+    mut rng: ResMut<GameRng>,
+) {
+    let Some(bounds) = bounds else { return; };
+    // A maze that's only just been built isn't in the cache until the next refresh
+    if cache.tanks.is_empty() { return; }
+    let base = q_base.iter().next().map(|t| t.translation.truncate());
+    for e in &q_player {
+        commands.entity(e).insert(Invulnerable(Timer::from_seconds(3600.0, TimerMode::Once)));
+    }
+
+    for _ in q_enemies.iter().count()..TANKS {
+        let Some(pos) = open_tile(&mut rng, &bounds, &cache, base) else { break; };
+        let kind = *KINDS.choose(&mut **rng).unwrap();
+        spawn_enemy(&mut commands, &mut rng, pos, kind, &cfg);
+    }
+    for _ in q_bullets.iter().count()..SHELLS {
+        let Some(pos) = open_tile(&mut rng, &bounds, &cache, base) else { break; };
+        pool.fire(
+            &mut commands,
+            Shot {
+                pos,
+                velocity: random_cardinal(&mut rng) * SHELL_SPEED,
+                faction: Faction::Enemy,
+                owner: Entity::PLACEHOLDER,
+                kind: BulletKind::Standard,
+                size: cfg.bullet.size(),
+                color: Color::srgb(1.0, 0.85, 0.2),
+            },
+        );
+    }
+}
+
+fn probe_overlaps(cache: Res<CollisionCache>, bounds: Option<Res<LevelBounds>>, mut timings: ResMut<Timings>) {
+    let Some(bounds) = bounds else { return; };
+    let mut rng = thread_rng();
+    let (lo, hi) = (bounds.center() - bounds.size() * 0.5, bounds.center() + bounds.size() * 0.5);
+    let points: Vec<Vec2> =
+        (0..PROBES).map(|_| Vec2::new(rng.gen_range(lo.x..hi.x), rng.gen_range(lo.y..hi.y))).collect();
+    let half = Vec2::splat(TILE * 0.4);
+
+    let start = Instant::now();
+    for &p in &points {
+        black_box(overlaps_any(black_box(p), half, &cache.tanks));
+    }
+    timings.overlap += start.elapsed();
+    timings.probes += PROBES as u32;
+}
+
+fn start_movement(mut timings: ResMut<Timings>) {
+    timings.movement_start = Some(Instant::now());
+}
+
+fn end_movement(mut timings: ResMut<Timings>) {
+    if let Some(start) = timings.movement_start.take() { timings.movement += start.elapsed(); }
+}
+
+fn start_hits(mut timings: ResMut<Timings>) {
+    timings.hits_start = Some(Instant::now());
+}
+
+fn end_hits(mut timings: ResMut<Timings>) {
+    if let Some(start) = timings.hits_start.take() { timings.hits += start.elapsed(); }
+}
+
+fn report(
+    time: Res<Time<Real>>,
+    mut next: Local<f32>,
+    mut timings: ResMut<Timings>,
+    q_enemies: Query<(), With<Enemy>>,
+    q_bullets: Query<(), With<Bullet>>,
+) {
+    timings.frames += 1;
+    timings.frame += time.delta();
+    *next += time.delta_secs();
+    if *next < REPORT_SECS { return; }
+    *next = 0.0;
+
+    let per_frame = |d: Duration| d.as_secs_f64() * 1000.0 / timings.frames as f64;
+    info!(
+        "stress: {} tanks, {} shells | frame {:.2} ms | move_with_collisions {:.3} ms | bullet_hits {:.3} ms \
+         | overlaps_any {:.0} ns",
+        q_enemies.iter().count(),
+        q_bullets.iter().count(),
+        per_frame(timings.frame),
+        per_frame(timings.movement),
+        per_frame(timings.hits),
+        timings.overlap.as_secs_f64() * 1e9 / timings.probes.max(1) as f64,
+    );
+    *timings = Timings::default();
+}
@@ -0,0 +1,111 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::config::GameConfig;
+use crate::recap::StageRecap;
+use crate::{AppState, Enemy, GameSet, LevelTile, Player, Size, TILE, aabb_overlap, move_with_collisions};
+
+// Teleporter pads (maze glyphs `1`-`4`): the two pads with the same digit are
+// partners, and a tank whose centre rolls onto one comes out on the other.
+// A tank has to leave the pad it arrived on before it can use it again, and a
+// pair rests for a moment after each jump so a queue of tanks can't flicker
+// through it. A pad whose partner is occupied waits until it is clear.
+
+const PAIR_COLORS: [Color; 4] = [
+    Color::srgb(0.2, 0.8, 0.9),
+    Color::srgb(0.9, 0.3, 0.8),
+    Color::srgb(0.4, 0.9, 0.3),
+    Color::srgb(1.0, 0.6, 0.2),
+];
+
+#[derive(Component)]
+struct Teleporter {
+    pair: u8,
+    // Shared with the partner: both are reset on every jump
+    cooldown: Timer,
+    // Tanks whose centre was on the pad as of the last update
+    occupants: HashSet<Entity>,
+}
+
+pub struct TeleportersPlugin;
+
+impl Plugin for TeleportersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            teleport_tanks
+                .after(move_with_collisions)
+                .in_set(GameSet::Physics)
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+// `pair` is the glyph's digit, 1 to 4
+pub fn spawn_teleporter(commands: &mut Commands, pos: Vec2, pair: u8, cfg: &GameConfig) {
+    let mut cooldown = Timer::from_seconds(cfg.terrain.teleport_cooldown_secs, TimerMode::Once);
+    // Ready from the start
+    cooldown.tick(cooldown.duration());
+    commands.spawn((
+        Sprite {
+            color: PAIR_COLORS[(pair as usize - 1) % PAIR_COLORS.len()],
+            custom_size: Some(Vec2::splat(TILE * 0.8)),
+            ..default()
+        },
+        Transform::from_xyz(pos.x, pos.y, -0.1),
+        Teleporter { pair, cooldown, occupants: HashSet::new() },
+        LevelTile,
+        Size(Vec2::splat(TILE)),
+    ));
+}
+
+fn teleport_tanks(
+    time: Res<Time>,
+    mut q_pads: Query<(Entity, &Transform, &Size, &mut Teleporter, &mut Sprite)>,
+    mut q_tanks: Query<(Entity, &mut Transform, &Size), (Or<(With<Player>, With<Enemy>)>, Without<Teleporter>)>,
+) {
+    for (.., mut pad, mut sprite) in &mut q_pads {
+        pad.cooldown.tick(time.delta());
+        // Dimmed while resting
+        sprite.color.set_alpha(if pad.cooldown.finished() { 1.0 } else { 0.35 });
+    }
+
+    let pads: Vec<(Entity, Vec2, Vec2, u8)> =
+        q_pads.iter().map(|(e, t, s, pad, _)| (e, t.translation.truncate(), s.0 * 0.5, pad.pair)).collect();
+    for &(pad_e, pad_pos, pad_half, pair) in &pads {
+        let Some(&(partner_e, partner_pos, ..)) = pads.iter().find(|p| p.3 == pair && p.0 != pad_e) else { continue; };
+
+        let on_pad: HashSet<Entity> = q_tanks
+            .iter()
+            .filter(|(_, t, _)| aabb_overlap(t.translation.truncate(), Vec2::ZERO, pad_pos, pad_half))
+            .map(|(e, ..)| e)
+            .collect();
+        let Ok((.., mut pad, _)) = q_pads.get_mut(pad_e) else { continue; };
+        let arrived: Vec<Entity> = on_pad.iter().filter(|e| !pad.occupants.contains(e)).copied().collect();
+        pad.occupants = on_pad;
+        // Whoever doesn't jump this frame still counts as arriving next frame
+        for e in &arrived { pad.occupants.remove(e); }
+        if !pad.cooldown.finished() { continue; }
+
+        // One tank per jump, and never onto someone already on the partner
+        let jumper = arrived.into_iter().find(|&tank_e| {
+            let Ok((.., size)) = q_tanks.get(tank_e) else { return false; };
+            let half = size.0 * 0.5;
+            !q_tanks
+                .iter()
+                .any(|(e, t, s)| e != tank_e && aabb_overlap(t.translation.truncate(), s.0 * 0.5, partner_pos, half))
+        });
+        let Some(tank_e) = jumper else { continue; };
+        if let Ok((_, mut t, _)) = q_tanks.get_mut(tank_e) {
+            t.translation.x = partner_pos.x;
+            t.translation.y = partner_pos.y;
+        }
+        for e in [pad_e, partner_e] {
+            let Ok((.., mut pad, _)) = q_pads.get_mut(e) else { continue; };
+            pad.cooldown.reset();
+            // Landing on the partner isn't arriving on it
+            if e == partner_e { pad.occupants.insert(tank_e); }
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+
+use crate::TileKind;
+
+// Per-level looks: wall and floor colours plus an ambient tint laid over the
+// maze's tiles, chosen when the maze is built. Levels cycle through the
+// table, so a long run keeps changing scenery. Night levels are also played
+// under fog of war, whatever the vision setting says.
+
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LevelTheme {
+    pub steel: Color,
+    pub brick: Color,
+    // Shown wherever no tile is drawn
+    pub floor: Color,
+    // Multiplies every maze tile's colour
+    pub tint: Color,
+    pub night: bool,
+}
+
+// In the order of THEMES, for level packs to pick one by
+const THEME_NAMES: [&str; 4] = ["classic", "desert", "winter", "night"];
+
+const THEMES: &[LevelTheme] = &[
+    // Classic
+    LevelTheme {
+        steel: Color::srgb(0.25, 0.25, 0.3),
+        brick: Color::srgb(0.65, 0.3, 0.15),
+        floor: Color::srgb(0.17, 0.17, 0.18),
+        tint: Color::WHITE,
+        night: false,
+    },
+    // Desert
+    LevelTheme {
+        steel: Color::srgb(0.45, 0.4, 0.32),
+        brick: Color::srgb(0.75, 0.5, 0.25),
+        floor: Color::srgb(0.36, 0.3, 0.2),
+        tint: Color::srgb(1.0, 0.95, 0.85),
+        night: false,
+    },
+    // Winter
+    LevelTheme {
+        steel: Color::srgb(0.4, 0.45, 0.55),
+        brick: Color::srgb(0.6, 0.35, 0.3),
+        floor: Color::srgb(0.3, 0.34, 0.4),
+        tint: Color::srgb(0.9, 0.95, 1.0),
+        night: false,
+    },
+    // Night
+    LevelTheme {
+        steel: Color::srgb(0.2, 0.22, 0.3),
+        brick: Color::srgb(0.4, 0.22, 0.15),
+        floor: Color::srgb(0.05, 0.06, 0.1),
+        tint: Color::srgb(0.6, 0.65, 0.9),
+        night: true,
+    },
+];
+
+impl Default for LevelTheme {
+    fn default() -> Self {
+        THEMES[0]
+    }
+}
+
+impl LevelTheme {
+    pub fn for_level(level: usize) -> Self {
+        THEMES[level % THEMES.len()]
+    }
+
+    pub fn named(name: &str) -> Option<Self> {
+        THEME_NAMES.iter().position(|n| n.eq_ignore_ascii_case(name)).map(|i| THEMES[i])
+    }
+
+    pub fn tile(&self, kind: TileKind) -> Color {
+        self.shade(match kind {
+            TileKind::Brick => self.brick,
+            TileKind::Steel => self.steel,
+        })
+    }
+
+    pub fn shade(&self, color: Color) -> Color {
+        let (c, t) = (color.to_linear(), self.tint.to_linear());
+        LinearRgba::new(c.red * t.red, c.green * t.green, c.blue * t.blue, c.alpha).into()
+    }
+}
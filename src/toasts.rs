@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+
+use crate::locale::Locale;
+use crate::waves::{CurrentLevel, GameMode};
+use crate::{AppState, LevelBounds};
+
+// Short messages for the player ("Level 3", "Base fortified!") that fade in
+// near the top of the screen, stay a moment and fade out again. Anything can
+// raise one with a `Notify` event, its text already in the player's language.
+// Several at once stack downwards, newest at the bottom; past MAX_TOASTS the
+// oldest goes early to make room. They run on real time, so a paused game
+// still shows them.
+
+const TOAST_SECS: f32 = 2.5;
+const FADE_SECS: f32 = 0.3;
+const MAX_TOASTS: usize = 4;
+const PANEL: Color = Color::srgba(0.0, 0.0, 0.0, 0.6);
+
+#[derive(Event)]
+pub struct Notify {
+    pub text: String,
+    pub color: Color,
+}
+
+impl Notify {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: Color::WHITE }
+    }
+
+    pub fn with_color(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+// The column the toasts are laid out in
+#[derive(Component)]
+struct ToastStack;
+
+#[derive(Component)]
+struct Toast {
+    life: Timer,
+    color: Color,
+}
+
+pub struct ToastsPlugin;
+
+impl Plugin for ToastsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<Notify>()
+            .add_systems(Startup, spawn_stack)
+            // Whatever was still showing belongs to the run that just ended
+            .add_systems(OnExit(AppState::Playing), clear_toasts)
+            .add_systems(
+                Update,
+                (
+                    announce_level
+                        .run_if(resource_exists_and_changed::<LevelBounds>)
+                        .run_if(in_state(AppState::Playing)),
+                    (show_toasts, fade_toasts).chain(),
+                ),
+            );
+    }
+}
+
+fn spawn_stack(mut commands: Commands) {
+    commands.spawn((
+        Node {
+            position_type: PositionType::Absolute,
+            // Clear of the boss bar
+            top: Val::Px(70.0),
+            width: Val::Percent(100.0),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            row_gap: Val::Px(6.0),
+            ..default()
+        },
+        GlobalZIndex(10),
+        ToastStack,
+    ));
+}
+
+// Every new maze, whether a fresh run, the next stage or a resumed run. Survival
+// and horde only ever play the one maze, so they announce themselves instead.
+fn announce_level(level: Res<CurrentLevel>, mode: Res<GameMode>, locale: Res<Locale>, mut notify: EventWriter<Notify>) {
+    let text = match *mode {
+        GameMode::Campaign | GameMode::Daily => locale.f("toast.level", &[("level", &(level.0 + 1))]),
+        other => locale.t(other.label()).to_string(),
+    };
+    notify.write(Notify::new(text));
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    mut notes: EventReader<Notify>,
+    q_stack: Query<Entity, With<ToastStack>>,
+    q_toasts: Query<(Entity, &Toast)>,
+) {
+    let Ok(stack) = q_stack.single() else { return; };
+    let mut live: Vec<(Entity, f32)> = q_toasts.iter().map(|(e, toast)| (e, toast.life.elapsed_secs())).collect();
+    for note in notes.read() {
+        if live.len() >= MAX_TOASTS {
+            let oldest = (0..live.len()).max_by(|&a, &b| live[a].1.total_cmp(&live[b].1)).unwrap_or(0);
+            commands.entity(live.swap_remove(oldest).0).despawn();
+        }
+        let toast = commands
+            .spawn((
+                Text::new(note.text.clone()),
+                TextFont { font_size: 18.0, ..default() },
+                TextColor(note.color.with_alpha(0.0)),
+                Node { padding: UiRect::axes(Val::Px(12.0), Val::Px(4.0)), ..default() },
+                BackgroundColor(PANEL.with_alpha(0.0)),
+                Toast { life: Timer::from_seconds(TOAST_SECS, TimerMode::Once), color: note.color },
+                ChildOf(stack),
+            ))
+            .id();
+        live.push((toast, 0.0));
+    }
+}
+
+fn fade_toasts(
+    time: Res<Time<Real>>,
+    mut commands: Commands,
+    mut q: Query<(Entity, &mut Toast, &mut TextColor, &mut BackgroundColor)>,
+) {
+    for (e, mut toast, mut text, mut panel) in &mut q {
+        if toast.life.tick(time.delta()).finished() {
+            commands.entity(e).despawn();
+            continue;
+        }
+        let shown = toast.life.elapsed_secs().min(toast.life.remaining_secs());
+        let alpha = (shown / FADE_SECS).min(1.0);
+        text.0 = toast.color.with_alpha(alpha);
+        panel.0 = PANEL.with_alpha(PANEL.alpha() * alpha);
+    }
+}
+
+fn clear_toasts(mut commands: Commands, q: Query<Entity, With<Toast>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
@@ -0,0 +1,227 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::AppState;
+use crate::intent::PlayerIntent;
+use crate::locale::Tr;
+use crate::profile::Profile;
+
+// Touch controls: a virtual stick in the bottom-left corner and buttons for
+// fire, utility and mine in the bottom-right, drawn over a run. They feed
+// PlayerIntent like the keys and pads do (see intent.rs), so nothing else
+// knows a finger is driving. By default they come up the first time the
+// screen is touched; the settings can also force them on or off. Each finger
+// is read on its own, so the tank can drive and fire at once.
+
+// In logical pixels from the screen's edges
+const MARGIN: f32 = 32.0;
+const STICK_RADIUS: f32 = 72.0;
+const KNOB_RADIUS: f32 = 30.0;
+// A finger coming down this far out (in radii) still takes the stick
+const STICK_GRAB: f32 = 1.5;
+// Share of the radius the knob moves before the tank does
+const STICK_DEADZONE: f32 = 0.2;
+const BUTTON_RADIUS: f32 = 44.0;
+const BUTTON_GAP: f32 = 16.0;
+const PAD_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.12);
+const KNOB_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.35);
+const BUTTON_COLOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.2);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TouchMode {
+    // Once the screen has been touched
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl TouchMode {
+    pub const ALL: [TouchMode; 3] = [TouchMode::Auto, TouchMode::On, TouchMode::Off];
+
+    // Key into the language files
+    pub fn label(self) -> &'static str {
+        match self {
+            TouchMode::Auto => "touch.auto",
+            TouchMode::On => "settings.on",
+            TouchMode::Off => "settings.off",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum TouchButton {
+    Fire,
+    Utility,
+    Mine,
+}
+
+// Each button's distance in buttons from the bottom-right corner, and its label
+const BUTTONS: [(TouchButton, Vec2, &str); 3] = [
+    (TouchButton::Fire, Vec2::ZERO, "hint.fire"),
+    (TouchButton::Utility, Vec2::X, "hint.utility"),
+    (TouchButton::Mine, Vec2::Y, "hint.mine"),
+];
+
+// How far a button's centre is from the bottom-right corner of the screen, left and up
+fn from_corner(slot: Vec2) -> Vec2 {
+    Vec2::splat(MARGIN + BUTTON_RADIUS) + slot * (BUTTON_RADIUS * 2.0 + BUTTON_GAP)
+}
+
+// Set for the rest of the session by the first touch
+#[derive(Resource, Default)]
+pub struct TouchSeen(bool);
+
+// The finger on the stick and how far it's pushed, up positive
+#[derive(Resource, Default)]
+pub struct TouchStick {
+    finger: Option<u64>,
+    offset: Vec2,
+}
+
+#[derive(Component)]
+struct TouchOverlay;
+
+#[derive(Component)]
+struct StickKnob;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchSeen>()
+            .init_resource::<TouchStick>()
+            .add_systems(PreUpdate, notice_touch)
+            .add_systems(OnEnter(AppState::Playing), spawn_overlay)
+            .add_systems(OnExit(AppState::Playing), despawn_overlay)
+            .add_systems(Update, (show_overlay, move_knob).run_if(in_state(AppState::Playing)));
+    }
+}
+
+pub fn touch_shown(profile: Res<Profile>, seen: Res<TouchSeen>) -> bool {
+    match profile.touch {
+        TouchMode::Auto => seen.0,
+        TouchMode::On => true,
+        TouchMode::Off => false,
+    }
+}
+
+fn notice_touch(touches: Res<Touches>, mut seen: ResMut<TouchSeen>) {
+    if touches.any_just_pressed() { seen.0 = true; }
+}
+
+fn stick_centre(screen: Vec2) -> Vec2 {
+    Vec2::new(MARGIN + STICK_RADIUS, screen.y - MARGIN - STICK_RADIUS)
+}
+
+// Touch positions are from the top left of the window
+fn button_at(pos: Vec2, screen: Vec2) -> Option<TouchButton> {
+    BUTTONS.iter().find_map(|&(button, slot, _)| {
+        let centre = screen - from_corner(slot);
+        (pos.distance(centre) <= BUTTON_RADIUS).then_some(button)
+    })
+}
+
+// Adds to what read_devices found, so a keyboard or pad still works alongside
+pub fn read_touch(
+    touches: Res<Touches>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut stick: ResMut<TouchStick>,
+    mut intent: ResMut<PlayerIntent>,
+) {
+    let Ok(window) = q_window.single() else { return; };
+    let screen = window.size();
+    let centre = stick_centre(screen);
+
+    // The finger that took the stick keeps it until it lifts, wherever it wanders
+    if stick.finger.is_some_and(|id| touches.get_pressed(id).is_none()) { stick.finger = None; }
+    if stick.finger.is_none() {
+        let grab = STICK_RADIUS * STICK_GRAB;
+        stick.finger = touches.iter_just_pressed().find(|t| t.position().distance(centre) <= grab).map(|t| t.id());
+    }
+    stick.offset = stick.finger.and_then(|id| touches.get_pressed(id)).map_or(Vec2::ZERO, |t| {
+        let pushed = (t.position() - centre) / STICK_RADIUS;
+        Vec2::new(pushed.x, -pushed.y).clamp_length_max(1.0)
+    });
+    if stick.offset.length() > STICK_DEADZONE { intent.movement += stick.offset; }
+
+    for touch in touches.iter_just_pressed() {
+        match button_at(touch.position(), screen) {
+            Some(TouchButton::Fire) => intent.fire = true,
+            Some(TouchButton::Utility) => intent.utility = true,
+            Some(TouchButton::Mine) => intent.lay_mine = true,
+            None => {}
+        }
+    }
+}
+
+fn spawn_overlay(mut commands: Commands) {
+    let circle = |radius: f32| Node {
+        position_type: PositionType::Absolute,
+        width: Val::Px(radius * 2.0),
+        height: Val::Px(radius * 2.0),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    commands
+        .spawn((
+            Node {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                ..default()
+            },
+            Visibility::Hidden,
+            // Under the settings panel and the recap
+            GlobalZIndex(1),
+            TouchOverlay,
+        ))
+        .with_children(|overlay| {
+            overlay
+                .spawn((
+                    Node { left: Val::Px(MARGIN), bottom: Val::Px(MARGIN), ..circle(STICK_RADIUS) },
+                    BackgroundColor(PAD_COLOR),
+                    BorderRadius::MAX,
+                ))
+                .with_children(|pad| {
+                    pad.spawn((circle(KNOB_RADIUS), BackgroundColor(KNOB_COLOR), BorderRadius::MAX, StickKnob));
+                });
+            for (_, slot, label) in BUTTONS {
+                let corner = from_corner(slot) - BUTTON_RADIUS;
+                overlay
+                    .spawn((
+                        Node { right: Val::Px(corner.x), bottom: Val::Px(corner.y), ..circle(BUTTON_RADIUS) },
+                        BackgroundColor(BUTTON_COLOR),
+                        BorderRadius::MAX,
+                    ))
+                    .with_children(|b| {
+                        let font = TextFont { font_size: 14.0, ..default() };
+                        b.spawn((Text::new(""), font, TextColor(Color::WHITE), Tr(label)));
+                    });
+            }
+        });
+}
+
+fn despawn_overlay(mut commands: Commands, q: Query<Entity, With<TouchOverlay>>) {
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+fn show_overlay(profile: Res<Profile>, seen: Res<TouchSeen>, mut q: Query<&mut Visibility, With<TouchOverlay>>) {
+    let visibility = if touch_shown(profile, seen) { Visibility::Inherited } else { Visibility::Hidden };
+    for mut v in &mut q {
+        if *v != visibility { *v = visibility; }
+    }
+}
+
+// Centred in the pad, pushed out as far as the finger is
+fn move_knob(stick: Res<TouchStick>, mut q: Query<&mut Node, With<StickKnob>>) {
+    if !stick.is_changed() { return; }
+    let pushed = Vec2::new(stick.offset.x, -stick.offset.y) * STICK_RADIUS;
+    let corner = Vec2::splat(STICK_RADIUS - KNOB_RADIUS) + pushed;
+    for mut node in &mut q {
+        node.left = Val::Px(corner.x);
+        node.top = Val::Px(corner.y);
+    }
+}
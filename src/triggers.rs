@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+
+use crate::{AppState, Enemy, GameSet, Player, Size, aabb_overlap};
+use crate::recap::StageRecap;
+
+// Invisible rectangles placed by the level (a `zone` line after a text maze,
+// a `Zone` entity in a .ron one) that announce tanks entering and leaving
+// them. Scripted behaviour (ambushes, doors, tutorial prompts) should listen
+// for `ZoneEvent` by zone name rather than polling positions itself.
+
+// A zone as a level lays it out: its name, top-left cell and size in tiles
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZoneSpec {
+    pub name: String,
+    pub at: UVec2,
+    pub size: UVec2,
+}
+
+#[derive(Component)]
+pub struct TriggerZone {
+    pub name: String,
+    // Tanks inside as of the last update
+    occupants: HashSet<Entity>,
+}
+
+impl TriggerZone {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), occupants: HashSet::new() }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ZoneTransition {
+    Enter,
+    Exit,
+}
+
+#[derive(Event, Debug)]
+pub struct ZoneEvent {
+    pub zone: String,
+    pub entity: Entity,
+    pub transition: ZoneTransition,
+    pub is_player: bool,
+}
+
+pub struct TriggersPlugin;
+
+impl Plugin for TriggersPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ZoneEvent>().add_systems(
+            Update,
+            (update_trigger_zones, log_zone_events)
+                .chain()
+                .in_set(GameSet::Cleanup)
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+fn update_trigger_zones(
+    mut q_zones: Query<(&Transform, &Size, &mut TriggerZone)>,
+    q_tanks: Query<(Entity, &Transform, &Size, Has<Player>), Or<(With<Player>, With<Enemy>)>>,
+    mut events: EventWriter<ZoneEvent>,
+) {
+    for (z_t, z_s, mut zone) in &mut q_zones {
+        let z_pos = z_t.translation.truncate();
+        let z_half = z_s.0 * 0.5;
+
+        let mut inside = HashSet::new();
+        for (e, t, s, is_player) in &q_tanks {
+            if !aabb_overlap(z_pos, z_half, t.translation.truncate(), s.0 * 0.5) { continue; }
+            inside.insert(e);
+            if !zone.occupants.contains(&e) {
+                let zone = zone.name.clone();
+                events.write(ZoneEvent { zone, entity: e, transition: ZoneTransition::Enter, is_player });
+            }
+        }
+
+        // Destroyed tanks count as leaving
+        for &e in zone.occupants.difference(&inside) {
+            let is_player = q_tanks.get(e).is_ok_and(|(_, _, _, p)| p);
+            events.write(ZoneEvent { zone: zone.name.clone(), entity: e, transition: ZoneTransition::Exit, is_player });
+        }
+        zone.occupants = inside;
+    }
+}
+
+fn log_zone_events(mut events: EventReader<ZoneEvent>) {
+    for ev in events.read() {
+        let who = if ev.is_player { "player" } else { "enemy" };
+        debug!("{who} {:?} {:?} zone '{}'", ev.entity, ev.transition, ev.zone);
+    }
+}
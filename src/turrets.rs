@@ -0,0 +1,174 @@
+use bevy::prelude::*;
+use std::collections::HashSet;
+use std::f32::consts::{PI, TAU};
+
+use crate::bullets::{BulletKind, BulletPool, Shot};
+use crate::config::GameConfig;
+use crate::death::{DeathEvent, DeathReason};
+use crate::explosions::spawn_explosion;
+use crate::feedback::HitEvent;
+use crate::recap::StageRecap;
+use crate::stats::ShotLanded;
+use crate::{
+    AppState, Blocks, Bullet, CollisionCache, Faction, FreezeEnemies, GameSet, Health, LevelTile, Owner, Player,
+    PlayerStart, Size, SmokeCloud, SpawnProtection, TILE, Velocity, aabb_overlap, in_smoke, line_clear,
+};
+
+// Turret emplacements (maze glyph `T`): fixed enemy guns that swing round
+// after the player and fire once lined up, as long as nothing solid is in the
+// way. They block like a wall and take a few player shells to destroy, but
+// don't count toward the wave, so a level can be cleared with them standing.
+
+const BASE_COLOR: Color = Color::srgb(0.45, 0.4, 0.45);
+const BARREL_COLOR: Color = Color::srgb(0.25, 0.2, 0.25);
+const SHELL_COLOR: Color = Color::srgb(1.0, 0.85, 0.2);
+
+#[derive(Component)]
+struct Turret {
+    gun: Timer,
+    // Where the barrel points, in radians
+    aim: f32,
+}
+
+#[derive(Component)]
+struct TurretBarrel;
+
+pub struct TurretsPlugin;
+
+impl Plugin for TurretsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                (turret_aim.run_if(not(resource_exists::<FreezeEnemies>)), turret_barrels)
+                    .chain()
+                    .in_set(GameSet::Ai),
+                turret_hits.in_set(GameSet::Damage),
+            )
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(resource_exists::<StageRecap>)),
+        );
+    }
+}
+
+// `fire_secs` is config.ron's unless the level file sets its own
+pub fn spawn_turret(commands: &mut Commands, pos: Vec2, fire_secs: f32, cfg: &GameConfig) {
+    let size = Vec2::splat(TILE * 0.8);
+    commands.spawn((
+        Sprite { color: BASE_COLOR, custom_size: Some(size), ..default() },
+        Transform::from_xyz(pos.x, pos.y, 0.0),
+        Turret { gun: Timer::from_seconds(fire_secs, TimerMode::Once), aim: -PI * 0.5 },
+        Health(cfg.turret.hit_points),
+        LevelTile,
+        Blocks::SOLID,
+        Size(size),
+        children![(
+            Sprite { color: BARREL_COLOR, custom_size: Some(Vec2::new(TILE * 0.55, TILE * 0.2)), ..default() },
+            // Pivots on the turret's centre, sticking out along +x
+            Transform::from_xyz(0.0, 0.0, 0.1),
+            TurretBarrel,
+        )],
+    ));
+}
+
+fn turret_aim(
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    cache: Res<CollisionCache>,
+    mut q_turrets: Query<(Entity, &Transform, &Size, &mut Turret)>,
+    q_player: Query<&Transform, With<Player>>,
+    q_smoke: Query<&Transform, With<SmokeCloud>>,
+    protection: Option<Res<SpawnProtection>>,
+    start: Option<Res<PlayerStart>>,
+    mut pool: ResMut<BulletPool>,
+    mut commands: Commands,
+) {
+    let Ok(player_t) = q_player.single() else { return; };
+    let player_pos = player_t.translation.truncate();
+    let protected = protection.is_some()
+        && start.is_some_and(|s| s.0.distance(player_pos) <= cfg.player.spawn_protect_radius);
+    let hidden = protected || in_smoke(player_pos, cfg.utility.smoke_radius, &q_smoke);
+    let turn = cfg.turret.turn_rate_degs.to_radians() * time.delta_secs();
+    let bullet_size = cfg.bullet.size();
+
+    for (e, t, size, mut turret) in &mut q_turrets {
+        turret.gun.tick(time.delta());
+        let pos = t.translation.truncate();
+        let to_player = player_pos - pos;
+        if hidden || to_player.length() > cfg.turret.range { continue; }
+
+        // The muzzle sits just clear of the turret's own block, whichever way it points
+        let reach = size.0.length() * 0.5 + bullet_size.max_element();
+        if !line_clear(pos + to_player.normalize_or_zero() * reach, player_pos, &cache.bullets) { continue; }
+
+        // Shortest way round to the player
+        let off = (to_player.to_angle() - turret.aim + PI).rem_euclid(TAU) - PI;
+        turret.aim += off.clamp(-turn, turn);
+        if !turret.gun.finished() || off.abs() > cfg.turret.aim_tolerance_degs.to_radians() { continue; }
+
+        let dir = Vec2::from_angle(turret.aim);
+        pool.fire(
+            &mut commands,
+            Shot {
+                pos: pos + dir * reach,
+                velocity: dir * cfg.turret.bullet_speed,
+                faction: Faction::Enemy,
+                owner: e,
+                kind: BulletKind::Standard,
+                size: bullet_size,
+                color: SHELL_COLOR,
+            },
+        );
+        turret.gun.reset();
+    }
+}
+
+fn turret_barrels(q_turrets: Query<&Turret>, mut q_barrels: Query<(&ChildOf, &mut Transform), With<TurretBarrel>>) {
+    for (parent, mut t) in &mut q_barrels {
+        let Ok(turret) = q_turrets.get(parent.parent()) else { continue; };
+        let dir = Vec2::from_angle(turret.aim);
+        t.rotation = Quat::from_rotation_z(turret.aim);
+        t.translation = (dir * TILE * 0.25).extend(t.translation.z);
+    }
+}
+
+// Shells reaching a turret (with bullet_wall_cull's lookahead) are spent on
+// it; those the damage rules let hurt enemies knock off a hit point.
+fn turret_hits(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut deaths: EventWriter<DeathEvent>,
+    mut hits: EventWriter<HitEvent>,
+    mut landed: EventWriter<ShotLanded>,
+    mut q_turrets: Query<(Entity, &Transform, &Size, &mut Health), With<Turret>>,
+    q_bullets: Query<(Entity, &Transform, &Size, &Velocity, &Faction, Option<&Owner>, &BulletKind), With<Bullet>>,
+) {
+    let dt = time.delta_secs();
+    let mut spent = HashSet::new();
+
+    for (turret_e, turret_t, turret_s, mut hp) in &mut q_turrets {
+        let turret_pos = turret_t.translation.truncate();
+        let turret_half = turret_s.0 * 0.5;
+        for (b_e, b_t, b_s, v, &faction, owner, shell) in &q_bullets {
+            if hp.0 == 0 || spent.contains(&b_e) || owner.is_some_and(|o| o.0 == turret_e) { continue; }
+            let pos = b_t.translation.truncate();
+            let half = b_s.0 * 0.5;
+            let touching = aabb_overlap(pos, half, turret_pos, turret_half)
+                || aabb_overlap(pos + v.0 * dt, half, turret_pos, turret_half);
+            if !touching { continue; }
+
+            spent.insert(b_e);
+            deaths.write(DeathEvent::new(b_e, DeathReason::Spent));
+            if !faction.hurts(Faction::Enemy, &cfg.damage) { continue; }
+
+            hp.0 = hp.0.saturating_sub(1);
+            hits.write(HitEvent { target: turret_e, fatal: hp.0 == 0, player: false });
+            if faction == Faction::Player && shell.first_hit() { landed.write_default(); }
+            if hp.0 == 0 {
+                spawn_explosion(&mut commands, turret_pos, turret_s.0);
+                deaths.write(DeathEvent::new(turret_e, DeathReason::Shot));
+            }
+        }
+    }
+}
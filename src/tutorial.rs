@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+
+use crate::attract::Demo;
+use crate::death::DeathEvent;
+use crate::input_glyphs::{Action, InputDevice, tank_glyph};
+use crate::intent::PlayerIntent;
+use crate::locale::Locale;
+use crate::profile::Profile;
+use crate::recap::{StageCleared, StageRecap};
+use crate::toasts::Notify;
+use crate::triggers::{ZoneEvent, ZoneTransition};
+use crate::waves::{CurrentLevel, GameMode, LevelEnemyQueue};
+use crate::{AppState, GameSet, TileKind, start_run};
+
+// Tutorial: a small maze played as level 0 ahead of the campaign, with a
+// prompt near the bottom of the screen for one thing at a time. Each step
+// waits for its action (driving into a trigger zone, shooting away a brick,
+// using the utility) as the game's own events report it, and the enemy tanks
+// are held back until the last step. Clearing the stage goes straight on into
+// the campaign's first level. A demo run on this mode skips the prompts.
+
+const PROMPT_COLOR: Color = Color::srgb(1.0, 0.9, 0.3);
+
+// In build_maze's characters
+pub const MAZE: &[&str] = &[
+    "####################",
+    "#P    #     b     S#",
+    "#     #     b      #",
+    "#     #     b      #",
+    "#           b      #",
+    "#           b      #",
+    "#     #     b      #",
+    "#     #     b     S#",
+    "# bbb #     b      #",
+    "# bEb #     b      #",
+    "####################",
+];
+
+// Laid over MAZE like TRIGGER_ZONES over the campaign maze
+pub const ZONES: &[(&str, UVec2, UVec2)] = &[("tutorial_doorway", UVec2::new(6, 4), UVec2::new(1, 2))];
+
+enum Goal {
+    // The player drives into the named trigger zone
+    Reach(&'static str),
+    BreakBrick,
+    UseUtility,
+    // The held tanks are let out, and destroying them clears the stage
+    ClearStage,
+}
+
+struct Step {
+    // Key into the language files; {key} is filled in with the action's button
+    prompt: &'static str,
+    action: Action,
+    goal: Goal,
+}
+
+const STEPS: &[Step] = &[
+    Step { prompt: "tutorial.move", action: Action::Move, goal: Goal::Reach("tutorial_doorway") },
+    Step { prompt: "tutorial.fire", action: Action::Fire, goal: Goal::BreakBrick },
+    Step { prompt: "tutorial.utility", action: Action::Utility, goal: Goal::UseUtility },
+    Step { prompt: "tutorial.fight", action: Action::Fire, goal: Goal::ClearStage },
+];
+
+// Present while a tutorial is being played, at the step it has reached
+#[derive(Resource)]
+struct Tutorial {
+    step: usize,
+}
+
+#[derive(Component)]
+struct Prompt;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            OnEnter(AppState::Playing),
+            begin_tutorial.after(start_run).run_if(resource_equals(GameMode::Tutorial)),
+        )
+        .add_systems(OnExit(AppState::Playing), end_tutorial)
+        .add_systems(
+            Update,
+            (
+                (
+                    advance_tutorial.run_if(not(resource_exists::<StageRecap>)),
+                    // The recap may already be up by the time the clear is read
+                    finish_tutorial,
+                )
+                    .chain()
+                    .in_set(GameSet::Cleanup)
+                    .run_if(in_state(AppState::Playing)),
+                refresh_prompt,
+            )
+                .run_if(resource_exists::<Tutorial>),
+        );
+    }
+}
+
+fn begin_tutorial(mut commands: Commands, demo: Option<Res<Demo>>, mut queue: ResMut<LevelEnemyQueue>) {
+    // The bot wouldn't follow the prompts, so a demo goes straight to the fight
+    if demo.is_some() {
+        queue.held = false;
+        return;
+    }
+    commands.insert_resource(Tutorial { step: 0 });
+    commands.spawn((
+        Text::new(""),
+        TextFont { font_size: 20.0, ..default() },
+        TextColor(PROMPT_COLOR),
+        TextLayout::new_with_justify(JustifyText::Center),
+        Node {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(48.0),
+            width: Val::Percent(100.0),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        Prompt,
+    ));
+}
+
+fn end_tutorial(mut commands: Commands, q: Query<Entity, With<Prompt>>) {
+    commands.remove_resource::<Tutorial>();
+    for e in &q { commands.entity(e).despawn(); }
+}
+
+// Every event is read each frame, so one from an earlier step can't count towards a later one
+fn advance_tutorial(
+    intent: Res<PlayerIntent>,
+    mut tutorial: ResMut<Tutorial>,
+    mut queue: ResMut<LevelEnemyQueue>,
+    mut zones: EventReader<ZoneEvent>,
+    mut deaths: EventReader<DeathEvent>,
+    q_tiles: Query<&TileKind>,
+) {
+    let entered: Vec<&str> = zones
+        .read()
+        .filter(|ev| ev.is_player && ev.transition == ZoneTransition::Enter)
+        .map(|ev| ev.zone.as_str())
+        .collect();
+    let broke_brick = deaths.read().any(|death| q_tiles.get(death.entity).is_ok_and(|t| *t == TileKind::Brick));
+
+    let Some(step) = STEPS.get(tutorial.step) else { return; };
+    let done = match step.goal {
+        Goal::Reach(zone) => entered.contains(&zone),
+        Goal::BreakBrick => broke_brick,
+        Goal::UseUtility => intent.utility,
+        Goal::ClearStage => false,
+    };
+    if done { tutorial.step += 1; }
+
+    let fighting = STEPS.get(tutorial.step).is_some_and(|s| matches!(s.goal, Goal::ClearStage));
+    if queue.held == fighting { queue.held = !fighting; }
+}
+
+// level_completion moved the level on to 1; the campaign starts over from its first
+fn finish_tutorial(
+    mut commands: Commands,
+    mut cleared: EventReader<StageCleared>,
+    locale: Res<Locale>,
+    mut mode: ResMut<GameMode>,
+    mut level: ResMut<CurrentLevel>,
+    mut notify: EventWriter<Notify>,
+    q_prompt: Query<Entity, With<Prompt>>,
+) {
+    if cleared.read().next().is_none() { return; }
+    *mode = GameMode::Campaign;
+    level.0 = 0;
+    notify.write(Notify::new(locale.t("tutorial.done")).with_color(PROMPT_COLOR));
+    commands.remove_resource::<Tutorial>();
+    for e in &q_prompt { commands.entity(e).despawn(); }
+}
+
+fn refresh_prompt(
+    tutorial: Res<Tutorial>,
+    device: Res<InputDevice>,
+    profile: Res<Profile>,
+    locale: Res<Locale>,
+    mut q: Query<&mut Text, With<Prompt>>,
+) {
+    if !(tutorial.is_changed() || device.is_changed() || profile.is_changed() || locale.is_changed()) { return; }
+    let Some(step) = STEPS.get(tutorial.step) else { return; };
+    let key = tank_glyph(step.action, *device, profile.controls);
+    for mut text in &mut q {
+        text.0 = locale.f(step.prompt, &[("key", &key)]);
+    }
+}
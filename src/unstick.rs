@@ -0,0 +1,88 @@
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::config::GameConfig;
+use crate::{AppState, Enemy, EnemyAI, GameRng, GameSet, TILE, Velocity, quantize_to_cardinal};
+
+// Stuck enemies: a watchdog on every enemy tank notices one that's been trying
+// to drive for `stuck_secs` (config.ron) without getting anywhere, usually
+// nosed into a wall corner, and turns it aside onto one of the two ways across
+// the one it was stuck on for a moment, after which its AI steers again from
+// where it's got to. Each time it happens an EnemyStuck event goes out, which
+// the F3 overlay marks and the log notes, so the spots can be found in
+// playtests.
+
+// Closer than this to where it was, it hasn't got anywhere
+const STUCK_DIST: f32 = TILE * 0.25;
+const UNSTICK_SECS: f32 = 0.6;
+
+#[derive(Event)]
+pub struct EnemyStuck {
+    pub enemy: Entity,
+    pub pos: Vec2,
+}
+
+#[derive(Component)]
+struct StuckWatch {
+    anchor: Vec2,
+    secs: f32,
+}
+
+// On a tank being turned aside; its AI drives this way until it runs out
+#[derive(Component)]
+pub struct Unstick {
+    pub dir: Vec2,
+    timer: Timer,
+}
+
+pub struct UnstickPlugin;
+
+impl Plugin for UnstickPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<EnemyStuck>().add_systems(
+            Update,
+            watch_stuck.in_set(GameSet::Cleanup).run_if(in_state(AppState::Playing)),
+        );
+    }
+}
+
+// After the tanks have moved. A tank standing still on purpose (holding its
+// rally point, or at brick it's shooting) isn't stuck.
+fn watch_stuck(
+    mut commands: Commands,
+    time: Res<Time>,
+    cfg: Res<GameConfig>,
+    mut stuck: EventWriter<EnemyStuck>,
+    mut rng: ResMut<GameRng>,
+    mut q: Query<
+        (Entity, &Transform, &Velocity, &mut EnemyAI, Option<&mut StuckWatch>, Option<&mut Unstick>),
+        With<Enemy>,
+    >,
+) {
+    let dt = time.delta_secs();
+    for (e, t, v, mut ai, watch, unstick) in &mut q {
+        let pos = t.translation.truncate();
+        if let Some(mut unstick) = unstick
+            && unstick.timer.tick(time.delta()).finished()
+        {
+            commands.entity(e).remove::<Unstick>();
+        }
+        let Some(mut watch) = watch else {
+            commands.entity(e).insert(StuckWatch { anchor: pos, secs: 0.0 });
+            continue;
+        };
+        if v.0 == Vec2::ZERO || pos.distance(watch.anchor) > STUCK_DIST {
+            *watch = StuckWatch { anchor: pos, secs: 0.0 };
+            continue;
+        }
+        watch.secs += dt;
+        if watch.secs < cfg.enemy.stuck_secs { continue; }
+
+        let across = quantize_to_cardinal(v.0).perp();
+        let dir = if rng.r#gen::<bool>() { across } else { -across };
+        ai.roam_dir = dir;
+        commands.entity(e).insert(Unstick { dir, timer: Timer::from_seconds(UNSTICK_SECS, TimerMode::Once) });
+        stuck.write(EnemyStuck { enemy: e, pos });
+        *watch = StuckWatch { anchor: pos, secs: 0.0 };
+    }
+}
@@ -0,0 +1,253 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::config::GameConfig;
+use crate::{EnemyKind, GameRng};
+
+// Per-level enemy schedule: how many tanks of each kind, in what order, how
+// many may be on the field at once, and which of them are bonus tanks. Levels
+// past the end of the table reuse the last entry. Boss levels replace the wave
+// with a single boss. Horde waves follow the same table without the bosses;
+// survival mode has a schedule of its own that never ends.
+
+struct WaveSpec {
+    max_on_screen: usize,
+    order: &'static [(EnemyKind, usize)],
+    // Positions in the spawn order (from 0) that roll out as blinking bonus
+    // tanks, which always leave a power-up
+    bonus: &'static [usize],
+}
+
+// A level file's own tanks, in place of the table's (see level_format.rs)
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WaveSchedule {
+    pub max_on_screen: usize,
+    // Kinds and how many of each, in the order they roll out
+    pub tanks: Vec<(EnemyKind, usize)>,
+}
+
+// The arcade's 4th, 11th and 18th tanks
+const CLASSIC_BONUS: &[usize] = &[3, 10, 17];
+
+// Survival: the mix of kinds hardens every SURVIVAL_TIER_TANKS tanks, each tank
+// brings the next one SURVIVAL_SPEEDUP sooner, and more may be out at once as
+// the run goes on. Every SURVIVAL_BONUS_EVERY-th tank is a bonus tank.
+const SURVIVAL_TIERS: &[&[EnemyKind]] = &[
+    &[EnemyKind::Basic, EnemyKind::Basic, EnemyKind::Fast],
+    &[EnemyKind::Basic, EnemyKind::Fast, EnemyKind::Power],
+    &[EnemyKind::Fast, EnemyKind::Power, EnemyKind::Saboteur, EnemyKind::Armor],
+    &[EnemyKind::Power, EnemyKind::Fast, EnemyKind::Armor, EnemyKind::Armor],
+];
+const SURVIVAL_TIER_TANKS: usize = 15;
+const SURVIVAL_SPEEDUP: f32 = 0.97;
+const SURVIVAL_MIN_SPAWN_SECS: f32 = 0.35;
+const SURVIVAL_START_ON_SCREEN: usize = 4;
+const SURVIVAL_MAX_ON_SCREEN: usize = 10;
+const SURVIVAL_BONUS_EVERY: usize = 8;
+const TUTORIAL_TANKS: &[EnemyKind] = &[EnemyKind::Basic, EnemyKind::Basic];
+
+// Campaign works through the levels and their waves. Survival is one endless
+// wave on the first maze (or a random one) with no base to defend, played for
+// time and kills. Horde stays on the first maze, with a build phase before every wave
+// (see horde.rs). Daily is the campaign on a seed and level order taken from
+// the date (see daily.rs). Tutorial is a level 0 of its own that leads into
+// the campaign (see tutorial.rs).
+#[derive(Resource, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum GameMode {
+    #[default]
+    Campaign,
+    Survival,
+    Horde,
+    Daily,
+    Tutorial,
+}
+
+impl GameMode {
+    // Key into the language files
+    pub fn label(self) -> &'static str {
+        match self {
+            GameMode::Campaign => "mode.campaign",
+            GameMode::Survival => "mode.survival",
+            GameMode::Horde => "mode.horde",
+            GameMode::Daily => "mode.daily",
+            GameMode::Tutorial => "mode.tutorial",
+        }
+    }
+
+    pub fn cycled(self) -> Self {
+        match self {
+            GameMode::Campaign => GameMode::Survival,
+            GameMode::Survival => GameMode::Horde,
+            GameMode::Horde => GameMode::Daily,
+            GameMode::Daily => GameMode::Tutorial,
+            GameMode::Tutorial => GameMode::Campaign,
+        }
+    }
+}
+
+const WAVES: &[WaveSpec] = &[
+    WaveSpec {
+        max_on_screen: 4,
+        order: &[(EnemyKind::Basic, 10), (EnemyKind::Fast, 2), (EnemyKind::Basic, 8)],
+        bonus: CLASSIC_BONUS,
+    },
+    WaveSpec {
+        max_on_screen: 4,
+        order: &[(EnemyKind::Basic, 6), (EnemyKind::Fast, 4), (EnemyKind::Basic, 6), (EnemyKind::Power, 4)],
+        bonus: CLASSIC_BONUS,
+    },
+    WaveSpec {
+        max_on_screen: 5,
+        order: &[
+            (EnemyKind::Fast, 6),
+            (EnemyKind::Basic, 6),
+            (EnemyKind::Saboteur, 2),
+            (EnemyKind::Power, 4),
+            (EnemyKind::Armor, 2),
+        ],
+        bonus: CLASSIC_BONUS,
+    },
+    WaveSpec {
+        max_on_screen: 6,
+        order: &[
+            (EnemyKind::Power, 6),
+            (EnemyKind::Saboteur, 2),
+            (EnemyKind::Fast, 6),
+            (EnemyKind::Armor, 4),
+            (EnemyKind::Basic, 2),
+        ],
+        bonus: CLASSIC_BONUS,
+    },
+];
+
+#[derive(Resource, Default)]
+pub struct CurrentLevel(pub usize);
+
+// Which level each stage of the run plays, when that isn't simply the next
+// one; stages past the end of the list play their own level
+#[derive(Resource, Default)]
+pub struct LevelOrder(pub Vec<usize>);
+
+impl LevelOrder {
+    pub fn level(&self, stage: usize) -> usize {
+        self.0.get(stage).copied().unwrap_or(stage)
+    }
+}
+
+#[derive(Resource)]
+pub struct LevelEnemyQueue {
+    pub remaining: VecDeque<EnemyKind>,
+    pub max_on_screen: usize,
+    pub spawn_timer: Timer,
+    // A boss is still to be spawned for this level
+    pub boss: bool,
+    // Set once the completion event has been sent for this level
+    pub cleared: bool,
+    // Survival: never runs out, so the level never clears
+    endless: bool,
+    // Nothing rolls out and the level can't clear while this is set (the
+    // tutorial holds its tanks back until the last step)
+    pub held: bool,
+    bonus: &'static [usize],
+    // Tanks taken from the queue so far this level
+    popped: usize,
+}
+
+impl LevelEnemyQueue {
+    pub fn for_level(level: usize, config: &GameConfig) -> Self {
+        let every = config.boss.every_n_levels;
+        Self::from_table(level, every > 0 && (level + 1).is_multiple_of(every), config)
+    }
+
+    fn from_table(level: usize, boss: bool, config: &GameConfig) -> Self {
+        let spec = &WAVES[level.min(WAVES.len() - 1)];
+        let remaining = if boss {
+            VecDeque::new()
+        } else {
+            spec.order.iter().flat_map(|&(kind, count)| std::iter::repeat_n(kind, count)).collect()
+        };
+        Self {
+            remaining,
+            max_on_screen: spec.max_on_screen,
+            spawn_timer: Timer::from_seconds(config.enemy.spawn_secs, TimerMode::Repeating),
+            boss,
+            cleared: false,
+            endless: false,
+            held: false,
+            bonus: spec.bonus,
+            popped: 0,
+        }
+    }
+
+    // No boss comes with it; the bonus tanks are the arcade's
+    pub fn from_schedule(schedule: &WaveSchedule, config: &GameConfig) -> Self {
+        Self {
+            remaining: schedule.tanks.iter().flat_map(|&(kind, count)| std::iter::repeat_n(kind, count)).collect(),
+            max_on_screen: schedule.max_on_screen,
+            spawn_timer: Timer::from_seconds(config.enemy.spawn_secs, TimerMode::Repeating),
+            boss: false,
+            cleared: false,
+            endless: false,
+            held: false,
+            bonus: CLASSIC_BONUS,
+            popped: 0,
+        }
+    }
+
+    pub fn for_run(mode: GameMode, level: usize, config: &GameConfig) -> Self {
+        match mode {
+            GameMode::Campaign | GameMode::Daily => Self::for_level(level, config),
+            GameMode::Horde => Self::from_table(level, false, config),
+            GameMode::Survival => Self {
+                remaining: VecDeque::new(),
+                max_on_screen: SURVIVAL_START_ON_SCREEN,
+                spawn_timer: Timer::from_seconds(config.enemy.spawn_secs, TimerMode::Repeating),
+                boss: false,
+                cleared: false,
+                endless: true,
+                held: false,
+                bonus: &[],
+                popped: 0,
+            },
+            GameMode::Tutorial => Self {
+                remaining: TUTORIAL_TANKS.iter().copied().collect(),
+                max_on_screen: TUTORIAL_TANKS.len(),
+                spawn_timer: Timer::from_seconds(config.enemy.spawn_secs, TimerMode::Repeating),
+                boss: false,
+                cleared: false,
+                endless: false,
+                held: true,
+                bonus: &[],
+                popped: 0,
+            },
+        }
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.endless || !self.remaining.is_empty()
+    }
+
+    // The next tank to roll out, and whether it is a bonus tank
+    pub fn pop_next(&mut self, rng: &mut GameRng) -> Option<(EnemyKind, bool)> {
+        if self.endless { return Some(self.pop_endless(rng)); }
+        let kind = self.remaining.pop_front()?;
+        let bonus = self.bonus.contains(&self.popped);
+        self.popped += 1;
+        Some((kind, bonus))
+    }
+
+    fn pop_endless(&mut self, rng: &mut GameRng) -> (EnemyKind, bool) {
+        let tier = SURVIVAL_TIERS[(self.popped / SURVIVAL_TIER_TANKS).min(SURVIVAL_TIERS.len() - 1)];
+        let kind = tier[rng.gen_range(0..tier.len())];
+        let bonus = self.popped % SURVIVAL_BONUS_EVERY == SURVIVAL_BONUS_EVERY - 1;
+        self.popped += 1;
+
+        let secs = self.spawn_timer.duration().as_secs_f32() * SURVIVAL_SPEEDUP;
+        self.spawn_timer.set_duration(Duration::from_secs_f32(secs.max(SURVIVAL_MIN_SPAWN_SECS)));
+        self.max_on_screen = (SURVIVAL_START_ON_SCREEN + self.popped / SURVIVAL_TIER_TANKS).min(SURVIVAL_MAX_ON_SCREEN);
+        (kind, bonus)
+    }
+}